@@ -8,6 +8,9 @@ use krokit_core::config::config::KrokitConfig;
 use krokit_core::config::agent::AgentConfig;
 use krokit_core::runners::coder::coder::CoderBrain;
 use krokit_core::runners::searcher::searcher::SearcherBrain;
+use krokit_core::session::SessionStore;
+use krokit_core::tools::bash::SandboxConfig;
+use krokit_core::usage::PricingTable;
 use krokit_llm::{ChatMessage, ChatMessageContent, LlmClient};
 
 pub enum AgentKind {
@@ -31,6 +34,7 @@ impl AppHeadless {
         tools: Option<String>, 
         remove: Option<String>,
         trace: bool,
+        sandbox: bool,
         agent_name: Option<String>
     ) -> Result<(), Box<dyn std::error::Error>> {   
         // Configure internal debug logging to file
@@ -48,16 +52,22 @@ impl AppHeadless {
             return Ok(());
         }
 
+        let store = SessionStore::new()?;
         let agent = if let Some(agent_name) = agent_name {
             // Use custom agent from config
-            let config = AgentConfig::load(&agent_name)
+            let mut config = AgentConfig::load(&agent_name)
                 .map_err(|e| format!("Failed to load agent '{}': {}", agent_name, e))?;
+            if sandbox && config.tools.sandbox.is_none() {
+                config.tools.sandbox = Some(SandboxConfig::default());
+            }
             
             let agent_builder = AgentBuilder::from_config(config).await
                 .map_err(|e| format!("Failed to create agent from config: {}", e))?;
             
             agent_builder
                 .with_traces(initial_trace)
+                .pricing(PricingTable::from_config())
+                .persist(store)
                 .sudo()
                 .build()
         } else {
@@ -65,28 +75,40 @@ impl AppHeadless {
             let (llm_client, model) = KrokitConfig::get_llm().await?;
             eprintln!("\x1b[2m░ {} on {}\x1b[0m", model, llm_client.provider().name());
             
-            // Handle tool selection
-            let tools = match (tools, remove) {
+            // Handle tool selection, the searcher also gets the semantic search
+            let default_tools = match self.kind {
+                AgentKind::Coder => ToolConfig::new(),
+                AgentKind::Searcher => ToolConfig::new().add_tools(vec![ToolName::SemanticSearch]),
+            };
+            let mut tools = match (tools, remove) {
                 (Some(tools_str), _) => {
                     let selected_tools = parse_tools_list(&tools_str)?;
-                    ToolConfig::new().add_tools(selected_tools)
+                    default_tools.add_tools(selected_tools)
                 }
                 (None, Some(remove_str)) => {
                     let tools_to_remove = parse_tools_list(&remove_str)?;
-                    ToolConfig::new().remove_tools(tools_to_remove)
+                    default_tools.remove_tools(tools_to_remove)
                 }
-                (None, None) => ToolConfig::new(),
+                (None, None) => default_tools,
             };
+            if sandbox {
+                tools = tools.sandbox(SandboxConfig::default());
+                eprintln!("\x1b[2m░ bash runs in a bubblewrap sandbox\x1b[0m");
+            }
             
-            let toolbox = tools.build_toolbox();
             let brain: Box<dyn Brain> = match self.kind {
                 AgentKind::Coder => Box::new(CoderBrain::new(Arc::new(llm_client), model)),
                 AgentKind::Searcher => Box::new(SearcherBrain::new(Arc::new(llm_client), model)),
             };
 
-            AgentBuilder::new(brain)
+            let agent_builder = AgentBuilder::new(brain);
+            let toolbox = tools.build_toolbox(&agent_builder.todo_storage, &agent_builder.fs_log)
+                .map_err(|e| format!("Failed to set up the bash sandbox: {}", e))?;
+            agent_builder
                 .with_traces(initial_trace)
                 .tools(toolbox)
+                .pricing(PricingTable::from_config())
+                .persist(store)
                 .sudo()
                 .build()
         };
@@ -96,7 +118,10 @@ impl AppHeadless {
             .run().await;
 
         match result {
-            Ok(AgentResult { success, message, trace: agent_trace }) => {
+            Ok(AgentResult { success, message, trace: agent_trace, usage }) => {
+                if !usage.is_empty() {
+                    eprintln!("\x1b[2m░ usage: {}\x1b[0m", usage.format_short());
+                }
                 if trace {
                     println!("{}", serde_json::to_string_pretty(&agent_trace)?);
                 } else {
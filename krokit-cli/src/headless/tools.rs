@@ -1,7 +1,8 @@
 use std::sync::Arc;
 use krokit_core::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, LsTool, 
-                     MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool,
+                     MultiEditTool, PatchTool, ReadTool, SemanticSearchTool, SymbolsTool, TodoReadTool, TodoWriteTool, WriteTool,
                      TodoStorage, FsOperationLog};
+use krokit_core::tools::bash::{SandboxConfig, SandboxError};
 
 /// Available tools for the coder agent
 #[derive(Debug, Clone, PartialEq)]
@@ -12,7 +13,10 @@ pub enum ToolName {
     Find,
     Ls,
     MultiEdit,
+    Patch,
     Read,
+    SemanticSearch,
+    Symbols,
     TodoRead,
     TodoWrite,
     Write,
@@ -27,13 +31,20 @@ impl ToolName {
             ToolName::Find,
             ToolName::Ls,
             ToolName::MultiEdit,
+            ToolName::Patch,
             ToolName::Read,
+            ToolName::Symbols,
             ToolName::TodoRead,
             ToolName::TodoWrite,
             ToolName::Write,
         ]
     }
 
+    /// Tools only added when they are asked for
+    pub fn optional() -> Vec<ToolName> {
+        vec![ToolName::SemanticSearch]
+    }
+
     pub fn name(&self) -> &'static str {
         match self {
             ToolName::Bash => "bash",
@@ -42,7 +53,10 @@ impl ToolName {
             ToolName::Find => "find",
             ToolName::Ls => "ls",
             ToolName::MultiEdit => "multiedit",
+            ToolName::Patch => "patch",
             ToolName::Read => "read",
+            ToolName::SemanticSearch => "semantic_search",
+            ToolName::Symbols => "symbols",
             ToolName::TodoRead => "todoread",
             ToolName::TodoWrite => "todowrite",
             ToolName::Write => "write",
@@ -57,7 +71,10 @@ impl ToolName {
             "find" => Some(ToolName::Find),
             "ls" => Some(ToolName::Ls),
             "multiedit" => Some(ToolName::MultiEdit),
+            "patch" => Some(ToolName::Patch),
             "read" => Some(ToolName::Read),
+            "semantic_search" => Some(ToolName::SemanticSearch),
+            "symbols" => Some(ToolName::Symbols),
             "todoread" => Some(ToolName::TodoRead),
             "todowrite" => Some(ToolName::TodoWrite),
             "write" => Some(ToolName::Write),
@@ -75,12 +92,15 @@ impl std::fmt::Display for ToolName {
 /// Tool configuration and manipulation
 pub struct ToolConfig {
     pub tools: Vec<ToolName>,
+    /// run the bash commands in a sandbox
+    pub sandbox: Option<SandboxConfig>,
 }
 
 impl Default for ToolConfig {
     fn default() -> Self {
         Self {
             tools: ToolName::all(),
+            sandbox: None,
         }
     }
 }
@@ -91,7 +111,12 @@ impl ToolConfig {
     }
 
     pub fn with_tools(tools: Vec<ToolName>) -> Self {
-        Self { tools }
+        Self { tools, sandbox: None }
+    }
+
+    pub fn sandbox(mut self, sandbox: SandboxConfig) -> Self {
+        self.sandbox = Some(sandbox);
+        self
     }
 
     pub fn remove_tools(mut self, tools_to_remove: Vec<ToolName>) -> Self {
@@ -112,25 +137,29 @@ impl ToolConfig {
         self.tools.iter().map(|t| t.name().to_string()).collect()
     }
 
-    pub fn build_toolbox(&self) -> Vec<Box<dyn AnyTool>> {
-        let todo_storage = Arc::new(TodoStorage::new());
-        let fs_log = Arc::new(FsOperationLog::new());
+    pub fn build_toolbox(&self, todo_storage: &Arc<TodoStorage>, fs_log: &Arc<FsOperationLog>) -> Result<Vec<Box<dyn AnyTool>>, SandboxError> {
         let mut toolbox: Vec<Box<dyn AnyTool>> = Vec::new();
         for tool_name in &self.tools {
             match tool_name {
-                ToolName::Bash => toolbox.push(Box::new(BashTool::new())),
+                ToolName::Bash => match &self.sandbox {
+                    Some(sandbox) => toolbox.push(Box::new(BashTool::with_backend(sandbox.backend()?))),
+                    None => toolbox.push(Box::new(BashTool::new())),
+                },
                 ToolName::Edit => toolbox.push(Box::new(EditTool::new(fs_log.clone()))),
-                ToolName::Fetch => toolbox.push(Box::new(FetchTool::new())),
+                ToolName::Fetch => toolbox.push(Box::new(FetchTool::from_config())),
                 ToolName::Find => toolbox.push(Box::new(FindTool::new())),
                 ToolName::Ls => toolbox.push(Box::new(LsTool::new())),
                 ToolName::MultiEdit => toolbox.push(Box::new(MultiEditTool::new(fs_log.clone()))),
+                ToolName::Patch => toolbox.push(Box::new(PatchTool::new(fs_log.clone()))),
                 ToolName::Read => toolbox.push(Box::new(ReadTool::new(fs_log.clone()))),
+                ToolName::SemanticSearch => toolbox.push(Box::new(SemanticSearchTool::from_config())),
+                ToolName::Symbols => toolbox.push(Box::new(SymbolsTool::new())),
                 ToolName::TodoRead => toolbox.push(Box::new(TodoReadTool::new(todo_storage.clone()))),
                 ToolName::TodoWrite => toolbox.push(Box::new(TodoWriteTool::new(todo_storage.clone()))),
                 ToolName::Write => toolbox.push(Box::new(WriteTool::new(fs_log.clone()))),
             }
         }
-        toolbox
+        Ok(toolbox)
     }
 }
 
@@ -140,6 +169,9 @@ pub fn list_all_tools() {
     for tool in ToolName::all() {
         eprintln!("  {}", tool.name());
     }
+    for tool in ToolName::optional() {
+        eprintln!("  {} (optional)", tool.name());
+    }
 }
 
 pub fn parse_tools_list(tools_str: &str) -> Result<Vec<ToolName>, String> {
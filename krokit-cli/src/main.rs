@@ -13,8 +13,12 @@ use krokit_core::agent::LoggingConfig;
 use krokit_core::config::config::KrokitConfig;
 use krokit_core::config::agent::AgentConfig;
 use krokit_core::agent::builder::AgentBuilder;
+use krokit_core::agent::ClaimManager;
 use krokit_core::runners::clifixer::fix::clifix;
+use krokit_core::session::SessionStore;
+use krokit_core::tools::fs::checkpoint::display_path;
 use krokit_llm::{ChatMessage, ChatMessageContent};
+use krokit_llm::image::{image_part_from_file, user_content};
 use tui::auth::AppAuth;
 use tui::theme::{apply_gradient, logo, logo_cyan, KROKIT_WHITE, KROKIT_YELLOW};
 use tui::App;
@@ -67,6 +71,12 @@ struct Cli {
     /// Remove specific tools from the default set (comma-separated)
     #[arg(long)]
     remove: Option<String>,
+    /// Run bash commands in a bubblewrap sandbox: read-only system, writable project directory (headless mode only)
+    #[arg(long)]
+    sandbox: bool,
+    /// Attach an image (png, jpeg, gif, webp) to the prompt, can be repeated (headless mode only)
+    #[arg(long = "image", value_name = "PATH")]
+    images: Vec<String>,
     /// Show version information
     #[arg(short, long)]
     version: bool,
@@ -84,6 +94,28 @@ enum AgentAction {
     Agent(Vec<String>),
 }
 
+#[derive(Subcommand)]
+enum SessionsAction {
+    /// List stored sessions, most recent first
+    List,
+}
+
+#[derive(Subcommand)]
+enum PermissionsAction {
+    /// List the permissions granted with "allow always"
+    List,
+    /// Revoke a permission by its number in the list
+    Revoke {
+        number: usize,
+    },
+    /// Remove all the permissions of the project (or the global ones)
+    Clear {
+        /// Clear the global permissions instead of the project ones
+        #[arg(long)]
+        global: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum Commands {
     #[cfg(unix)]
@@ -109,6 +141,29 @@ enum Commands {
         #[command(subcommand)]
         action: AgentAction,
     },
+    /// Resume a previous session (the latest one in this directory if no id is given)
+    Resume {
+        /// Session id, or a unique prefix of it
+        id: Option<String>,
+    },
+    /// Restore the files of a session as they were before a turn, and drop the conversation from there.
+    /// Lists the turns that modified files if no turn is given.
+    Rewind {
+        /// Session id, or a unique prefix of it
+        session: String,
+        /// Turn to rewind to (its changes are reverted too)
+        turn: Option<usize>,
+    },
+    /// Session management commands
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+    /// Manage the permissions granted to the agent
+    Permissions {
+        #[command(subcommand)]
+        action: PermissionsAction,
+    },
     #[cfg(unix)]
     /// Send pre-command hook (before command execution)
     #[command(hide = true)]
@@ -153,6 +208,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(Commands::Agent { action }) => {
             handle_agent_command(action).await?;
         },
+        Some(Commands::Resume { id }) => {
+            handle_resume(id).await?;
+        },
+        Some(Commands::Rewind { session, turn }) => {
+            handle_rewind(session, turn).await?;
+        },
+        Some(Commands::Sessions { action }) => {
+            handle_sessions_command(action)?;
+        },
+        Some(Commands::Permissions { action }) => {
+            handle_permissions_command(action)?;
+        },
         #[cfg(unix)]
         Some(Commands::Precmd { command }) => {
             let command_str = command.join(" ");
@@ -199,7 +266,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             if !messages.is_empty() || cli.list_tools {
                 // Route to fix command with combined messages and global options
-                handle_fix(messages, cli.tools, cli.remove, cli.trace, None).await?;
+                handle_fix(messages, cli.images, cli.tools, cli.remove, cli.trace, cli.sandbox, None).await?;
             } else {
                 // No input, show TUI
                 handle_main(None).await?;
@@ -240,6 +307,166 @@ async fn handle_main(agent_name: Option<String>) -> Result<(), Box<dyn std::erro
     Ok(())
 }
 
+async fn handle_resume(id: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let session = match SessionStore::new()?.resolve(id.as_deref()) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("Use krokit sessions list to see the stored sessions");
+            return Ok(());
+        }
+    };
+
+    let logo = logo();
+    println!("{}", apply_gradient(&logo, KROKIT_YELLOW, KROKIT_YELLOW));
+    let mut app = App::new().with_session(&session.id);
+    match app.run(session.agent_name.clone()).await {
+        Err(e) => eprintln!("error: {}",e),
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn handle_rewind(id: String, turn: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let store = SessionStore::new()?;
+    let mut session = match store.resolve(Some(&id)) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("Use krokit sessions list to see the stored sessions");
+            return Ok(());
+        }
+    };
+
+    let Some(turn) = turn else {
+        let turns = session.file_turns();
+        if turns.is_empty() {
+            println!("No file changes recorded in session {}.", session.id);
+            return Ok(());
+        }
+        println!("Turns of session {} that modified files:", session.id);
+        for turn in turns {
+            let prompt = turn.prompt
+                .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+                .map(|p| if p.chars().count() > 60 { format!("{}...", p.chars().take(60).collect::<String>()) } else { p })
+                .unwrap_or_else(|| "(compacted)".to_string());
+            println!("  \x1b[1m{:>3}\x1b[0m {}", turn.turn, prompt);
+            for file in turn.files {
+                println!("      \x1b[2m{}\x1b[0m", display_path(&file));
+            }
+        }
+        return Ok(());
+    };
+
+    match store.rewind(&mut session, turn).await {
+        Ok(rewind) => {
+            for file in &rewind.files {
+                println!("restored {}", display_path(file));
+            }
+            match rewind.messages_removed {
+                Some(count) => println!("Session {} rewound to turn {} ({} messages removed).", session.id, turn, count),
+                None => println!("Files restored, the conversation was compacted since turn {} and was left as is.", turn),
+            }
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+    Ok(())
+}
+
+fn handle_sessions_command(action: SessionsAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        SessionsAction::List => {
+            let store = SessionStore::new()?;
+            let sessions = store.list()?;
+            if sessions.is_empty() {
+                println!("No sessions found.");
+                return Ok(());
+            }
+
+            println!("Sessions:");
+            for session in sessions {
+                let updated = session.updated_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M");
+                let agent = session.agent_name
+                    .map(|name| format!(" [{}]", name))
+                    .unwrap_or_default();
+                println!("  \x1b[1m{}\x1b[0m \x1b[2m{} · {} messages · {}{}\x1b[0m",
+                    session.id,
+                    updated,
+                    session.message_count,
+                    session.working_dir.display(),
+                    agent
+                );
+                println!("    {}", session.title);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_permissions_command(action: PermissionsAction) -> Result<(), Box<dyn std::error::Error>> {
+    let load = |path: std::path::PathBuf| -> Result<ClaimManager, Box<dyn std::error::Error>> {
+        let mut manager = ClaimManager::with_config_file(path);
+        manager.load_from_file()?;
+        Ok(manager)
+    };
+    let mut project = load(ClaimManager::project_permissions_path()?)?;
+    let mut global = load(ClaimManager::global_permissions_path()?)?;
+
+    match action {
+        PermissionsAction::List => {
+            if project.is_empty() && global.is_empty() {
+                println!("No permissions granted.");
+                return Ok(());
+            }
+
+            let scoped = project.get_all_permissions().iter().map(|p| ("project", p))
+                .chain(global.get_all_permissions().iter().map(|p| ("global", p)));
+            println!("Permissions:");
+            for (i, (scope, permission)) in scoped.enumerate() {
+                let description = permission.description.clone()
+                    .unwrap_or_else(|| permission.parameters.to_string());
+                println!("  \x1b[1m{:>2}\x1b[0m {:<8} \x1b[1m{}\x1b[0m {} \x1b[2m({})\x1b[0m",
+                    i + 1,
+                    scope,
+                    permission.tool_name,
+                    description,
+                    permission.granted_at.with_timezone(&chrono::Local).format("%Y-%m-%d")
+                );
+            }
+        }
+        PermissionsAction::Revoke { number } => {
+            let index = number.checked_sub(1).ok_or("permission numbers start at 1")?;
+            let (manager, index) = if index < project.len() {
+                (&mut project, index)
+            } else {
+                (&mut global, index - project.len())
+            };
+            match manager.remove_permission(index) {
+                Some(permission) => {
+                    manager.save_to_file()?;
+                    println!("Revoked: {} {}", permission.tool_name, permission.description.unwrap_or_default());
+                }
+                None => {
+                    eprintln!("Error: no permission #{}", number);
+                    eprintln!("Use krokit permissions list to see the granted permissions");
+                }
+            }
+        }
+        PermissionsAction::Clear { global: clear_global } => {
+            let (manager, scope) = if clear_global { (&mut global, "global") } else { (&mut project, "project") };
+            let count = manager.len();
+            if count > 0 {
+                manager.clear();
+                manager.save_to_file()?;
+            }
+            println!("Removed {} {} permission(s).", count, scope);
+        }
+    }
+    Ok(())
+}
+
 async fn handle_config() -> Result<(), Box<dyn std::error::Error>> {
     let mut auth = AppAuth::new();
     auth.run().await;
@@ -252,19 +479,30 @@ async fn ensure_config() -> Result<(), Box<dyn std::error::Error>> {
 
 async fn handle_fix(
     prompt: Vec<String>, 
+    images: Vec<String>,
     tools: Option<String>, 
     remove: Option<String>,
     trace: bool,
+    sandbox: bool,
     agent_name: Option<String>
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let images = images.iter()
+        .map(image_part_from_file)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    // the images go with the last prompt
+    let count = prompt.len();
+    let mut images = Some(images);
     let initial_trace: Vec<ChatMessage> = prompt.into_iter()
-        .map(|p| ChatMessage::User { 
-            content: ChatMessageContent::Text(p), 
+        .enumerate()
+        .map(|(i, p)| ChatMessage::User { 
+            content: if i + 1 == count { user_content(p, images.take().unwrap_or_default()) } else { ChatMessageContent::Text(p) }, 
             name: None 
         })
         .collect();
     
-    AppHeadless::new().run(initial_trace, tools, remove, trace, agent_name).await
+    AppHeadless::new().run(initial_trace, tools, remove, trace, sandbox, agent_name).await
 }
 
 fn show_version() -> Result<(), Box<dyn std::error::Error>> {
@@ -501,7 +739,7 @@ async fn handle_agent_command(action: AgentAction) -> Result<(), Box<dyn std::er
             } else {
                 // Prompt provided, run in headless mode
                 let prompt = prompt_args.join(" ");
-                handle_fix(vec![prompt], None, None, false, Some(agent_name.clone())).await?;
+                handle_fix(vec![prompt], vec![], None, None, false, false, Some(agent_name.clone())).await?;
             }
         }
     }
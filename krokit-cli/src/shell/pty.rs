@@ -4,6 +4,7 @@ use std::thread;
 use std::io::{self, Read, Write};
 use std::sync::atomic::{AtomicI32, Ordering};
 use tempfile::NamedTempFile;
+use krokit_core::tools::bash::pty::open_pty_pair;
 
 use crate::fc::server::KrokitSessionServer;
 use crate::shell::terminal::TerminalManager;
@@ -70,42 +71,7 @@ impl KrokitPtyManager {
     }
 
     fn create_pty_pair() -> Result<(RawFd, RawFd), Box<dyn std::error::Error>> {
-        let master_fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
-        if master_fd == -1 {
-            return Err("Failed to open master pty".into());
-        }
-
-        if unsafe { libc::grantpt(master_fd) } == -1 {
-            unsafe { libc::close(master_fd) };
-            return Err("Failed to grant pty".into());
-        }
-
-        if unsafe { libc::unlockpt(master_fd) } == -1 {
-            unsafe { libc::close(master_fd) };
-            return Err("Failed to unlock pty".into());
-        }
-
-        let slave_name = unsafe {
-            let ptr = libc::ptsname(master_fd);
-            if ptr.is_null() {
-                libc::close(master_fd);
-                return Err("Failed to get slave pty name".into());
-            }
-            std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned()
-        };
-
-        let slave_fd = unsafe { 
-            libc::open(
-                slave_name.as_ptr() as *const i8, 
-                libc::O_RDWR | libc::O_NOCTTY
-            ) 
-        };
-        if slave_fd == -1 {
-            unsafe { libc::close(master_fd) };
-            return Err("Failed to open slave pty".into());
-        }
-
-        Ok((master_fd, slave_fd))
+        open_pty_pair().map_err(|e| format!("Failed to open pty: {}", e).into())
     }
 
     pub fn start_session(&mut self, shell: Shell, quiet: bool) -> Result<(), Box<dyn std::error::Error>> {
@@ -16,12 +16,15 @@ use krokit_core::agent::{Agent, AgentRequest, AgentEvent, AgentController, Publi
 use krokit_core::agent::events::{PermissionRequest, PermissionResponse};
 use krokit_core::agent::output::PrettyFormatter;
 use krokit_core::config::config::KrokitConfig;
+use krokit_core::config::catalog::ModelCatalog;
 use krokit_core::config::agent::AgentConfig;
 use krokit_core::agent::builder::AgentBuilder;
 use krokit_core::logging::LoggingConfig;
-use krokit_core::runners::coder::coder::coder;
+use krokit_core::runners::coder::coder::CoderBrain;
+use krokit_core::session::SessionStore;
+use krokit_core::usage::PricingTable;
 use krokit_core::tools::{ToolCall, ToolResult};
-use krokit_llm::{LlmClient, ToolCallMethod};
+use krokit_llm::{ChatDelta, LlmClient, ToolCallMethod};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Style},
@@ -37,6 +40,8 @@ use std::collections::{HashMap, VecDeque};
 
 use crate::tui::input::InputArea;
 use super::input::UserAction;
+use super::filenav::image_references;
+use krokit_llm::image::image_part_from_file;
 use crate::tui::perm::PermissionWidget;
 use crate::tui::auth::config_model::ModalModel;
 use crate::tui::auth::auth::NavAction;
@@ -44,6 +49,11 @@ use krokit_llm::provider::ProviderInfo;
 use crate::tui::perm_alt_screen::AlternateScreenPermissionModal;
 use super::perm::PermissionModalAction;
 
+/// number of lines of the streamed message shown live
+const MAX_STREAMING_LINES: usize = 6;
+const MAX_TOOL_PROGRESS_LINES: usize = 4;
+const MAX_TOOL_PROGRESS_BYTES: usize = 8 * 1024;
+
 
 pub enum AppModalState<'a> {
     InputShown,
@@ -71,11 +81,15 @@ pub struct App<'a> {
     pub(crate) state: AppModalState<'a>,
     pub(crate) formatter: PrettyFormatter, // streaming log formatter
     pub(crate) running_tools: HashMap<String, ToolCall>, // (request_id, request)
+    pub(crate) tool_progress: HashMap<String, String>, // (request_id, last output of the running tool)
+    pub(crate) streaming_content: String,   // partial answer of the brain
+    pub(crate) streaming_reasoning: String, // partial reasoning of the brain
     pub(crate) input: InputArea<'a>,       // input text
     pub(crate) commands: HashMap<(String, String),Vec<String>>,
     pub(crate) exit: bool,
     pub(crate) permission_queue: VecDeque<(String, PermissionRequest)>, // (request_id, request)
     pub(crate) agent_name: Option<String>,
+    pub(crate) session_id: Option<String>, // stored session to resume
     pub(crate) banner_line: Option<String>,
     pub(crate) cwd_line: Option<String>,
 }
@@ -84,7 +98,14 @@ pub struct App<'a> {
 // Agent-related Internals
 impl App<'_> {
     pub async fn start_agent(&mut self, agent_name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
-        let mut agent: Box<dyn Agent> = if let Some(agent_name) = agent_name {
+        let store = SessionStore::new()?;
+        let mut agent: Box<dyn Agent> = if let Some(session_id) = self.session_id.clone() {
+            // Resume a stored session with its own agent config
+            let agent_builder = AgentBuilder::from_session(Some(&session_id)).await?;
+            let messages = agent_builder.trace.len();
+            self.banner_line = Some(format!("\x1b[2m░ resumed session {} ({} messages)\x1b[0m", session_id, messages));
+            Box::new(agent_builder.build())
+        } else if let Some(agent_name) = agent_name {
             // Load custom agent config
             let config = AgentConfig::load(agent_name)?;
             // Update banner (rendered by draw_ui)
@@ -92,13 +113,15 @@ impl App<'_> {
             
             // Create agent from config
             let agent_builder = AgentBuilder::from_config(config).await?;
-            Box::new(agent_builder.build())
+            Box::new(agent_builder.pricing(PricingTable::from_config()).load_permissions().persist(store).build())
         } else {
             // Use default coder agent
             let (llm, model) = KrokitConfig::get_llm().await?;
             self.banner_line = Some(format!("\x1b[2m░ {} on {}\x1b[0m", model, llm.provider().name()));
 
-            Box::new(coder(Arc::new(llm), model))
+            let agent_builder = AgentBuilder::new(Box::new(CoderBrain::new(Arc::new(llm), model)));
+            let tools = AgentBuilder::default_tools(&agent_builder.todo_storage, &agent_builder.fs_log);
+            Box::new(agent_builder.tools(tools).pricing(PricingTable::from_config()).load_permissions().persist(store).build())
         };
 
         // cwd printed by header draw; no stdout printing here
@@ -137,24 +160,46 @@ impl App<'_> {
             self.input.set_agent_running(!matches!(new_status, PublicAgentState::Paused));
         }
 
+        // running token totals shown in the status line
+        if let AgentEvent::UsageUpdated { session, .. } = &event {
+            self.input.set_usage(*session);
+        }
+
         // updated inprogress list
         if let AgentEvent::ToolCallStarted { call, .. }= &event {
             self.running_tools.insert(call.tool_call_id.clone(), call.clone());
         }
+        if let AgentEvent::ToolCallProgress { call_id, chunk } = &event {
+            let output = self.tool_progress.entry(call_id.clone()).or_default();
+            output.push_str(chunk);
+            // only the tail is shown, keep the buffer bounded
+            if output.len() > MAX_TOOL_PROGRESS_BYTES {
+                let mut cut = output.len() - MAX_TOOL_PROGRESS_BYTES;
+                while !output.is_char_boundary(cut) {
+                    cut += 1;
+                }
+                output.drain(..cut);
+            }
+        }
         if let AgentEvent::ToolCallCompleted { call, .. }= &event {
             self.running_tools.remove(&call.tool_call_id);
+            self.tool_progress.remove(&call.tool_call_id);
+        }
+
+        // accumulate the message being streamed, it is replaced by the complete one once available
+        match &event {
+            AgentEvent::BrainDelta { delta: ChatDelta::Content(text) } => self.streaming_content.push_str(text),
+            AgentEvent::BrainDelta { delta: ChatDelta::Reasoning(text) } => self.streaming_reasoning.push_str(text),
+            AgentEvent::BrainResult { .. } | AgentEvent::StatusChanged { .. } => {
+                self.streaming_content.clear();
+                self.streaming_reasoning.clear();
+            }
+            _ => {}
         }
 
         // Format and display event
         if let Some(formatted) = self.formatter.format_event(&event) {
-            if let Some(ref mut terminal) = self.terminal {
-                let wrapped = formatted.into_text().unwrap();
-                let line_count = wrapped.lines.iter().len() as u16;
-                terminal.clear()?; // this is to avoid visual artifact
-                terminal.insert_before(line_count, |buf| {
-                    wrapped.render(buf.area, buf);
-                })?;
-            }
+            self.print_above(&formatted)?;
         }
 
         // Handle permission requests - just add to queue
@@ -169,6 +214,19 @@ impl App<'_> {
 
 // UI-related Internals
 impl App<'_> {
+    /// Print ansi text in the scrollback, above the input area
+    pub(crate) fn print_above(&mut self, text: &str) -> io::Result<()> {
+        if let Some(ref mut terminal) = self.terminal {
+            let wrapped = text.into_text().unwrap();
+            let line_count = wrapped.lines.iter().len() as u16;
+            terminal.clear()?; // this is to avoid visual artifact
+            terminal.insert_before(line_count, |buf| {
+                wrapped.render(buf.area, buf);
+            })?;
+        }
+        Ok(())
+    }
+
     pub fn new() -> Self {
         Self {
             terminal: None,
@@ -181,13 +239,23 @@ impl App<'_> {
             commands: Self::list_command(),
             exit: false,
             running_tools: HashMap::new(),
+            tool_progress: HashMap::new(),
+            streaming_content: String::new(),
+            streaming_reasoning: String::new(),
             permission_queue: VecDeque::new(),
             agent_name: None,
+            session_id: None,
             banner_line: None,
             cwd_line: std::env::current_dir().ok().map(|p| format!("\x1b[2m░ cwd: {}\x1b[0m", p.display())),
         }
     }
 
+    /// Resume a stored session instead of starting a new one
+    pub fn with_session(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
     pub async fn run(&mut self, agent_name: Option<String>) -> io::Result<()> {
         let x = self.try_run(agent_name).await;
         let _ = disable_raw_mode();
@@ -208,7 +276,9 @@ impl App<'_> {
         self.agent_name = agent_name.clone();
         let agent_name_ref = agent_name.as_deref();
         self.start_agent(agent_name_ref).await.map_err(|e| -> Box<dyn std::error::Error> { 
-            if agent_name_ref.is_some() {
+            if let Some(session_id) = &self.session_id {
+                format!("could not resume session '{}': {}", session_id, e).into()
+            } else if agent_name_ref.is_some() {
                 format!("could not start custom agent '{}': {}", agent_name_ref.unwrap(), e).into()
             } else {
                 format!("could not start krokit agent, run krokit auth first").into()
@@ -312,14 +382,16 @@ impl App<'_> {
         match action {
             PermissionModalAction::Response { request_id, choice } => {
                 // Send response to agent
-                if let Some(ref agent) = self.agent {     
-                    if matches!(choice, PermissionResponse::AllowAlways) {
-                        let _ = agent.controller.sudo().await;
-                    }        
+                if let Some(ref agent) = self.agent {
+                    // the agent stores "allow always" answers as permissions
+                    let allow_always = matches!(choice, PermissionResponse::AllowAlways);
                     match agent.controller.response_permission_request(request_id, choice).await {
                         Err(e) => {
                             self.input.alert_msg("channel with agent closed. Please restart the app", Duration::from_secs(3));
                         },
+                        Ok(_) if allow_always => {
+                            self.input.alert_msg("permission saved, see krokit permissions list", Duration::from_secs(3));
+                        },
                         _ => {},
                     }
                 }
@@ -379,8 +451,15 @@ impl App<'_> {
                 }
             }
             UserAction::UserInput { input } => {
+                let images = match image_references(&input).iter().map(image_part_from_file).collect::<Result<Vec<_>, _>>() {
+                    Ok(images) => images,
+                    Err(e) => {
+                        self.input.alert_msg(&e.to_string(), Duration::from_secs(3));
+                        return Ok(());
+                    }
+                };
                 if let Some(ref agent) = self.agent {                                
-                    match agent.controller.send_user_input(input.clone()).await {
+                    match agent.controller.send_user_input_with_images(input.clone(), images).await {
                         Err(e) => {
                             self.input.alert_msg("channel with agent closed. Please restart the app", Duration::from_secs(3));
                         },
@@ -406,9 +485,34 @@ impl App<'_> {
         let header_lines = self.banner_line.is_some() as u16 + self.cwd_line.is_some() as u16;
         let header_height = if header_lines > 0 { header_lines } else { 1 };
 
+        let streaming = if self.streaming_content.trim().is_empty() && self.streaming_reasoning.trim().is_empty() {
+            None
+        } else {
+            Some(self.formatter.format_streaming(&self.streaming_content, &self.streaming_reasoning, MAX_STREAMING_LINES))
+        };
+        let streaming_height = streaming.as_ref().map_or(0, |s| s.lines().count() as u16);
+
+        // running tools, each followed by the tail of its output (if any)
+        let running: Vec<(String, u16)> = self.running_tools.iter()
+            .map(|(id, tc)| {
+                let mut text = self.formatter.format_tool_running(tc);
+                if let Some(output) = self.tool_progress.get(id) {
+                    let tail = self.formatter.format_tool_progress(output, MAX_TOOL_PROGRESS_LINES);
+                    if !tail.is_empty() {
+                        text.push('\n');
+                        text.push_str(&tail);
+                    }
+                }
+                let lines = text.lines().count() as u16;
+                (text, lines)
+            })
+            .collect();
+        let running_height: u16 = running.iter().map(|(_, lines)| lines).sum();
+
         let height = modal_height
         + header_height 
-        + self.running_tools.len() as u16;
+        + streaming_height
+        + running_height;
 
         if let Some(ref mut terminal) = self.terminal {  
             if height != self.terminal_height {
@@ -417,9 +521,10 @@ impl App<'_> {
             }
 
             terminal.draw(|frame| {                    
-                let [header, inprogress, modal] = Layout::vertical([
+                let [header, streamed, inprogress, modal] = Layout::vertical([
                     Constraint::Length(header_height), // header lines
-                    Constraint::Length(self.running_tools.len() as u16 + 1), // running tool (if any)
+                    Constraint::Length(streaming_height), // message being streamed (if any)
+                    Constraint::Length(running_height + 1), // running tool (if any)
                     Constraint::Length(modal_height)])                // input or modal
                     .areas(frame.area()); 
 
@@ -431,11 +536,17 @@ impl App<'_> {
                     frame.render_widget(Text::from(lines), header);
                 }
 
+                // draw message being streamed
+                if let Some(ref text) = streaming {
+                    frame.render_widget(text.into_text().unwrap(), streamed);
+                }
+
                 // draw running tool
-                if !self.running_tools.is_empty() {
-                    let layout: std::rc::Rc<[Rect]> = Layout::vertical(vec![Constraint::Length(1); self.running_tools.len()+1]).split(inprogress);
-                    for ((_,tc), &area) in self.running_tools.iter().zip(layout.into_iter()) {
-                        frame.render_widget(self.formatter.format_tool_running(tc).into_text().unwrap(), area);
+                if !running.is_empty() {
+                    let constraints: Vec<Constraint> = running.iter().map(|(_, lines)| Constraint::Length(*lines)).collect();
+                    let layout: std::rc::Rc<[Rect]> = Layout::vertical(constraints).split(inprogress);
+                    for ((text, _), &area) in running.iter().zip(layout.into_iter()) {
+                        frame.render_widget(text.into_text().unwrap(), area);
                     }
                 }
 
@@ -469,16 +580,10 @@ impl App<'_> {
             return Ok(());
         };
 
-        // Build client and fetch models
-        let client = match LlmClient::create_provider(&provider_cfg.provider, &provider_cfg.env_vars) {
-            Ok(c) => c,
-            Err(e) => {
-                self.input.alert_msg(&format!("create client failed: {}", e), Duration::from_secs(3));
-                return Ok(());
-            }
-        };
-        let models = match client.models().await {
-            Ok(list) => list.data.into_iter().map(|m| m.id).collect::<Vec<_>>(),
+        // Models from the cached catalog, fetched when missing or outdated
+        let http = config.http_settings(&provider_cfg);
+        let models = match ModelCatalog::load().models(&provider_cfg.provider, &provider_cfg.env_vars, &http).await {
+            Ok(models) => models,
             Err(e) => {
                 self.input.alert_msg(&format!("fetch models failed: {}", e), Duration::from_secs(3));
                 return Ok(());
@@ -496,9 +601,15 @@ impl App<'_> {
             providers: vec![],
             selected_provider: 0,
             mcp_configs: HashMap::new(),
+            pricing: HashMap::new(),
+            capabilities: HashMap::new(),
+            fallback: None,
+            http: Default::default(),
+            embeddings: None,
         };
 
-        let modal = ModalModel::new(models, mm_config, providers, provider_info, provider_cfg.env_vars.clone());
+        let modal = ModalModel::new(models, mm_config, providers, provider_info, provider_cfg.env_vars.clone())
+            .with_http(http);
         self.state = AppModalState::ModelModal { modal };
         Ok(())
     }
@@ -87,7 +87,9 @@ impl AppAuth {
                         if let Some(Ok(models)) = modal_envs.poll_fetch() {
                             if let AuthState::EnvConfig(modal_envs) = std::mem::replace(&mut self.state, AuthState::Done) {
                                 let (config, providers, provider, env_values) = modal_envs.extract_state();
-                                let modal_model = ModalModel::new(models, config.clone(), providers, provider, env_values);
+                                let http = config.http.clone();
+                                let modal_model = ModalModel::new(models, config, providers, provider, env_values)
+                                    .with_http(http);
                                 self.state = AuthState::ModelSelection(modal_model);
                             }
                         }
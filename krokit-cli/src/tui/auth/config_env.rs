@@ -10,8 +10,8 @@ use ratatui::{
     Frame,
 };
 use krokit_core::config::config::KrokitConfig;
+use krokit_core::config::catalog::{CatalogModel, ModelCatalog};
 use krokit_llm::provider::ProviderInfo;
-use krokit_llm::client::LlmClient;
 use tui_textarea::TextArea;
 use tokio::task::JoinHandle;
 
@@ -21,7 +21,7 @@ use super::auth::NavAction;
 pub enum FetchState {
     Idle,
     Fetching,
-    Success(Vec<String>),
+    Success(Vec<CatalogModel>),
     Error(String),
 }
 
@@ -35,7 +35,7 @@ pub struct ModalEnvs {
     env_values: HashMap<String, String>,
     error_message: Option<String>,
     fetch_state: FetchState,
-    fetch_task: Option<JoinHandle<Result<Vec<String>, String>>>,
+    fetch_task: Option<JoinHandle<Result<Vec<CatalogModel>, String>>>,
 }
 
 impl ModalEnvs {
@@ -95,23 +95,14 @@ impl ModalEnvs {
         
         let provider_name = self.provider.name.to_string();
         let env_values = self.env_values.clone();
+        let http = self.config.http.clone();
         
+        // always fetched, this also checks the credentials, and updates the cached catalog
         self.fetch_task = Some(tokio::spawn(async move {
-            match LlmClient::create_provider(&provider_name, &env_values) {
-                Ok(client) => {
-                    match client.models().await {
-                        Ok(models) => {
-                            Ok(models.data.into_iter().map(|m| m.id).collect())
-                        },
-                        Err(e) => {
-                            Err(format!("Failed to fetch models: {}", e))
-                        }
-                    }
-                },
-                Err(e) => {
-                    Err(format!("Failed to create client: {}", e))
-                }
-            }
+            ModelCatalog::load()
+                .refresh(&provider_name, &env_values, &http)
+                .await
+                .map_err(|e| e.to_string())
         }));
     }
 
@@ -120,7 +111,7 @@ impl ModalEnvs {
         matches!(self.fetch_state, FetchState::Fetching)
     }
 
-    pub fn poll_fetch(&mut self) -> Option<Result<Vec<String>, String>> {
+    pub fn poll_fetch(&mut self) -> Option<Result<Vec<CatalogModel>, String>> {
         if self.fetch_task.as_ref()?.is_finished() {
             let task = self.fetch_task.take()?;
             let result = futures::executor::block_on(task).unwrap_or_else(|_| Err("Cancelled".to_string()));
@@ -1,5 +1,5 @@
 use std::{collections::HashMap, io};
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::Rect,
     style::{Color, Style, Modifier},
@@ -9,14 +9,17 @@ use ratatui::{
     Frame,
 };
 use krokit_core::config::config::KrokitConfig;
+use krokit_core::config::catalog::{CatalogModel, ModelCatalog};
 use krokit_llm::provider::ProviderInfo;
+use krokit_llm::HttpSettings;
 
 use super::auth::NavAction;
 
 #[derive(Debug)]
 pub struct ModalModel {
-    pub all_models: Vec<String>,
-    pub filtered_models: Vec<String>,
+    pub all_models: Vec<CatalogModel>,
+    pub filtered_models: Vec<CatalogModel>,
+    pub catalog: ModelCatalog,
     pub selected_index: usize,
     pub scroll_offset: usize,
     pub search_query: String,
@@ -25,6 +28,7 @@ pub struct ModalModel {
     pub providers: Vec<ProviderInfo>,
     pub provider: ProviderInfo,
     pub env_values: HashMap<String, String>,
+    pub http: HttpSettings,
     pub error_message: Option<String>,
 }
 
@@ -32,11 +36,11 @@ const MAX_VISIBLE_MODELS: usize = 20;
 const SCROLL_MARGIN: usize = 10;
 
 impl ModalModel {
-    pub fn new(available_models: Vec<String>, config: KrokitConfig, providers: Vec<ProviderInfo>, provider: ProviderInfo, env_values: HashMap<String, String>) -> Self {
-        let filtered_models = available_models.clone();
-        Self {
+    pub fn new(available_models: Vec<CatalogModel>, config: KrokitConfig, providers: Vec<ProviderInfo>, provider: ProviderInfo, env_values: HashMap<String, String>) -> Self {
+        let mut modal = Self {
             all_models: available_models,
-            filtered_models,
+            filtered_models: Vec::new(),
+            catalog: ModelCatalog::load(),
             selected_index: 0,
             scroll_offset: 0,
             search_query: String::new(),
@@ -45,33 +49,38 @@ impl ModalModel {
             providers,
             provider,
             env_values,
+            http: HttpSettings::default(),
             error_message: None,
-        }
+        };
+        modal.filter_models();
+        modal
+    }
+
+    /// Http settings used when the models are fetched again
+    pub fn with_http(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
     }
 
     pub fn selected_model(&self) -> String {
         if self.selected_index < self.filtered_models.len() {
-            self.filtered_models[self.selected_index].clone()
+            self.filtered_models[self.selected_index].id.clone()
         } else {
             String::new()
         }
     }
 
-    pub fn available_models(&self) -> &[String] {
+    pub fn available_models(&self) -> &[CatalogModel] {
         &self.filtered_models
     }
 
+    /// Fuzzy search of the models, favorites first
     fn filter_models(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_models = self.all_models.clone();
-        } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_models = self.all_models
-                .iter()
-                .filter(|model| model.to_lowercase().contains(&query))
-                .cloned()
-                .collect();
-        }
+        self.filtered_models = self.catalog
+            .search(self.provider.name, &self.all_models, &self.search_query)
+            .into_iter()
+            .cloned()
+            .collect();
         
         // Reset selection if it's out of bounds
         if self.selected_index >= self.filtered_models.len() && !self.filtered_models.is_empty() {
@@ -80,6 +89,38 @@ impl ModalModel {
         }
     }
 
+    /// Keep the same model selected after the list was sorted again
+    fn select_model(&mut self, model: &str) {
+        if let Some(index) = self.filtered_models.iter().position(|m| m.id == model) {
+            self.selected_index = index;
+            self.update_scroll();
+        }
+    }
+
+    fn toggle_favorite(&mut self) {
+        let model = self.selected_model();
+        if model.is_empty() {
+            return;
+        }
+        self.catalog.toggle_favorite(self.provider.name, &model);
+        self.filter_models();
+        self.select_model(&model);
+    }
+
+    /// Fetch the models again, bypassing the cache
+    async fn refresh(&mut self) {
+        let model = self.selected_model();
+        match self.catalog.refresh(self.provider.name, &self.env_values, &self.http).await {
+            Ok(models) => {
+                self.all_models = models;
+                self.filter_models();
+                self.select_model(&model);
+                self.error_message = None;
+            }
+            Err(e) => self.error_message = Some(e.to_string()),
+        }
+    }
+
     fn update_scroll(&mut self) {
         if self.filtered_models.len() <= MAX_VISIBLE_MODELS {
             self.scroll_offset = 0;
@@ -121,7 +162,7 @@ impl ModalModel {
                 if self.filtered_models.is_empty() {
                     return NavAction::None;
                 }
-                let selected_model = &self.filtered_models[self.selected_index];
+                let selected_model = &self.filtered_models[self.selected_index].id;
                 if self.config.is_duplicate_config(&self.provider.name, &self.env_values, selected_model) {
                     self.error_message = Some("This instance already exists in the configuration".to_string());
                     return NavAction::None;
@@ -134,6 +175,12 @@ impl ModalModel {
                 self.update_scroll();
                 self.error_message = None;
             }
+            KeyCode::Char('f') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.toggle_favorite();
+            }
+            KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.refresh().await;
+            }
             KeyCode::Char(c) => {
                 if !self.search_mode {
                     self.search_mode = true;
@@ -244,10 +291,18 @@ impl ModalModel {
 
             let model = &self.filtered_models[model_idx];
             let is_selected = model_idx == self.selected_index;
-            let is_duplicate = self.config.is_duplicate_config(&self.provider.name, &self.env_values, model);
+            let is_duplicate = self.config.is_duplicate_config(&self.provider.name, &self.env_values, &model.id);
+            let is_favorite = self.catalog.is_favorite(self.provider.name, &model.id);
             
             let prefix = if is_selected { "● " } else { "○ " };
-            let line = format!("{}{}", prefix, model);
+            let favorite = if is_favorite { "★ " } else { "" };
+            let context = model.context_window.map(format_context).unwrap_or_default();
+            let name = format!("{}{}{}", prefix, favorite, model.id);
+            // context length aligned on the right
+            let padding = (layout_areas[area_index].width as usize)
+                .saturating_sub(name.chars().count() + context.len())
+                .max(1);
+            let line = format!("{}{}{}", name, " ".repeat(padding), context);
             
             let style = if is_duplicate {
                 Style::default().fg(Color::DarkGray).add_modifier(Modifier::DIM)
@@ -276,11 +331,22 @@ impl ModalModel {
         let help_text = if self.search_mode {
             "Type to search • ↑↓ navigate • Backspace clear • Esc clear search • Enter select"
         } else {
-            "↑↓ navigate • Type to search • Enter select • Esc back"
+            "↑↓ navigate • Type to search • Ctrl+F favorite • Ctrl+R refresh • Enter select • Esc back"
         };
         let help_paragraph = Paragraph::new(help_text)
             .style(Style::default().fg(Color::DarkGray));
         frame.render_widget(help_paragraph, layout_areas[area_index]);
     }
 
-}
\ No newline at end of file
+}
+
+/// Context length in a short form (128k, 1M)
+fn format_context(tokens: u32) -> String {
+    if tokens >= 1_000_000 {
+        format!("{}M", tokens / 1_000_000)
+    } else if tokens >= 1_000 {
+        format!("{}k", tokens / 1_000)
+    } else {
+        tokens.to_string()
+    }
+}
@@ -1,4 +1,5 @@
 use std::{collections::HashMap, io, time::Duration};
+use krokit_core::tools::fs::checkpoint::display_path;
 use krokit_llm::ToolCallMethod;
 
 use crate::tui::App;
@@ -9,7 +10,10 @@ impl App<'_> {
             (("/exit","exit from the tui"), vec![]),
             (("/auth","select a provider"), vec![]),
             (("/model","change model for current provider"), vec![]),
-            (("/tc","set the tool call method: [fc | fc2 | so]"), vec!["method"]),
+            (("/compact","summarize older messages to free up context"), vec![]),
+            (("/undo","revert the last turn that changed files (or the last N) and drop it from the conversation"), vec![]),
+            (("/diff","show the file changes of the last turn"), vec![]),
+            (("/tc","set the tool call method: [auto | fc | fc2 | so | parse]"), vec!["method"]),
         ])
         .into_iter()
         .map(|((cmd,desc),args)|((cmd.to_string(),desc.to_string()),args.into_iter().map(|s|s.to_string()).collect()))
@@ -29,6 +33,46 @@ impl App<'_> {
                 // Open inline model selector
                 let _ = self.open_model_selector().await;
             }
+            "/compact" => {
+                if let Some(ref agent) = self.agent {
+                    match agent.controller.compact().await {
+                        Ok(_) => self.input.alert_msg("compacting conversation...", Duration::from_secs(3)),
+                        Err(e) => self.input.alert_msg(&e.to_string(), Duration::from_secs(3)),
+                    }
+                }
+            }
+            "/undo" => {
+                let turns = match args.first().map(|n| n.parse::<usize>()) {
+                    None => 1,
+                    Some(Ok(n)) if n > 0 => n,
+                    _ => {
+                        self.input.alert_msg("usage: /undo [N]", Duration::from_secs(3));
+                        return Ok(());
+                    }
+                };
+                if let Some(ref agent) = self.agent {
+                    match agent.controller.undo(turns).await {
+                        Ok(files) => {
+                            let restored = files.iter()
+                                .map(|f| format!("\x1b[2m░ restored {}\x1b[0m", display_path(f)))
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            self.print_above(&restored)?;
+                            self.input.alert_msg(&format!("{} file(s) restored", files.len()), Duration::from_secs(3));
+                        }
+                        Err(e) => self.input.alert_msg(&e.to_string(), Duration::from_secs(3)),
+                    }
+                }
+            }
+            "/diff" => {
+                if let Some(ref agent) = self.agent {
+                    match agent.controller.diff().await {
+                        Ok(diff) if diff.is_empty() => self.input.alert_msg("no changes", Duration::from_secs(3)),
+                        Ok(diff) => self.print_above(&colorize_diff(&diff))?,
+                        Err(e) => self.input.alert_msg(&e.to_string(), Duration::from_secs(3)),
+                    }
+                }
+            }
             "/tc" => {
                 if let Some(ref agent) = self.agent {
                     match args.into_iter().next() {
@@ -56,6 +100,12 @@ impl App<'_> {
                                 self.input.set_tool_call_method(method);
                             }
                         }
+                        Some("parse") => {
+                            if let Ok(method) = agent.controller.set_method(Some(ToolCallMethod::Parsing)).await {
+                                self.input.alert_msg("llm will now write <tool_call> tags parsed from its answer", Duration::from_secs(3));
+                                self.input.set_tool_call_method(method);
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -67,3 +117,19 @@ impl App<'_> {
         Ok(())
     }
 }
+
+fn colorize_diff(diff: &str) -> String {
+    diff.lines().map(|line| {
+        if line.starts_with("+++") || line.starts_with("---") {
+            format!("\x1b[1m{}\x1b[0m", line)
+        } else if line.starts_with('+') {
+            format!("\x1b[32m{}\x1b[0m", line)
+        } else if line.starts_with('-') {
+            format!("\x1b[31m{}\x1b[0m", line)
+        } else if line.starts_with("@@") {
+            format!("\x1b[36m{}\x1b[0m", line)
+        } else {
+            line.to_string()
+        }
+    }).collect::<Vec<_>>().join("\n")
+}
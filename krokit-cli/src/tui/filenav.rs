@@ -6,6 +6,8 @@ use ratatui::{
     Frame,
 };
 use ignore::WalkBuilder;
+use krokit_llm::image::is_image_path;
+use std::path::PathBuf;
 
 pub struct FileNav {
     all_files: Option<Vec<String>>, // relative paths
@@ -183,3 +185,16 @@ impl FileNav {
         f.render_widget(text, area);
     }
 }
+
+/// Images referenced with @path in the input, attached to the prompt
+pub fn image_references(input: &str) -> Vec<PathBuf> {
+    let mut images: Vec<PathBuf> = Vec::new();
+    for token in input.split_whitespace() {
+        let Some(path) = token.strip_prefix('@') else { continue };
+        let path = PathBuf::from(path.trim_end_matches(|c: char| matches!(c, ',' | ';' | ':' | '?' | '!' | ')')));
+        if is_image_path(&path) && path.is_file() && !images.contains(&path) {
+            images.push(path);
+        }
+    }
+    images
+}
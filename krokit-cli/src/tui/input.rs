@@ -12,6 +12,7 @@ use ratatui::{
     Frame,
 };
 use krokit_core::agent::{AgentController, AgentEvent, PublicAgentState};
+use krokit_core::usage::TokenUsage;
 use krokit_llm::{tool::call_fc_auto::ToolCallFunctionCallingAuto, ToolCallMethod};
 use tui_textarea::{Input, TextArea};
 
@@ -43,6 +44,7 @@ pub struct InputArea<'a> {
     // alert top left
     animation_start: Option<Instant>,
     status_message: Option<String>,
+    usage: Option<TokenUsage>, // session totals
 
     // status bottom left
     last_keystroke_time: Option<Instant>,
@@ -73,6 +75,7 @@ impl Default for InputArea<'_> {
             current_draft: None,
             animation_start: None,
             status_message: None,
+            usage: None,
             last_keystroke_time: None,
             pending_enter: None,
             helper_msg: None,
@@ -264,7 +267,14 @@ impl InputArea<'_> {
         self.animation_start.is_some()
     }
 
+    pub fn set_usage(&mut self, usage: TokenUsage) {
+        self.usage = Some(usage);
+    }
+
     fn get_status_text(&self) -> String {
+        let usage = self.usage.as_ref()
+            .filter(|u| !u.is_empty())
+            .map(|u| u.format_short());
         if let Some(ref msg) = self.status_message {
             // Show status message if we have one (like "Task cancelled")
             format!(" {}", msg)
@@ -273,10 +283,13 @@ impl InputArea<'_> {
             let spinner_chars = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
             let elapsed = animation_start.elapsed().as_millis();
             let index = (elapsed / 100) % spinner_chars.len() as u128;
-            format!(" {} Agent is working... (press esc to cancel)", spinner_chars[index as usize])
+            match usage {
+                Some(usage) => format!(" {} Agent is working... (press esc to cancel) · {}", spinner_chars[index as usize], usage),
+                None => format!(" {} Agent is working... (press esc to cancel)", spinner_chars[index as usize]),
+            }
         } else {
-            // Agent is waiting for input, no status to show
-            String::new()
+            // Agent is waiting for input, show the session usage if any
+            usage.map(|usage| format!(" {}", usage)).unwrap_or_default()
         }
     }
 }
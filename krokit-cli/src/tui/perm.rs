@@ -10,7 +10,7 @@ use ratatui::{
     widgets::{Block, Borders, List, ListDirection, ListItem, Padding, Paragraph, Widget}, 
     Frame
 };
-use krokit_core::{agent::{events::PermissionRequest, output::PrettyFormatter, PermissionResponse}, tools::{ToolCall, ToolResult}};
+use krokit_core::{agent::{claims::Permission, events::PermissionRequest, output::PrettyFormatter, PermissionResponse}, tools::{ToolCall, ToolResult}};
 use tui_textarea::{Input, TextArea};
 
 use super::theme::KROKIT_YELLOW;
@@ -148,7 +148,12 @@ impl PermissionWidget<'_> {
         f.render_widget(block, tool);
         f.render_widget(&self.preview, inner);
 
-        let items = ["Allow", "Allow all tools and don't ask again for this session", "Deny"];
+        // describe what "allow always" will grant, e.g. "run `cargo test` commands"
+        let always = Permission::allow_always(&self.request.call.tool_name, &self.request.call.parameters)
+            .description
+            .map(|d| format!("Allow and don't ask again to {}", d))
+            .unwrap_or_else(|| "Allow and don't ask again".to_string());
+        let items = ["Allow".to_string(), always, "Deny".to_string()];
         let mut lines = vec![Line::from("Do you want to run this tool?")];
         for (i,s) in items.into_iter().enumerate() {
             if i == self.selected_index {
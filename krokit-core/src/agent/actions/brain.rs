@@ -1,7 +1,10 @@
+use std::sync::Arc;
+
 use chrono::Utc;
-use krokit_llm::ChatMessage;
-use tracing::info;
+use krokit_llm::{ChatMessage, DeltaSink};
+use tracing::{info, warn};
 use tokio_util::sync::CancellationToken;
+use crate::usage::CallUsage;
 use crate::agent::{AgentCore, AgentError, AgentEvent, InternalAgentEvent, InternalAgentState, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
 
 impl AgentCore {
@@ -10,21 +13,41 @@ impl AgentCore {
         let cancellation_token = CancellationToken::new();
         let cancel_token_clone = cancellation_token.clone();
         let trace = self.trace.clone();
+        let tool_images = self.tool_images.clone();
         let tx_clone = self.internal_tx.clone();
         let available_tools = self.available_tools.clone();
         let method = self.method.clone();
         let context = ThinkerContext {
             trace,
+            tool_images,
             available_tools,
             method
         };
         let brain = self.brain.clone();
+        let fs_log = self.fs_log.clone();
+        let public_event_tx = self.socket.tx_event.clone();
         
         //////////////////////// TOKIO SPAWN
         tokio::spawn(async move {
             tokio::select! {
                 result = async {
-                    brain.write().await.next_step(context).await
+                    // give the brain a chance to compact the trace if it grew too large
+                    if let Err(error) = Self::compact_trace(brain.clone(), context.trace.clone(), fs_log, tx_clone.clone(), public_event_tx.clone(), false).await {
+                        warn!(target: "agent::compact", error = ?error, "automatic compaction failed");
+                        if let Some(tx) = &public_event_tx {
+                            let _ = tx.send(AgentEvent::Error { error: format!("automatic compaction failed: {}", error) });
+                        }
+                    }
+                    // stream the answer if someone is listening, dropping this future aborts the stream
+                    match public_event_tx.clone() {
+                        Some(tx) => {
+                            let on_delta: DeltaSink = Arc::new(move |delta| {
+                                let _ = tx.send(AgentEvent::BrainDelta { delta });
+                            });
+                            brain.write().await.next_step_stream(context, on_delta).await
+                        }
+                        None => brain.write().await.next_step(context).await
+                    }
                 } => {
                     let _ = tx_clone.send(InternalAgentEvent::BrainResult {
                         result
@@ -47,7 +70,11 @@ impl AgentCore {
 
     /// Process a brain task result
     pub async fn process_next_step(&mut self, result: Result<ThinkerDecision, AgentError>) -> Result<(), AgentError> {
-        let ThinkerDecision{message, flow} = self.handle_brain_error(result).await?;
+        let ThinkerDecision{message, flow, usage} = self.handle_brain_error(result).await?;
+        if let Some(usage) = usage {
+            self.record_usage(&usage).await;
+        }
+
         let ChatMessage::Assistant { content, reasoning_content, tool_calls, .. } = message.clone() else {
             return self.handle_brain_error::<ThinkerDecision>(
                 Err(AgentError::InvalidResponse(format!("ChatMessage::Assistant expected, but got {:?} instead", message)))).await.map(|_| ()
@@ -84,6 +111,16 @@ impl AgentCore {
         Ok(())
     }
 
+    /// Add the tokens of a brain call to the turn and the session, and notify
+    pub(crate) async fn record_usage(&mut self, usage: &CallUsage) {
+        let call = self.usage.record(usage);
+        let _ = self.emit_event(AgentEvent::UsageUpdated {
+            call,
+            turn: self.usage.turn,
+            session: self.usage.session,
+        }).await;
+    }
+
     // Helper method that emits error events before returning the error
     async fn handle_brain_error<T>(&mut self, result: Result<T, AgentError>) -> Result<T, AgentError> {
         match result {
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use krokit_llm::ChatMessage;
+use tokio::sync::{broadcast, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use crate::agent::{AgentCore, AgentError, AgentEvent, Brain, Compaction, InternalAgentEvent, InternalAgentState};
+use crate::runners::compacter::estimate_tokens;
+use crate::tools::FsOperationLog;
+
+impl AgentCore {
+    /// Launch a cancellable compaction task (on user request)
+    pub async fn spawn_compact(&mut self) {
+        let cancellation_token = CancellationToken::new();
+        let cancel_token_clone = cancellation_token.clone();
+        let trace = self.trace.clone();
+        let brain = self.brain.clone();
+        let fs_log = self.fs_log.clone();
+        let tx_clone = self.internal_tx.clone();
+        let public_event_tx = self.socket.tx_event.clone();
+
+        //////////////////////// TOKIO SPAWN
+        tokio::spawn(async move {
+            tokio::select! {
+                result = Self::compact_trace(brain, trace, fs_log, tx_clone.clone(), public_event_tx, true) => {
+                    let _ = tx_clone.send(InternalAgentEvent::CompactCompleted {
+                        result
+                    });
+                }
+                _ = cancel_token_clone.cancelled() => {
+                    // Compaction was cancelled, trace is left untouched
+                }
+            }
+        });
+        //////////////////////// TOKIO SPAWN
+
+        self.set_state(InternalAgentState::Processing {
+            task_name: "compact".to_string(),
+            tools_exec_at: Utc::now(),
+            cancellation_token
+        }).await;
+    }
+
+    /// Process a compaction task result
+    pub async fn process_compact(&mut self, result: Result<bool, AgentError>) -> Result<(), AgentError> {
+        match result {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = self.emit_event(AgentEvent::Error {
+                    error: "the conversation is too short to be compacted".to_string()
+                }).await;
+            }
+            Err(error) => {
+                let _ = self.emit_event(AgentEvent::Error {
+                    error: format!("compaction failed: {}", error)
+                }).await;
+            }
+        }
+        self.set_state(InternalAgentState::Paused).await;
+        Ok(())
+    }
+
+    /// Ask the brain to compact the trace, replace the trace and notify if it did
+    /// The tokens of the summary are reported to the agent through internal_tx
+    /// Returns true if the trace was compacted
+    pub(crate) async fn compact_trace(
+        brain: Arc<RwLock<Box<dyn Brain>>>,
+        trace: Arc<RwLock<Vec<ChatMessage>>>,
+        fs_log: Arc<FsOperationLog>,
+        internal_tx: broadcast::Sender<InternalAgentEvent>,
+        public_event_tx: Option<broadcast::Sender<AgentEvent>>,
+        force: bool,
+    ) -> Result<bool, AgentError> {
+        let snapshot = trace.read().await.clone();
+        let Some(Compaction { trace: compacted, usage }) = brain.write().await.compact(&snapshot, force).await? else {
+            return Ok(false);
+        };
+        if let Some(usage) = usage {
+            let _ = internal_tx.send(InternalAgentEvent::UsageReported { usage });
+        }
+
+        let tokens_before = estimate_tokens(&snapshot);
+        let tokens_after = estimate_tokens(&compacted);
+        info!(target: "agent::compact", tokens_before = tokens_before, tokens_after = tokens_after);
+        {
+            // keep anything that was appended while the brain was summarizing
+            let mut guard = trace.write().await;
+            let appended = guard.split_off(snapshot.len().min(guard.len()));
+            *guard = compacted;
+            guard.extend(appended);
+        }
+        // turns can no longer be rewound in the compacted trace
+        fs_log.checkpoints.forget_trace_marks().await;
+
+        if let Some(tx) = public_event_tx {
+            let _ = tx.send(AgentEvent::TraceCompacted {
+                tokens_before,
+                tokens_after
+            });
+        }
+        Ok(true)
+    }
+}
@@ -1,2 +1,3 @@
 pub mod brain;
+pub mod compact;
 pub mod tools;
@@ -5,11 +5,12 @@ use krokit_llm::{ChatMessage, ToolCall as LlmToolCall};
 use tokio::sync::{broadcast, RwLock};
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{info, warn};
 use serde_json::from_str;
 use uuid::Uuid;
-use crate::agent::{AgentCore, AgentEvent, ClaimManager, InternalAgentEvent, InternalAgentState, PermissionRequest, PermissionResponse};
-use crate::tools::{AnyTool, ToolCall, ToolCapability, ToolResult};
+use crate::agent::{AgentCore, AgentEvent, ClaimManager, InternalAgentEvent, InternalAgentState, PermissionRequest, PermissionResponse, ToolImages};
+use crate::agent::claims::Permission;
+use crate::tools::{AnyTool, ProgressSink, ToolCall, ToolCapability, ToolResult};
 use tracing::debug;
 
 impl AgentCore {
@@ -25,6 +26,7 @@ impl AgentCore {
         let available_tools = self.available_tools.clone();
         let claims = self.permissions.clone();
         let trace = self.trace.clone();
+        let tool_images = self.tool_images.clone();
 
         // Spawn a task to wait for all tool executions
         let mut join_handles = Vec::new();
@@ -39,6 +41,7 @@ impl AgentCore {
                 claims.clone(),
                 internal_tx.clone(),
                 trace.clone(),
+                tool_images.clone(),
             );
             join_handles.push(handle);
         }
@@ -83,6 +86,7 @@ impl AgentCore {
         claims: Arc<RwLock<ClaimManager>>,
         internal_tx: broadcast::Sender<InternalAgentEvent>,
         trace: Arc<RwLock<Vec<ChatMessage>>>,
+        tool_images: ToolImages,
     ) -> tokio::task::JoinHandle<bool> {
         tokio::spawn(async move {
             let tc_for_error = tc.clone();
@@ -150,9 +154,14 @@ impl AgentCore {
                         });
                     };
 
+                    // images cannot be part of the tool message, the brain adds them to its requests
+                    if let Some(image) = result.image() {
+                        tool_images.write().await.insert(call.tool_call_id.clone(), image);
+                    }
+
                     // Emit tool call finish event
                     let tool_was_denied = result.is_denied();
-                    info!(target: "agent::tool_completed", call = ?tc_for_error.function.name.clone(), result = ?result);
+                    info!(target: "agent::tool_completed", call = ?tc_for_error.function.name.clone(), result = ?result.without_image());
                     if let Some(tx) = public_event_tx.clone() {
                         let _ = tx.send(AgentEvent::ToolCallCompleted { 
                             duration: Utc::now() - start, 
@@ -161,7 +170,7 @@ impl AgentCore {
                         });   
                     }
 
-                    tool_was_denied                    
+                    tool_was_denied
                 }
             }
         })
@@ -183,7 +192,7 @@ impl AgentCore {
             || claims.read().await.is_permitted(&tool.name(), &call.parameters);
 
             // request permission if needed (|| is short-circuiting, so won't call if can_run is true)
-            let can_run = can_run || match Self::request_permission_if_needed(&call, &tool, &public_event_tx, &mut internal_rx, &cancel_token).await {
+            let can_run = can_run || match Self::request_permission_if_needed(&call, &tool, &claims, &public_event_tx, &mut internal_rx, &cancel_token).await {
                 Ok(permission_granted) => permission_granted,
                 Err(preview_error) => return preview_error, // Return preview error immediately
             };
@@ -192,9 +201,17 @@ impl AgentCore {
                 return ToolResult::denied()
             }
             
+            // forward the output of the tools that stream it
+            let progress: Option<ProgressSink> = public_event_tx.clone().map(|tx| {
+                let call_id = call.tool_call_id.clone();
+                Arc::new(move |chunk: String| {
+                    let _ = tx.send(AgentEvent::ToolCallProgress { call_id: call_id.clone(), chunk });
+                }) as ProgressSink
+            });
+
             // Execute tool with cancellation support
             tokio::select! {
-                result = tool.execute_json(call.parameters.clone(), Some(cancel_token.clone())) => result,
+                result = tool.execute_json_with_progress(call.parameters.clone(), Some(cancel_token.clone()), progress) => result,
                 _ = cancel_token.cancelled() => {
                     ToolResult::error("tool call was cancelled by the user".to_string())
                 }
@@ -203,10 +220,12 @@ impl AgentCore {
     }
 
     /// send a permission request (if necessary) and wait for the answer
+    /// "allow always" answers are stored as a permission so that similar calls are not asked again
     /// Returns Ok(true) if permission granted, Ok(false) if denied, Err(ToolResult) if preview failed
     async fn request_permission_if_needed(
         call: &ToolCall,
         tool: &Arc<dyn AnyTool>,
+        claims: &Arc<RwLock<ClaimManager>>,
         public_event_tx: &Option<broadcast::Sender<AgentEvent>>,
         internal_rx: &mut broadcast::Receiver<InternalAgentEvent>,
         cancel_token: &CancellationToken,
@@ -244,6 +263,12 @@ impl AgentCore {
                 recv_result = internal_rx.recv() => {
                     match recv_result {
                         Ok(InternalAgentEvent::PermissionResponseReceived { request_id, response }) if request_id == req_id => {
+                            if response == PermissionResponse::AllowAlways {
+                                let permission = Permission::allow_always(&call.tool_name, &call.parameters);
+                                if let Err(e) = claims.write().await.grant_always(permission) {
+                                    warn!(target: "agent::permission", error = %e, "failed to save permission");
+                                }
+                            }
                             return Ok(matches!(response, PermissionResponse::Allow | PermissionResponse::AllowAlways));
                         }
                         Ok(_) => continue,
@@ -1,19 +1,23 @@
 use std::sync::Arc;
 use std::boxed::Box;
 use krokit_llm::{ChatMessage, ChatMessageContent, ToolCallMethod};
+use krokit_llm::image::user_content;
 use tokio::sync::{mpsc, broadcast, RwLock, oneshot};
 use serde::{Serialize, Deserialize};
 use async_trait::async_trait;
-use crate::tools::AnyTool;
+use crate::tools::{AnyTool, FsOperationLog};
+use crate::tools::fs::checkpoint::display_path;
 use crate::agent::ClaimManager;
+use crate::session::SessionRecorder;
+use crate::usage::{TokenUsage, UsageTracker};
 
 // Helper functions to make the main loop more readable
 
-use crate::agent::{Brain, InternalAgentEvent};
+use crate::agent::{Brain, InternalAgentEvent, ToolImages};
 use crate::agent::AgentError;
 use crate::agent::{AgentRequest, AgentEvent};
 use crate::agent::InternalAgentState;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use super::protocol::{AgentController, SentCommand};
 use super::{AgentResponse, AgentEventHandler};
@@ -50,6 +54,8 @@ pub struct AgentResult {
     pub success: bool,
     pub message: String,
     pub trace:   Vec<ChatMessage>,
+    #[serde(default)]
+    pub usage:   TokenUsage,
 }
 
 /// Core agent implementation that orchestrates any Thinker implementation
@@ -65,10 +71,20 @@ pub struct AgentCore {
 
     /// agent state (manipulated by main looper + brain/tool coroutines)
     pub trace:           Arc<RwLock<Vec<ChatMessage>>>,
+    pub tool_images:     ToolImages,
     pub available_tools: Vec<Arc<dyn AnyTool>>,
     pub permissions:     Arc<RwLock<ClaimManager>>,
     pub state:           InternalAgentState,
 
+    /// session persistence, a snapshot is written at the end of every turn
+    pub session:         Option<SessionRecorder>,
+
+    /// tokens consumed by the brain
+    pub usage:           UsageTracker,
+
+    /// file operations of the builtin tools, holds the per turn file checkpoints
+    pub fs_log:          Arc<FsOperationLog>,
+
     /// internal event
     pub internal_tx: broadcast::Sender<InternalAgentEvent>,   // event may be produced from many part of the agent
     pub internal_rx: broadcast::Receiver<InternalAgentEvent>, // events are mostly consumed by the main event loop, but also in spawn tool to monitor permissions
@@ -101,9 +117,13 @@ impl AgentCore {
             brain: Arc::new(RwLock::new(brain)),
             method: ToolCallMethod::FunctionCall,
             trace: Arc::new(RwLock::new(trace)),
+            tool_images: ToolImages::default(),
             available_tools: available_tools.into_iter().map(|t| Arc::from(t) as Arc<dyn AnyTool>).collect(),
             permissions: Arc::new(RwLock::new(permissions)),
             state: InternalAgentState::Starting,
+            session: None,
+            usage: UsageTracker::default(),
+            fs_log: Arc::new(FsOperationLog::new()),
             internal_tx,
             internal_rx,
         }
@@ -293,6 +313,7 @@ impl AgentCore {
                         success: success.clone(),
                         message: "Agent completed".to_string(),
                         trace: guard.clone(),
+                        usage: self.usage.session,
                     });
                 },
                 InternalAgentState::Failed { error } => {
@@ -396,7 +417,7 @@ impl AgentCore {
                 }
                 Ok(AgentResponse::Method { method: self.method })
             }
-            AgentRequest::SendUserInput{ input } => {
+            AgentRequest::SendUserInput{ input, images } => {
                 self.handle_event(InternalAgentEvent::CancelTask).await
                 .and({
                     // Emit UserInput event
@@ -404,10 +425,13 @@ impl AgentCore {
                         input: input.clone() 
                     }).await;
                     
+                    let trace_len = self.trace.read().await.len();
+                    self.fs_log.checkpoints.begin_turn(trace_len).await;
                     self.trace.write().await.push(ChatMessage::User { 
-                        content: ChatMessageContent::Text(input), 
+                        content: user_content(input, images), 
                         name: None 
                     });
+                    self.usage.new_turn();
                     
                     self.set_state(InternalAgentState::Running).await;
                     Ok(AgentResponse::Ack)
@@ -429,6 +453,26 @@ impl AgentCore {
                 }).map_err(|_| AgentError::SessionClosed)?;
                 Ok(AgentResponse::Ack)
             }
+            AgentRequest::Compact => {
+                if matches!(self.state, InternalAgentState::Paused) {
+                    self.spawn_compact().await;
+                    Ok(AgentResponse::Ack)
+                } else {
+                    Err(AgentError::InvalidState("cannot compact while the agent is busy".to_string()))
+                }
+            }
+            AgentRequest::Undo { turns } => {
+                if matches!(self.state, InternalAgentState::Paused) {
+                    self.undo(turns).await
+                } else {
+                    Err(AgentError::InvalidState("cannot undo while the agent is busy".to_string()))
+                }
+            }
+            AgentRequest::Diff => {
+                self.fs_log.checkpoints.diff_last().await
+                    .map(|diff| AgentResponse::Diff { diff })
+                    .map_err(|e| AgentError::ExecutionError(e.to_string()))
+            }
             AgentRequest::WaitTurn => {
                 self.handle_wait_turn(backchannel).await;
                 return Ok(()); // We handle the response in the spawned task
@@ -480,6 +524,51 @@ impl AgentCore {
         }).await;
         
         self.state = to_state;
+
+        if matches!(self.state, InternalAgentState::Paused | InternalAgentState::Completed { .. } | InternalAgentState::Failed { .. }) {
+            self.checkpoint().await;
+        }
+    }
+
+    /// Revert the file changes of the last turns and drop these turns from the trace.
+    /// When the trace was compacted since, the brain is told about the reverted files instead.
+    async fn undo(&mut self, turns: usize) -> Result<AgentResponse, AgentError> {
+        let checkpoints = &self.fs_log.checkpoints;
+        let turn = checkpoints.undo_turn(turns).await
+            .map_err(|e| AgentError::ExecutionError(e.to_string()))?;
+        let trace_len = self.trace.read().await.len();
+        let (files, mark) = checkpoints.rewind(turn, trace_len).await
+            .map_err(|e| AgentError::ExecutionError(e.to_string()))?;
+
+        let mut trace = self.trace.write().await;
+        match mark {
+            Some(len) => trace.truncate(len),
+            None => {
+                let list = files.iter().map(|f| display_path(f)).collect::<Vec<_>>().join(", ");
+                trace.push(ChatMessage::User {
+                    content: ChatMessageContent::Text(format!(
+                        "[The user reverted your file changes, these files are back to their previous content: {}. Read them again before editing them.]",
+                        list
+                    )),
+                    name: None
+                });
+            }
+        }
+        drop(trace);
+        self.checkpoint().await;
+        Ok(AgentResponse::FilesRestored { files })
+    }
+
+    /// Write a snapshot of the session if persistence is enabled
+    pub async fn checkpoint(&mut self) {
+        let Some(recorder) = self.session.as_mut() else {
+            return;
+        };
+        let trace = self.trace.read().await.clone();
+        let permissions = self.permissions.read().await.clone();
+        if let Err(e) = recorder.checkpoint(&trace, &permissions, self.usage.session).await {
+            warn!(target: "agent::session", error = %e, "failed to save session");
+        }
     }
     
     /// Emit an event to the controller
@@ -1,15 +1,22 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use async_trait::async_trait;
-use krokit_llm::{ChatMessage, ToolCallMethod};
+use krokit_llm::{ChatMessage, ChatMessageContentPart, DeltaSink, ToolCallMethod};
 use tokio::sync::RwLock;
 
 use crate::tools::types::AnyToolBox;
+use crate::usage::CallUsage;
 use super::error::AgentError;
 
 
+/// Images returned by the tools, keyed by tool call id. They are not part of the trace,
+/// the brain adds them to its requests.
+pub type ToolImages = Arc<RwLock<HashMap<String, ChatMessageContentPart>>>;
+
 /// ThinkerContext is the agent internal state
 pub struct ThinkerContext {
     pub trace:           Arc<RwLock<Vec<ChatMessage>>>,
+    pub tool_images:     ToolImages,
     pub available_tools: AnyToolBox,
     pub method:          ToolCallMethod
 }
@@ -27,42 +34,72 @@ pub enum ThinkerFlowControl {
 #[derive(Debug, Clone)]
 pub struct ThinkerDecision {
     pub message: ChatMessage,
-    pub flow:    ThinkerFlowControl
+    pub flow:    ThinkerFlowControl,
+    pub usage:   Option<CallUsage>
 }
 
 impl ThinkerDecision {
     pub fn new(message: ChatMessage) -> Self {
         ThinkerDecision{
             message,
-            flow: ThinkerFlowControl::AgentPause
+            flow: ThinkerFlowControl::AgentPause,
+            usage: None
         }
     }
 
     pub fn agent_continue(message: ChatMessage) -> Self {
         ThinkerDecision{
             message,
-            flow: ThinkerFlowControl::AgentContinue
+            flow: ThinkerFlowControl::AgentContinue,
+            usage: None
         }
     }
 
     pub fn agent_pause(message: ChatMessage) -> Self {
         ThinkerDecision{
             message,
-            flow: ThinkerFlowControl::AgentPause
+            flow: ThinkerFlowControl::AgentPause,
+            usage: None
         }
     }
 
+    /// tokens consumed to produce this decision
+    pub fn with_usage(mut self, usage: Option<CallUsage>) -> Self {
+        self.usage = usage;
+        self
+    }
+
     pub fn unwrap(self) -> ChatMessage {
         self.message
     }
 }
 
+/// Trace shrunk by the brain, with the tokens it took to summarize it
+#[derive(Debug, Clone)]
+pub struct Compaction {
+    pub trace: Vec<ChatMessage>,
+    pub usage: Option<CallUsage>
+}
+
 /// Core thinking interface - pure decision making
 #[async_trait]
 pub trait Brain: Send + Sync {
     /// This method is called at every step of the agent to decide next step
     /// note that if the message contains toolcall, it will always continue
     async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError>;
+
+    /// Streaming variant of next_step, partial content is pushed to on_delta as it arrives
+    /// brains that do not support streaming simply fall back to next_step
+    async fn next_step_stream(&mut self, context: ThinkerContext, _on_delta: DeltaSink) -> Result<ThinkerDecision, AgentError> {
+        self.next_step(context).await
+    }
+
+    /// This method is called before every step to give the brain a chance to shrink the trace
+    /// if force is true, the brain should compact regardless of its own threshold
+    /// returns the compacted trace, or None if nothing was done
+    async fn compact(&mut self, _trace: &[ChatMessage], _force: bool) -> Result<Option<Compaction>, AgentError> {
+        Ok(None)
+    }
 }
 
 
@@ -2,15 +2,24 @@ use krokit_llm::{ChatMessage, LlmClient};
 use uuid::Uuid;
 use std::sync::Arc;
 
-use crate::tools::mcp::mcp_oauth::signin_oauth;
-use crate::tools::{create_mcp_client, get_mcp_tools, AnyTool, BashTool, EditTool, FetchTool, FindTool, FsOperationLog, LsTool, McpConfig, MultiEditTool, ReadTool, TodoReadTool, TodoStorage, TodoWriteTool, WriteTool};
+use crate::tools::mcp::mcp_oauth::signin_oauth_with;
+use crate::tools::{create_mcp_client, get_mcp_tools, AnyTool, BashTool, EditTool, FetchTool, FindTool, FsOperationLog, LsTool, McpConfig, MultiEditTool, PatchTool, ReadTool, SemanticSearchTool, SymbolsTool, TodoReadTool, TodoStorage, TodoWriteTool, WriteTool};
 use crate::config::agent::AgentConfig;
+use crate::config::config::KrokitConfig;
 use crate::runners::coder::CoderBrain;
+use crate::session::{Session, SessionRecorder, SessionStore};
+use crate::usage::{PricingTable, UsageTracker};
 use super::Brain;
 use super::AgentCore;
 use super::claims::ClaimManager;
 use super::AgentError;
 
+/// Names of all the builtin tools
+pub const BUILTIN_TOOLS: &[&str] = &["bash", "edit", "multiedit", "fetch", "find", "ls", "patch", "read", "symbols", "todo_read", "todo_write", "write"];
+
+/// Builtin tools an agent only gets when it names them, "*" does not include them
+pub const OPTIONAL_TOOLS: &[&str] = &["semantic_search"];
+
 /// Builder for AgentCore
 pub struct AgentBuilder {
     pub session_id: String,
@@ -19,6 +28,12 @@ pub struct AgentBuilder {
     pub trace: Vec<ChatMessage>,
     pub available_tools: Vec<Box<dyn AnyTool>>,
     pub permissions: ClaimManager,
+    pub todo_storage: Arc<TodoStorage>,
+    pub fs_log: Arc<FsOperationLog>,
+    pub agent_name: Option<String>,
+    pub session_store: Option<SessionStore>,
+    pub resumed_session: Option<Session>,
+    pub pricing: PricingTable,
 }
 
 impl AgentBuilder {
@@ -30,6 +45,12 @@ impl AgentBuilder {
             trace: vec![],
             available_tools: vec![],
             permissions: ClaimManager::new(),
+            todo_storage: Arc::new(TodoStorage::new()),
+            fs_log: Arc::new(FsOperationLog::new()),
+            agent_name: None,
+            session_store: None,
+            resumed_session: None,
+            pricing: PricingTable::new(),
         }
     }
 }
@@ -71,26 +92,154 @@ impl AgentBuilder {
         self
     }
 
+    /// Add the permissions saved in the project and global permission files,
+    /// "allow always" answers are then written to the project file
+    pub fn load_permissions(mut self) -> Self {
+        match ClaimManager::from_default_files() {
+            Ok(mut saved) => {
+                if self.permissions.is_sudo() {
+                    saved.sudo();
+                }
+                saved.merge(self.permissions.get_all_permissions());
+                self.permissions = saved;
+            }
+            Err(e) => {
+                eprintln!("\x1b[2m░ could not load saved permissions: {}\x1b[0m", e);
+            }
+        }
+        self
+    }
+
+    /// Todo list shared with the todo tools, saved along with the session
+    pub fn todo_storage(mut self, todo_storage: Arc<TodoStorage>) -> Self {
+        self.todo_storage = todo_storage;
+        self
+    }
+
+    /// Operation log shared with the fs tools, saved along with the session
+    pub fn fs_log(mut self, fs_log: Arc<FsOperationLog>) -> Self {
+        self.fs_log = fs_log;
+        self
+    }
+
+    /// Name of the agent config this agent was built from
+    pub fn agent_name(mut self, agent_name: &str) -> Self {
+        self.agent_name = Some(agent_name.to_string());
+        self
+    }
+
+    /// Prices used to compute the cost of the brain calls
+    pub fn pricing(mut self, pricing: PricingTable) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Save the session in the store at the end of every turn
+    pub fn persist(mut self, store: SessionStore) -> Self {
+        self.session_store = Some(store);
+        self
+    }
+
     /// Build the AgentCore with required runtime fields
     pub fn build(mut self) -> AgentCore {        
         if let Some(goal) = self.goal {
             self.trace.push(ChatMessage::User { content: krokit_llm::ChatMessageContent::Text(goal.clone()), name: None });
         }
         
-        AgentCore::new(
+        let mut agent = AgentCore::new(
             self.session_id.clone(),
             self.brain,
             self.trace,
             self.available_tools,
             self.permissions
-        )
+        );
+
+        agent.usage = UsageTracker::new(self.pricing);
+        if let Some(session) = &self.resumed_session {
+            agent.usage.restore(session.usage);
+        }
+
+        agent.fs_log = self.fs_log.clone();
+
+        if let Some(store) = self.session_store {
+            let session = self.resumed_session
+                .unwrap_or_else(|| Session::new(&self.session_id, self.agent_name.clone()));
+            self.fs_log.checkpoints.set_blob_dir(store.blobs_dir(&session.id));
+            agent.session = Some(SessionRecorder::new(store, session, self.todo_storage, self.fs_log));
+        }
+        agent
+    }
+
+    /// Create an AgentBuilder that resumes a stored session, the latest one if no id is given.
+    /// The agent is rebuilt from the same agent config (or the default coder) and gets back
+    /// its trace, todo list, file operation log and granted permissions.
+    pub async fn from_session(id: Option<&str>) -> Result<Self, AgentError> {
+        let store = SessionStore::new()
+            .map_err(|e| AgentError::ConfigurationError(e.to_string()))?;
+        let session = store.resolve(id)
+            .map_err(|e| AgentError::ConfigurationError(e.to_string()))?;
+
+        let builder = if let Some(agent_name) = &session.agent_name {
+            let config = AgentConfig::load(agent_name)
+                .map_err(|e| AgentError::ConfigurationError(format!("Failed to load agent '{}': {}", agent_name, e)))?;
+            Self::from_config(config).await?
+        } else {
+            let (llm, model) = KrokitConfig::get_llm().await
+                .map_err(|e| AgentError::LlmError(e.to_string()))?;
+            let builder = Self::new(Box::new(CoderBrain::new(Arc::new(llm), model)));
+            let tools = Self::default_tools(&builder.todo_storage, &builder.fs_log);
+            builder.tools(tools)
+        };
+
+        builder.todo_storage.replace_all(session.todos.clone()).await;
+        builder.fs_log.restore(session.fs_operations.clone()).await;
+        builder.fs_log.checkpoints.restore(session.checkpoints.clone()).await;
+
+        let mut builder = builder
+            .id(&session.id)
+            .with_traces(session.trace.clone())
+            .pricing(PricingTable::from_config())
+            .load_permissions();
+        builder.permissions.merge(&session.permissions);
+        builder.resumed_session = Some(session);
+        Ok(builder.persist(store))
+    }
+
+    /// All builtin tools, sharing the given todo list and operation log
+    pub fn default_tools(todo_storage: &Arc<TodoStorage>, fs_log: &Arc<FsOperationLog>) -> Vec<Box<dyn AnyTool>> {
+        BUILTIN_TOOLS.iter()
+            .filter_map(|name| Self::builtin_tool(name, todo_storage, fs_log))
+            .collect()
+    }
+
+    /// Create a builtin tool by name
+    pub fn builtin_tool(name: &str, todo_storage: &Arc<TodoStorage>, fs_log: &Arc<FsOperationLog>) -> Option<Box<dyn AnyTool>> {
+        let tool: Box<dyn AnyTool> = match name {
+            "bash" => Box::new(BashTool::new()),
+            "edit" => Box::new(EditTool::new(fs_log.clone())),
+            "multiedit" => Box::new(MultiEditTool::new(fs_log.clone())),
+            "patch" => Box::new(PatchTool::new(fs_log.clone())),
+            "fetch" => Box::new(FetchTool::from_config()),
+            "find" => Box::new(FindTool::new()),
+            "ls" => Box::new(LsTool::new()),
+            "read" => Box::new(ReadTool::new(fs_log.clone())),
+            "semantic_search" => Box::new(SemanticSearchTool::from_config()),
+            "symbols" => Box::new(SymbolsTool::new()),
+            "todo_read" => Box::new(TodoReadTool::new(todo_storage.clone())),
+            "todo_write" => Box::new(TodoWriteTool::new(todo_storage.clone())),
+            "write" => Box::new(WriteTool::new(fs_log.clone())),
+            _ => return None,
+        };
+        Some(tool)
     }
 
     /// Create an AgentBuilder from an AgentConfig
     pub async fn from_config(mut config: AgentConfig) -> Result<Self, AgentError> {
         // Create LLM client from provider config using the utility method
+        let http = config.llm_provider.http.clone()
+            .or(KrokitConfig::load().map(|krokit| krokit.http).unwrap_or_default());
         let llm_client = Arc::new(
-            LlmClient::create_provider(&config.llm_provider.provider, &config.llm_provider.env_vars)
+            LlmClient::create_provider_with(&config.llm_provider.provider, &config.llm_provider.env_vars, &http)
                 .map_err(|e| AgentError::LlmError(e.to_string()))?
         );
         
@@ -102,8 +251,10 @@ impl AgentBuilder {
             config.temperature,
         ));
 
-        // Create tools
-        let tools = Self::create_tools_from_config(&mut config).await?;
+        // Create tools, sharing the todo list and operation log with the builder
+        let todo_storage = Arc::new(TodoStorage::new());
+        let fs_log = Arc::new(FsOperationLog::new());
+        let tools = Self::create_tools_from_config(&mut config, &todo_storage, &fs_log).await?;
         
         // Display available tools by category
         let mut tool_groups: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
@@ -127,23 +278,19 @@ impl AgentBuilder {
 
         Ok(Self::new(brain)
             .tools(tools)
-            .id(&format!("agent-{}", config.name)))
+            .todo_storage(todo_storage)
+            .fs_log(fs_log)
+            .agent_name(&config.name))
     }
 
     /// Create tools from config
-    async fn create_tools_from_config(config: &mut AgentConfig) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
+    async fn create_tools_from_config(config: &mut AgentConfig, todo_storage: &Arc<TodoStorage>, fs_log: &Arc<FsOperationLog>) -> Result<Vec<Box<dyn AnyTool>>, AgentError> {
         let mut tools: Vec<Box<dyn AnyTool>> = Vec::new();
 
-        // Create shared storage for todo tools
-        let todo_storage = Arc::new(TodoStorage::new());
-        
-        // Create shared operation log for file system tools
-        let fs_log = Arc::new(FsOperationLog::new());
-
         // Add builtin tools based on config
         let builtin_tools_to_add = if config.tools.builtin.contains(&"*".to_string()) {
             // Add all builtin tools
-            vec!["bash", "edit", "multiedit", "fetch", "find", "ls", "read", "todo_read", "todo_write", "write"]
+            BUILTIN_TOOLS.to_vec()
         } else {
             // Add only specified tools
             config.tools.builtin.iter().map(|s| s.as_str()).collect()
@@ -155,18 +302,17 @@ impl AgentBuilder {
                 continue;
             }
             
-            match tool_name {
-                "bash" => tools.push(Box::new(BashTool::new())),
-                "edit" => tools.push(Box::new(EditTool::new(fs_log.clone()))),
-                "multiedit" => tools.push(Box::new(MultiEditTool::new(fs_log.clone()))),
-                "fetch" => tools.push(Box::new(FetchTool::new())),
-                "find" => tools.push(Box::new(FindTool::new())),
-                "ls" => tools.push(Box::new(LsTool::new())),
-                "read" => tools.push(Box::new(ReadTool::new(fs_log.clone()))),
-                "todo_read" => tools.push(Box::new(TodoReadTool::new(todo_storage.clone()))),
-                "todo_write" => tools.push(Box::new(TodoWriteTool::new(todo_storage.clone()))),
-                "write" => tools.push(Box::new(WriteTool::new(fs_log.clone()))),
-                _ => return Err(AgentError::ConfigurationError(format!("Unknown builtin tool: {}", tool_name))),
+            if let ("bash", Some(sandbox)) = (tool_name, &config.tools.sandbox) {
+                let backend = sandbox.backend()
+                    .map_err(|e| AgentError::ConfigurationError(format!("Failed to set up the bash sandbox: {}", e)))?;
+                eprintln!("\x1b[2m░ bash runs in a {} sandbox\x1b[0m", backend.name());
+                tools.push(Box::new(BashTool::with_backend(backend)));
+                continue;
+            }
+
+            match Self::builtin_tool(tool_name, todo_storage, fs_log) {
+                Some(tool) => tools.push(tool),
+                None => return Err(AgentError::ConfigurationError(format!("Unknown builtin tool: {}", tool_name))),
             }
         }
 
@@ -226,11 +372,12 @@ impl AgentBuilder {
         let mut config_changed = false;
         
         // Only handle HTTP configs that might need OAuth
-        if let McpConfig::Http { url, bearer_token } = mcp_config {
+        if let McpConfig::Http { url, bearer_token, http } = mcp_config {
             // Test connection with current config
             let test_config = McpConfig::Http { 
                 url: url.clone(), 
-                bearer_token: bearer_token.clone() 
+                bearer_token: bearer_token.clone(),
+                http: http.clone(),
             };
             let mut test_client = create_mcp_client(test_config);
             match test_client.connect().await {
@@ -244,7 +391,7 @@ impl AgentBuilder {
                 Err(_) => {
                     eprintln!("\x1b[2m░ MCP '{}' connection failed, starting OAuth flow...\x1b[0m", mcp_name);
                     let url_clone = url.clone();
-                    match signin_oauth(&url_clone).await {
+                    match signin_oauth_with(&url_clone, http).await {
                         Ok(token) => {
                             eprintln!("\x1b[2m░ MCP '{}' connected (OAuth successful)\x1b[0m", mcp_name);
                             *bearer_token = Some(token);
@@ -1,7 +1,8 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 use regex::Regex;
+use sha2::{Digest, Sha256};
 
 /// Match strategy for permission checking
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -29,6 +30,9 @@ pub struct Permission {
     pub granted_at: DateTime<Utc>,
     pub session_only: bool,
     pub description: Option<String>,
+    /// loaded from the global permission file, never written to the project file
+    #[serde(skip)]
+    pub global: bool,
 }
 
 impl Permission {
@@ -45,9 +49,69 @@ impl Permission {
             granted_at: Utc::now(),
             session_only,
             description: None,
+            global: false,
         }
     }
 
+    /// Permission granted when the user answers "allow always" to a tool call.
+    /// The match is widened to what the user most likely meant: the command
+    /// prefix for bash, the file path for file modifications, the host for fetch.
+    /// Bash commands without a safe prefix (interpreters, wrappers, programs that
+    /// are not known to take subcommands) and any other tool get an exact match.
+    pub fn allow_always(tool_name: &str, call_params: &serde_json::Value) -> Self {
+        let param = |key: &str| call_params.get(key).and_then(|v| v.as_str());
+
+        let (strategy, parameters, description) = match tool_name {
+            "bash" if param("command").is_some_and(|c| !c.trim().is_empty()) => {
+                let command = param("command").unwrap();
+                match command_prefix(command) {
+                    Some(prefix) => (
+                        MatchStrategy::Glob,
+                        serde_json::json!({ "command": format!(r"^{}( [^;&|`$<>\r\n]*)?$", regex::escape(&prefix)) }),
+                        format!("run `{}` commands", prefix),
+                    ),
+                    None => (
+                        MatchStrategy::Glob,
+                        serde_json::json!({ "command": format!("^{}$", regex::escape(command)) }),
+                        format!("run `{}`", command),
+                    ),
+                }
+            }
+            "edit" | "write" if param("path").is_some() => {
+                let path = param("path").unwrap();
+                (
+                    MatchStrategy::Glob,
+                    serde_json::json!({ "path": format!("^{}$", regex::escape(path)) }),
+                    format!("modify {}", path),
+                )
+            }
+            "multiedit" if param("file_path").is_some() => {
+                let path = param("file_path").unwrap();
+                (
+                    MatchStrategy::Glob,
+                    serde_json::json!({ "file_path": format!("^{}$", regex::escape(path)) }),
+                    format!("modify {}", path),
+                )
+            }
+            "fetch" if param("url").and_then(url_origin).is_some() => {
+                let origin = param("url").and_then(url_origin).unwrap();
+                (
+                    MatchStrategy::Glob,
+                    serde_json::json!({ "url": format!("^{}(/.*)?$", regex::escape(&origin)) }),
+                    format!("fetch from {}", origin),
+                )
+            }
+            _ => (
+                MatchStrategy::Exact,
+                call_params.clone(),
+                format!("call {} with the same parameters", tool_name),
+            ),
+        };
+
+        Self::new(tool_name.to_string(), strategy, parameters, false)
+            .with_description(description)
+    }
+
     pub fn with_description(mut self, description: String) -> Self {
         self.description = Some(description);
         self
@@ -121,11 +185,66 @@ impl Permission {
     }
 }
 
+/// Programs running the code or the commands given in their arguments, allowing one of
+/// their invocations never allows the others
+const COMMAND_RUNNERS: &[&str] = &[
+    "sudo", "doas", "su", "env", "exec", "eval", "source", ".", "command", "nohup", "time", "timeout",
+    "nice", "xargs", "watch", "ssh", "bash", "sh", "zsh", "dash", "ksh", "fish", "csh", "tcsh",
+    "python", "node", "deno", "bun", "ruby", "perl", "php", "lua", "pwsh", "powershell", "osascript",
+    "awk", "gawk", "npx",
+];
+
+/// Programs that only read, all their invocations are allowed at once
+const READ_ONLY_PROGRAMS: &[&str] = &["ls", "pwd", "cat", "head", "tail", "wc", "tree", "du", "df", "which", "stat", "grep"];
+
+/// Programs whose first argument is a subcommand, "cargo test" is allowed whatever its arguments
+const SUBCOMMAND_PROGRAMS: &[&str] = &[
+    "cargo", "rustup", "git", "gh", "npm", "pnpm", "yarn", "go", "make", "just", "docker", "kubectl",
+    "helm", "terraform", "pip", "poetry", "uv", "dotnet", "mvn", "gradle", "bundle", "rake", "mix",
+];
+
+/// Prefix of a shell command that can be allowed with any arguments, e.g. "cargo test" for
+/// "cargo test --all" or "ls" for "ls -la src". None when there is no safe prefix: the
+/// program runs other commands or code, its first argument is not a subcommand (or it has
+/// none), or the program is not known.
+fn command_prefix(command: &str) -> Option<String> {
+    let mut words = command.split_whitespace();
+    let program = words.next()?;
+    // "python3.12" or "/usr/bin/python3" are python
+    let name = program.rsplit('/').next().unwrap_or(program)
+        .trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if program.contains('=') || COMMAND_RUNNERS.contains(&name) {
+        return None;
+    }
+    if READ_ONLY_PROGRAMS.contains(&name) {
+        return Some(program.to_string());
+    }
+    if !SUBCOMMAND_PROGRAMS.contains(&name) {
+        return None;
+    }
+    match words.next() {
+        // the bare program says nothing of the subcommands meant, "git" must not allow "git push"
+        None => None,
+        Some(sub) if sub.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') && !sub.starts_with('-') => {
+            Some(format!("{} {}", program, sub))
+        }
+        _ => None,
+    }
+}
+
+/// Scheme and host of an url, e.g. "https://docs.rs" for "https://docs.rs/tokio"
+fn url_origin(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    (!host.is_empty()).then(|| format!("{}://{}", scheme, host))
+}
+
 /// Permission Manager for storing and retrieving permissions
 #[derive(Debug, Clone)]
 pub struct ClaimManager {
     permissions: Vec<Permission>,
     config_file: Option<PathBuf>,
+    global_file: Option<PathBuf>,
     sudo_mode: bool,
 }
 
@@ -136,6 +255,7 @@ impl ClaimManager {
         Self {
             permissions: Vec::new(),
             config_file: None,
+            global_file: None,
             sudo_mode: false,
         }
     }
@@ -145,15 +265,57 @@ impl ClaimManager {
         Self {
             permissions: Vec::new(),
             config_file: Some(path),
+            global_file: None,
             sudo_mode: false,
         }
     }
 
+    /// Create a permission manager backed by the permission file of the current project
+    /// and loads both the project and the global (~/.config/krokit/permissions.json) permissions
+    pub fn from_default_files() -> Result<Self, PermissionError> {
+        let mut manager = Self::with_config_file(Self::project_permissions_path()?);
+        manager.load_from_file()?;
+        manager.load_global_from_file(Self::global_permissions_path()?)?;
+        Ok(manager)
+    }
+
+    /// Path of the permission file of the current project
+    pub fn project_permissions_path() -> Result<PathBuf, PermissionError> {
+        Self::project_permissions_path_for(&std::env::current_dir()?)
+    }
+
+    /// Path of the permission file of a project, in the config dir of the user
+    /// (~/.config/krokit/projects/<hash of the project path>/permissions.json). It is not
+    /// kept in the project: a cloned repository, or a command writing in the project,
+    /// could otherwise grant itself permissions.
+    pub fn project_permissions_path_for(project: &Path) -> Result<PathBuf, PermissionError> {
+        let project = project.canonicalize()?;
+        let hash = format!("{:x}", Sha256::digest(project.to_string_lossy().as_bytes()));
+        Ok(Self::config_dir()?.join("projects").join(&hash[..16]).join("permissions.json"))
+    }
+
+    /// Path of the permission file shared by all projects
+    pub fn global_permissions_path() -> Result<PathBuf, PermissionError> {
+        Ok(Self::config_dir()?.join("permissions.json"))
+    }
+
+    fn config_dir() -> Result<PathBuf, PermissionError> {
+        let config_dir = std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| {
+                dirs::home_dir()
+                    .map(|home| home.join(".config"))
+                    .ok_or(PermissionError::NoConfigFile)
+            })?;
+        Ok(config_dir.join("krokit"))
+    }
+
     /// Create a permission manager with sudo mode enabled
     pub fn with_sudo() -> Self {
         Self {
             permissions: Vec::new(),
             config_file: None,
+            global_file: None,
             sudo_mode: true,
         }
     }
@@ -163,6 +325,7 @@ impl ClaimManager {
         Self {
             permissions: Vec::new(),
             config_file: Some(path),
+            global_file: None,
             sudo_mode: true,
         }
     }
@@ -205,6 +368,23 @@ impl ClaimManager {
             .collect()
     }
     
+    /// Grant a permission for good. It is written to the config file if there is
+    /// one, otherwise it only lasts for the session.
+    pub fn grant_always(&mut self, mut permission: Permission) -> Result<(), PermissionError> {
+        if self.config_file.is_none() {
+            permission.session_only = true;
+            self.permissions.push(permission);
+            return Ok(());
+        }
+        self.permissions.push(permission);
+        self.save_to_file()
+    }
+
+    /// Remove the permission at the given index (as returned by get_all_permissions)
+    pub fn remove_permission(&mut self, index: usize) -> Option<Permission> {
+        (index < self.permissions.len()).then(|| self.permissions.remove(index))
+    }
+
     /// Remove session-only permissions (called when session ends)
     pub fn clear_session_permissions(&mut self) {
         self.permissions.retain(|perm| !perm.session_only);
@@ -231,15 +411,20 @@ impl ClaimManager {
     }
     
     /// Save permissions to JSON file (if config file is set)
+    /// Global permissions are left out, they belong to the global file.
     pub fn save_to_file(&self) -> Result<(), PermissionError> {
         if let Some(path) = &self.config_file {
             let persistent_permissions: Vec<&Permission> = self.permissions.iter()
-                .filter(|perm| !perm.session_only)
+                .filter(|perm| !perm.session_only && !perm.global)
                 .collect();
             
             let json_str = serde_json::to_string_pretty(&persistent_permissions)
                 .map_err(PermissionError::Serialization)?;
             
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            
             std::fs::write(path, json_str)
                 .map_err(PermissionError::FileAccess)?;
                 
@@ -276,6 +461,40 @@ impl ClaimManager {
     }
 }
 
+impl ClaimManager {
+    /// Load the permissions of the global file in addition to the ones of the config file.
+    /// They are checked like any other permission but never saved by save_to_file.
+    pub fn load_global_from_file(&mut self, path: PathBuf) -> Result<(), PermissionError> {
+        if path.exists() {
+            let json_str = std::fs::read_to_string(&path)?;
+            let loaded_permissions: Vec<Permission> = serde_json::from_str(&json_str)?;
+            for mut permission in loaded_permissions {
+                if !permission.session_only {
+                    permission.global = true;
+                    self.permissions.push(permission);
+                }
+            }
+        }
+        self.global_file = Some(path);
+        Ok(())
+    }
+
+    /// Add the permissions of another manager that are not already present
+    /// (used to merge the permissions of a resumed session)
+    pub fn merge(&mut self, permissions: &[Permission]) {
+        for permission in permissions {
+            let exists = self.permissions.iter().any(|p| {
+                p.tool_name == permission.tool_name
+                && p.match_strategy == permission.match_strategy
+                && p.parameters == permission.parameters
+            });
+            if !exists {
+                self.permissions.push(permission.clone());
+            }
+        }
+    }
+}
+
 impl Default for ClaimManager {
     fn default() -> Self {
         Self::new()
@@ -536,4 +755,128 @@ mod tests {
         assert_eq!(manager.len(), cloned.len());
         assert_eq!(manager.config_file, cloned.config_file);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_allow_always_bash_matches_command_prefix() {
+        let permission = Permission::allow_always("bash", &serde_json::json!({"command": "cargo test --all"}));
+        assert_eq!(permission.match_strategy, MatchStrategy::Glob);
+        assert!(!permission.session_only);
+
+        assert!(permission.matches("bash", &serde_json::json!({"command": "cargo test"})));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "cargo test -p krokit-core"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo build"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo testing"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo test && rm -rf /"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo test $(whoami)"})));
+        assert!(!permission.matches("bash", &serde_json::json!({"command": "cargo test\nrm -rf /"})));
+
+        let permission = Permission::allow_always("bash", &serde_json::json!({"command": "ls -la src"}));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "ls"})));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "ls docs"})));
+    }
+
+    #[test]
+    fn test_allow_always_bash_without_safe_prefix_is_exact() {
+        let cases = [
+            ("rm -rf build/", "rm -rf /"),
+            ("python script.py", "python -c 'import os'"),
+            ("/usr/bin/python3 script.py", "/usr/bin/python3 other.py"),
+            ("sudo apt install jq", "sudo rm -rf /"),
+            ("env FOO=1 make", "env FOO=1 rm -rf /"),
+            ("bash build.sh", "bash -c 'curl evil.sh'"),
+            ("sh -c 'make'", "sh -c 'rm -rf /'"),
+            ("node index.js", "node -e 'process.exit()'"),
+            ("git -c core.pager=less log", "git -c core.pager=evil log"),
+            ("FOO=1 cargo test", "FOO=1 cargo publish"),
+            ("git", "git push --force"),
+            ("docker", "docker run -v /:/host alpine"),
+        ];
+        for (approved, other) in cases {
+            let permission = Permission::allow_always("bash", &serde_json::json!({"command": approved}));
+            assert!(permission.matches("bash", &serde_json::json!({"command": approved})), "{}", approved);
+            assert!(!permission.matches("bash", &serde_json::json!({"command": other})), "{} allows {}", approved, other);
+            assert!(!permission.matches("bash", &serde_json::json!({"command": format!("{} x", approved)})), "{}", approved);
+        }
+
+        let permission = Permission::allow_always("bash", &serde_json::json!({"command": "git status"}));
+        assert!(permission.matches("bash", &serde_json::json!({"command": "git status --short"})));
+    }
+
+    #[test]
+    fn test_allow_always_edit_matches_path() {
+        let permission = Permission::allow_always("edit", &serde_json::json!({
+            "path": "src/main.rs", "old_string": "a", "new_string": "b"
+        }));
+        assert!(permission.matches("edit", &serde_json::json!({
+            "path": "src/main.rs", "old_string": "c", "new_string": "d"
+        })));
+        assert!(!permission.matches("edit", &serde_json::json!({
+            "path": "src/main.rs.bak", "old_string": "c", "new_string": "d"
+        })));
+        assert!(!permission.matches("write", &serde_json::json!({"path": "src/main.rs", "content": ""})));
+    }
+
+    #[test]
+    fn test_allow_always_fetch_matches_origin() {
+        let permission = Permission::allow_always("fetch", &serde_json::json!({"url": "https://docs.rs/tokio/latest"}));
+        assert!(permission.matches("fetch", &serde_json::json!({"url": "https://docs.rs/serde"})));
+        assert!(!permission.matches("fetch", &serde_json::json!({"url": "https://docs.rs.evil.com/serde"})));
+    }
+
+    #[test]
+    fn test_grant_always_persists_project_permissions_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join(".krokit").join("permissions.json");
+        let global = dir.path().join("global.json");
+
+        let global_permission = Permission::new("ls".to_string(), MatchStrategy::Exact, serde_json::json!({}), false);
+        std::fs::write(&global, serde_json::to_string(&vec![global_permission]).unwrap()).unwrap();
+
+        let mut manager = ClaimManager::with_config_file(project.clone());
+        manager.load_global_from_file(global.clone()).unwrap();
+        assert!(manager.get_all_permissions()[0].global);
+
+        manager.grant_always(Permission::allow_always("bash", &serde_json::json!({"command": "make test"}))).unwrap();
+        assert_eq!(manager.len(), 2);
+
+        let mut reloaded = ClaimManager::with_config_file(project);
+        reloaded.load_from_file().unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(reloaded.get_all_permissions()[0].tool_name, "bash");
+        assert!(reloaded.is_permitted("bash", &serde_json::json!({"command": "make test -j4"})));
+    }
+
+    #[test]
+    fn test_project_permissions_are_not_in_the_project() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a")).unwrap();
+        std::fs::create_dir_all(dir.path().join("b")).unwrap();
+
+        let path = ClaimManager::project_permissions_path_for(&dir.path().join("a")).unwrap();
+        assert!(!path.starts_with(dir.path()), "{}", path.display());
+        assert!(path.starts_with(ClaimManager::global_permissions_path().unwrap().parent().unwrap()));
+        // the same project under another spelling shares its permissions, another one does not
+        assert_eq!(path, ClaimManager::project_permissions_path_for(&dir.path().join("b/../a")).unwrap());
+        assert_ne!(path, ClaimManager::project_permissions_path_for(&dir.path().join("b")).unwrap());
+    }
+
+    #[test]
+    fn test_grant_always_without_file_is_session_only() {
+        let mut manager = ClaimManager::new();
+        manager.grant_always(Permission::allow_always("bash", &serde_json::json!({"command": "make"}))).unwrap();
+        assert!(manager.get_all_permissions()[0].session_only);
+    }
+
+    #[test]
+    fn test_remove_and_merge_permissions() {
+        let mut manager = ClaimManager::new();
+        let permission = Permission::allow_always("bash", &serde_json::json!({"command": "make"}));
+        manager.add_permission(permission.clone());
+        manager.merge(&[permission.clone()]);
+        assert_eq!(manager.len(), 1);
+
+        assert!(manager.remove_permission(3).is_none());
+        assert!(manager.remove_permission(0).is_some());
+        assert!(manager.is_empty());
+    }
+}
@@ -1,13 +1,14 @@
 use std::sync::Arc;
 use std::future::Future;
 use futures::future::BoxFuture;
-use krokit_llm::ChatMessage;
+use krokit_llm::{ChatDelta, ChatMessage};
 use serde::{Serialize, Deserialize};
 use async_trait::async_trait;
 use super::brain::ThinkerDecision;
 use super::AgentError;
 use crate::agent::PublicAgentState;
 use crate::tools::{ToolResult, ToolCall};
+use crate::usage::{CallUsage, TokenUsage};
 use chrono::{DateTime, TimeDelta, Utc};
 
 /// Internal events for agent state machine communication
@@ -48,6 +49,14 @@ pub enum InternalAgentEvent {
     PermissionResponseReceived { 
         request_id: String,
         response: PermissionResponse
+    },
+    /// Compaction requested by the user completed (true if the trace was compacted)
+    CompactCompleted {
+        result: Result<bool, AgentError>
+    },
+    /// Tokens consumed by a brain call that did not produce a decision (compaction)
+    UsageReported {
+        usage: CallUsage
     }
 }
 
@@ -62,6 +71,10 @@ pub enum AgentEvent {
     },
     /// Thinking Start
     ThinkingStart,
+    /// Partial content of the message being generated by the brain
+    BrainDelta {
+        delta: ChatDelta
+    },
     /// Agent is thinking - provides the thought content to display to user
     BrainResult { 
         timestamp: DateTime<Utc>,
@@ -72,6 +85,11 @@ pub enum AgentEvent {
         timestamp: DateTime<Utc>,
         call: ToolCall 
     },
+    /// Output produced by a running tool, for the tools that stream it
+    ToolCallProgress {
+        call_id: String,
+        chunk: String,
+    },
     /// Tool execution completed and returned a result
     ToolCallCompleted {
         duration: TimeDelta,
@@ -92,6 +110,17 @@ pub enum AgentEvent {
         request_id: String,
         request: PermissionRequest,
     },
+    /// A brain call reported its token usage, with the running totals
+    UsageUpdated {
+        call: TokenUsage,
+        turn: TokenUsage,
+        session: TokenUsage,
+    },
+    /// Older messages of the trace were replaced by a summary
+    TraceCompacted {
+        tokens_before: usize,
+        tokens_after: usize,
+    },
     /// Agent encountered an error
     Error { error: String },
     /// Agent execution completed
@@ -214,6 +243,11 @@ impl std::fmt::Debug for AgentEvent {
                 f.debug_struct("ThinkingStart")
                     .finish()
             }
+            AgentEvent::BrainDelta { delta } => {
+                f.debug_struct("BrainDelta")
+                    .field("delta", delta)
+                    .finish()
+            }
             AgentEvent::BrainResult { timestamp, thought } => {
                 f.debug_struct("BrainResult")
                     .field("timestamp", timestamp)
@@ -226,6 +260,12 @@ impl std::fmt::Debug for AgentEvent {
                     .field("call", call)
                     .finish()
             }
+            AgentEvent::ToolCallProgress { call_id, chunk } => {
+                f.debug_struct("ToolCallProgress")
+                    .field("call_id", call_id)
+                    .field("chunk", chunk)
+                    .finish()
+            }
             AgentEvent::ToolCallCompleted { duration, call, result } => {
                 f.debug_struct("ToolCallCompleted")
                     .field("timestamp", duration)
@@ -252,6 +292,19 @@ impl std::fmt::Debug for AgentEvent {
                     //.field("response_channel", &"<oneshot::Sender>")
                     .finish()
             }
+            AgentEvent::UsageUpdated { call, turn, session } => {
+                f.debug_struct("UsageUpdated")
+                    .field("call", call)
+                    .field("turn", turn)
+                    .field("session", session)
+                    .finish()
+            }
+            AgentEvent::TraceCompacted { tokens_before, tokens_after } => {
+                f.debug_struct("TraceCompacted")
+                    .field("tokens_before", tokens_before)
+                    .field("tokens_after", tokens_after)
+                    .finish()
+            }
             AgentEvent::Error { error } => {
                 f.debug_struct("Error")
                     .field("error", error)
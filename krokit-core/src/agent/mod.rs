@@ -30,5 +30,5 @@ pub use output::StdoutEventManager;
 pub use builder::AgentBuilder;
 pub use claims::{ClaimManager, PermissionError};
 pub use error::{AgentError, AgentExecutionError};
-pub use brain::{Brain, ThinkerContext, ThinkerDecision, ThinkerFlowControl};
+pub use brain::{Brain, Compaction, ThinkerContext, ThinkerDecision, ThinkerFlowControl, ToolImages};
 pub use crate::logging::LoggingConfig;
\ No newline at end of file
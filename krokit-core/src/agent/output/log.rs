@@ -29,14 +29,20 @@ impl FileEventLogger {
             AgentEvent::ThinkingStart => {
                 format!("ThinkingStart")
             }
+            AgentEvent::BrainDelta { delta } => {
+                format!("BrainDelta: {:?}", delta)
+            }
             AgentEvent::BrainResult { timestamp: event_time, thought } => {
                 format!("BrainResult: {:?} - {:?}", event_time, thought)
             }
             AgentEvent::ToolCallStarted { timestamp: event_time, call } => {
                 format!("ToolCallStarted: {:?} - {}", event_time, call.tool_name)
             }
+            AgentEvent::ToolCallProgress { call_id, chunk } => {
+                format!("ToolCallProgress: {} - {} bytes", call_id, chunk.len())
+            }
             AgentEvent::ToolCallCompleted { duration, call, result } => {
-                format!("ToolCallCompleted: {} in {:?} - {:?}", call.tool_name, duration, result)
+                format!("ToolCallCompleted: {} in {:?} - {:?}", call.tool_name, duration, result.without_image())
             }
             AgentEvent::UserInput { input } => {
                 format!("UserInput: {}", input)
@@ -47,6 +53,12 @@ impl FileEventLogger {
             AgentEvent::PermissionRequired { request_id, request } => {
                 format!("PermissionRequired: {} - {}", request_id, request.operation)
             }
+            AgentEvent::UsageUpdated { call, session, .. } => {
+                format!("UsageUpdated: call {} - session {}", call.format_short(), session.format_short())
+            }
+            AgentEvent::TraceCompacted { tokens_before, tokens_after } => {
+                format!("TraceCompacted: ~{} -> ~{} tokens", tokens_before, tokens_after)
+            }
             AgentEvent::Error { error } => {
                 format!("Error: {}", error)
             }
@@ -1,5 +1,5 @@
 use chrono::Utc;
-use krokit_llm::{ChatMessage, ChatMessageContent};
+use krokit_llm::{ChatDelta, ChatMessage, ChatMessageContent};
 use termimad::crossterm::style::Color;
 use termimad::{rgb, MadSkin};
 use crate::agent::{AgentError, AgentEvent};
@@ -28,6 +28,11 @@ impl PrettyFormatter {
             AgentEvent::ThinkingStart => {
                 None
             },
+            AgentEvent::BrainDelta { .. } => {
+                // partial content is rendered live with format_streaming (tui) or format_delta (headless),
+                // the complete message comes with BrainResult
+                None
+            },
             AgentEvent::BrainResult { thought, .. } => {
                 self.format_thinking(thought)
             },
@@ -35,6 +40,10 @@ impl PrettyFormatter {
                 // do nothing because tool can be call in parallel, we only display the result
                 None
             },
+            AgentEvent::ToolCallProgress { .. } => {
+                // the output tail is rendered live with format_tool_progress, the result comes with ToolCallCompleted
+                None
+            },
             AgentEvent::ToolCallCompleted { call, result, .. } => {
                 Some(self.format_tool_result(call, result))
             },
@@ -85,6 +94,13 @@ impl PrettyFormatter {
                 //Some(self.skin.term_text(&markdown).to_string())
                 None
             },
+            AgentEvent::UsageUpdated { .. } => {
+                // shown in the status line
+                None
+            },
+            AgentEvent::TraceCompacted { tokens_before, tokens_after } => {
+                Some(format!("\x1b[2m✻ Conversation compacted (~{} → ~{} tokens)\x1b[0m", tokens_before, tokens_after))
+            },
             AgentEvent::Error { error } => {
                 let markdown = format!("❌ **Error:** {}", error);
                 let mut error_skin = self.skin.clone();
@@ -147,6 +163,47 @@ impl PrettyFormatter {
         }
     }
 
+    /// Format the tail of a message that is still being streamed
+    pub fn format_streaming(&self, content: &str, reasoning: &str, max_lines: usize) -> String {
+        // show the answer once it started, the reasoning until then
+        let (text, bullet, dim) = if content.trim().is_empty() {
+            (reasoning, "✻", "\x1b[2m")
+        } else {
+            (content, "●", "")
+        };
+        let lines: Vec<&str> = text.trim().lines().collect();
+        let skip = lines.len().saturating_sub(max_lines);
+        lines.iter()
+            .skip(skip)
+            .enumerate()
+            .map(|(i, line)| {
+                let prefix = if i == 0 && skip == 0 { bullet } else { " " };
+                format!("{}{} {}\x1b[0m", dim, prefix, line)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Format a piece of the message being streamed, to be printed right after the previous
+    /// one. A bullet starts the answer and the reasoning, the reasoning is dimmed.
+    pub fn format_delta(&self, delta: &ChatDelta, previous: Option<&ChatDelta>) -> Option<String> {
+        let same_kind = |previous: Option<&ChatDelta>, reasoning: bool| match previous {
+            Some(ChatDelta::Reasoning(_)) => reasoning,
+            Some(ChatDelta::Content(_)) => !reasoning,
+            _ => false,
+        };
+        let (text, bullet, dim, reasoning) = match delta {
+            ChatDelta::Content(text) => (text, "●", "", false),
+            ChatDelta::Reasoning(text) => (text, "✻", "\x1b[2m", true),
+            ChatDelta::ToolCall { .. } => return None,
+        };
+        if same_kind(previous, reasoning) {
+            return Some(format!("{}{}\x1b[0m", dim, text));
+        }
+        let separator = if previous.is_some() { "\n" } else { "" };
+        Some(format!("{}{}{} {}\x1b[0m", separator, dim, bullet, text.trim_start()))
+    }
+
     /// Format tool started
     pub fn format_tool_started(&self, call: &ToolCall) -> String {
         let tool_name = Self::capitalize_first(&call.tool_name);
@@ -176,6 +233,17 @@ impl PrettyFormatter {
         output
     }
 
+    /// Format the last lines printed by a running tool
+    pub fn format_tool_progress(&self, output: &str, max_lines: usize) -> String {
+        let lines: Vec<&str> = output.trim_end().lines().collect();
+        let skip = lines.len().saturating_sub(max_lines);
+        lines.iter()
+            .skip(skip)
+            // only what is left after a carriage return is visible
+            .map(|line| format!("\x1b[2m  │ {}\x1b[0m", line.rsplit('\r').next().unwrap_or(line)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
     /// Format tool result
     fn format_tool_result(&self, call: &ToolCall, result: &ToolResult) -> String {
@@ -207,7 +275,7 @@ impl PrettyFormatter {
                     }
                     
                     // Show first N lines for user display only for specific tools
-                    if matches!(call.tool_name.as_str(), "ls" | "bash" | "edit" | "multiedit" | "find" | "todo_read" | "todo_write") {
+                    if matches!(call.tool_name.as_str(), "ls" | "bash" | "edit" | "multiedit" | "patch" | "find" | "todo_read" | "todo_write") {
                         let preview_lines: Vec<&str> = tool_output.lines().take(self.max_preview_lines).collect();
                         if !preview_lines.is_empty() {
                             let mut markdown_content = String::new();
@@ -247,6 +315,9 @@ impl PrettyFormatter {
                 "read" | "write" | "edit" | "multiedit" => vec!["file_path", "path"],
                 "ls" | "glob" => vec!["path", "pattern"],
                 "find" | "grep" => vec!["pattern", "path"],
+                "symbols" => vec!["name", "path"],
+                "semantic_search" => vec!["query", "path"],
+                "patch" => vec!["patch"],
                 "bash" => vec!["command"],
                 _ => vec!["path", "file_path", "pattern", "command", "query", "input"]
             };
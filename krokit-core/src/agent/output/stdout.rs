@@ -1,17 +1,23 @@
 use std::io::{self, Write};
+use std::sync::Mutex;
 use async_trait::async_trait;
+use krokit_llm::ChatDelta;
 use crate::agent::{AgentEvent, AgentEventHandler};
 use super::pretty::PrettyFormatter;
 
 /// Stdout event manager that formats and prints agent activity in a user-friendly way
 pub struct StdoutEventManager {
     formatter: PrettyFormatter,
+    /// last delta printed of the message being streamed, a streamed message is not
+    /// printed again once complete
+    streamed: Mutex<Option<ChatDelta>>,
 }
 
 impl StdoutEventManager {
     pub fn new() -> Self {
         Self {
             formatter: PrettyFormatter::new(),
+            streamed: Mutex::new(None),
         }
     }
 }
@@ -19,6 +25,25 @@ impl StdoutEventManager {
 #[async_trait]
 impl AgentEventHandler for StdoutEventManager {
     async fn handle_event(&self, event: AgentEvent) {
+        let mut streamed = self.streamed.lock().unwrap();
+        match &event {
+            AgentEvent::BrainDelta { delta } => {
+                if let Some(piece) = self.formatter.format_delta(delta, streamed.as_ref()) {
+                    eprint!("{}", piece);
+                    *streamed = Some(delta.clone());
+                }
+                return;
+            }
+            AgentEvent::BrainResult { thought, .. } if streamed.is_some() => {
+                // the answer is already printed, only end its line
+                *streamed = None;
+                eprintln!();
+                if thought.is_ok() {
+                    return;
+                }
+            }
+            _ => {}
+        }
         if let Some(formatted) = self.formatter.format_event(&event) {
             eprintln!("{}", formatted);
             let _ = io::stdout().flush();
@@ -1,4 +1,4 @@
-use krokit_llm::ToolCallMethod;
+use krokit_llm::{ChatMessageContentPart, ToolCallMethod};
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::{timeout, Duration};
 use crate::agent::AgentError;
@@ -16,7 +16,9 @@ pub enum AgentRequest {
     GetState,
     /// Send user input (cancels current task, adds to trace, resumes agent)
     SendUserInput{
-        input: String
+        input: String,
+        /// image content parts attached to the input
+        images: Vec<ChatMessageContentPart>
     },
     /// Switch method for tool call
     SwitchToolCallMethod {
@@ -32,6 +34,14 @@ pub enum AgentRequest {
         request_id: String,
         response: PermissionResponse
     },
+    /// Summarize older messages of the trace (only while paused)
+    Compact,
+    /// Revert the file changes of the last N turns that modified files (only while paused)
+    Undo {
+        turns: usize
+    },
+    /// Diff of the files modified during the last turn that modified files
+    Diff,
     /// Wait until the agent reaches the Paused state
     WaitTurn,
     /// Manage sudo mode: Some(true) = enable, Some(false) = disable, None = get status
@@ -55,6 +65,12 @@ pub enum AgentResponse {
     SudoStatus {
         enabled: bool
     },
+    FilesRestored {
+        files: Vec<String>
+    },
+    Diff {
+        diff: String
+    },
     Error {
         error: String
     }
@@ -112,7 +128,11 @@ impl AgentController {
     }
 
     pub async fn send_user_input(&self, input: String) -> Result<(), AgentError> {
-        self.send(AgentRequest::SendUserInput { input: input }).await.map(|_| Ok(()))?
+        self.send(AgentRequest::SendUserInput { input: input, images: vec![] }).await.map(|_| Ok(()))?
+    }
+
+    pub async fn send_user_input_with_images(&self, input: String, images: Vec<ChatMessageContentPart>) -> Result<(), AgentError> {
+        self.send(AgentRequest::SendUserInput { input, images }).await.map(|_| Ok(()))?
     }
 
     pub async fn response_user_query(&self,  request_id: String, response: UserResponse) -> Result<(), AgentError> {
@@ -123,6 +143,33 @@ impl AgentController {
         self.send(AgentRequest::UserPermissionResponse { request_id, response }).await.map(|_| Ok(()))?
     }
 
+    /// Ask the agent to compact its trace, the result is notified through events
+    pub async fn compact(&self) -> Result<(), AgentError> {
+        match self.send(AgentRequest::Compact).await? {
+            AgentResponse::Ack => Ok(()),
+            AgentResponse::Error { error } => Err(AgentError::ExecutionError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected Ack response".to_string()))
+        }
+    }
+
+    /// Revert the file changes of the last `turns` turns, returns the restored files
+    pub async fn undo(&self, turns: usize) -> Result<Vec<String>, AgentError> {
+        match self.send(AgentRequest::Undo { turns }).await? {
+            AgentResponse::FilesRestored { files } => Ok(files),
+            AgentResponse::Error { error } => Err(AgentError::ExecutionError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected FilesRestored response".to_string()))
+        }
+    }
+
+    /// Unified diff of the changes made during the last turn that modified files
+    pub async fn diff(&self) -> Result<String, AgentError> {
+        match self.send(AgentRequest::Diff).await? {
+            AgentResponse::Diff { diff } => Ok(diff),
+            AgentResponse::Error { error } => Err(AgentError::ExecutionError(error)),
+            _ => Err(AgentError::InvalidResponse("Expected Diff response".to_string()))
+        }
+    }
+
     pub async fn get_state(&self) -> Result<PublicAgentState, AgentError> {
         match self.send(AgentRequest::GetState).await? {
             AgentResponse::State{state} => Ok(state),
@@ -12,6 +12,13 @@ impl AgentCore {
             InternalAgentEvent::BrainResult { result } => {
                 self.process_next_step(result).await
             },
+            InternalAgentEvent::CompactCompleted { result } => {
+                self.process_compact(result).await
+            },
+            InternalAgentEvent::UsageReported { usage } => {
+                self.record_usage(&usage).await;
+                Ok(())
+            },
             InternalAgentEvent::ToolsCompleted { any_denied } => {
                 if any_denied {
                     self.set_state(InternalAgentState::Paused).await;
@@ -26,6 +26,8 @@ impl AgentCore {
         let trace = self.trace.clone();
         let guard = trace.read().await;
         if let Some(ChatMessage::User { .. }) = guard.last() {
+            // the initial goal is the first turn
+            self.fs_log.checkpoints.begin_turn(guard.len() - 1).await;
             self.set_state(InternalAgentState::Running).await;
         } else {
             self.set_state(InternalAgentState::Paused).await;
@@ -198,7 +198,7 @@ async fn test_stop_current_task() {
     tokio::time::sleep(Duration::from_millis(500)).await;
 
     // run a command to resume
-    controller.send(AgentRequest::SendUserInput { input: "hello".to_string() }).await.expect("Failed to resume");
+    controller.send(AgentRequest::SendUserInput { input: "hello".to_string(), images: vec![] }).await.expect("Failed to resume");
 
     // droping controller and wait for completion
     controller.drop().await.expect("failed to drop the controller");
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
-use krokit_llm::ToolCallMethod;
+use krokit_llm::{HttpSettings, ToolCallMethod};
 use crate::tools::mcp::McpConfig;
+use crate::tools::bash::SandboxConfig;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentProviderConfig {
@@ -10,6 +11,9 @@ pub struct AgentProviderConfig {
     pub env_vars: HashMap<String, String>,
     pub model: String,
     pub tool_method: ToolCallMethod,
+    /// proxy, CA and timeouts of the provider, over the global ones of the krokit config
+    #[serde(default, skip_serializing_if = "HttpSettings::is_default")]
+    pub http: HttpSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +33,9 @@ pub struct AgentTools {
     pub builtin_excluded: Vec<String>,
     #[serde(default)]
     pub mcp: HashMap<String, McpToolConfig>,
+    /// run the bash tool in a sandbox
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<SandboxConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +75,7 @@ impl Default for AgentTools {
             builtin: vec!["*".to_string()],
             builtin_excluded: Vec::new(),
             mcp: HashMap::new(),
+            sandbox: None,
         }
     }
 }
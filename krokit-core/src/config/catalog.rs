@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Serialize, Deserialize};
+use krokit_llm::{HttpSettings, LlmClient};
+use krokit_llm::provider::LlmError;
+use super::config::KrokitConfig;
+
+/// How long the models of a provider are served from the cache before being fetched again
+pub const CATALOG_TTL_HOURS: i64 = 24;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogModel {
+    pub id: String,
+    /// context window in tokens, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderModels {
+    pub fetched_at: DateTime<Utc>,
+    pub models: Vec<CatalogModel>,
+}
+
+impl ProviderModels {
+    pub fn is_fresh(&self) -> bool {
+        Utc::now() - self.fetched_at < Duration::hours(CATALOG_TTL_HOURS)
+    }
+}
+
+/// Models of the configured providers cached on disk (~/.config/krokit/models.json),
+/// and the favorite models. Shared by `krokit auth` and the `/model` picker so that
+/// opening the picker does not hit the network, and still works offline.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCatalog {
+    /// favorite models, as "provider/model"
+    #[serde(default)]
+    pub favorites: Vec<String>,
+    /// models keyed by provider (and base url, see `ModelCatalog::key`)
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderModels>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl ModelCatalog {
+    pub fn catalog_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let config_path = KrokitConfig::config_path()?;
+        Ok(config_path.with_file_name("models.json"))
+    }
+
+    /// Load the catalog, an empty one when the file is missing or unreadable
+    pub fn load() -> Self {
+        match Self::catalog_path() {
+            Ok(path) => Self::load_from(path),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn load_from(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut catalog: ModelCatalog = fs::read_to_string(&path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        catalog.path = Some(path);
+        catalog
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Cache key of a provider, the base url is part of it since two endpoints of the
+    /// same provider (openai_compatible, ovhcloud...) serve different models
+    pub fn key(provider: &str, env_vars: &HashMap<String, String>) -> String {
+        let mut base_urls: Vec<&str> = env_vars.iter()
+            .filter(|(name, value)| name.ends_with("BASE_URL") && !value.is_empty())
+            .map(|(_, value)| value.trim_end_matches('/'))
+            .collect();
+        base_urls.sort();
+        std::iter::once(provider).chain(base_urls).collect::<Vec<_>>().join("@")
+    }
+
+    pub fn cached(&self, provider: &str, env_vars: &HashMap<String, String>) -> Option<&ProviderModels> {
+        self.providers.get(&Self::key(provider, env_vars))
+    }
+
+    /// Models of a provider, from the cache while it is fresh. A failed fetch falls back
+    /// to the stale cache, so that the picker keeps working offline.
+    pub async fn models(&mut self, provider: &str, env_vars: &HashMap<String, String>, http: &HttpSettings) -> Result<Vec<CatalogModel>, LlmError> {
+        if let Some(cached) = self.cached(provider, env_vars).filter(|cached| cached.is_fresh()) {
+            return Ok(cached.models.clone());
+        }
+        match self.refresh(provider, env_vars, http).await {
+            Ok(models) => Ok(models),
+            Err(e) => self.cached(provider, env_vars)
+                .map(|cached| cached.models.clone())
+                .ok_or(e),
+        }
+    }
+
+    /// Fetch the models of a provider and update the cache
+    pub async fn refresh(&mut self, provider: &str, env_vars: &HashMap<String, String>, http: &HttpSettings) -> Result<Vec<CatalogModel>, LlmError> {
+        let client = LlmClient::create_provider_with(provider, env_vars, http)
+            .map_err(|e| format!("Failed to create client: {}", e))?;
+        let models: Vec<CatalogModel> = client.models_with_capabilities().await
+            .map_err(|e| format!("Failed to fetch models: {}", e))?
+            .into_iter()
+            .map(|(id, capabilities)| CatalogModel { id, context_window: capabilities.context_window })
+            .collect();
+        self.update(provider, env_vars, models.clone());
+        Ok(models)
+    }
+
+    pub fn update(&mut self, provider: &str, env_vars: &HashMap<String, String>, models: Vec<CatalogModel>) {
+        self.providers.insert(Self::key(provider, env_vars), ProviderModels {
+            fetched_at: Utc::now(),
+            models,
+        });
+        // the cache is an optimization, failing to write it is not an error
+        let _ = self.save();
+    }
+
+    pub fn is_favorite(&self, provider: &str, model: &str) -> bool {
+        let favorite = format!("{}/{}", provider, model);
+        self.favorites.contains(&favorite)
+    }
+
+    /// Add or remove a favorite model, returns whether it is now a favorite
+    pub fn toggle_favorite(&mut self, provider: &str, model: &str) -> bool {
+        let favorite = format!("{}/{}", provider, model);
+        let is_favorite = match self.favorites.iter().position(|f| *f == favorite) {
+            Some(index) => {
+                self.favorites.remove(index);
+                false
+            }
+            None => {
+                self.favorites.push(favorite);
+                true
+            }
+        };
+        let _ = self.save();
+        is_favorite
+    }
+
+    /// Favorite model to use when none is configured, without network: the first favorite
+    /// of the provider that is still served. None without a favorite, the provider then
+    /// picks its own default model among the `fresh_models`
+    pub fn default_model(&self, provider: &str, env_vars: &HashMap<String, String>) -> Option<String> {
+        let cached = self.cached(provider, env_vars)?;
+        cached.models.iter()
+            .find(|model| self.is_favorite(provider, &model.id))
+            .map(|model| model.id.clone())
+    }
+
+    /// Ids of the cached models of a provider, None when they are missing or stale
+    pub fn fresh_models(&self, provider: &str, env_vars: &HashMap<String, String>) -> Option<Vec<String>> {
+        let cached = self.cached(provider, env_vars).filter(|cached| cached.is_fresh())?;
+        Some(cached.models.iter().map(|model| model.id.clone()).collect())
+    }
+
+    /// Models matching a fuzzy query, best matches first, favorites first on a tie
+    /// (and in catalog order for an empty query)
+    pub fn search<'a>(&self, provider: &str, models: &'a [CatalogModel], query: &str) -> Vec<&'a CatalogModel> {
+        let mut matches: Vec<(i64, bool, &CatalogModel)> = models.iter()
+            .filter_map(|model| {
+                let score = fuzzy_score(query, &model.id)?;
+                Some((score, self.is_favorite(provider, &model.id), model))
+            })
+            .collect();
+        // stable sort, keeps the catalog order between equal matches
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.cmp(&a.1)));
+        matches.into_iter().map(|(_, _, model)| model).collect()
+    }
+}
+
+/// Score of a candidate whose characters contain the query in order (case insensitive),
+/// None when they do not. Consecutive characters and characters starting a word
+/// (after '/', '-', ':', '.' or '_') score higher, so "sonnet4" ranks
+/// "claude-sonnet-4" above "claude-3-5-sonnet-20241022".
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for q in query {
+        let index = position + candidate[position..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous.map_or(false, |p| p + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || matches!(candidate[index - 1], '/' | '-' | ':' | '.' | '_') {
+            score += 3;
+        }
+        previous = Some(index);
+        position = index + 1;
+    }
+    // shorter names first among equal matches
+    Some(score * 1000 - candidate.len() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str) -> CatalogModel {
+        CatalogModel { id: id.to_string(), context_window: None }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("sonnet4", "claude-sonnet-4").is_some());
+        assert!(fuzzy_score("sonnet4", "gpt-4o").is_none());
+        assert!(fuzzy_score("", "gpt-4o").is_some());
+        assert!(fuzzy_score("sonnet4", "claude-sonnet-4") > fuzzy_score("sonnet4", "claude-3-5-sonnet-20241022"));
+        assert!(fuzzy_score("QWEN coder", "qwen/qwen3-coder").is_some());
+    }
+
+    #[test]
+    fn test_search_puts_favorites_first() {
+        let mut catalog = ModelCatalog::default();
+        let models = vec![model("gpt-4o"), model("gpt-4o-mini"), model("o3")];
+        assert!(catalog.toggle_favorite("openai", "o3"));
+
+        let all: Vec<&str> = catalog.search("openai", &models, "").iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(all, vec!["o3", "gpt-4o", "gpt-4o-mini"]);
+        let found: Vec<&str> = catalog.search("openai", &models, "4o").iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(found, vec!["gpt-4o", "gpt-4o-mini"]);
+
+        assert!(!catalog.toggle_favorite("openai", "o3"));
+        assert!(!catalog.is_favorite("openai", "o3"));
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models.json");
+        let env = HashMap::from([("OPENAI_COMPATIBLE_BASE_URL".to_string(), "http://localhost:8080/v1/".to_string())]);
+
+        let mut catalog = ModelCatalog::load_from(&path);
+        assert!(catalog.default_model("openai_compatible", &env).is_none());
+        catalog.update("openai_compatible", &env, vec![model("llama-3.1-8b"), model("qwen3-coder")]);
+        // without a favorite the provider chooses
+        assert!(catalog.default_model("openai_compatible", &env).is_none());
+        assert_eq!(catalog.fresh_models("openai_compatible", &env), Some(vec!["llama-3.1-8b".to_string(), "qwen3-coder".to_string()]));
+        catalog.toggle_favorite("openai_compatible", "qwen3-coder");
+
+        let catalog = ModelCatalog::load_from(&path);
+        let cached = catalog.cached("openai_compatible", &env).unwrap();
+        assert!(cached.is_fresh());
+        assert_eq!(cached.models.len(), 2);
+        assert_eq!(catalog.default_model("openai_compatible", &env), Some("qwen3-coder".to_string()));
+        // another endpoint of the same provider is cached separately
+        assert!(catalog.cached("openai_compatible", &HashMap::new()).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stale_cache_is_served_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut catalog = ModelCatalog::load_from(dir.path().join("models.json"));
+        catalog.providers.insert("unknown_provider".to_string(), ProviderModels {
+            fetched_at: Utc::now() - Duration::hours(CATALOG_TTL_HOURS + 1),
+            models: vec![model("cached-model")],
+        });
+
+        // the provider cannot be created, the stale models are served
+        let http = HttpSettings::default();
+        let models = catalog.models("unknown_provider", &HashMap::new(), &http).await.unwrap();
+        assert_eq!(models, vec![model("cached-model")]);
+        assert!(catalog.fresh_models("unknown_provider", &HashMap::new()).is_none());
+        assert!(catalog.refresh("unknown_provider", &HashMap::new(), &http).await.is_err());
+    }
+}
@@ -4,15 +4,42 @@ use std::fs;
 use std::os::unix::fs::PermissionsExt;
 use reqwest::Url;
 use serde::{Serialize, Deserialize};
-use krokit_llm::{LlmClient, ToolCallMethod};
+use krokit_llm::{HttpSettings, LlmClient, ModelCapabilities, ToolCallMethod};
+use krokit_llm::providers::fallback::RetryPolicy;
 use crate::tools::mcp::McpConfig;
+use crate::usage::ModelPricing;
+use super::catalog::ModelCatalog;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderConfig {
     pub provider: String,
     pub env_vars: std::collections::HashMap<String, String>,
     pub model: String,
-    pub tool_method: ToolCallMethod
+    pub tool_method: ToolCallMethod,
+    /// proxy, CA and timeouts of this provider, over the global ones
+    #[serde(default, skip_serializing_if = "HttpSettings::is_default")]
+    pub http: HttpSettings,
+}
+
+/// Providers tried in order when the selected one keeps failing (rate limits,
+/// server errors, timeouts)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FallbackConfig {
+    pub providers: Vec<ProviderConfig>,
+    #[serde(default, flatten)]
+    pub retry: RetryPolicy,
+}
+
+/// Embedding model of the semantic_search tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingsConfig {
+    /// provider of the model, the selected provider when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// env vars of the provider, those of the selected provider when empty
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env_vars: HashMap<String, String>,
+    pub model: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +48,20 @@ pub struct KrokitConfig {
     pub selected_provider: usize,
     #[serde(default)]
     pub mcp_configs: HashMap<String, McpConfig>,
+    /// price overrides in USD per million tokens, keyed by "provider/model", "model" or "provider"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub pricing: HashMap<String, ModelPricing>,
+    /// model capability overrides (context window, tools, vision...), keyed by "provider/model" or "model"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub capabilities: HashMap<String, ModelCapabilities>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<FallbackConfig>,
+    /// proxy, CA and timeouts of all the providers and of the fetch tool
+    #[serde(default, skip_serializing_if = "HttpSettings::is_default")]
+    pub http: HttpSettings,
+    /// embedding model of the semantic_search tool, a local model is used when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embeddings: Option<EmbeddingsConfig>,
 }
 
 impl KrokitConfig {
@@ -36,7 +77,8 @@ impl KrokitConfig {
             provider,
             env_vars,
             model,
-            tool_method: ToolCallMethod::FunctionCall
+            tool_method: ToolCallMethod::FunctionCall,
+            http: HttpSettings::default(),
         };
         
         self.providers.push(provider_config);
@@ -199,14 +241,14 @@ impl KrokitConfig {
             .map(|(name, config)| {
                 let description = match config {
                     McpConfig::Stdio { command, .. } => format!("stdio: {}", command),
-                    McpConfig::Http { url, bearer_token } => {
+                    McpConfig::Http { url, bearer_token, .. } => {
                         if bearer_token.is_some() {
                             format!("http: {} (authenticated)", url)
                         } else {
                             format!("http: {}", url)
                         }
                     },
-                    McpConfig::Sse { url } => format!("sse: {}", url),
+                    McpConfig::Sse { url, .. } => format!("sse: {}", url),
                 };
                 (name.clone(), description)
             })
@@ -228,10 +270,16 @@ impl Default for KrokitConfig {
                     (String::from("OVH_BASE_URL"), String::from("https://qwen-3-32b.endpoints.kepler.ai.cloud.ovh.net/api/openai_compat/v1"))
                 ]),
                 model: "Qwen3-32B".to_string(),
-                tool_method: ToolCallMethod::FunctionCall
+                tool_method: ToolCallMethod::FunctionCall,
+                http: HttpSettings::default(),
             }],
             selected_provider: 0,
             mcp_configs: HashMap::new(),
+            pricing: HashMap::new(),
+            capabilities: HashMap::new(),
+            fallback: None,
+            http: HttpSettings::default(),
+            embeddings: None,
         }
     }
 }
@@ -243,16 +291,76 @@ impl KrokitConfig {
 
         config.set_env_vars();
         
-        let llm = if let Some(provider_config) = config.get_selected_provider() {
-            LlmClient::create_provider(
-                &provider_config.provider, 
-                &provider_config.env_vars)
-                .map_err(|e| format!("Failed to create {} client: {}", provider_config.provider, e))?
-        } else {
+        let Some(provider_config) = config.get_selected_provider() else {
             return Err("No provider configured".into());
         };
+        let llm = LlmClient::create_provider_with(
+            &provider_config.provider, 
+            &provider_config.env_vars,
+            &config.http_settings(provider_config))
+            .map_err(|e| format!("Failed to create {} client: {}", provider_config.provider, e))?;
+        let llm = config.with_fallback(llm)?
+            .with_capabilities(config.capabilities.clone());
     
-        let model = llm.default_model().await.map_err(|_| "no Model available")?;
+        // without a configured model, the cached catalog avoids asking the provider: a favorite,
+        // else the default model of the provider among the fresh cached models
+        let cached = Some(&provider_config.model)
+            .filter(|model| model.is_empty())
+            .and_then(|_| {
+                let catalog = ModelCatalog::load();
+                catalog.default_model(&provider_config.provider, &provider_config.env_vars)
+                    .or_else(|| llm.default_model_among(&catalog.fresh_models(&provider_config.provider, &provider_config.env_vars)?))
+            });
+        let model = match cached {
+            Some(model) => model,
+            None => llm.default_model().await.map_err(|_| "no Model available")?,
+        };
         Ok((llm, model))
     }
-}
\ No newline at end of file
+
+    /// Http settings of a provider, completed by the global ones
+    pub fn http_settings(&self, provider_config: &ProviderConfig) -> HttpSettings {
+        provider_config.http.clone().or(self.http.clone())
+    }
+
+    /// Client and model of the configured embeddings, None when they are not configured
+    pub fn embeddings_llm(&self) -> Result<Option<(LlmClient, String)>, Box<dyn std::error::Error>> {
+        let Some(embeddings) = self.embeddings.as_ref().filter(|embeddings| !embeddings.model.is_empty()) else {
+            return Ok(None);
+        };
+        let selected = self.get_selected_provider();
+        let provider = match (&embeddings.provider, selected) {
+            (Some(provider), _) => provider.clone(),
+            (None, Some(selected)) => selected.provider.clone(),
+            (None, None) => return Err("No provider configured for the embeddings".into()),
+        };
+        // the env vars and http settings of the selected provider apply to the same provider
+        let same_provider = selected.filter(|selected| selected.provider == provider);
+        let env_vars = match same_provider {
+            Some(selected) if embeddings.env_vars.is_empty() => selected.env_vars.clone(),
+            _ => embeddings.env_vars.clone(),
+        };
+        let http = same_provider.map_or(self.http.clone(), |selected| self.http_settings(selected));
+
+        let llm = LlmClient::create_provider_with(&provider, &env_vars, &http)
+            .map_err(|e| format!("Failed to create {} embeddings client: {}", provider, e))?;
+        Ok(Some((llm, embeddings.model.clone())))
+    }
+
+    /// Chain the client of the selected provider with the fallback providers if any are
+    /// configured, the selected one keeps the requested model
+    pub fn with_fallback(&self, llm: LlmClient) -> Result<LlmClient, Box<dyn std::error::Error>> {
+        let Some(fallback) = self.fallback.as_ref().filter(|fallback| !fallback.providers.is_empty()) else {
+            return Ok(llm);
+        };
+
+        let mut chain = vec![(llm, None)];
+        for provider_config in &fallback.providers {
+            let client = LlmClient::create_provider_with(&provider_config.provider, &provider_config.env_vars, &self.http_settings(provider_config))
+                .map_err(|e| format!("Failed to create {} fallback client: {}", provider_config.provider, e))?;
+            let model = Some(provider_config.model.clone()).filter(|model| !model.is_empty());
+            chain.push((client, model));
+        }
+        Ok(LlmClient::fallback(chain, fallback.retry.clone()))
+    }
+}
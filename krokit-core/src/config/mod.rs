@@ -1,2 +1,3 @@
 pub mod config;
-pub mod agent;
\ No newline at end of file
+pub mod agent;
+pub mod catalog;
\ No newline at end of file
@@ -2,4 +2,6 @@ pub mod tools;
 pub mod agent;
 pub mod runners;
 pub mod logging;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod session;
+pub mod usage;
\ No newline at end of file
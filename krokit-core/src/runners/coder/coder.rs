@@ -1,15 +1,19 @@
 use std::sync::Arc;
 
 use openai_dive::v1::resources::chat::ChatCompletionParametersBuilder;
-use krokit_llm::{client::LlmClient, ChatMessage, ChatMessageContent};
+use openai_dive::v1::resources::shared::Usage;
+use krokit_llm::{client::LlmClient, ChatCompletionParameters, ChatCompletionResponse, ChatMessage, ChatMessageContent, DeltaSink};
 use async_trait::async_trait;
 use tracing::debug;
 
-use crate::agent::brain::ThinkerDecision;
+use crate::agent::brain::{Compaction, ThinkerDecision};
 use crate::agent::{Agent, AgentBuilder, AgentError, Brain, ThinkerContext};
 use crate::tools::types::{ContainsAnyTool, IntoToolBox};
-use krokit_llm::tool::LlmToolCall;
-use crate::tools::{AnyTool, BashTool, EditTool, FetchTool, FindTool, LsTool, MultiEditTool, ReadTool, TodoReadTool, TodoWriteTool, WriteTool, TodoStorage, FsOperationLog};
+use krokit_llm::tool::{LlmToolCall, LlmToolCallStream};
+use krokit_llm::image::{strip_images, with_tool_images};
+
+use crate::runners::compacter::{compact, should_compact};
+use crate::usage::CallUsage;
 
 use super::prompt::{render_system_prompt_template, get_todo_read};
 
@@ -44,10 +48,16 @@ impl CoderBrain {
 }
 
 
-#[async_trait]
-impl Brain for CoderBrain {
-    async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
-        let mut trace = context.trace.read().await.clone();
+impl CoderBrain {
+    /// Build the request for the next step: system prompt (with todo status) followed by the trace
+    async fn build_request(&self, context: &ThinkerContext) -> Result<ChatCompletionParameters, AgentError> {
+        let trace = context.trace.read().await.clone();
+        let mut trace = with_tool_images(trace, &*context.tool_images.read().await);
+
+        // the tool images are only added to the request, a model without vision gets a note instead
+        if !self.llm.capabilities(&self.model).await.supports_vision() {
+            trace = strip_images(trace);
+        }
 
         // Render the user's system prompt template
         let mut system_prompt = render_system_prompt_template(&self.system_prompt_template);
@@ -64,12 +74,43 @@ impl Brain for CoderBrain {
         });
 
         // get next step with custom temperature
-        let request = ChatCompletionParametersBuilder::default()
+        ChatCompletionParametersBuilder::default()
             .model(&self.model)
             .messages(trace)
             .temperature(self.temperature)
             .build()
-            .map_err(|e| AgentError::LlmError(e.to_string()))?;
+            .map_err(|e| AgentError::LlmError(e.to_string()))
+    }
+
+    /// Usage of the last call, with a fallback chain it goes to the provider that answered
+    fn call_usage(&self, usage: Option<&Usage>) -> Option<CallUsage> {
+        let (provider, model) = self.llm.answered_by()
+            .unwrap_or_else(|| (self.llm.provider_name(), self.model.clone()));
+        if provider != self.llm.provider_name() {
+            debug!(target: "brain::coder", provider = ?provider, model = ?model, "answered by fallback provider");
+        }
+        usage.map(|usage| CallUsage::new(provider, &model, usage))
+    }
+
+    /// Pause if the brain did not call any tool
+    fn decide(&self, response: ChatCompletionResponse) -> Result<ThinkerDecision, AgentError> {
+        let usage = self.call_usage(response.usage.as_ref());
+        let message = response.choices.into_iter().next()
+            .ok_or_else(|| AgentError::InvalidResponse("no choice in llm response".to_string()))?
+            .message;
+        if let ChatMessage::Assistant { tool_calls, .. } = &message {
+            if tool_calls.as_ref().map_or(true, |calls| calls.is_empty()) {
+                return Ok(ThinkerDecision::agent_pause(message).with_usage(usage));
+            }
+        } 
+        Ok(ThinkerDecision::agent_continue(message).with_usage(usage))
+    }
+}
+
+#[async_trait]
+impl Brain for CoderBrain {
+    async fn next_step(&mut self, context: ThinkerContext) -> Result<ThinkerDecision, AgentError> {
+        let request = self.build_request(&context).await?;
         
         let brain_decision = self.llm.chat_with_tools(
                 request,
@@ -78,38 +119,43 @@ impl Brain for CoderBrain {
                 .await
                 .map_err(|e| AgentError::LlmError(e.to_string()))?;
      
-        // stop here if there's no other tool calls
-        let message = brain_decision.choices.into_iter().next().unwrap().message;
-        if let ChatMessage::Assistant { reasoning_content, content, tool_calls, .. } = &message {
-            if tool_calls.as_ref().map_or(true, |calls| calls.is_empty()) {
-                return Ok(ThinkerDecision::agent_pause(message));
-            }
-        } 
-        Ok(ThinkerDecision::agent_continue(message))
+        self.decide(brain_decision)
+    }
+
+    async fn next_step_stream(&mut self, context: ThinkerContext, on_delta: DeltaSink) -> Result<ThinkerDecision, AgentError> {
+        let request = self.build_request(&context).await?;
+
+        let brain_decision = self.llm.chat_with_tools_stream(
+                request,
+                &context.available_tools.into_toolbox(),
+                context.method,
+                on_delta)
+                .await
+                .map_err(|e| AgentError::LlmError(e.to_string()))?;
+
+        self.decide(brain_decision)
+    }
+
+    async fn compact(&mut self, trace: &[ChatMessage], force: bool) -> Result<Option<Compaction>, AgentError> {
+        let context_window = self.llm.capabilities(&self.model).await.context_window();
+        if !force && !should_compact(context_window, trace) {
+            return Ok(None);
+        }
+        let compacted = compact(self.llm.clone(), self.model.clone(), trace.to_vec())
+            .await
+            .map_err(|e| AgentError::LlmError(e.to_string()))?;
+        Ok(compacted.map(|(trace, usage)| Compaction {
+            usage: self.call_usage(usage.as_ref()),
+            trace,
+        }))
     }
 }
 
 
 pub fn coder(llm: Arc<LlmClient>, model: String) -> impl Agent {
-    // Create shared storage for todo tools
-    let todo_storage = Arc::new(TodoStorage::new());
-    
-    // Create shared operation log for file system tools
-    let fs_log = Arc::new(FsOperationLog::new());
-    
-    let bash = Box::new(BashTool::new());
-    let edit = Box::new(EditTool::new(fs_log.clone()));
-    let multiedit = Box::new(MultiEditTool::new(fs_log.clone()));
-    let fetch = Box::new(FetchTool::new());
-    let find = Box::new(FindTool::new());
-    let ls = Box::new(LsTool::new());
-    let read = Box::new(ReadTool::new(fs_log.clone()));
-    let todoread = Box::new(TodoReadTool::new(todo_storage.clone()));
-    let todowrite = Box::new(TodoWriteTool::new(todo_storage.clone()));
-    let write = Box::new(WriteTool::new(fs_log.clone()));
-    let toolbox: Vec<Box<dyn AnyTool>> = vec![bash, edit, multiedit, fetch, find, ls, read, todoread, todowrite, write];
-
-    AgentBuilder::new(Box::new(CoderBrain::new(llm.clone(), model)))
+    let builder = AgentBuilder::new(Box::new(CoderBrain::new(llm.clone(), model)));
+    let toolbox = AgentBuilder::default_tools(&builder.todo_storage, &builder.fs_log);
+    builder
     .tools(toolbox)
     .build()
-}
\ No newline at end of file
+}
@@ -2,7 +2,7 @@ use super::coder::CoderBrain;
 use crate::agent::{Agent, Brain, StdoutEventManager, ThinkerContext};
 use crate::logging::LoggingConfig;
 use crate::tools::AnyTool;
-use krokit_llm::ToolCallMethod;
+use krokit_llm::{DeltaSink, ToolCallMethod};
 use krokit_llm::{ChatMessage, ChatMessageContent, client::LlmClient};
 use tokio::sync::RwLock;
 use std::sync::Arc;
@@ -16,9 +16,22 @@ fn init_test_logging() {
     });
 }
 
+fn fixture_path(fixture: &str) -> String {
+    format!("{}/src/runners/coder/fixtures/{}.json", env!("CARGO_MANIFEST_DIR"), fixture)
+}
+
+/// Client replaying the recorded fixture of a test, a provider from the environment
+/// when there is none (KROKIT_RECORD=1 records it)
+fn llm_client(fixture: &str) -> Arc<LlmClient> {
+    Arc::new(LlmClient::recorded(fixture_path(fixture)).expect("No LLM provider available"))
+}
+
 // Helper function to create a coder agent with full toolbox
-async fn create_coder_agent_with_goal(goal: &str) -> impl Agent {
-    let llm_client = Arc::new(LlmClient::first_from_env().expect("No LLM provider available"));
+async fn create_coder_agent_with_goal(fixture: &str, goal: &str) -> impl Agent {
+    create_coder_agent(llm_client(fixture), goal).await
+}
+
+async fn create_coder_agent(llm_client: Arc<LlmClient>, goal: &str) -> impl Agent {
     let model = llm_client.default_model().await.expect("default model");
     println!("using model: {:?}", model);
     
@@ -50,7 +63,7 @@ async fn create_coder_agent_with_goal(goal: &str) -> impl Agent {
 
 #[tokio::test]
 async fn test_coder_brain_creation() {
-    let llm_client = Arc::new(LlmClient::first_from_env().expect("No LLM provider available"));
+    let llm_client = llm_client("brain_creation");
     let model = llm_client.default_model().await.expect("default model");
     
     let brain = CoderBrain::new(llm_client, model.clone());
@@ -60,7 +73,7 @@ async fn test_coder_brain_creation() {
 
 #[tokio::test]
 async fn test_coder_brain_think_simple() {
-    let llm_client = Arc::new(LlmClient::first_from_env().expect("No LLM provider available"));
+    let llm_client = llm_client("brain_think_simple");
     let model = llm_client.default_model().await.expect("default model");
     let mut brain = CoderBrain::new(llm_client, model);
     
@@ -70,6 +83,7 @@ async fn test_coder_brain_think_simple() {
             content: ChatMessageContent::Text("Say hello".to_string()),
             name: None,
         }])),
+        tool_images: Default::default(),
         available_tools: vec![],
         method: ToolCallMethod::FunctionCall
     };
@@ -99,6 +113,7 @@ async fn test_coder_integration_simple_file_creation() {
     
     // Create a coder agent with full toolbox and goal
     let agent = create_coder_agent_with_goal(
+        "simple_file_creation",
         "Create a Python file called 'hello.py' that prints 'Hello, World!' when executed. The file should contain a proper function and a main guard."
     ).await;
     
@@ -135,6 +150,80 @@ async fn test_coder_integration_simple_file_creation() {
 
 
 
+#[tokio::test]
+async fn test_coder_replay_file_creation() {
+    init_test_logging();
+    let goal = "Create a Python file called 'hello.py' that prints 'Hello, World!' when executed.";
+    let recording_dir = TempDir::new().expect("Failed to create temp directory");
+    let fixture = recording_dir.path().join("replay_file_creation.json");
+
+    // record the hand written answers, the fixture gets the hash of each request
+    std::env::set_current_dir(recording_dir.path()).expect("Failed to change directory");
+    let script = LlmClient::replay(fixture_path("replay_file_creation")).expect("script of the test");
+    let mut agent = create_coder_agent(Arc::new(LlmClient::recording(script, &fixture)), goal).await;
+    let agent_result = agent.run().await.expect("Coder agent should complete successfully");
+    assert!(agent_result.success, "Agent should report success");
+
+    let cassette: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&fixture).unwrap()).unwrap();
+    let interactions = cassette["interactions"].as_array().unwrap();
+    assert_eq!(interactions.len(), 2);
+    assert!(interactions.iter().all(|interaction| interaction["hash"].is_string()), "{:#}", cassette);
+
+    // the replay in another directory answers the same requests
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    std::env::set_current_dir(temp_path).expect("Failed to change directory");
+    let agent = create_coder_agent(Arc::new(LlmClient::replay(&fixture).unwrap()), goal).await;
+    let agent_result = agent
+        .with_event_handler(StdoutEventManager::new())
+        .run().await
+        .expect("Coder agent should complete successfully");
+    assert!(agent_result.success, "Agent should report success");
+
+    let content = std::fs::read_to_string(temp_path.join("hello.py"))
+        .expect("hello.py should be created in temp directory");
+    assert!(content.contains("print(\"Hello, World!\")"));
+
+    // and not another one
+    let mut brain = CoderBrain::new(Arc::new(LlmClient::replay(&fixture).unwrap()), "replay-model".to_string());
+    let context = ThinkerContext {
+        trace: Arc::new(RwLock::new(vec![ChatMessage::User {
+            content: ChatMessageContent::Text("Create a Rust file instead.".to_string()),
+            name: None,
+        }])),
+        tool_images: Default::default(),
+        available_tools: vec![],
+        method: ToolCallMethod::FunctionCall
+    };
+    assert!(brain.next_step(context).await.is_err(), "a different request must not be answered");
+}
+
+#[tokio::test]
+async fn test_coder_streamed_usage() {
+    // hand written fixture, the usage comes in the last chunk of the stream
+    let llm_client = llm_client("replay_streamed_usage");
+    let model = llm_client.default_model().await.expect("default model");
+    let mut brain = CoderBrain::new(llm_client, model);
+
+    let context = ThinkerContext {
+        trace: Arc::new(RwLock::new(vec![ChatMessage::User {
+            content: ChatMessageContent::Text("Say hello".to_string()),
+            name: None,
+        }])),
+        tool_images: Default::default(),
+        available_tools: vec![],
+        method: ToolCallMethod::FunctionCall
+    };
+    let on_delta: DeltaSink = Arc::new(|_| {});
+
+    let decision = brain.next_step_stream(context, on_delta).await
+        .expect("Brain should stream the answer");
+    let usage = decision.usage.expect("a streamed answer reports its usage");
+    assert_eq!(usage.model, "replay-model");
+    assert_eq!(usage.tokens.prompt_tokens, 120);
+    assert_eq!(usage.tokens.completion_tokens, 2);
+}
+
 #[tokio::test]
 async fn test_multi_turn_conversation() {
     init_test_logging();
@@ -145,7 +234,7 @@ async fn test_multi_turn_conversation() {
     
     // Create a coder agent with full toolbox and goal
     let goal = "Create a Python file called 'hello.py' that prints 'Hello, World!' when executed.";
-    let mut agent = create_coder_agent_with_goal(goal).await;
+    let mut agent = create_coder_agent_with_goal("multi_turn_conversation", goal).await;
 
     // get a controller
     let controller = agent.controller();
@@ -213,6 +302,7 @@ if __name__ == "__main__":
     
     // Create a coder agent with full toolbox and goal
     let agent = create_coder_agent_with_goal(
+        "bug_fix_task",
         "There's a bug in calculator.py. Please read the file, identify the bug, and fix it so the code calculates the average correctly."
     ).await;
     
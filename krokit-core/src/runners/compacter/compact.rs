@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use openai_dive::v1::resources::chat::{ChatCompletionParametersBuilder, ChatMessageContentPart};
+use openai_dive::v1::resources::shared::Usage;
+use krokit_llm::{client::LlmClient, provider::LlmError, ChatMessage, ChatMessageContent};
+use krokit_llm::image::text_part;
+use tracing::debug;
+
+use super::prompt::{compact_prompt, compact_summary_message};
+
+/// rough number of characters per token, good enough to decide when to compact
+const CHARS_PER_TOKEN: usize = 4;
+
+/// fixed overhead per message (role, separators...)
+const TOKENS_PER_MESSAGE: usize = 4;
+
+/// tool results are truncated in the transcript sent to the summarizer
+const MAX_TOOL_RESULT_CHARS: usize = 4000;
+
+/// fraction (in percent) of the context window that triggers an automatic compaction
+const COMPACT_THRESHOLD_PERCENT: usize = 75;
+
+/// fraction (in percent) of the trace that is kept verbatim after compaction
+const KEEP_RECENT_PERCENT: usize = 30;
+
+/// Estimate the number of tokens of a list of messages
+pub fn estimate_tokens(messages: &[ChatMessage]) -> usize {
+    messages.iter().map(estimate_message_tokens).sum()
+}
+
+fn estimate_message_tokens(message: &ChatMessage) -> usize {
+    let chars = match message {
+        ChatMessage::System { content, .. } | ChatMessage::User { content, .. } | ChatMessage::Developer { content, .. } => {
+            content_text(content).len()
+        }
+        ChatMessage::Assistant { content, reasoning_content, tool_calls, .. } => {
+            content.as_ref().map_or(0, |c| content_text(c).len())
+            + reasoning_content.as_ref().map_or(0, |r| r.len())
+            + tool_calls.as_ref().map_or(0, |calls| calls.iter()
+                .map(|tc| tc.id.len() + tc.function.name.len() + tc.function.arguments.len())
+                .sum())
+        }
+        ChatMessage::Tool { content, tool_call_id } => {
+            content.len() + tool_call_id.len()
+        }
+    };
+    chars / CHARS_PER_TOKEN + TOKENS_PER_MESSAGE
+}
+
+/// Number of estimated tokens above which the trace should be compacted, for a model
+/// with this context window (see `LlmClient::capabilities`)
+pub fn compact_threshold(context_window: usize) -> usize {
+    context_window * COMPACT_THRESHOLD_PERCENT / 100
+}
+
+/// Returns true if the trace is large enough to be compacted for this context window
+pub fn should_compact(context_window: usize, trace: &[ChatMessage]) -> bool {
+    estimate_tokens(trace) >= compact_threshold(context_window)
+}
+
+/// Find the index where the recent (kept verbatim) part of the trace starts.
+/// Leading system messages are never summarized. The split never lands on a
+/// tool result so that an assistant message and the results of its tool calls
+/// always stay on the same side. Returns None if there is nothing to summarize.
+pub fn find_split_point(trace: &[ChatMessage], keep_tokens: usize) -> Option<usize> {
+    let start = trace.iter()
+        .take_while(|m| matches!(m, ChatMessage::System { .. }))
+        .count();
+
+    // walk back from the end until we kept enough, always keeping the last message
+    let mut kept = 0;
+    let mut split = trace.len();
+    while split > start + 1 && (split == trace.len() || kept < keep_tokens) {
+        split -= 1;
+        kept += estimate_message_tokens(&trace[split]);
+    }
+
+    // move backward so that we do not separate tool results from their call
+    while split > start && matches!(trace.get(split), Some(ChatMessage::Tool { .. })) {
+        split -= 1;
+    }
+
+    (split > start && split < trace.len()).then_some(split)
+}
+
+/// Summarize the oldest part of the trace. The returned trace starts with the
+/// leading system messages (if any), followed by a user message containing the
+/// summary and the most recent messages untouched, along with the usage of the
+/// summarizer call. Returns None if the trace is too short to be compacted.
+pub async fn compact(llm: Arc<LlmClient>, model: String, trace: Vec<ChatMessage>) -> Result<Option<(Vec<ChatMessage>, Option<Usage>)>, LlmError> {
+    let keep_tokens = estimate_tokens(&trace) * KEEP_RECENT_PERCENT / 100;
+    let Some(split) = find_split_point(&trace, keep_tokens) else {
+        return Ok(None);
+    };
+
+    let start = trace.iter()
+        .take_while(|m| matches!(m, ChatMessage::System { .. }))
+        .count();
+    debug!(target: "runner::compacter", start = start, split = split, len = trace.len());
+
+    let transcript = trace[start..split].iter()
+        .map(render_message)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let messages = vec![
+        ChatMessage::System {
+            content: ChatMessageContent::Text(compact_prompt()),
+            name: None
+        },
+        ChatMessage::User {
+            content: ChatMessageContent::Text(format!("<transcript>\n{}\n</transcript>", transcript)),
+            name: None
+        },
+    ];
+
+    let request = ChatCompletionParametersBuilder::default()
+        .model(model.clone())
+        .messages(messages)
+        .temperature(0.1)
+        .build()
+        .map_err(|e| -> LlmError { e.into() })?;
+
+    let response = llm.chat(request)
+        .await?;
+
+    let usage = response.usage;
+    let summary = match response.choices.into_iter().next().map(|c| c.message) {
+        Some(ChatMessage::Assistant { content: Some(content), .. }) => content_text(&content),
+        _ => String::new(),
+    };
+    if summary.trim().is_empty() {
+        return Err("summarizer returned an empty summary".into());
+    }
+
+    let mut compacted: Vec<ChatMessage> = trace[..start].to_vec();
+    compacted.extend(with_summary(&trace[split..], compact_summary_message(&summary)));
+    Ok(Some((compacted, usage)))
+}
+
+/// Recent messages preceded by the summary. When they start with a user message the
+/// summary is merged into it, two user messages in a row are rejected by some providers
+pub fn with_summary(recent: &[ChatMessage], summary: String) -> Vec<ChatMessage> {
+    let mut messages = recent.to_vec();
+    match messages.first_mut() {
+        Some(ChatMessage::User { content, .. }) => {
+            *content = match std::mem::replace(content, ChatMessageContent::None) {
+                ChatMessageContent::Text(text) => ChatMessageContent::Text(format!("{}\n\n{}", summary, text)),
+                ChatMessageContent::ContentPart(mut parts) => {
+                    parts.insert(0, text_part(summary));
+                    ChatMessageContent::ContentPart(parts)
+                }
+                ChatMessageContent::None => ChatMessageContent::Text(summary),
+            };
+        }
+        _ => messages.insert(0, ChatMessage::User {
+            content: ChatMessageContent::Text(summary),
+            name: None
+        }),
+    }
+    messages
+}
+
+/// Render a message as plain text for the summarizer, the summarizer request
+/// carries no tool definition so tool calls cannot be sent as is
+fn render_message(message: &ChatMessage) -> String {
+    match message {
+        ChatMessage::System { content, .. } | ChatMessage::Developer { content, .. } => {
+            format!("[system]\n{}", content_text(content))
+        }
+        ChatMessage::User { content, .. } => {
+            format!("[user]\n{}", content_text(content))
+        }
+        ChatMessage::Assistant { content, tool_calls, .. } => {
+            let mut out = String::from("[assistant]");
+            if let Some(text) = content.as_ref().map(content_text).filter(|t| !t.trim().is_empty()) {
+                out.push('\n');
+                out.push_str(&text);
+            }
+            for tc in tool_calls.iter().flatten() {
+                out.push_str(&format!("\n-> call {} {}", tc.function.name, tc.function.arguments));
+            }
+            out
+        }
+        ChatMessage::Tool { content, .. } => {
+            if content.chars().count() > MAX_TOOL_RESULT_CHARS {
+                let truncated: String = content.chars().take(MAX_TOOL_RESULT_CHARS).collect();
+                format!("[tool result]\n{}\n... (truncated)", truncated)
+            } else {
+                format!("[tool result]\n{}", content)
+            }
+        }
+    }
+}
+
+fn content_text(content: &ChatMessageContent) -> String {
+    match content {
+        ChatMessageContent::Text(text) => text.clone(),
+        ChatMessageContent::ContentPart(parts) => {
+            parts.iter().filter_map(|part| match part {
+                ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                _ => None,
+            }).collect::<Vec<_>>().join(" ")
+        }
+        ChatMessageContent::None => String::new(),
+    }
+}
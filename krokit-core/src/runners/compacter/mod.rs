@@ -1 +1,7 @@
-pub mod compact;
\ No newline at end of file
+pub mod compact;
+pub mod prompt;
+
+#[cfg(test)]
+mod tests;
+
+pub use compact::{compact, estimate_tokens, compact_threshold, should_compact};
@@ -0,0 +1,27 @@
+static COMPACT_PROMPT: &str = r#"
+You are compacting the history of a coding session between a user and an AI coding agent so that the agent can keep working with a smaller context. You will receive a transcript of the oldest part of the conversation. Write a summary that lets the agent continue the work seamlessly without access to the original messages.
+
+Your summary must cover, in this order:
+1. The user's requests and intents, quoting explicit instructions or constraints verbatim when they matter
+2. Key technical decisions, concepts and conventions discovered along the way
+3. Files and code sections that were read, created or modified, with the important details (paths, function names, short snippets if critical)
+4. Errors encountered and how they were fixed
+5. Work that was completed, and work that is still pending
+6. The task that was in progress at the end of the transcript and the next step
+
+Guidelines:
+• Be precise and factual, never invent anything that is not in the transcript
+• Prefer dense bullet points over prose
+• Keep file paths, commands and identifiers exactly as written
+• Do not address the user, do not add any preamble or closing remark, output only the summary
+"#;
+
+static COMPACT_HEADER: &str = "This session is being continued from a previous conversation that was compacted to save context space. The summary below covers the earlier portion of the conversation.";
+
+pub fn compact_prompt() -> String {
+    COMPACT_PROMPT.to_string()
+}
+
+pub fn compact_summary_message(summary: &str) -> String {
+    format!("{}\n\n<summary>\n{}\n</summary>", COMPACT_HEADER, summary.trim())
+}
@@ -0,0 +1,128 @@
+use super::compact::{compact, compact_threshold, estimate_tokens, find_split_point, should_compact, with_summary};
+use krokit_llm::capabilities::builtin_capabilities;
+use krokit_llm::{ChatMessage, ChatMessageContent, ToolCall, Function, client::LlmClient};
+use std::sync::Arc;
+
+fn user(text: &str) -> ChatMessage {
+    ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }
+}
+
+fn assistant(text: &str, calls: Vec<&str>) -> ChatMessage {
+    ChatMessage::Assistant {
+        content: Some(ChatMessageContent::Text(text.to_string())),
+        reasoning_content: None,
+        refusal: None,
+        name: None,
+        audio: None,
+        tool_calls: if calls.is_empty() { None } else {
+            Some(calls.into_iter().map(|id| ToolCall {
+                id: id.to_string(),
+                r#type: "function".to_string(),
+                function: Function { name: "read".to_string(), arguments: "{}".to_string() }
+            }).collect())
+        },
+    }
+}
+
+fn tool(id: &str, text: &str) -> ChatMessage {
+    ChatMessage::Tool { content: text.to_string(), tool_call_id: id.to_string() }
+}
+
+#[test]
+fn test_estimate_tokens_grows_with_content() {
+    let small = vec![user("hello")];
+    let large = vec![user(&"x".repeat(4000))];
+    assert!(estimate_tokens(&small) < estimate_tokens(&large));
+    assert!(estimate_tokens(&large) >= 1000);
+}
+
+#[test]
+fn test_threshold_depends_on_context_window() {
+    let claude = builtin_capabilities("claude-sonnet-4").context_window();
+    let unknown = builtin_capabilities("unknown-model").context_window();
+    assert!(claude > unknown);
+    assert!(compact_threshold(claude) < claude);
+    assert!(!should_compact(claude, &[user("hello")]));
+    assert!(should_compact(unknown, &[user(&"x".repeat(200_000))]));
+}
+
+#[test]
+fn test_split_point_never_lands_on_tool_result() {
+    let big = "y".repeat(2000);
+    let trace = vec![
+        user("first request"),
+        assistant("reading", vec!["call_1", "call_2"]),
+        tool("call_1", &big),
+        tool("call_2", &big),
+        assistant("done", vec![]),
+        user("second request"),
+        assistant("reading again", vec!["call_3"]),
+        tool("call_3", &big),
+    ];
+
+    for keep in [0, 10, 500, 1000, 2000] {
+        if let Some(split) = find_split_point(&trace, keep) {
+            assert!(split > 0 && split < trace.len());
+            assert!(!matches!(trace[split], ChatMessage::Tool { .. }), "split {} on tool result (keep {})", split, keep);
+        }
+    }
+}
+
+#[test]
+fn test_split_point_keeps_leading_system_and_needs_history() {
+    let trace = vec![
+        ChatMessage::System { content: ChatMessageContent::Text("system".to_string()), name: None },
+        user("only message"),
+    ];
+    assert_eq!(find_split_point(&trace, 0), None);
+
+    let trace = vec![
+        ChatMessage::System { content: ChatMessageContent::Text("system".to_string()), name: None },
+        user("old request"),
+        assistant("old answer", vec![]),
+        user("new request"),
+    ];
+    let split = find_split_point(&trace, 0).expect("split point");
+    assert!(split >= 2);
+}
+
+#[test]
+fn test_summary_is_merged_into_the_next_user_message() {
+    let merged = with_summary(&[user("now rename main to start"), assistant("ok", vec![])], "summary".to_string());
+    assert_eq!(merged.len(), 2);
+    assert!(matches!(&merged[0], ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text == "summary\n\nnow rename main to start"));
+
+    let inserted = with_summary(&[assistant("ok", vec![])], "summary".to_string());
+    assert_eq!(inserted.len(), 2);
+    assert!(matches!(&inserted[0], ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text == "summary"));
+}
+
+#[tokio::test]
+async fn test_compact_with_llm() {
+    let Some(llm) = LlmClient::first_from_env() else {
+        println!("no llm provider available, skipping");
+        return;
+    };
+    let llm = Arc::new(llm);
+    let model = llm.default_model().await.expect("default model");
+
+    let big = "fn main() { println!(\"hello\"); }\n".repeat(200);
+    let trace = vec![
+        user("please read src/main.rs and explain it"),
+        assistant("let me read the file", vec!["call_1"]),
+        tool("call_1", &big),
+        assistant("the file prints hello many times", vec![]),
+        user("now rename main to start"),
+        assistant("ok", vec![]),
+    ];
+
+    let (compacted, _usage) = compact(llm, model, trace.clone()).await
+        .expect("compaction failed")
+        .expect("trace should be compacted");
+
+    assert!(compacted.len() < trace.len());
+    assert!(matches!(compacted[0], ChatMessage::User { .. }));
+    // the summary never makes two user messages in a row
+    assert!(compacted.windows(2).all(|pair| !matches!(pair, [ChatMessage::User { .. }, ChatMessage::User { .. }])));
+    assert!(estimate_tokens(&compacted) < estimate_tokens(&trace));
+}
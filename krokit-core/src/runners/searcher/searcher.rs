@@ -6,7 +6,7 @@ use async_trait::async_trait;
 
 use crate::agent::brain::ThinkerDecision;
 use crate::agent::{Agent, AgentBuilder, AgentError, Brain, ThinkerContext};
-use crate::tools::{AnyTool, FetchTool, FindTool, LsTool, ReadTool, TodoReadTool, TodoWriteTool, TodoStorage};
+use crate::tools::{AnyTool, FetchTool, FindTool, LsTool, ReadTool, SemanticSearchTool, TodoReadTool, TodoWriteTool, TodoStorage};
 
 use super::prompt::searcher_next_step;
 
@@ -82,13 +82,14 @@ pub fn searcher(llm: Arc<LlmClient>, model: String) -> impl Agent {
     let todo_storage = Arc::new(TodoStorage::new());
     
     // Only read-only tools for the searcher
-    let fetch = Box::new(FetchTool::new());
+    let fetch = Box::new(FetchTool::from_config());
     let find = Box::new(FindTool::new());
     let ls = Box::new(LsTool::new());
     let read = Box::new(ReadTool::new(Arc::new(crate::tools::FsOperationLog::new())));
+    let semantic_search = Box::new(SemanticSearchTool::from_config());
     let todoread = Box::new(TodoReadTool::new(todo_storage.clone()));
     let todowrite = Box::new(TodoWriteTool::new(todo_storage.clone()));
-    let toolbox: Vec<Box<dyn AnyTool>> = vec![fetch, find, ls, read, todoread, todowrite];
+    let toolbox: Vec<Box<dyn AnyTool>> = vec![fetch, find, ls, read, semantic_search, todoread, todowrite];
     
     AgentBuilder::new(Box::new(SearcherBrain{llm: llm.clone(), model}))
     .tools(toolbox)
@@ -26,6 +26,7 @@ async fn create_searcher_agent_with_goal(goal: &str) -> impl Agent {
             Box::new(crate::tools::FindTool::new()),
             Box::new(crate::tools::LsTool::new()),
             Box::new(crate::tools::ReadTool::new(Arc::new(crate::tools::FsOperationLog::new()))),
+            Box::new(crate::tools::SemanticSearchTool::local()),
             Box::new(crate::tools::TodoReadTool::new(Arc::new(crate::tools::TodoStorage::new()))),
             Box::new(crate::tools::TodoWriteTool::new(Arc::new(crate::tools::TodoStorage::new()))),
         ])
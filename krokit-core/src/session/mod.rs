@@ -0,0 +1,8 @@
+pub mod session;
+pub mod store;
+
+#[cfg(test)]
+mod tests;
+
+pub use session::{Session, SessionSummary, TurnSummary};
+pub use store::{SessionStore, SessionRecorder, SessionError, Rewind};
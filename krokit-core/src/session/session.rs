@@ -0,0 +1,142 @@
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use krokit_llm::{ChatMessage, ChatMessageContent};
+use openai_dive::v1::resources::chat::ChatMessageContentPart;
+use serde::{Deserialize, Serialize};
+
+use crate::agent::claims::Permission;
+use crate::tools::{CheckpointLog, FsOperation, TodoItem};
+use crate::usage::TokenUsage;
+
+/// maximum length of the title derived from the first user message
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Snapshot of an agent session, written after every turn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub working_dir: PathBuf,
+    /// name of the agent config used to start the session, None for the default coder
+    pub agent_name: Option<String>,
+    pub trace: Vec<ChatMessage>,
+    #[serde(default)]
+    pub todos: Vec<TodoItem>,
+    #[serde(default)]
+    pub fs_operations: Vec<FsOperation>,
+    /// permissions granted for this session only, sudo mode is never saved
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+    /// tokens consumed since the session started
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// previous content of the files modified at each turn, the contents are in the blob store
+    #[serde(default)]
+    pub checkpoints: CheckpointLog,
+}
+
+impl Session {
+    pub fn new(id: &str, agent_name: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: id.to_string(),
+            created_at: now,
+            updated_at: now,
+            working_dir: std::env::current_dir().unwrap_or_default(),
+            agent_name,
+            trace: vec![],
+            todos: vec![],
+            fs_operations: vec![],
+            permissions: vec![],
+            usage: TokenUsage::default(),
+            checkpoints: CheckpointLog::default(),
+        }
+    }
+
+    /// First user message of the trace, on a single line and truncated
+    pub fn title(&self) -> String {
+        let first = self.trace.iter().find_map(|m| match m {
+            ChatMessage::User { content, .. } => Some(content_text(content)),
+            _ => None,
+        }).unwrap_or_default();
+
+        let line = first.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.chars().count() > MAX_TITLE_CHARS {
+            format!("{}...", line.chars().take(MAX_TITLE_CHARS).collect::<String>())
+        } else {
+            line
+        }
+    }
+
+    /// Turns that modified files, oldest first
+    pub fn file_turns(&self) -> Vec<TurnSummary> {
+        let mut turns: Vec<TurnSummary> = Vec::new();
+        for entry in &self.checkpoints.entries {
+            match turns.iter_mut().find(|t| t.turn == entry.turn) {
+                Some(turn) => turn.files.push(entry.path.clone()),
+                None => turns.push(TurnSummary {
+                    turn: entry.turn,
+                    prompt: self.turn_prompt(entry.turn),
+                    files: vec![entry.path.clone()],
+                }),
+            }
+        }
+        turns.sort_by_key(|t| t.turn);
+        turns
+    }
+
+    /// User message that started a turn, if the trace still has it
+    fn turn_prompt(&self, turn: usize) -> Option<String> {
+        let index = *self.checkpoints.trace_marks.get(&turn)?;
+        match self.trace.get(index)? {
+            ChatMessage::User { content, .. } => Some(content_text(content)),
+            _ => None,
+        }
+    }
+
+    pub fn summary(&self) -> SessionSummary {
+        SessionSummary {
+            id: self.id.clone(),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            working_dir: self.working_dir.clone(),
+            agent_name: self.agent_name.clone(),
+            title: self.title(),
+            message_count: self.trace.len(),
+        }
+    }
+}
+
+/// Lightweight description of a stored session, used for listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub working_dir: PathBuf,
+    pub agent_name: Option<String>,
+    pub title: String,
+    pub message_count: usize,
+}
+
+/// A turn of the session and the files it modified
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnSummary {
+    pub turn: usize,
+    pub prompt: Option<String>,
+    pub files: Vec<String>,
+}
+
+fn content_text(content: &ChatMessageContent) -> String {
+    match content {
+        ChatMessageContent::Text(text) => text.clone(),
+        ChatMessageContent::ContentPart(parts) => {
+            parts.iter().filter_map(|part| match part {
+                ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                _ => None,
+            }).collect::<Vec<_>>().join(" ")
+        }
+        ChatMessageContent::None => String::new(),
+    }
+}
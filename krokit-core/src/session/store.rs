@@ -0,0 +1,244 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use chrono::Utc;
+use krokit_llm::ChatMessage;
+
+use crate::agent::ClaimManager;
+use crate::tools::{CheckpointError, FileCheckpoints, FsOperationLog, TodoStorage};
+use crate::usage::TokenUsage;
+use super::session::{Session, SessionSummary};
+
+/// Directory based session storage, one json file per session
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    /// Store located in ~/.config/krokit/sessions
+    pub fn new() -> Result<Self, SessionError> {
+        Ok(Self { dir: Self::sessions_dir()? })
+    }
+
+    /// Store located in an arbitrary directory
+    pub fn with_dir(dir: PathBuf) -> Result<Self, SessionError> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Get the sessions directory path, creating it if needed
+    pub fn sessions_dir() -> Result<PathBuf, SessionError> {
+        let config_dir = std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| {
+                dirs::home_dir()
+                    .map(|home| home.join(".config"))
+                    .ok_or(SessionError::NoSessionDir)
+            })?;
+
+        let sessions_dir = config_dir.join("krokit").join("sessions");
+        std::fs::create_dir_all(&sessions_dir)?;
+        Ok(sessions_dir)
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+
+    /// Content addressed store of the file checkpoints of a session
+    pub fn blobs_dir(&self, id: &str) -> PathBuf {
+        self.dir.join("blobs").join(id)
+    }
+
+    /// Path of the session file, ids are made of letters, digits, '-' and '_' so that they
+    /// cannot point outside of the sessions directory
+    fn session_path(&self, id: &str) -> Result<PathBuf, SessionError> {
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(SessionError::InvalidId(id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    /// Write the session, the file is replaced atomically
+    pub fn save(&self, session: &Session) -> Result<(), SessionError> {
+        let json_str = serde_json::to_string_pretty(session)?;
+        let path = self.session_path(&session.id)?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json_str)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn load(&self, id: &str) -> Result<Session, SessionError> {
+        let path = self.session_path(id)?;
+        if !path.exists() {
+            return Err(SessionError::NotFound(id.to_string()));
+        }
+        let json_str = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json_str)?)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), SessionError> {
+        let path = self.session_path(id)?;
+        if !path.exists() {
+            return Err(SessionError::NotFound(id.to_string()));
+        }
+        std::fs::remove_file(path)?;
+        match std::fs::remove_dir_all(self.blobs_dir(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// All stored sessions, most recently updated first.
+    /// Files that cannot be parsed are skipped.
+    pub fn list(&self) -> Result<Vec<SessionSummary>, SessionError> {
+        let mut sessions = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(json_str) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Ok(session) = serde_json::from_str::<Session>(&json_str) {
+                sessions.push(session.summary());
+            }
+        }
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        Ok(sessions)
+    }
+
+    /// Most recently updated session started from the current directory,
+    /// or the most recent one overall if there is none
+    pub fn latest(&self) -> Result<Session, SessionError> {
+        let sessions = self.list()?;
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let summary = sessions.iter()
+            .find(|s| s.working_dir == cwd)
+            .or(sessions.first())
+            .ok_or(SessionError::NoSession)?;
+        self.load(&summary.id)
+    }
+
+    /// Load a session by id or unique id prefix, or the latest session if no id is given
+    pub fn resolve(&self, id: Option<&str>) -> Result<Session, SessionError> {
+        let Some(id) = id else {
+            return self.latest();
+        };
+        if self.session_path(id)?.exists() {
+            return self.load(id);
+        }
+        let matches: Vec<SessionSummary> = self.list()?
+            .into_iter()
+            .filter(|s| s.id.starts_with(id))
+            .collect();
+        match matches.as_slice() {
+            [single] => self.load(&single.id),
+            [] => Err(SessionError::NotFound(id.to_string())),
+            _ => Err(SessionError::Ambiguous(id.to_string())),
+        }
+    }
+
+    /// Bring the files modified by the session back to their state before `turn`
+    /// and drop the conversation from that turn on (when the trace was not compacted since).
+    /// The session is saved and the restored files are returned.
+    pub async fn rewind(&self, session: &mut Session, turn: usize) -> Result<Rewind, SessionError> {
+        if turn == 0 || turn > session.checkpoints.turn {
+            return Err(SessionError::InvalidTurn(turn));
+        }
+
+        let checkpoints = FileCheckpoints::new();
+        checkpoints.set_blob_dir(self.blobs_dir(&session.id));
+        checkpoints.restore(session.checkpoints.clone()).await;
+        let (files, trace_len) = checkpoints.rewind(turn, session.trace.len()).await?;
+
+        let messages_removed = trace_len.map(|len| {
+            let removed = session.trace.len() - len;
+            session.trace.truncate(len);
+            removed
+        });
+
+        session.checkpoints = checkpoints.log().await;
+        session.updated_at = Utc::now();
+        self.save(session)?;
+        Ok(Rewind { files, messages_removed })
+    }
+}
+
+/// Outcome of a session rewind
+#[derive(Debug, Clone)]
+pub struct Rewind {
+    pub files: Vec<String>,
+    /// None if the conversation could not be rewound
+    pub messages_removed: Option<usize>,
+}
+
+/// Keeps the session metadata along with the shared tool states so that the
+/// agent can write a complete snapshot at the end of each turn
+#[derive(Clone)]
+pub struct SessionRecorder {
+    pub store: SessionStore,
+    pub session: Session,
+    pub todo_storage: Arc<TodoStorage>,
+    pub fs_log: Arc<FsOperationLog>,
+}
+
+impl SessionRecorder {
+    pub fn new(store: SessionStore, session: Session, todo_storage: Arc<TodoStorage>, fs_log: Arc<FsOperationLog>) -> Self {
+        Self { store, session, todo_storage, fs_log }
+    }
+
+    /// Snapshot the current agent state and write it to the store.
+    /// Empty sessions are not written.
+    pub async fn checkpoint(&mut self, trace: &[ChatMessage], permissions: &ClaimManager, usage: TokenUsage) -> Result<(), SessionError> {
+        if !trace.iter().any(|m| matches!(m, ChatMessage::User { .. })) {
+            return Ok(());
+        }
+        self.session.updated_at = Utc::now();
+        self.session.trace = trace.to_vec();
+        self.session.todos = self.todo_storage.get_all().await;
+        self.session.fs_operations = self.fs_log.get_all_operations().await;
+        // permissions granted for good are already saved in the permission files
+        self.session.permissions = permissions.get_all_permissions().iter()
+            .filter(|p| p.session_only)
+            .cloned()
+            .collect();
+        self.session.usage = usage;
+        self.session.checkpoints = self.fs_log.checkpoints.log().await;
+        self.store.save(&self.session)
+    }
+}
+
+/// Errors that can occur while storing or loading sessions
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("Session '{0}' not found")]
+    NotFound(String),
+
+    #[error("Session id '{0}' is ambiguous")]
+    Ambiguous(String),
+
+    #[error("Invalid session id '{0}', ids are made of letters, digits, '-' and '_'")]
+    InvalidId(String),
+
+    #[error("No session to resume")]
+    NoSession,
+
+    #[error("Turn {0} does not exist in this session")]
+    InvalidTurn(usize),
+
+    #[error("Checkpoint error: {0}")]
+    Checkpoint(#[from] CheckpointError),
+
+    #[error("Could not find home directory")]
+    NoSessionDir,
+
+    #[error("File access error: {0}")]
+    FileAccess(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
@@ -0,0 +1,175 @@
+use std::sync::Arc;
+use krokit_llm::{ChatMessage, ChatMessageContent};
+use serde_json::json;
+use tempfile::TempDir;
+
+use crate::agent::ClaimManager;
+use crate::agent::claims::{MatchStrategy, Permission};
+use crate::tools::{FsOperationLog, FsOperationType, TodoItem, TodoStatus, TodoStorage};
+use crate::usage::TokenUsage;
+use super::{Session, SessionError, SessionRecorder, SessionStore};
+
+fn user(text: &str) -> ChatMessage {
+    ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }
+}
+
+fn session_with_trace(id: &str, messages: &[&str]) -> Session {
+    let mut session = Session::new(id, None);
+    session.trace = messages.iter().map(|m| user(m)).collect();
+    session
+}
+
+#[test]
+fn test_save_and_load_session() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().to_path_buf()).unwrap();
+
+    let mut session = session_with_trace("abc", &["fix the build"]);
+    session.agent_name = Some("reviewer".to_string());
+    store.save(&session).unwrap();
+
+    let loaded = store.load("abc").unwrap();
+    assert_eq!(loaded.id, "abc");
+    assert_eq!(loaded.trace.len(), 1);
+    assert_eq!(loaded.agent_name.as_deref(), Some("reviewer"));
+}
+
+#[test]
+fn test_load_missing_session() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().to_path_buf()).unwrap();
+    assert!(matches!(store.load("nope"), Err(SessionError::NotFound(_))));
+    assert!(matches!(store.latest(), Err(SessionError::NoSession)));
+}
+
+#[test]
+fn test_list_is_sorted_by_update_time() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().to_path_buf()).unwrap();
+
+    let mut old = session_with_trace("old", &["first"]);
+    old.updated_at = old.updated_at - chrono::Duration::hours(1);
+    store.save(&old).unwrap();
+    store.save(&session_with_trace("new", &["second"])).unwrap();
+    std::fs::write(dir.path().join("garbage.json"), "not a session").unwrap();
+
+    let sessions = store.list().unwrap();
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].id, "new");
+    assert_eq!(sessions[1].id, "old");
+    assert_eq!(store.latest().unwrap().id, "new");
+}
+
+#[test]
+fn test_resolve_by_prefix() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().to_path_buf()).unwrap();
+    store.save(&session_with_trace("1234-aaaa", &["a"])).unwrap();
+    store.save(&session_with_trace("1234-bbbb", &["b"])).unwrap();
+
+    assert_eq!(store.resolve(Some("1234-a")).unwrap().id, "1234-aaaa");
+    assert!(matches!(store.resolve(Some("1234")), Err(SessionError::Ambiguous(_))));
+    assert!(matches!(store.resolve(Some("9")), Err(SessionError::NotFound(_))));
+}
+
+#[test]
+fn test_ids_cannot_leave_the_sessions_dir() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().join("sessions")).unwrap();
+    std::fs::write(dir.path().join("secret.json"), "{}").unwrap();
+
+    for id in ["../secret", "/tmp/x", "a/b", ""] {
+        assert!(matches!(store.resolve(Some(id)), Err(SessionError::InvalidId(_))), "{}", id);
+        assert!(matches!(store.delete(id), Err(SessionError::InvalidId(_))), "{}", id);
+    }
+    assert!(store.save(&session_with_trace("../escape", &["a"])).is_err());
+    assert!(!dir.path().join("escape.json").exists());
+    assert!(dir.path().join("secret.json").exists());
+}
+
+#[test]
+fn test_title_is_first_user_message_on_one_line() {
+    let long = "word ".repeat(40);
+    let session = session_with_trace("t", &["refactor\nthe   parser", "second"]);
+    assert_eq!(session.title(), "refactor the parser");
+
+    let session = session_with_trace("t", &[&long]);
+    assert!(session.title().ends_with("..."));
+    assert_eq!(session.title().chars().count(), 83);
+}
+
+#[tokio::test]
+async fn test_recorder_checkpoint() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().to_path_buf()).unwrap();
+    let todo_storage = Arc::new(TodoStorage::new());
+    let fs_log = Arc::new(FsOperationLog::new());
+    let mut recorder = SessionRecorder::new(store.clone(), Session::new("rec", None), todo_storage.clone(), fs_log.clone());
+
+    let mut permissions = ClaimManager::new();
+    permissions.add_permission(Permission::new("bash".to_string(), MatchStrategy::Exact, json!({"command": "ls"}), true));
+
+    // nothing is written before the user said something
+    recorder.checkpoint(&[], &permissions, TokenUsage::default()).await.unwrap();
+    assert!(store.list().unwrap().is_empty());
+
+    todo_storage.replace_all(vec![TodoItem {
+        id: "1".to_string(),
+        content: "write tests".to_string(),
+        status: TodoStatus::InProgress,
+        created_at: String::new(),
+        updated_at: String::new(),
+    }]).await;
+    fs_log.log_operation(FsOperationType::Read, "src/main.rs".to_string()).await;
+
+    let usage = TokenUsage { prompt_tokens: 100, completion_tokens: 10, cached_tokens: 0, cost: Some(0.01) };
+    recorder.checkpoint(&[user("hello")], &permissions, usage).await.unwrap();
+    let saved = store.load("rec").unwrap();
+    assert_eq!(saved.trace.len(), 1);
+    assert_eq!(saved.todos.len(), 1);
+    assert_eq!(saved.fs_operations.len(), 1);
+    assert_eq!(saved.permissions.len(), 1);
+    assert_eq!(saved.usage, usage);
+}
+
+#[tokio::test]
+async fn test_rewind_restores_files_and_trace() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::with_dir(dir.path().join("sessions")).unwrap();
+    let file = dir.path().join("main.rs").to_string_lossy().to_string();
+    std::fs::write(&file, "v1").unwrap();
+
+    let fs_log = Arc::new(FsOperationLog::new());
+    fs_log.checkpoints.set_blob_dir(store.blobs_dir("rw"));
+    let mut recorder = SessionRecorder::new(store.clone(), Session::new("rw", None), Arc::new(TodoStorage::new()), fs_log.clone());
+
+    let mut trace = vec![];
+    for (i, prompt) in ["first", "second"].iter().enumerate() {
+        fs_log.checkpoints.begin_turn(trace.len()).await;
+        trace.push(user(prompt));
+        fs_log.checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, format!("v{}", i + 2)).unwrap();
+    }
+    recorder.checkpoint(&trace, &ClaimManager::new(), TokenUsage::default()).await.unwrap();
+
+    let mut session = store.load("rw").unwrap();
+    let turns = session.file_turns();
+    assert_eq!(turns.len(), 2);
+    assert_eq!(turns[1].prompt.as_deref(), Some("second"));
+
+    assert!(matches!(store.rewind(&mut session, 3).await, Err(SessionError::InvalidTurn(3))));
+    let rewind = store.rewind(&mut session, 2).await.unwrap();
+    assert_eq!(rewind.files, vec![file.clone()]);
+    assert_eq!(rewind.messages_removed, Some(1));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+
+    let saved = store.load("rw").unwrap();
+    assert_eq!(saved.trace.len(), 1);
+    assert_eq!(saved.checkpoints.turn, 1);
+    assert_eq!(saved.file_turns().len(), 1);
+    // only the snapshot of the first turn is left
+    assert_eq!(std::fs::read_dir(store.blobs_dir("rw")).unwrap().count(), 1);
+
+    store.delete("rw").unwrap();
+    assert!(!store.blobs_dir("rw").exists());
+}
@@ -1,58 +1,112 @@
-use super::structs::BashToolParams;
-use crate::tools::{tool, ToolResult};
+use super::structs::{BashAction, BashToolParams};
+use super::sandbox::{ExecBackend, HostBackend};
+#[cfg(unix)]
+use super::shell::{ShellError, ShellSession};
+use crate::tools::{tool, ProgressSink, ToolResult};
 use serde_json::json;
 use tokio_util::sync::CancellationToken;
 use std::collections::HashMap;
+#[cfg(not(unix))]
+use std::path::Path;
+#[cfg(not(unix))]
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::process::Command;
-use tokio::io::{AsyncReadExt, BufReader};
+#[cfg(not(unix))]
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+use tokio::sync::Mutex;
 
-pub struct BashTool;
+pub struct BashTool {
+    backend: Arc<dyn ExecBackend>,
+    /// persistent shell of the agent, started on the first command
+    #[cfg(unix)]
+    shell: Mutex<Option<ShellSession>>,
+}
 
 impl BashTool {
     pub fn new() -> Self {
-        Self
+        Self::with_backend(Box::new(HostBackend))
     }
 
-    async fn kill_process_group(child: &mut tokio::process::Child) {
-        #[cfg(unix)]
-        {
-            // Try to kill the entire process group
-            if let Some(pid) = child.id() {
-                unsafe {
-                    // Kill the process group (negative PID kills the group)
-                    libc::kill(-(pid as i32), libc::SIGTERM);
-                    
-                    // Give it a moment to terminate gracefully
-                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                    
-                    // Force kill if still running
-                    libc::kill(-(pid as i32), libc::SIGKILL);
-                }
+    /// Run the commands through another backend, typically a sandbox
+    pub fn with_backend(backend: Box<dyn ExecBackend>) -> Self {
+        Self {
+            backend: Arc::from(backend),
+            #[cfg(unix)]
+            shell: Mutex::new(None),
+        }
+    }
+
+    /// The running shell, a new one is started if needed
+    #[cfg(unix)]
+    async fn shell_session<'a>(&self, shell: &'a mut Option<ShellSession>) -> Result<&'a mut ShellSession, ShellError> {
+        if shell.is_none() {
+            *shell = Some(ShellSession::start(self.backend.as_ref()).await?);
+        }
+        Ok(shell.as_mut().unwrap())
+    }
+
+    /// Read a stream to the end, forwarding it to the progress sink as it comes
+    #[cfg(not(unix))]
+    async fn read_output<R: AsyncRead + Unpin>(stream: R, progress: Option<ProgressSink>) -> Result<String, std::io::Error> {
+        let mut reader = BufReader::new(stream);
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            let n = reader.read(&mut buffer).await?;
+            if n == 0 {
+                break;
             }
+            if let Some(progress) = &progress {
+                progress(String::from_utf8_lossy(&buffer[..n]).to_string());
+            }
+            output.extend_from_slice(&buffer[..n]);
         }
-        
-        // Fallback: kill just the immediate child
+        Ok(String::from_utf8_lossy(&output).to_string())
+    }
+
+    /// Kill the process, there are no process groups to kill without unix
+    #[cfg(not(unix))]
+    async fn kill_process_group(child: &mut tokio::process::Child) {
         let _ = child.kill().await;
         let _ = child.wait().await;
     }
 
-    async fn execute_command(&self, params: &BashToolParams, cancel_token: Option<CancellationToken>) -> Result<(String, String, i32), Box<dyn std::error::Error + Send + Sync>> {       
+    async fn execute_command(&self, params: &BashToolParams, cancel_token: Option<CancellationToken>, progress: Option<ProgressSink>) -> Result<(String, String, i32), Box<dyn std::error::Error + Send + Sync>> {       
         // Validate command is not empty
         if params.command.trim().is_empty() {
             return Err("Command cannot be empty".into());
         }
 
-        // Create the command with process group
-        let mut cmd = Command::new("bash");
-        cmd.args(["-c", &params.command]);
-
-        // Set working directory if specified
-        if let Some(working_dir) = &params.working_dir {
-            cmd.current_dir(working_dir);
+        // In the persistent shell stdout and stderr come interleaved from the terminal
+        #[cfg(unix)]
+        {
+            let mut shell = self.shell.lock().await;
+            let session = self.shell_session(&mut *shell).await?;
+            let timeout = params.timeout.map(|secs| Duration::from_secs(secs as u64));
+            let result = session.run(&params.command, params.working_dir.as_deref(), &params.env, timeout, cancel_token, progress).await;
+            if !session.is_alive() {
+                *shell = None;
+            }
+            return match result {
+                Ok((output, exit_code)) => Ok((output, String::new(), exit_code)),
+                Err(e) => match e.output().filter(|output| !output.trim().is_empty()) {
+                    Some(output) => Err(format!("{}\n{}", e, output).into()),
+                    None => Err(e.into()),
+                },
+            };
         }
 
+        #[cfg(not(unix))]
+        self.execute_oneshot(params, cancel_token, progress).await
+    }
+
+    /// Run the command in a new process, where the persistent shell is not available
+    #[cfg(not(unix))]
+    async fn execute_oneshot(&self, params: &BashToolParams, cancel_token: Option<CancellationToken>, progress: Option<ProgressSink>) -> Result<(String, String, i32), Box<dyn std::error::Error + Send + Sync>> {
+        // Create the command (in the working directory if specified)
+        let mut cmd = self.backend.command(&params.command, params.working_dir.as_deref().map(Path::new))?;
+
         // Set environment variables
         for (key, value) in &params.env {
             cmd.env(key, value);
@@ -64,26 +118,13 @@ impl BashTool {
            .stdin(Stdio::null());
 
         // Spawn the process
-        #[cfg(unix)]
-        cmd.process_group(0);
-        
         let mut child = cmd.spawn()?;
         
         // Read output asynchronously (needed to prevent blocking on full buffers)
         let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
-        let stdout_task = tokio::spawn(async move {
-            let mut reader = BufReader::new(stdout);
-            let mut output = String::new();
-            reader.read_to_string(&mut output).await?;
-            Ok::<String, std::io::Error>(output)
-        });
+        let stdout_task = tokio::spawn(Self::read_output(stdout, progress.clone()));
         let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
-        let stderr_task = tokio::spawn(async move {
-            let mut reader = BufReader::new(stderr);
-            let mut output = String::new();
-            reader.read_to_string(&mut output).await?;
-            Ok::<String, std::io::Error>(output)
-        });
+        let stderr_task = tokio::spawn(Self::read_output(stderr, progress));
 
 
         // Optionable Future
@@ -128,13 +169,62 @@ impl BashTool {
             }
         }
     }
+
+    /// start, read, kill and jobs actions
+    async fn execute_job(&self, params: &BashToolParams) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        #[cfg(not(unix))]
+        return Err("Background jobs are only supported on unix".into());
+
+        #[cfg(unix)]
+        {
+            let job_id = || params.job_id.ok_or_else(|| format!("job_id is required for the {:?} action", params.action).to_lowercase());
+            let mut shell = self.shell.lock().await;
+            let session = self.shell_session(&mut *shell).await?;
+
+            let result = match params.action {
+                BashAction::Start => {
+                    if params.command.trim().is_empty() {
+                        return Err("Command cannot be empty".into());
+                    }
+                    session.start_job(&params.command, params.working_dir.as_deref(), &params.env).await
+                        .map(|job| format!("Started job {} (pid {}): {}\nUse the read action with job_id {} to see its output.", job.id, job.pid, job.command, job.id))
+                }
+                BashAction::Read => {
+                    let id = job_id()?;
+                    session.read_job(id).map(|(output, status)| match output.is_empty() {
+                        true => format!("Job {} is {}, no new output", id, status),
+                        false => format!("Job {} is {}, new output:\n{}", id, status, output),
+                    })
+                }
+                BashAction::Kill => {
+                    let id = job_id()?;
+                    session.kill_job(id).await.map(|status| format!("Job {} {}", id, status))
+                }
+                BashAction::Jobs => {
+                    let jobs = session.jobs();
+                    Ok(match jobs.is_empty() {
+                        true => "No background jobs".to_string(),
+                        false => jobs.iter()
+                            .map(|(job, status)| format!("[{}] {}: {}", job.id, status, job.command))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    })
+                }
+                BashAction::Run => unreachable!("run is not a job action"),
+            };
+            if !session.is_alive() {
+                *shell = None;
+            }
+            Ok(result?)
+        }
+    }
 }
 
 #[tool(name = "bash", description = r#"
 Executes shell commands within the user's environment. This tool is powerful and requires careful handling to ensure safety and predictability. It is your primary tool for compiling code, running tests, and managing version control with git.
 
 SECURITY WARNING:
- - You are operating in a live user environment without a sandbox.
+ - Commands run in the user's environment, with the user's privileges, unless the agent is configured with a sandbox. In a sandbox only the project directory (and the directories configured as writable) can be written to and the network may be cut off: "Read-only file system" and network errors come from the sandbox, do not try to work around them.
  - NEVER execute commands that could have unintended consequences, such as deleting files (rm), modifying system-wide configurations, or installing software without explicit, step-by-step user consent.
 - When in doubt, ask the user for confirmation before proceeding with any command that modifies the file system.
 
@@ -143,7 +233,8 @@ Development Workflow:
 - Running Tests: After making changes, always run the project's test suite (e.g., npm test, pytest, cargo test) to verify that your changes haven't introduced any regressions.
 
 Usage Guidelines:
-- this tool always runs from the same path. If you need to execute command in another directory, chain the commands with && for instance "cd subcrate && cargo test"
+- Commands run one after the other in the same shell: the current directory, exported variables and activated virtualenvs are kept between calls. The shell has no input, do not run interactive programs.
+- Long running processes (dev servers, watchers) must not be run directly: start them in the background with action "start", then use action "read" with the returned job_id to get their new output, "kill" to stop them and "jobs" to list them.
 - For file system navigation and inspection, prefer the built-in ls, read, and find tools. Use bash for executing other programs or scripts.
 - Always provide a clear, concise description of the command's purpose for the user.
 - Chain commands using && to ensure that subsequent commands only run if the previous ones succeed.
@@ -158,10 +249,24 @@ Examples:
 - DANGEROUS: curl http://example.com/install.sh | sh (Executes a script from the internet without inspection)
 "#, capabilities = [ToolCapability::Read, ToolCapability::Write, ToolCapability::Network])]
 impl BashTool {
-    async fn execute(&self, params: BashToolParams, cancel_token: Option<CancellationToken>) -> ToolResult {
+    async fn execute(&self, params: BashToolParams, cancel_token: Option<CancellationToken>, progress: Option<ProgressSink>) -> ToolResult {
         let start_time = Instant::now();
+
+        if params.action != BashAction::Run {
+            let mut metadata = HashMap::new();
+            metadata.insert("action".to_string(), json!(format!("{:?}", params.action).to_lowercase()));
+            if let Some(job_id) = params.job_id {
+                metadata.insert("job_id".to_string(), json!(job_id));
+            }
+            let result = self.execute_job(&params).await;
+            metadata.insert("execution_time_ms".to_string(), json!(start_time.elapsed().as_millis()));
+            return match result {
+                Ok(output) => ToolResult::Success { output, metadata: Some(metadata) },
+                Err(e) => ToolResult::Error { error: e.to_string(), metadata: Some(metadata) },
+            };
+        }
         
-        match self.execute_command(&params, cancel_token).await {
+        match self.execute_command(&params, cancel_token, progress).await {
             Ok((stdout, stderr, exit_code)) => {
                 let execution_time = start_time.elapsed();
                 let mut metadata = HashMap::new();
@@ -175,6 +280,7 @@ impl BashTool {
                     metadata.insert("timeout".to_string(), json!("none"));
                 }
                 metadata.insert("success".to_string(), json!(exit_code == 0));
+                metadata.insert("backend".to_string(), json!(self.backend.name()));
                 
                 if let Some(working_dir) = &params.working_dir {
                     metadata.insert("working_dir".to_string(), json!(working_dir));
@@ -191,15 +297,6 @@ impl BashTool {
                     metadata.insert("stderr_length".to_string(), json!(stderr.len()));
                 }
                 
-                // Prepare error message if needed
-                let error_message = if exit_code != 0 && has_stderr {
-                    Some(format!("Command failed with exit code {}: {}", exit_code, stderr))
-                } else if exit_code != 0 {
-                    Some(format!("Command failed with exit code {}", exit_code))
-                } else {
-                    None
-                };
-                
                 // Combine stdout and stderr for output
                 let output = if stderr.is_empty() {
                     stdout
@@ -215,8 +312,14 @@ impl BashTool {
                         metadata: Some(metadata),
                     }
                 } else {
+                    // in the persistent shell stderr comes in the output, the model needs all of it
+                    let error = if output.trim().is_empty() {
+                        format!("Command failed with exit code {}", exit_code)
+                    } else {
+                        format!("Command failed with exit code {}:\n{}", exit_code, output)
+                    };
                     ToolResult::Error {
-                        error: error_message.unwrap_or_else(|| format!("Command failed with exit code {}", exit_code)),
+                        error,
                         metadata: Some(metadata),
                     }
                 }
@@ -1,8 +1,16 @@
 pub mod structs;
 pub mod bash;
+pub mod sandbox;
+#[cfg(unix)]
+pub mod pty;
+#[cfg(unix)]
+pub mod shell;
 
 #[cfg(test)]
 mod tests;
 
-pub use structs::BashToolParams;
-pub use bash::BashTool;
\ No newline at end of file
+pub use structs::{BashAction, BashToolParams};
+pub use bash::BashTool;
+pub use sandbox::{ExecBackend, HostBackend, BubblewrapBackend, SandboxConfig, SandboxKind, ResourceLimits, SandboxError};
+#[cfg(unix)]
+pub use shell::{ShellSession, ShellError, Job, JobStatus};
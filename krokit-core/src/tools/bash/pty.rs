@@ -0,0 +1,60 @@
+use std::io;
+use std::os::unix::io::RawFd;
+
+/// Open a new pseudo terminal, returns the (master, slave) file descriptors
+pub fn open_pty_pair() -> io::Result<(RawFd, RawFd)> {
+    let master_fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY) };
+    if master_fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    if unsafe { libc::grantpt(master_fd) } == -1 || unsafe { libc::unlockpt(master_fd) } == -1 {
+        let err = io::Error::last_os_error();
+        unsafe { libc::close(master_fd) };
+        return Err(err);
+    }
+
+    let slave_name = unsafe {
+        let ptr = libc::ptsname(master_fd);
+        if ptr.is_null() {
+            let err = io::Error::last_os_error();
+            libc::close(master_fd);
+            return Err(err);
+        }
+        std::ffi::CStr::from_ptr(ptr).to_owned()
+    };
+
+    let slave_fd = unsafe { libc::open(slave_name.as_ptr(), libc::O_RDWR | libc::O_NOCTTY) };
+    if slave_fd == -1 {
+        let err = io::Error::last_os_error();
+        unsafe { libc::close(master_fd) };
+        return Err(err);
+    }
+
+    Ok((master_fd, slave_fd))
+}
+
+/// Stop the terminal from echoing the input back, so that only the output of the
+/// commands is read from the master
+pub fn disable_echo(fd: RawFd) -> io::Result<()> {
+    unsafe {
+        let mut termios: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(fd, &mut termios) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        termios.c_lflag &= !(libc::ECHO | libc::ECHONL);
+        if libc::tcsetattr(fd, libc::TCSANOW, &termios) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Size of the terminal seen by the programs running in it
+pub fn set_window_size(fd: RawFd, rows: u16, cols: u16) -> io::Result<()> {
+    let size = libc::winsize { ws_row: rows, ws_col: cols, ws_xpixel: 0, ws_ypixel: 0 };
+    if unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &size) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Builds the process that runs a bash command. The bash tool takes care of
+/// the io, timeouts and cancellation, the backend decides where the command runs.
+pub trait ExecBackend: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Command running `script` with bash, from `working_dir` if given. Only used where
+    /// the persistent shell is not available.
+    fn command(&self, _script: &str, _working_dir: Option<&Path>) -> Result<Command, SandboxError> {
+        Err(SandboxError::Unsupported(format!("{} cannot run one-shot commands", self.name())))
+    }
+
+    /// Command starting the persistent interactive shell. `runtime_dir` holds the
+    /// scripts and job logs exchanged with the tool and must be writable by the shell.
+    fn shell(&self, _runtime_dir: &Path) -> Result<Command, SandboxError> {
+        self.command(SHELL_COMMAND, None)
+    }
+}
+
+/// Interactive bash without user configuration and line editing
+pub(crate) const SHELL_COMMAND: &str = "exec bash --noprofile --norc --noediting -i";
+
+/// Runs the command directly on the host, with the privileges of the user
+#[derive(Debug, Clone, Default)]
+pub struct HostBackend;
+
+impl ExecBackend for HostBackend {
+    fn name(&self) -> &str {
+        "host"
+    }
+
+    fn command(&self, script: &str, working_dir: Option<&Path>) -> Result<Command, SandboxError> {
+        let mut cmd = Command::new("bash");
+        cmd.args(["-c", script]);
+        if let Some(dir) = working_dir {
+            cmd.current_dir(dir);
+        }
+        Ok(cmd)
+    }
+}
+
+/// Which backend runs the bash commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxKind {
+    /// no isolation
+    Host,
+    /// bubblewrap (linux namespaces)
+    #[default]
+    Bubblewrap,
+}
+
+/// Resource limits applied to the sandboxed commands (setrlimit), unset means unlimited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// address space in MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u64>,
+    /// cpu time in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_secs: Option<u64>,
+    /// size of a written file in MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size_mb: Option<u64>,
+    /// number of open files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_files: Option<u64>,
+}
+
+/// Sandbox settings of an agent config:
+///
+/// ```json
+/// "sandbox": { "backend": "bubblewrap", "network": false, "writable": ["~/.cargo"], "limits": { "memory_mb": 4096 } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    #[serde(default)]
+    pub backend: SandboxKind,
+    /// allow network access
+    #[serde(default = "default_network")]
+    pub network: bool,
+    /// directories writable in addition to the project directory
+    #[serde(default)]
+    pub writable: Vec<String>,
+    #[serde(default)]
+    pub limits: ResourceLimits,
+}
+
+fn default_network() -> bool {
+    true
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            backend: SandboxKind::default(),
+            network: true,
+            writable: Vec::new(),
+            limits: ResourceLimits::default(),
+        }
+    }
+}
+
+impl SandboxConfig {
+    /// Create the backend, the project directory is the current directory
+    pub fn backend(&self) -> Result<Box<dyn ExecBackend>, SandboxError> {
+        match self.backend {
+            SandboxKind::Host => Ok(Box::new(HostBackend)),
+            SandboxKind::Bubblewrap => {
+                let project_dir = std::env::current_dir()?;
+                Ok(Box::new(BubblewrapBackend::new(project_dir, self.clone())?))
+            }
+        }
+    }
+}
+
+/// Runs the commands with bubblewrap: the root filesystem is mounted read-only, the
+/// project directory (and the configured ones) stay writable, /tmp is private and
+/// the network can be cut off.
+#[derive(Debug, Clone)]
+pub struct BubblewrapBackend {
+    bwrap: PathBuf,
+    project_dir: PathBuf,
+    config: SandboxConfig,
+}
+
+impl BubblewrapBackend {
+    pub fn new(project_dir: PathBuf, config: SandboxConfig) -> Result<Self, SandboxError> {
+        if !cfg!(target_os = "linux") {
+            return Err(SandboxError::Unsupported("bubblewrap is only available on linux".to_string()));
+        }
+        let bwrap = find_in_path("bwrap")
+            .ok_or_else(|| SandboxError::Unsupported("bwrap was not found in PATH, install bubblewrap".to_string()))?;
+        Ok(Self { bwrap, project_dir, config })
+    }
+}
+
+impl ExecBackend for BubblewrapBackend {
+    fn name(&self) -> &str {
+        "bubblewrap"
+    }
+
+    fn shell(&self, runtime_dir: &Path) -> Result<Command, SandboxError> {
+        let runtime_dir = runtime_dir.to_string_lossy().to_string();
+        let mut cmd = Command::new(&self.bwrap);
+        cmd.args(bwrap_args(&self.project_dir, &self.config, None));
+        cmd.args(["--bind", &runtime_dir, &runtime_dir]);
+        cmd.args(["--", "bash", "-c", SHELL_COMMAND]);
+        apply_limits(&mut cmd, &self.config.limits);
+        Ok(cmd)
+    }
+}
+
+/// bwrap arguments, before the command itself
+pub(crate) fn bwrap_args(project_dir: &Path, config: &SandboxConfig, working_dir: Option<&Path>) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "--ro-bind", "/", "/",
+        "--dev", "/dev",
+        "--proc", "/proc",
+        "--tmpfs", "/tmp",
+        "--unshare-pid",
+        "--unshare-ipc",
+        "--unshare-uts",
+        // no --new-session: the shell runs in a pty and needs it as controlling terminal
+        // for the job control and the interrupts
+        "--die-with-parent",
+    ].into_iter().map(String::from).collect();
+
+    if !config.network {
+        args.push("--unshare-net".to_string());
+    }
+
+    let writable = std::iter::once(project_dir.to_path_buf())
+        .chain(config.writable.iter().map(|dir| expand_home(dir)));
+    for dir in writable {
+        if dir.exists() {
+            let dir = dir.to_string_lossy().to_string();
+            args.extend(["--bind".to_string(), dir.clone(), dir]);
+        }
+    }
+
+    let cwd = working_dir
+        .map(|dir| if dir.is_absolute() { dir.to_path_buf() } else { project_dir.join(dir) })
+        .unwrap_or_else(|| project_dir.to_path_buf());
+    args.extend(["--chdir".to_string(), cwd.to_string_lossy().to_string()]);
+    args
+}
+
+/// Set the resource limits in the child before exec, they are inherited by the sandbox
+fn apply_limits(cmd: &mut Command, limits: &ResourceLimits) {
+    #[cfg(unix)]
+    {
+        const MB: u64 = 1024 * 1024;
+        let mut rlimits: Vec<(libc::c_int, u64)> = Vec::new();
+        if let Some(memory) = limits.memory_mb {
+            rlimits.push((libc::RLIMIT_AS as libc::c_int, memory * MB));
+        }
+        if let Some(cpu) = limits.cpu_secs {
+            rlimits.push((libc::RLIMIT_CPU as libc::c_int, cpu));
+        }
+        if let Some(size) = limits.file_size_mb {
+            rlimits.push((libc::RLIMIT_FSIZE as libc::c_int, size * MB));
+        }
+        if let Some(files) = limits.open_files {
+            rlimits.push((libc::RLIMIT_NOFILE as libc::c_int, files));
+        }
+        if rlimits.is_empty() {
+            return;
+        }
+
+        // only async-signal-safe calls are allowed between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                for (resource, value) in &rlimits {
+                    let limit = libc::rlimit {
+                        rlim_cur: *value as libc::rlim_t,
+                        rlim_max: *value as libc::rlim_t,
+                    };
+                    if libc::setrlimit(*resource as _, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+fn find_in_path(binary: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(binary))
+            .find(|path| path.is_file())
+    })
+}
+
+fn expand_home(dir: &str) -> PathBuf {
+    match dir.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|home| home.join(rest)).unwrap_or_else(|| PathBuf::from(dir)),
+        None => PathBuf::from(dir),
+    }
+}
+
+/// Errors that can occur while setting up a sandbox
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Sandbox not supported: {0}")]
+    Unsupported(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
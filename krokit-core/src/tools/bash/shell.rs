@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+use std::fs::{DirBuilder, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::io::FromRawFd;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use super::pty::{disable_echo, open_pty_pair, set_window_size};
+use super::sandbox::{ExecBackend, SandboxError};
+use crate::tools::ProgressSink;
+
+/// Time given to the shell to start, or to answer an internal command
+const SHELL_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time given to the shell to come back after a command was interrupted
+const RECOVER_TIMEOUT: Duration = Duration::from_secs(2);
+/// Maximum output returned by a single read of a background job
+const MAX_JOB_READ: usize = 64 * 1024;
+
+/// State of a background job
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Exited(i32),
+    Killed,
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "running"),
+            JobStatus::Exited(code) => write!(f, "exited with code {}", code),
+            JobStatus::Killed => write!(f, "killed"),
+        }
+    }
+}
+
+/// A command started in the background, its output goes to a log file
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: usize,
+    pub command: String,
+    /// pid of the job as seen from the shell (it differs from the host in a sandbox)
+    pub pid: u32,
+    log: PathBuf,
+    status_file: PathBuf,
+    /// how much of the log was already read
+    offset: u64,
+    killed: bool,
+}
+
+impl Job {
+    pub fn status(&self) -> JobStatus {
+        if self.killed {
+            return JobStatus::Killed;
+        }
+        match std::fs::read_to_string(&self.status_file) {
+            Ok(code) => JobStatus::Exited(code.trim().parse().unwrap_or(-1)),
+            Err(_) => JobStatus::Running,
+        }
+    }
+}
+
+/// Long-lived interactive bash running in a pseudo terminal. The working directory,
+/// variables and background jobs survive between commands. Each command is followed
+/// by a marker carrying its exit code, the output is everything printed before it.
+pub struct ShellSession {
+    child: Child,
+    master: File,
+    output: mpsc::UnboundedReceiver<Vec<u8>>,
+    /// output read from the terminal but not consumed yet
+    pending: Vec<u8>,
+    /// how much of the pending output was already sent as progress
+    streamed: usize,
+    /// scripts and job logs, shared with the shell
+    runtime_dir: PathBuf,
+    token: String,
+    next_id: usize,
+    jobs: Vec<Job>,
+    alive: bool,
+}
+
+impl ShellSession {
+    pub async fn start(backend: &dyn ExecBackend) -> Result<Self, ShellError> {
+        let token = Uuid::new_v4().simple().to_string();
+        let runtime_dir = std::env::temp_dir().join(format!("krokit-shell-{}", token));
+        // only the user may read the scripts and the job logs, and a directory created
+        // in advance by someone else is not reused
+        DirBuilder::new().mode(0o700).create(&runtime_dir)?;
+
+        let (master_fd, slave_fd) = open_pty_pair()?;
+        let master = unsafe { File::from_raw_fd(master_fd) };
+        let slave = unsafe { File::from_raw_fd(slave_fd) };
+        disable_echo(slave_fd)?;
+        set_window_size(master_fd, 50, 200)?;
+
+        let mut cmd = backend.shell(&runtime_dir)?;
+        cmd.env("PS1", "")
+           .env("PS2", "")
+           .env("HISTFILE", "/dev/null")
+           .env("TERM", "dumb")
+           .env("PAGER", "cat")
+           .env("GIT_PAGER", "cat")
+           .stdin(Stdio::from(slave.try_clone()?))
+           .stdout(Stdio::from(slave.try_clone()?))
+           .stderr(Stdio::from(slave));
+        unsafe {
+            cmd.pre_exec(|| {
+                // new session with the pty as controlling terminal, for job control and ctrl-c
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                libc::ioctl(0, libc::TIOCSCTTY as _, 0);
+                Ok(())
+            });
+        }
+        let child = cmd.spawn()?;
+        // the parent must not keep the slave open, otherwise the end of the shell goes unnoticed
+        drop(cmd);
+
+        let mut reader = master.try_clone()?;
+        let (tx, rx) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if tx.send(buffer[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let mut session = Self {
+            child,
+            master,
+            output: rx,
+            pending: Vec::new(),
+            streamed: 0,
+            runtime_dir,
+            token,
+            next_id: 0,
+            jobs: Vec::new(),
+            alive: true,
+        };
+        // anything printed before (startup warnings) is dropped with the output of this command
+        session.exec("PS1=''; PS2=''; PROMPT_COMMAND=''; set +o history", Some(SHELL_TIMEOUT), None, None).await?;
+        Ok(session)
+    }
+
+    /// False once the shell exited or stopped answering, a new session must be started
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+
+    /// Run a command in the shell and wait for it, returns its output (stdout and
+    /// stderr interleaved) and exit code. `env` only applies to this command.
+    /// Complete lines are sent to `progress` as they are printed.
+    pub async fn run(
+        &mut self,
+        command: &str,
+        working_dir: Option<&str>,
+        env: &HashMap<String, String>,
+        timeout: Option<Duration>,
+        cancel_token: Option<CancellationToken>,
+        progress: Option<ProgressSink>,
+    ) -> Result<(String, i32), ShellError> {
+        let script = self.runtime_dir.join(format!("cmd-{}.sh", self.next_id + 1));
+        std::fs::write(&script, command)?;
+
+        let mut line = String::new();
+        if let Some(dir) = working_dir {
+            line.push_str(&format!("cd -- {} && ", quote(dir)));
+        }
+        line.push_str(&env_prefix(env)?);
+        // sourced so that cd, exports and functions stay in the shell
+        line.push_str(&format!(". {} < /dev/null", quote(&script.to_string_lossy())));
+
+        let result = self.exec(&line, timeout, cancel_token, progress.as_ref()).await;
+        let _ = std::fs::remove_file(&script);
+        result
+    }
+
+    /// Start a command in the background, it inherits the directory and variables of the shell
+    pub async fn start_job(
+        &mut self,
+        command: &str,
+        working_dir: Option<&str>,
+        env: &HashMap<String, String>,
+    ) -> Result<Job, ShellError> {
+        let id = self.jobs.len() + 1;
+        let script = self.runtime_dir.join(format!("job-{}.sh", id));
+        let log = self.runtime_dir.join(format!("job-{}.log", id));
+        let status_file = self.runtime_dir.join(format!("job-{}.status", id));
+        std::fs::write(&script, command)?;
+
+        let mut inner = String::new();
+        if let Some(dir) = working_dir {
+            inner.push_str(&format!("cd -- {} && ", quote(dir)));
+        }
+        inner.push_str(&env_prefix(env)?);
+        inner.push_str(&format!(". {}", quote(&script.to_string_lossy())));
+        let line = format!(
+            "( ( {} ); echo $? > {} ) > {} 2>&1 < /dev/null &\nprintf '%s\\n' \"$!\"; disown 2>/dev/null",
+            inner,
+            quote(&status_file.to_string_lossy()),
+            quote(&log.to_string_lossy()),
+        );
+
+        let (output, _) = self.exec(&line, Some(SHELL_TIMEOUT), None, None).await?;
+        let pid = output.lines().rev()
+            .find_map(|line| line.trim().parse::<u32>().ok())
+            .ok_or_else(|| ShellError::JobStart(output.clone()))?;
+
+        let job = Job { id, command: command.to_string(), pid, log, status_file, offset: 0, killed: false };
+        self.jobs.push(job.clone());
+        Ok(job)
+    }
+
+    /// Output of a background job since the previous read, and its status
+    pub fn read_job(&mut self, id: usize) -> Result<(String, JobStatus), ShellError> {
+        let job = self.jobs.iter_mut().find(|j| j.id == id).ok_or(ShellError::UnknownJob(id))?;
+        // status first, so that the last output of a finished job is not missed
+        let status = job.status();
+
+        let mut content = Vec::new();
+        match File::open(&job.log) {
+            Ok(mut file) => {
+                file.seek(SeekFrom::Start(job.offset))?;
+                file.read_to_end(&mut content)?;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        job.offset += content.len() as u64;
+
+        let mut output = String::new();
+        if content.len() > MAX_JOB_READ {
+            output.push_str(&format!("... ({} bytes skipped)\n", content.len() - MAX_JOB_READ));
+            content.drain(..content.len() - MAX_JOB_READ);
+        }
+        output.push_str(&String::from_utf8_lossy(&content));
+        Ok((output, status))
+    }
+
+    /// Stop a background job and the processes it started
+    pub async fn kill_job(&mut self, id: usize) -> Result<JobStatus, ShellError> {
+        let job = self.jobs.iter().find(|j| j.id == id).ok_or(ShellError::UnknownJob(id))?;
+        let status = job.status();
+        if status != JobStatus::Running {
+            return Ok(status);
+        }
+
+        // with job control the job has its own process group
+        let pid = job.pid;
+        let line = format!(
+            "kill -TERM -- -{pid} 2>/dev/null || kill -TERM {pid} 2>/dev/null; sleep 0.2; kill -KILL -- -{pid} 2>/dev/null; kill -KILL {pid} 2>/dev/null; true",
+            pid = pid
+        );
+        self.exec(&line, Some(SHELL_TIMEOUT), None, None).await?;
+
+        if let Some(job) = self.jobs.iter_mut().find(|j| j.id == id) {
+            job.killed = true;
+        }
+        Ok(JobStatus::Killed)
+    }
+
+    pub fn jobs(&self) -> Vec<(Job, JobStatus)> {
+        self.jobs.iter().map(|job| (job.clone(), job.status())).collect()
+    }
+
+    /// Send a line to the shell followed by the marker, returns the marker
+    fn send(&mut self, line: &str) -> Result<String, ShellError> {
+        self.next_id += 1;
+        let marker = format!("__KROKIT_{}_{}_", self.token, self.next_id);
+        let input = format!("{}\nprintf '\\n{}%s__\\n' \"$?\"\n", line, marker);
+        self.master.write_all(input.as_bytes())?;
+        self.master.flush()?;
+        Ok(marker)
+    }
+
+    async fn exec(
+        &mut self,
+        line: &str,
+        timeout: Option<Duration>,
+        cancel_token: Option<CancellationToken>,
+        progress: Option<&ProgressSink>,
+    ) -> Result<(String, i32), ShellError> {
+        let marker = self.send(line)?;
+
+        let cancel_future = async {
+            match &cancel_token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+        let timeout_future = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+
+        let timed_out = tokio::select! {
+            result = self.wait_marker(&marker, progress) => return result,
+            _ = cancel_future => false,
+            _ = timeout_future => true,
+        };
+
+        let output = self.interrupt().await;
+        if timed_out {
+            Err(ShellError::Timeout(timeout.unwrap_or_default().as_secs(), output))
+        } else {
+            Err(ShellError::Cancelled(output))
+        }
+    }
+
+    /// Ctrl-c the running command and wait for the shell to be ready again,
+    /// returns the output printed so far. The session is abandoned if the shell
+    /// does not come back.
+    async fn interrupt(&mut self) -> String {
+        if self.master.write_all(&[0x03]).is_err() {
+            self.alive = false;
+            return self.take_pending();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let Ok(marker) = self.send("true") else {
+            self.alive = false;
+            return self.take_pending();
+        };
+        match tokio::time::timeout(RECOVER_TIMEOUT, self.wait_marker(&marker, None)).await {
+            Ok(Ok((output, _))) => output,
+            _ => {
+                self.alive = false;
+                self.take_pending()
+            }
+        }
+    }
+
+    async fn wait_marker(&mut self, marker: &str, progress: Option<&ProgressSink>) -> Result<(String, i32), ShellError> {
+        loop {
+            if let Some(result) = self.take_until(marker) {
+                return Ok(result);
+            }
+            match self.output.recv().await {
+                Some(chunk) => {
+                    self.pending.extend_from_slice(&chunk);
+                    if let Some(progress) = progress {
+                        self.stream_lines(marker, progress);
+                    }
+                }
+                None => {
+                    self.alive = false;
+                    return Err(ShellError::Exited(self.take_pending()));
+                }
+            }
+        }
+    }
+
+    /// Send the complete lines not streamed yet. The last newline is held back with
+    /// the marker: it may be the one printed before it.
+    fn stream_lines(&mut self, marker: &str, progress: &ProgressSink) {
+        let limit = find(&self.pending, marker.as_bytes()).unwrap_or(self.pending.len());
+        let Some(mut end) = self.pending[..limit].iter().rposition(|b| *b == b'\n') else {
+            return;
+        };
+        if end > 0 && self.pending[end - 1] == b'\r' {
+            end -= 1;
+        }
+        if end <= self.streamed {
+            return;
+        }
+        let lines = self.clean_output(&self.pending[self.streamed..end]);
+        self.streamed = end;
+        if !lines.is_empty() {
+            progress(lines);
+        }
+    }
+
+    /// Consume the pending output up to the complete marker line
+    fn take_until(&mut self, marker: &str) -> Option<(String, i32)> {
+        let start = find(&self.pending, marker.as_bytes())?;
+        let code_start = start + marker.len();
+        let code_len = find(&self.pending[code_start..], b"__")?;
+        let line_end = code_start + code_len + find(&self.pending[code_start + code_len..], b"\n")? + 1;
+
+        let code = String::from_utf8_lossy(&self.pending[code_start..code_start + code_len])
+            .parse()
+            .unwrap_or(-1);
+        let output: Vec<u8> = self.pending.drain(..line_end).take(start).collect();
+        self.streamed = 0;
+        let output = self.clean_output(&output);
+        // the marker is printed after a newline
+        let output = output.strip_suffix('\n').map(|o| o.to_string()).unwrap_or(output);
+        Some((output, code))
+    }
+
+    fn take_pending(&mut self) -> String {
+        let pending = std::mem::take(&mut self.pending);
+        self.streamed = 0;
+        self.clean_output(&pending)
+    }
+
+    /// Terminal line endings, and markers of interrupted commands
+    fn clean_output(&self, output: &[u8]) -> String {
+        let prefix = format!("__KROKIT_{}_", self.token);
+        String::from_utf8_lossy(output)
+            .replace("\r\n", "\n")
+            .split_inclusive('\n')
+            .filter(|line| !line.contains(&prefix))
+            .collect()
+    }
+}
+
+impl Drop for ShellSession {
+    fn drop(&mut self) {
+        if self.alive {
+            // stop the background jobs and leave
+            let running: Vec<String> = self.jobs.iter()
+                .filter(|job| job.status() == JobStatus::Running)
+                .map(|job| format!("-{}", job.pid))
+                .collect();
+            let mut line = String::new();
+            if !running.is_empty() {
+                line.push_str(&format!("kill -TERM -- {} 2>/dev/null; ", running.join(" ")));
+            }
+            line.push_str("exit\n");
+            let _ = self.master.write_all(line.as_bytes());
+        }
+
+        // kill the shell's session if it did not leave by itself
+        let pid = self.child.id();
+        let runtime_dir = self.runtime_dir.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(500));
+            if let Some(pid) = pid {
+                unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
+            }
+            let _ = std::fs::remove_dir_all(runtime_dir);
+        });
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Single quote a string for the shell
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Variable assignments prefixing a command, they only apply to it
+fn env_prefix(env: &HashMap<String, String>) -> Result<String, ShellError> {
+    let mut prefix = String::new();
+    for (key, value) in env {
+        let valid = key.chars().enumerate()
+            .all(|(i, c)| c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit()));
+        if key.is_empty() || !valid {
+            return Err(ShellError::InvalidEnv(key.clone()));
+        }
+        prefix.push_str(&format!("{}={} ", key, quote(value)));
+    }
+    Ok(prefix)
+}
+
+/// Errors of the persistent shell
+#[derive(Debug, thiserror::Error)]
+pub enum ShellError {
+    #[error("Command timed out after {0} seconds")]
+    Timeout(u64, String),
+
+    #[error("Command was cancelled by user")]
+    Cancelled(String),
+
+    #[error("The shell exited, a new one will be started for the next command")]
+    Exited(String),
+
+    #[error("Unknown background job {0}")]
+    UnknownJob(usize),
+
+    #[error("Invalid environment variable name: {0}")]
+    InvalidEnv(String),
+
+    #[error("Failed to start the background job: {0}")]
+    JobStart(String),
+
+    #[error("Sandbox error: {0}")]
+    Sandbox(#[from] SandboxError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ShellError {
+    /// Output printed by the command before it was stopped
+    pub fn output(&self) -> Option<&str> {
+        match self {
+            ShellError::Timeout(_, output) | ShellError::Cancelled(output) | ShellError::Exited(output) => Some(output),
+            _ => None,
+        }
+    }
+}
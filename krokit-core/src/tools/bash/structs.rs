@@ -2,15 +2,37 @@ use serde::Deserialize;
 use schemars::JsonSchema;
 use std::collections::HashMap;
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BashAction {
+    /// Run the command in the shell and wait for it to finish
+    #[default]
+    Run,
+    /// Start the command as a background job and return immediately
+    Start,
+    /// Read the new output of a background job
+    Read,
+    /// Stop a background job
+    Kill,
+    /// List the background jobs
+    Jobs,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct BashToolParams {
-    /// The bash command to execute
+    /// The bash command to execute (run and start actions)
+    #[serde(default)]
     pub command: String,
+    /// What to do: run (default), start, read, kill or jobs
+    #[serde(default)]
+    pub action: BashAction,
+    /// Id of the background job (read and kill actions)
+    pub job_id: Option<usize>,
     /// Timeout in seconds (optional, None = no timeout)
     pub timeout: Option<u32>,
-    /// Working directory for command execution (optional)
+    /// Working directory for command execution (optional), the shell stays in it afterwards
     pub working_dir: Option<String>,
-    /// Environment variables to set (optional)
+    /// Environment variables to set for this command only (optional)
     #[serde(default)]
     pub env: HashMap<String, String>,
 }
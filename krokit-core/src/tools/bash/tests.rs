@@ -1,4 +1,4 @@
-use super::structs::BashToolParams;
+use super::structs::{BashAction, BashToolParams};
 use super::bash::BashTool;
 use crate::tools::{Tool, ToolCapability};
 use krokit_llm::ToolDescription;
@@ -27,6 +27,8 @@ async fn test_bash_tool_execution() {
     let tool = BashTool::new();
     let params = BashToolParams {
         command: "echo hello".to_string(),
+        action: BashAction::Run,
+        job_id: None,
         timeout: None,
         working_dir: None,
         env: HashMap::new(),
@@ -42,4 +44,194 @@ async fn test_bash_tool_execution() {
     } else {
         panic!("Expected success result");
     }
-}
\ No newline at end of file
+}
+#[test]
+fn test_bwrap_args() {
+    use super::sandbox::{bwrap_args, SandboxConfig};
+    use std::path::Path;
+
+    let project = tempfile::tempdir().unwrap();
+    let mut config: SandboxConfig = serde_json::from_value(json!({"network": false})).unwrap();
+    config.writable = vec!["/does/not/exist".to_string()];
+
+    let args = bwrap_args(project.path(), &config, Some(Path::new("sub")));
+    let project_dir = project.path().to_string_lossy().to_string();
+    assert!(args.windows(3).any(|w| w == ["--ro-bind", "/", "/"]));
+    assert!(args.windows(3).any(|w| w == ["--bind".to_string(), project_dir.clone(), project_dir.clone()]));
+    assert!(args.contains(&"--unshare-net".to_string()));
+    // missing directories cannot be bound
+    assert!(!args.contains(&"/does/not/exist".to_string()));
+    assert_eq!(args[args.len() - 2..], ["--chdir".to_string(), format!("{}/sub", project_dir)]);
+
+    let config: SandboxConfig = serde_json::from_value(json!({})).unwrap();
+    assert!(!bwrap_args(project.path(), &config, None).contains(&"--unshare-net".to_string()));
+}
+
+#[tokio::test]
+async fn test_bash_tool_sandboxed() {
+    use super::sandbox::{BubblewrapBackend, SandboxConfig};
+
+    let project = tempfile::tempdir().unwrap();
+    let config: SandboxConfig = serde_json::from_value(json!({"network": false})).unwrap();
+    let backend = match BubblewrapBackend::new(project.path().to_path_buf(), config) {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("skipping test_bash_tool_sandboxed: {}", e);
+            return;
+        }
+    };
+    let tool = BashTool::with_backend(Box::new(backend));
+    let run = |command: &str| params(json!({"command": command, "timeout": 10}));
+
+    // the project directory stays writable
+    let result = Tool::execute(&tool, run("echo inside > inside.txt"), None).await;
+    assert!(result.is_success(), "{:?}", result);
+    assert_eq!(std::fs::read_to_string(project.path().join("inside.txt")).unwrap().trim(), "inside");
+
+    // the rest of the filesystem is not
+    let result = Tool::execute(&tool, run("touch /usr/krokit_sandbox_test"), None).await;
+    match result {
+        crate::tools::types::ToolResult::Error { error, .. } => assert!(
+            error.contains("Read-only file system") || error.contains("Permission denied"),
+            "expected a permission error: {}", error),
+        other => panic!("Expected error result, got {:?}", other),
+    }
+    assert!(!std::path::Path::new("/usr/krokit_sandbox_test").exists());
+
+    // and the network is cut off
+    let result = Tool::execute(&tool, run("echo > /dev/tcp/1.1.1.1/53"), None).await;
+    match result {
+        crate::tools::types::ToolResult::Error { error, .. } => assert!(
+            error.contains("unreachable"), "expected a network error: {}", error),
+        other => panic!("Expected error result, got {:?}", other),
+    }
+}
+
+fn params(value: serde_json::Value) -> BashToolParams {
+    serde_json::from_value(value).unwrap()
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bash_tool_persistent_shell() {
+    let dir = tempfile::tempdir().unwrap();
+    let tool = BashTool::new();
+
+    let result = Tool::execute(&tool, params(json!({"command": format!("cd '{}' && export KROKIT_TEST_VAR=kept", dir.path().display())})), None).await;
+    assert!(result.is_success());
+
+    let result = Tool::execute(&tool, params(json!({"command": "pwd; echo $KROKIT_TEST_VAR"})), None).await;
+    if let crate::tools::types::ToolResult::Success { output, .. } = result {
+        let expected = std::fs::canonicalize(dir.path()).unwrap();
+        assert!(output.contains(&expected.to_string_lossy().to_string()), "{}", output);
+        assert!(output.contains("kept"));
+    } else {
+        panic!("Expected success result");
+    }
+
+    // per command variables do not leak into the shell
+    let result = Tool::execute(&tool, params(json!({"command": "echo \"[$ONCE]\"", "env": {"ONCE": "1"}})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Success { ref output, .. } if output.trim() == "[1]"));
+    let result = Tool::execute(&tool, params(json!({"command": "echo \"[$ONCE]\""})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Success { ref output, .. } if output.trim() == "[]"));
+
+    let result = Tool::execute(&tool, params(json!({"command": "echo oops; (exit 3)"})), None).await;
+    if let crate::tools::types::ToolResult::Error { error, metadata } = result {
+        assert!(error.contains("exit code 3") && error.contains("oops"), "the output is part of the error: {}", error);
+        assert_eq!(metadata.unwrap()["exit_code"], json!(3));
+    } else {
+        panic!("Expected error result");
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bash_tool_timeout_keeps_shell() {
+    let tool = BashTool::new();
+    Tool::execute(&tool, params(json!({"command": "export KROKIT_TEST_VAR=still_here"})), None).await;
+
+    let result = Tool::execute(&tool, params(json!({"command": "echo before; sleep 30", "timeout": 1})), None).await;
+    if let crate::tools::types::ToolResult::Error { error, .. } = result {
+        assert!(error.contains("timed out"));
+        assert!(error.contains("before"));
+    } else {
+        panic!("Expected error result");
+    }
+
+    let result = Tool::execute(&tool, params(json!({"command": "echo $KROKIT_TEST_VAR"})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Success { ref output, .. } if output.contains("still_here")));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bash_tool_sandboxed_timeout_keeps_shell() {
+    use super::sandbox::{BubblewrapBackend, SandboxConfig};
+
+    let project = tempfile::tempdir().unwrap();
+    let backend = match BubblewrapBackend::new(project.path().to_path_buf(), SandboxConfig::default()) {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!("skipping test_bash_tool_sandboxed_timeout_keeps_shell: {}", e);
+            return;
+        }
+    };
+    let tool = BashTool::with_backend(Box::new(backend));
+    Tool::execute(&tool, params(json!({"command": "export KROKIT_TEST_VAR=still_here"})), None).await;
+
+    let result = Tool::execute(&tool, params(json!({"command": "sleep 100", "timeout": 1})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Error { ref error, .. } if error.contains("timed out")), "{:?}", result);
+
+    // the interrupt reached the sleep, not the sandboxed shell
+    let result = Tool::execute(&tool, params(json!({"command": "echo $KROKIT_TEST_VAR", "timeout": 10})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Success { ref output, .. } if output.contains("still_here")), "{:?}", result);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_bash_tool_background_jobs() {
+    let tool = BashTool::new();
+
+    let result = Tool::execute(&tool, params(json!({"command": "echo started; sleep 30", "action": "start"})), None).await;
+    assert!(result.is_success());
+
+    let mut output = String::new();
+    for _ in 0..50 {
+        if let crate::tools::types::ToolResult::Success { output: read, .. } = Tool::execute(&tool, params(json!({"action": "read", "job_id": 1})), None).await {
+            output.push_str(&read);
+        }
+        if output.contains("started") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(output.contains("is running"), "{}", output);
+    assert!(output.contains("started"));
+
+    let result = Tool::execute(&tool, params(json!({"action": "kill", "job_id": 1})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Success { ref output, .. } if output.contains("killed")));
+
+    let result = Tool::execute(&tool, params(json!({"action": "jobs"})), None).await;
+    assert!(matches!(result, crate::tools::types::ToolResult::Success { ref output, .. } if output.contains("[1] killed")));
+
+    let result = Tool::execute(&tool, params(json!({"action": "read", "job_id": 7})), None).await;
+    assert!(!result.is_success());
+}
+
+#[tokio::test]
+async fn test_bash_tool_streams_progress() {
+    use std::sync::{Arc, Mutex};
+
+    let tool = BashTool::new();
+    let chunks: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink_chunks = chunks.clone();
+    let sink: crate::tools::ProgressSink = Arc::new(move |chunk| sink_chunks.lock().unwrap().push(chunk));
+
+    let result = Tool::execute_with_progress(&tool, params(json!({"command": "echo one; sleep 0.3; echo two"})), None, Some(sink)).await;
+    assert!(result.is_success());
+
+    let chunks = chunks.lock().unwrap();
+    // the first line was sent before the command finished
+    assert!(chunks.len() >= 2, "{:?}", chunks);
+    assert_eq!(chunks[0].trim(), "one");
+    assert_eq!(chunks.concat().trim(), "one\ntwo");
+}
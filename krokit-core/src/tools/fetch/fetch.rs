@@ -4,12 +4,30 @@ use serde_json::json;
 use std::collections::HashMap;
 use reqwest;
 use std::time::Duration;
+use krokit_llm::HttpSettings;
+use crate::config::config::KrokitConfig;
 
-pub struct FetchTool;
+pub struct FetchTool {
+    http: HttpSettings,
+}
 
 impl FetchTool {
     pub fn new() -> Self {
-        Self
+        Self::with_http(HttpSettings::default())
+    }
+
+    /// Requests go through the proxy and CA of the settings, the timeout of a call
+    /// replaces the one of the settings
+    pub fn with_http(http: HttpSettings) -> Self {
+        Self { http }
+    }
+
+    /// Fetch tool with the http settings of the krokit config (if any)
+    pub fn from_config() -> Self {
+        let http = KrokitConfig::load()
+            .map(|config| config.http)
+            .unwrap_or_default();
+        Self::with_http(http)
     }
 }
 
@@ -32,9 +50,12 @@ impl FetchTool {
 "#, capabilities = [ToolCapability::Network])]
 impl FetchTool {
     async fn execute(&self, params: FetchToolParams) -> ToolResult {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(params.timeout))
-            .build();
+        let client = self.http.client_builder()
+            .map_err(|e| e.to_string())
+            .and_then(|builder| builder
+                .timeout(Duration::from_secs(params.timeout))
+                .build()
+                .map_err(|e| e.to_string()));
 
         let client = match client {
             Ok(c) => c,
@@ -0,0 +1,452 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use tokio::sync::RwLock;
+
+/// Content of a file before it was first modified during a turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileCheckpoint {
+    pub turn: usize,
+    /// absolute path of the file
+    pub path: String,
+    /// sha256 of the previous content in the blob store, None if the file did not exist
+    pub blob: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Serializable state of the checkpoints, saved along with the session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckpointLog {
+    /// current turn, incremented on every user message
+    pub turn: usize,
+    pub entries: Vec<FileCheckpoint>,
+    /// length of the trace when each turn started, forgotten when the trace is compacted
+    #[serde(default)]
+    pub trace_marks: BTreeMap<usize, usize>,
+}
+
+/// Snapshots the files modified by the write, edit, multiedit and patch tools so that the
+/// changes of a turn can be reverted. Contents are kept in a content addressed store,
+/// in memory or in a directory of the session when it is persisted.
+#[derive(Debug, Default)]
+pub struct FileCheckpoints {
+    log: RwLock<CheckpointLog>,
+    blob_dir: std::sync::RwLock<Option<PathBuf>>,
+    memory: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl FileCheckpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the file contents in a directory instead of memory, the directory must not be
+    /// shared with another session: the contents no checkpoint refers to are removed from it
+    pub fn set_blob_dir(&self, dir: PathBuf) {
+        *self.blob_dir.write().unwrap() = Some(dir);
+    }
+
+    /// Start a new turn, returns its number (starting at 1).
+    /// `trace_len` is the length of the trace before the user message of the turn.
+    pub async fn begin_turn(&self, trace_len: usize) -> usize {
+        let mut log = self.log.write().await;
+        log.turn += 1;
+        let turn = log.turn;
+        log.trace_marks.insert(turn, trace_len);
+        turn
+    }
+
+    /// The trace was rewritten, the previous turns cannot be located in it anymore
+    pub async fn forget_trace_marks(&self) {
+        self.log.write().await.trace_marks.clear();
+    }
+
+    pub async fn current_turn(&self) -> usize {
+        self.log.read().await.turn
+    }
+
+    /// Save the current content of a file, only the first call of a turn is recorded
+    /// for a given file. Must be called before the file is modified.
+    pub async fn snapshot(&self, path: &str) -> std::io::Result<()> {
+        let path = absolute_path(path);
+        let mut log = self.log.write().await;
+        let turn = log.turn;
+        if log.entries.iter().any(|e| e.turn == turn && e.path == path) {
+            return Ok(());
+        }
+
+        let blob = match std::fs::read(&path) {
+            Ok(content) => Some(self.put_blob(content).await?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        log.entries.push(FileCheckpoint { turn, path, blob, timestamp: Utc::now() });
+        Ok(())
+    }
+
+    /// Turns that modified at least one file, most recent first
+    pub async fn turns(&self) -> Vec<usize> {
+        let log = self.log.read().await;
+        let turns: BTreeSet<usize> = log.entries.iter().map(|e| e.turn).collect();
+        turns.into_iter().rev().collect()
+    }
+
+    /// Files modified during a turn
+    pub async fn files(&self, turn: usize) -> Vec<String> {
+        let log = self.log.read().await;
+        log.entries.iter().filter(|e| e.turn == turn).map(|e| e.path.clone()).collect()
+    }
+
+    /// Revert the changes of the last `count` turns that modified files.
+    /// Returns the restored files.
+    pub async fn undo(&self, count: usize) -> Result<Vec<String>, CheckpointError> {
+        let since = self.undo_turn(count).await?;
+        self.restore_since(since).await
+    }
+
+    /// First turn reverted by undoing the last `count` turns that modified files
+    pub async fn undo_turn(&self, count: usize) -> Result<usize, CheckpointError> {
+        let turns = self.turns().await;
+        if turns.is_empty() || count == 0 {
+            return Err(CheckpointError::NothingToUndo);
+        }
+        Ok(turns[count.min(turns.len()) - 1])
+    }
+
+    /// Restore the files as they were before `turn` and go back to the turn before it.
+    /// Returns the restored files and the length of the trace when `turn` started, the
+    /// turns are only rewound when that length is known and within `trace_len`.
+    pub async fn rewind(&self, turn: usize, trace_len: usize) -> Result<(Vec<String>, Option<usize>), CheckpointError> {
+        let files = self.restore_since(turn).await?;
+        let mut log = self.log.write().await;
+        let mark = log.trace_marks.get(&turn).copied()
+            .filter(|len| *len <= trace_len);
+        if mark.is_some() {
+            log.turn = turn - 1;
+            log.trace_marks.retain(|t, _| *t < turn);
+        }
+        Ok((files, mark))
+    }
+
+    /// Put back every file as it was before `turn` started and forget the
+    /// checkpoints taken since then. Returns the restored files. The checkpoints are
+    /// only forgotten once every file is restored, so that a failed restore can be retried.
+    pub async fn restore_since(&self, turn: usize) -> Result<Vec<String>, CheckpointError> {
+        let mut log = self.log.write().await;
+        let reverted: Vec<FileCheckpoint> = log.entries.iter()
+            .filter(|e| e.turn >= turn)
+            .cloned()
+            .collect();
+
+        // most recent first, so that each file ends up with its oldest snapshot
+        let mut restored = Vec::new();
+        for entry in reverted.iter().rev() {
+            let result = match &entry.blob {
+                Some(blob) => {
+                    let content = self.get_blob(blob).await?;
+                    if let Some(parent) = Path::new(&entry.path).parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&entry.path, content)
+                }
+                None => match std::fs::remove_file(&entry.path) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    result => result,
+                },
+            };
+            result?;
+            if !restored.contains(&entry.path) {
+                restored.push(entry.path.clone());
+            }
+        }
+        log.entries.retain(|e| e.turn < turn);
+
+        if let Err(e) = self.prune_blobs(&log).await {
+            tracing::warn!(target: "tools::checkpoint", error = %e, "failed to remove unused snapshots");
+        }
+        Ok(restored)
+    }
+
+    /// Unified diff of the files modified since `turn` against their current content
+    pub async fn diff_since(&self, turn: usize) -> Result<String, CheckpointError> {
+        let entries: Vec<FileCheckpoint> = self.log.read().await.entries.iter()
+            .filter(|e| e.turn >= turn)
+            .cloned()
+            .collect();
+
+        let mut seen = Vec::new();
+        let mut out = String::new();
+        for entry in entries {
+            // the oldest snapshot of a file is the reference
+            if seen.contains(&entry.path) {
+                continue;
+            }
+            seen.push(entry.path.clone());
+
+            let before = match &entry.blob {
+                Some(blob) => String::from_utf8_lossy(&self.get_blob(blob).await?).to_string(),
+                None => String::new(),
+            };
+            let after = std::fs::read_to_string(&entry.path).unwrap_or_default();
+            if before == after {
+                continue;
+            }
+            let name = display_path(&entry.path);
+            out.push_str(&TextDiff::from_lines(&before, &after)
+                .unified_diff()
+                .context_radius(3)
+                .header(&format!("a/{}", name), &format!("b/{}", name))
+                .to_string());
+        }
+        Ok(out)
+    }
+
+    /// Diff of the last turn that modified files
+    pub async fn diff_last(&self) -> Result<String, CheckpointError> {
+        match self.turns().await.first() {
+            Some(turn) => self.diff_since(*turn).await,
+            None => Err(CheckpointError::NothingToUndo),
+        }
+    }
+
+    pub async fn log(&self) -> CheckpointLog {
+        self.log.read().await.clone()
+    }
+
+    /// Replace the checkpoints with previously saved ones (used when resuming a session)
+    pub async fn restore(&self, log: CheckpointLog) {
+        *self.log.write().await = log;
+    }
+
+    async fn put_blob(&self, content: Vec<u8>) -> std::io::Result<String> {
+        let hash = format!("{:x}", Sha256::digest(&content));
+        let dir = self.blob_dir.read().unwrap().clone();
+        match dir {
+            Some(dir) => {
+                let path = dir.join(&hash);
+                if !path.exists() {
+                    std::fs::create_dir_all(&dir)?;
+                    std::fs::write(path, content)?;
+                }
+            }
+            None => {
+                self.memory.write().await.insert(hash.clone(), content);
+            }
+        }
+        Ok(hash)
+    }
+
+    /// Remove the contents no checkpoint of the log refers to
+    async fn prune_blobs(&self, log: &CheckpointLog) -> std::io::Result<()> {
+        let referenced: HashSet<&str> = log.entries.iter()
+            .filter_map(|e| e.blob.as_deref())
+            .collect();
+        self.memory.write().await.retain(|hash, _| referenced.contains(hash.as_str()));
+
+        let dir = self.blob_dir.read().unwrap().clone();
+        let Some(dir) = dir else {
+            return Ok(());
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            entries => entries?,
+        };
+        for entry in entries {
+            let entry = entry?;
+            if !referenced.contains(entry.file_name().to_string_lossy().as_ref()) {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn get_blob(&self, hash: &str) -> Result<Vec<u8>, CheckpointError> {
+        if let Some(content) = self.memory.read().await.get(hash) {
+            return Ok(content.clone());
+        }
+        let dir = self.blob_dir.read().unwrap().clone();
+        dir.map(|dir| dir.join(hash))
+            .filter(|path| path.exists())
+            .map(std::fs::read)
+            .transpose()?
+            .ok_or_else(|| CheckpointError::MissingBlob(hash.to_string()))
+    }
+}
+
+fn absolute_path(path: &str) -> String {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_string_lossy().to_string()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Path relative to the current directory when possible
+pub fn display_path(path: &str) -> String {
+    std::env::current_dir().ok()
+        .and_then(|cwd| Path::new(path).strip_prefix(cwd).ok().map(|p| p.to_string_lossy().to_string()))
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Errors that can occur while restoring checkpoints
+#[derive(Debug, thiserror::Error)]
+pub enum CheckpointError {
+    #[error("No file changes to undo")]
+    NothingToUndo,
+
+    #[error("Snapshot {0} is missing from the checkpoint store")]
+    MissingBlob(String),
+
+    #[error("File access error: {0}")]
+    FileAccess(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn path_in(dir: &Path, name: &str) -> String {
+        dir.join(name).to_string_lossy().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_undo_last_turn() {
+        let dir = tempdir().unwrap();
+        let file = path_in(dir.path(), "a.txt");
+        std::fs::write(&file, "v1").unwrap();
+
+        let checkpoints = FileCheckpoints::new();
+        checkpoints.begin_turn(0).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        // only the first snapshot of the turn counts
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "v3").unwrap();
+
+        let restored = checkpoints.undo(1).await.unwrap();
+        assert_eq!(restored, vec![file.clone()]);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+        assert!(matches!(checkpoints.undo(1).await, Err(CheckpointError::NothingToUndo)));
+    }
+
+    #[tokio::test]
+    async fn test_undo_several_turns_and_created_files() {
+        let dir = tempdir().unwrap();
+        let file = path_in(dir.path(), "a.txt");
+        let created = path_in(dir.path(), "new/b.txt");
+        std::fs::write(&file, "v1").unwrap();
+
+        let checkpoints = FileCheckpoints::new();
+        checkpoints.begin_turn(0).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "v2").unwrap();
+
+        checkpoints.begin_turn(2).await; // no file change in turn 2
+        checkpoints.begin_turn(0).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "v3").unwrap();
+        checkpoints.snapshot(&created).await.unwrap();
+        std::fs::create_dir_all(dir.path().join("new")).unwrap();
+        std::fs::write(&created, "hello").unwrap();
+
+        assert_eq!(checkpoints.turns().await, vec![3, 1]);
+        checkpoints.undo(1).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+        assert!(!Path::new(&created).exists());
+
+        checkpoints.undo(5).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+    }
+
+    #[tokio::test]
+    async fn test_diff_and_persisted_blobs() {
+        let dir = tempdir().unwrap();
+        let file = path_in(dir.path(), "a.txt");
+        std::fs::write(&file, "one\ntwo\n").unwrap();
+
+        let checkpoints = FileCheckpoints::new();
+        checkpoints.set_blob_dir(dir.path().join("blobs"));
+        checkpoints.begin_turn(0).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "one\nthree\n").unwrap();
+
+        let diff = checkpoints.diff_last().await.unwrap();
+        assert!(diff.contains("-two"));
+        assert!(diff.contains("+three"));
+
+        // a new instance restored from the saved log finds the blobs on disk
+        let restored = FileCheckpoints::new();
+        restored.set_blob_dir(dir.path().join("blobs"));
+        restored.restore(checkpoints.log().await).await;
+        restored.restore_since(1).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\ntwo\n");
+        // no checkpoint refers to the snapshot anymore
+        assert_eq!(std::fs::read_dir(dir.path().join("blobs")).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_restore_keeps_the_checkpoints() {
+        let dir = tempdir().unwrap();
+        let file = path_in(dir.path(), "a.txt");
+        std::fs::write(&file, "v1").unwrap();
+
+        let checkpoints = FileCheckpoints::new();
+        checkpoints.set_blob_dir(dir.path().join("blobs"));
+        checkpoints.begin_turn(0).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "v2").unwrap();
+
+        let blobs = dir.path().join("blobs");
+        let blob = std::fs::read_dir(&blobs).unwrap().next().unwrap().unwrap().path();
+        let content = std::fs::read(&blob).unwrap();
+        std::fs::remove_file(&blob).unwrap();
+        assert!(matches!(checkpoints.undo(1).await, Err(CheckpointError::MissingBlob(_))));
+        assert_eq!(checkpoints.turns().await, vec![1]);
+
+        // the undo can be retried once the snapshot is back
+        std::fs::write(&blob, content).unwrap();
+        checkpoints.undo(1).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+    }
+
+    #[tokio::test]
+    async fn test_rewind_keeps_the_blobs_still_used() {
+        let dir = tempdir().unwrap();
+        let file = path_in(dir.path(), "a.txt");
+        let other = path_in(dir.path(), "b.txt");
+        std::fs::write(&file, "v1").unwrap();
+        std::fs::write(&other, "b1").unwrap();
+
+        let checkpoints = FileCheckpoints::new();
+        checkpoints.set_blob_dir(dir.path().join("blobs"));
+        checkpoints.begin_turn(0).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        std::fs::write(&file, "v2").unwrap();
+        checkpoints.begin_turn(2).await;
+        checkpoints.snapshot(&file).await.unwrap();
+        checkpoints.snapshot(&other).await.unwrap();
+        std::fs::write(&file, "v3").unwrap();
+        std::fs::write(&other, "b2").unwrap();
+        assert_eq!(std::fs::read_dir(dir.path().join("blobs")).unwrap().count(), 3);
+
+        let turn = checkpoints.undo_turn(1).await.unwrap();
+        let (files, trace_len) = checkpoints.rewind(turn, 4).await.unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(trace_len, Some(2));
+        assert_eq!(checkpoints.current_turn().await, 1);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+        // the snapshot of the first turn is still needed
+        assert_eq!(std::fs::read_dir(dir.path().join("blobs")).unwrap().count(), 1);
+        checkpoints.undo(1).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+    }
+}
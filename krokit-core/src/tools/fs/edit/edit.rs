@@ -1,4 +1,5 @@
-use super::structs::EditToolParams;
+use super::fuzzy::{diagnose, find_fuzzy, mode_name, replace_fuzzy};
+use super::structs::{EditToolParams, MatchMode};
 use super::super::{FsOperationLog, FsOperationType};
 use crate::tools::{tool, ToolResult};
 use similar::{ChangeTag, TextDiff};
@@ -79,7 +80,7 @@ impl EditTool {
     pub fn perform_edit_on_content(&self, content: &str, old_string: &str, new_string: &str, replace_all: bool) -> Result<(String, usize), String> {
         // Check if the old_string exists in the content
         if !content.contains(old_string) {
-            return Err(format!("Pattern '{}' not found in content", old_string));
+            return Err(format!("Pattern '{}' not found in content\n{}", old_string, diagnose(content, old_string)));
         }
 
         // Perform the replacement
@@ -95,11 +96,32 @@ impl EditTool {
         Ok((new_content, replacements))
     }
 
+    /// Replace `old_string`, or the single region of whole lines it matches in the relaxed
+    /// mode when it is not found as is. Returns the mode that matched.
+    pub fn perform_edit_with_mode(&self, content: &str, old_string: &str, new_string: &str, replace_all: bool, mode: MatchMode) -> Result<(String, usize, MatchMode), String> {
+        if mode == MatchMode::Exact || content.contains(old_string) {
+            let (new_content, replacements) = self.perform_edit_on_content(content, old_string, new_string, replace_all)?;
+            return Ok((new_content, replacements, MatchMode::Exact));
+        }
+
+        match find_fuzzy(content, old_string, mode).as_slice() {
+            [matched] => Ok((replace_fuzzy(content, matched, old_string, new_string), 1, mode)),
+            [] => Err(format!("Pattern '{}' not found in content, even with match_mode \"{}\"\n{}", old_string, mode_name(mode), diagnose(content, old_string))),
+            matches => {
+                let regions = matches.iter()
+                    .map(|matched| format!("{}-{}", matched.start_line, matched.end_line))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(format!("Pattern '{}' is not unique with match_mode \"{}\", it matches lines {}. Add surrounding lines to old_string to select one", old_string, mode_name(mode), regions))
+            }
+        }
+    }
+
     pub fn commit_edit(&self, path: &str, new_content: &str) -> Result<(), String> {
         fs::write(path, new_content).map_err(|e| e.to_string())
     }
 
-    fn perform_edit(&self, params: &EditToolParams, preview: bool) -> Result<(String, usize), String> {
+    fn perform_edit(&self, params: &EditToolParams, preview: bool) -> Result<(String, usize, MatchMode), String> {
         let path = Path::new(&params.path);
 
         // Check if file exists
@@ -111,12 +133,15 @@ impl EditTool {
         let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
 
         // Perform edit on content
-        let (new_content, replacements) = self.perform_edit_on_content(&content, &params.old_string, &params.new_string, params.replace_all)?;
+        let (new_content, replacements, matched_with) = self.perform_edit_with_mode(&content, &params.old_string, &params.new_string, params.replace_all, params.match_mode)?;
 
         // Generate proper diff using Myers' algorithm
         let diff = self.myers_diff(&content, &new_content);
         
         let mut diff_output = Vec::new();
+        if matched_with != MatchMode::Exact {
+            diff_output.push(format!("old_string was not found as is, it matched with match_mode \"{}\"", mode_name(matched_with)));
+        }
         diff_output.push("".to_string());
         diff_output.push(diff);
 
@@ -125,7 +150,7 @@ impl EditTool {
             self.commit_edit(&params.path, &new_content)?;
         }
 
-        Ok((diff_output.join("\n"), replacements))
+        Ok((diff_output.join("\n"), replacements, matched_with))
     }
 }
 
@@ -137,6 +162,7 @@ impl EditTool {
 **Usage Guidelines:**
 - The `old_string` parameter demands an exact, literal match of the text to be replaced. This includes all whitespace and indentation. When copying text from the `read` tool's output, you must omit the line number prefix.
 - The operation will fail if the `old_string` is not unique within the file. To resolve this, provide more surrounding context to make the `old_string` unique.
+- When `old_string` is not found, the error shows the regions of the file closest to it with their similarity. If it only differs by whitespace, line endings or indentation, retry with `match_mode` set to "whitespace" or "indentation": the edit then applies if a single region of whole lines matches.
 - For situations where you intend to replace every occurrence of a string (e.g., renaming a variable), set the `replace_all` parameter to `true`.
 - Prioritize modifying existing files. Avoid creating new files unless the task explicitly requires it.
 "#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
@@ -160,8 +186,15 @@ impl EditTool {
             return ToolResult::error(err);
         }
 
+        if !preview {
+            // Keep the previous content so the change can be undone
+            if let Err(e) = self.operation_log.checkpoints.snapshot(&params.path).await {
+                tracing::warn!(target: "tools::checkpoint", error = %e, path = %params.path, "failed to snapshot file");
+            }
+        }
+
         match self.perform_edit(&params, preview) {
-            Ok((message, replacement_count)) => {
+            Ok((message, replacement_count, matched_with)) => {
                 // Log the edit operation only if not preview
                 if !preview {
                     self.operation_log.log_operation(FsOperationType::Edit, params.path.clone()).await;
@@ -173,6 +206,7 @@ impl EditTool {
                 meta.insert("new_string".to_string(), json!(params.new_string));
                 meta.insert("replace_all".to_string(), json!(params.replace_all));
                 meta.insert("replacements_made".to_string(), json!(replacement_count));
+                meta.insert("match_mode".to_string(), json!(mode_name(matched_with)));
                 meta.insert("preview_mode".to_string(), json!(preview));
 
                 // Add file size information
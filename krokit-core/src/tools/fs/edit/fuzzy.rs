@@ -0,0 +1,300 @@
+use super::structs::MatchMode;
+use similar::TextDiff;
+use std::collections::HashMap;
+
+/// Width of a tab when indentations made of tabs and spaces are compared
+const TAB_WIDTH: usize = 4;
+/// Regions less similar than this are not worth showing
+const MIN_SIMILARITY: f32 = 0.5;
+/// Regions shown when `old_string` is not found
+const MAX_CANDIDATES: usize = 3;
+
+/// Line of a file without its line ending, with its byte range
+struct Line<'a> {
+    text: &'a str,
+    start: usize,
+    end: usize,
+}
+
+fn file_lines(content: &str) -> Vec<Line<'_>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for piece in content.split_inclusive('\n') {
+        let text = piece.strip_suffix('\n').unwrap_or(piece);
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        lines.push(Line { text, start, end: start + text.len() });
+        start += piece.len();
+    }
+    lines
+}
+
+/// Region of whole lines matched by a relaxed `old_string`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// byte range of the lines in the file, without the last line ending
+    start: usize,
+    end: usize,
+    /// columns to add to the indentation of `new_string`
+    indent_shift: isize,
+    /// the file indents with tabs
+    tabs: bool,
+}
+
+/// Region of the file close to `old_string`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// 1-based, inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    /// from 0 to 1, 1 when the lines are the same
+    pub score: f32,
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn collapse_whitespace(line: &str) -> String {
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Regions of whole lines matching `old_string` in the mode, in the order of the file.
+/// Nothing matches in the exact mode, it is not a relaxed one.
+pub fn find_fuzzy(content: &str, old_string: &str, mode: MatchMode) -> Vec<FuzzyMatch> {
+    let lines = file_lines(content);
+    let pattern: Vec<&str> = old_string.lines().collect();
+    if mode == MatchMode::Exact || pattern.iter().all(|line| line.trim().is_empty()) || pattern.len() > lines.len() {
+        return Vec::new();
+    }
+
+    (0..=lines.len() - pattern.len())
+        .filter_map(|start| {
+            let window = &lines[start..start + pattern.len()];
+            let indent_shift = match mode {
+                MatchMode::Whitespace => {
+                    let same = window.iter().zip(&pattern)
+                        .all(|(line, expected)| collapse_whitespace(line.text) == collapse_whitespace(expected));
+                    same.then_some(0)?
+                }
+                _ => block_indent_shift(window, &pattern)?,
+            };
+            let tabs = window.iter().any(|line| line.text.starts_with('\t'));
+            Some(FuzzyMatch {
+                start_line: start + 1,
+                end_line: start + pattern.len(),
+                start: window[0].start,
+                end: window[window.len() - 1].end,
+                indent_shift,
+                tabs,
+            })
+        })
+        .collect()
+}
+
+/// Columns the block of the file is indented more than the pattern, when their lines only
+/// differ by a same amount of indentation
+fn block_indent_shift(window: &[Line], pattern: &[&str]) -> Option<isize> {
+    let mut shift = None;
+    for (line, expected) in window.iter().zip(pattern) {
+        if line.text.trim() != expected.trim() {
+            return None;
+        }
+        if expected.trim().is_empty() {
+            continue;
+        }
+        let line_shift = indent_width(line.text) as isize - indent_width(expected) as isize;
+        match shift {
+            None => shift = Some(line_shift),
+            Some(shift) if shift != line_shift => return None,
+            _ => {}
+        }
+    }
+    Some(shift.unwrap_or(0))
+}
+
+/// Content with the matched region replaced by `new_string`, re-indented by the shift of the
+/// match and with the line endings of the file
+pub fn replace_fuzzy(content: &str, matched: &FuzzyMatch, old_string: &str, new_string: &str) -> String {
+    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    // the region stops before the line ending of its last line
+    let new_string = if old_string.ends_with('\n') {
+        new_string.strip_suffix('\n').map(|text| text.strip_suffix('\r').unwrap_or(text)).unwrap_or(new_string)
+    } else {
+        new_string
+    };
+
+    let replacement = new_string.split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if matched.indent_shift == 0 || line.trim().is_empty() {
+                return line.to_string();
+            }
+            let width = (indent_width(line) as isize + matched.indent_shift).max(0) as usize;
+            let indent = if matched.tabs {
+                format!("{}{}", "\t".repeat(width / TAB_WIDTH), " ".repeat(width % TAB_WIDTH))
+            } else {
+                " ".repeat(width)
+            };
+            format!("{}{}", indent, line.trim_start())
+        })
+        .collect::<Vec<_>>()
+        .join(line_ending);
+
+    format!("{}{}{}", &content[..matched.start], replacement, &content[matched.end..])
+}
+
+/// Regions of the file with as many lines as `old_string` that look the most like it,
+/// best first. The lines are compared one by one, ignoring their trailing whitespace.
+pub fn closest_regions(content: &str, old_string: &str) -> Vec<Candidate> {
+    let lines: Vec<&str> = file_lines(content).iter().map(|line| line.text.trim_end()).collect();
+    let pattern: Vec<&str> = old_string.lines().map(str::trim_end).collect();
+    if lines.is_empty() || pattern.is_empty() {
+        return Vec::new();
+    }
+    let size = pattern.len().min(lines.len());
+
+    // the characters two lines have in common bound their similarity, the windows that
+    // cannot reach MIN_SIMILARITY are never diffed
+    let line_chars: Vec<HashMap<char, usize>> = lines.iter().map(|line| char_counts(line)).collect();
+    let pattern_chars: Vec<HashMap<char, usize>> = pattern.iter().map(|line| char_counts(line)).collect();
+    let mut windows: Vec<(usize, f32)> = (0..=lines.len() - size)
+        .filter_map(|start| {
+            let bound = window_score(&lines, &pattern, start, size, |line, offset| {
+                similarity_bound(&line_chars[line], &pattern_chars[offset])
+            });
+            (bound >= MIN_SIMILARITY).then_some((start, bound))
+        })
+        .collect();
+    windows.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    // the most promising windows are diffed first, until the windows left cannot beat
+    // the regions found
+    let mut scored: Vec<Candidate> = Vec::new();
+    for (index, &(start, _)) in windows.iter().enumerate() {
+        let score = window_score(&lines, &pattern, start, size, |line, offset| {
+            line_similarity(lines[line], pattern[offset])
+        });
+        scored.push(Candidate { start_line: start + 1, end_line: start + size, score });
+
+        let next_bound = windows.get(index + 1).map(|&(_, bound)| bound);
+        let settled = next_bound.map_or(true, |bound| {
+            scored.iter().filter(|candidate| candidate.score > bound).count() >= MAX_CANDIDATES
+                && best_regions(&scored, bound).len() == MAX_CANDIDATES
+        });
+        if settled {
+            return best_regions(&scored, next_bound.unwrap_or(0.0));
+        }
+    }
+    Vec::new()
+}
+
+/// Weighted mean of the similarities of the lines of a window, longer lines weigh more
+fn window_score(lines: &[&str], pattern: &[&str], start: usize, size: usize, mut similarity: impl FnMut(usize, usize) -> f32) -> f32 {
+    let weight = |a: &str, b: &str| a.chars().count().max(b.chars().count()).max(1) as f32;
+    let (total, weights) = (0..size).fold((0.0, 0.0), |(total, weights), offset| {
+        let weight = weight(lines[start + offset], pattern[offset]);
+        (total + similarity(start + offset, offset) * weight, weights + weight)
+    });
+    total / weights
+}
+
+/// Best regions that do not overlap among the candidates scoring more than `floor`
+fn best_regions(candidates: &[Candidate], floor: f32) -> Vec<Candidate> {
+    let mut sorted: Vec<&Candidate> = candidates.iter()
+        .filter(|candidate| candidate.score >= MIN_SIMILARITY && candidate.score > floor)
+        .collect();
+    sorted.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.start_line.cmp(&b.start_line)));
+
+    let mut best: Vec<Candidate> = Vec::new();
+    for candidate in sorted {
+        if best.len() == MAX_CANDIDATES {
+            break;
+        }
+        let overlaps = best.iter()
+            .any(|other| candidate.start_line <= other.end_line && other.start_line <= candidate.end_line);
+        if !overlaps {
+            best.push(candidate.clone());
+        }
+    }
+    best
+}
+
+fn char_counts(line: &str) -> HashMap<char, usize> {
+    let mut counts = HashMap::new();
+    for c in line.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    counts
+}
+
+/// Highest similarity two lines can have: the diff cannot match more characters than
+/// they have in common, and lines of very different lengths are not compared
+fn similarity_bound(a: &HashMap<char, usize>, b: &HashMap<char, usize>) -> f32 {
+    let (len_a, len_b) = (a.values().sum::<usize>(), b.values().sum::<usize>());
+    if len_a + len_b == 0 {
+        return 1.0;
+    }
+    if 2.0 * len_a.min(len_b) as f32 / ((len_a + len_b) as f32) < MIN_SIMILARITY {
+        return 0.0;
+    }
+    let common: usize = a.iter().map(|(c, count)| (*count).min(b.get(c).copied().unwrap_or(0))).sum();
+    2.0 * common as f32 / (len_a + len_b) as f32
+}
+
+fn line_similarity(a: &str, b: &str) -> f32 {
+    if a == b {
+        return 1.0;
+    }
+    let (len_a, len_b) = (a.chars().count(), b.chars().count());
+    // the ratio cannot exceed this bound, lines of very different lengths are not compared
+    if 2.0 * len_a.min(len_b) as f32 / ((len_a + len_b) as f32) < MIN_SIMILARITY {
+        return 0.0;
+    }
+    TextDiff::from_chars(a, b).ratio()
+}
+
+/// Why `old_string` is not in the content: the closest regions with their similarity, and
+/// the relaxed match mode that would apply
+pub fn diagnose(content: &str, old_string: &str) -> String {
+    let mut notes = Vec::new();
+    if content.contains("\r\n") && old_string.contains('\n') && !old_string.contains("\r\n") {
+        notes.push("The file has CRLF line endings.".to_string());
+    }
+    for mode in [MatchMode::Whitespace, MatchMode::Indentation] {
+        let matches = find_fuzzy(content, old_string, mode);
+        if let [matched] = matches.as_slice() {
+            notes.push(format!("It matches lines {}-{} with match_mode \"{}\".",
+                matched.start_line, matched.end_line, mode_name(mode)));
+            break;
+        }
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    for candidate in closest_regions(content, old_string) {
+        let region = lines[candidate.start_line - 1..candidate.end_line].iter()
+            .enumerate()
+            .map(|(offset, line)| format!("{:>5} {}", candidate.start_line + offset, line))
+            .collect::<Vec<_>>()
+            .join("\n");
+        notes.push(format!("Closest region, lines {}-{} ({:.0}% similar):\n{}",
+            candidate.start_line, candidate.end_line, candidate.score * 100.0, region));
+    }
+    if notes.is_empty() {
+        notes.push("No region of the file looks like it, read the file again.".to_string());
+    }
+    notes.join("\n")
+}
+
+pub fn mode_name(mode: MatchMode) -> &'static str {
+    match mode {
+        MatchMode::Exact => "exact",
+        MatchMode::Whitespace => "whitespace",
+        MatchMode::Indentation => "indentation",
+    }
+}
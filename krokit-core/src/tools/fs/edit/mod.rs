@@ -1,8 +1,9 @@
 pub mod structs;
 pub mod edit;
+pub mod fuzzy;
 
 #[cfg(test)]
 mod tests;
 
-pub use structs::EditToolParams;
-pub use edit::EditTool;
\ No newline at end of file
+pub use structs::{EditToolParams, MatchMode};
+pub use edit::EditTool;
@@ -1,6 +1,21 @@
 use serde::Deserialize;
 use schemars::JsonSchema;
 
+/// How `old_string` is matched when it is not found as is in the file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[schemars(inline)]
+pub enum MatchMode {
+    /// Only the exact text
+    #[default]
+    Exact,
+    /// Lines equal once their whitespace and line endings are ignored
+    Whitespace,
+    /// Lines equal once the indentation of the block is ignored, `new_string` is re-indented
+    /// to the file
+    Indentation,
+}
+
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub struct EditToolParams {
     /// Path to the file to edit
@@ -12,4 +27,7 @@ pub struct EditToolParams {
     /// Whether to replace all occurrences (default: false, replaces only first)
     #[serde(default)]
     pub replace_all: bool,
-}
\ No newline at end of file
+    /// Fallback when `old_string` is not found exactly: "exact" (default) fails, "whitespace" ignores whitespace and line ending differences, "indentation" ignores the indentation of the block (tabs or spaces) and re-indents `new_string` like the file. The fallback only applies when it matches a single region of whole lines.
+    #[serde(default)]
+    pub match_mode: MatchMode,
+}
@@ -1,4 +1,4 @@
-use super::structs::EditToolParams;
+use super::structs::{EditToolParams, MatchMode};
 use super::edit::EditTool;
 use crate::tools::{Tool, ToolCapability, FsOperationLog};
 use krokit_llm::ToolDescription;
@@ -40,6 +40,7 @@ async fn test_edit_file_replacement() {
         old_string: "Hello".to_string(),
         new_string: "Hi".to_string(),
         replace_all: false,
+        match_mode: MatchMode::Exact,
     };
 
     let result = tool.execute(params, None).await;
@@ -65,6 +66,7 @@ async fn test_edit_preview_functionality() {
         old_string: "Hello".to_string(),
         new_string: "Hi".to_string(),
         replace_all: false,
+        match_mode: MatchMode::Exact,
     };
 
     // Test preview - should return Some(ToolResult) with diff
@@ -164,6 +166,7 @@ async fn test_execute_vs_preview_behavior() {
         old_string: "Original".to_string(),
         new_string: "Modified".to_string(),
         replace_all: false,
+        match_mode: MatchMode::Exact,
     };
 
     // Preview should not modify file
@@ -177,4 +180,95 @@ async fn test_execute_vs_preview_behavior() {
     assert!(execute_result.is_success());
     let content_after_execute = fs::read_to_string(&file_path).unwrap();
     assert_eq!(content_after_execute, "Modified content");
-}
\ No newline at end of file
+}
+async fn edit_read_file(content: &str, old_string: &str, new_string: &str, match_mode: MatchMode) -> (crate::tools::ToolResult, String) {
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("test.rs");
+    fs::write(&file_path, content).unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    log.log_operation(crate::tools::FsOperationType::Read, file_path.to_string_lossy().to_string()).await;
+
+    let tool = EditTool::new(log);
+    let params = EditToolParams {
+        path: file_path.to_string_lossy().to_string(),
+        old_string: old_string.to_string(),
+        new_string: new_string.to_string(),
+        replace_all: false,
+        match_mode,
+    };
+    let result = tool.execute(params, None).await;
+    (result, fs::read_to_string(&file_path).unwrap())
+}
+
+#[tokio::test]
+async fn test_edit_whitespace_match_mode() {
+    let content = "fn main() {\r\n    let  x = 1;\r\n    run(x);\r\n}\r\n";
+    let old_string = "    let x = 1;\n    run(x);";
+    let new_string = "    let x = 2;\n    run(x);";
+
+    // the exact match fails and tells why
+    let (result, unchanged) = edit_read_file(content, old_string, new_string, MatchMode::Exact).await;
+    match result {
+        crate::tools::ToolResult::Error { error, .. } => {
+            assert!(error.contains("CRLF line endings"), "{}", error);
+            assert!(error.contains("It matches lines 2-3 with match_mode \"whitespace\""), "{}", error);
+        }
+        other => panic!("the exact match should fail: {:?}", other),
+    }
+    assert_eq!(unchanged, content);
+
+    let (result, edited) = edit_read_file(content, old_string, new_string, MatchMode::Whitespace).await;
+    assert!(result.is_success());
+    assert_eq!(edited, "fn main() {\r\n    let x = 2;\r\n    run(x);\r\n}\r\n");
+}
+
+#[tokio::test]
+async fn test_edit_indentation_match_mode() {
+    let content = "impl A {\n\tfn a() {\n\t\tone();\n\t}\n}\n";
+    let (result, edited) = edit_read_file(content,
+        "fn a() {\n    one();\n}\n",
+        "fn a() {\n    one();\n    two();\n}\n",
+        MatchMode::Indentation).await;
+    assert!(result.is_success());
+    assert_eq!(edited, "impl A {\n\tfn a() {\n\t\tone();\n\t\ttwo();\n\t}\n}\n", "new_string takes the indentation of the file");
+
+    // a relaxed match must be unique
+    let content = "a()\n  b  ()\nc()\n  b  ()\n";
+    let (result, unchanged) = edit_read_file(content, "b ()", "d()", MatchMode::Whitespace).await;
+    match result {
+        crate::tools::ToolResult::Error { error, .. } => assert!(error.contains("matches lines 2-2, 4-4"), "{}", error),
+        other => panic!("an ambiguous match should fail: {:?}", other),
+    }
+    assert_eq!(unchanged, content);
+}
+
+#[test]
+fn test_edit_reports_closest_regions() {
+    let content = "fn connect(host: &str) {\n    open(host);\n}\n\nfn close() {}\n";
+    let old_string = "fn conect(host: &str) {\n    open(host);";
+
+    let candidates = super::fuzzy::closest_regions(content, old_string);
+    assert_eq!((candidates[0].start_line, candidates[0].end_line), (1, 2));
+    assert!(candidates[0].score > 0.9 && candidates[0].score < 1.0, "{:?}", candidates);
+
+    let tool = EditTool::new(Arc::new(FsOperationLog::new()));
+    let error = tool.perform_edit_on_content(content, old_string, "", false).unwrap_err();
+    assert!(error.contains("Closest region, lines 1-2 (9"), "{}", error);
+    assert!(error.contains("    1 fn connect(host: &str) {"), "{}", error);
+
+    // in a large file only the windows that can be similar enough are diffed
+    let mut large: String = (0..2000).map(|i| format!("let value_{} = compute({}, {});\n", i, i, i * 7)).collect();
+    large.push_str("fn connect(host: &str) {\n    open(host);\n}\n");
+    let candidates = super::fuzzy::closest_regions(&large, old_string);
+    assert_eq!((candidates[0].start_line, candidates[0].end_line), (2001, 2002), "{:?}", candidates);
+    assert!(candidates.iter().all(|candidate| candidate.score >= 0.5));
+}
+
+#[test]
+fn test_edit_schema_is_inline() {
+    let tool = EditTool::new(Arc::new(FsOperationLog::new()));
+    let schema = tool.parameters_schema().to_string();
+    assert!(!schema.contains("$ref") && !schema.contains("$defs"), "{}", schema);
+    assert!(schema.contains("indentation"), "{}", schema);
+}
@@ -1,100 +1,176 @@
 use super::structs::{FindToolParams, SearchResult, FindType};
 use crate::tools::{tool, ToolResult};
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{WalkBuilder, WalkState};
 use serde_json::json;
 use std::collections::HashMap;
-use std::path::Path;
-use regex::Regex;
-use walkdir::WalkDir;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use regex::{Regex, RegexBuilder};
 use std::fs;
-use std::io::{BufRead, BufReader};
+
+/// Ignore files read in every directory in addition to .gitignore, with the same syntax
+pub const KROKIT_IGNORE: &str = ".krokitignore";
+
+/// A file with a NUL byte in its first bytes is binary and its content is not searched
+const BINARY_CHECK_BYTES: usize = 8192;
 
 pub struct FindTool;
 
+/// Files searched at once per core, the batches are searched from the most recent files
+const SEARCH_BATCH_PER_CORE: usize = 16;
+
+/// A file to search, files are sorted by modification time
+struct Candidate {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
 impl FindTool {
     pub fn new() -> Self {
         Self
     }
 
-    fn should_include_file(&self, path: &Path, include_extensions: &Option<String>, exclude_patterns: &Option<String>) -> bool {
-        let path_str = path.to_string_lossy();
-        
-        // Check exclude patterns first
-        if let Some(exclude) = exclude_patterns {
-            for pattern in exclude.split(',') {
-                let pattern = pattern.trim();
-                if !pattern.is_empty() && path_str.contains(pattern) {
-                    return false;
-                }
+    fn build_regex(params: &FindToolParams) -> Result<Regex, regex::Error> {
+        let pattern = if params.whole_word {
+            format!("\\b{}\\b", regex::escape(&params.pattern))
+        } else {
+            params.pattern.clone()
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!params.case_sensitive)
+            .multi_line(params.multiline)
+            .dot_matches_new_line(params.multiline)
+            .build()
+    }
+
+    fn split_list(list: &Option<String>) -> impl Iterator<Item = &str> {
+        list.iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+    }
+
+    /// Globs in the gitignore syntax, rooted at the search directory. Excluded ones are
+    /// skipped by the walker, a directory is not even entered.
+    fn build_globs(root: &Path, globs: &Option<String>, exclude: bool) -> Result<Override, ignore::Error> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in Self::split_list(globs) {
+            if exclude {
+                builder.add(&format!("!{}", glob))?;
+            } else {
+                builder.add(glob)?;
             }
         }
-        
-        // Check include extensions
-        if let Some(include) = include_extensions {
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy();
-                for allowed_ext in include.split(',') {
-                    let allowed_ext = allowed_ext.trim();
-                    if !allowed_ext.is_empty() && ext_str == allowed_ext {
-                        return true;
-                    }
-                }
-                return false; // Has extension but not in allowed list
-            } else {
-                return false; // No extension but extensions are specified
+        if exclude {
+            // searched with hidden files, but never useful
+            builder.add("!.git")?;
+        }
+        builder.build()
+    }
+
+    fn should_include_file(path: &Path, include_globs: &Override, include_extensions: &Option<String>) -> bool {
+        if !include_globs.is_empty() && !include_globs.matched(path, false).is_whitelist() {
+            return false;
+        }
+
+        if include_extensions.is_none() {
+            return true;
+        }
+        let Some(ext) = path.extension() else {
+            return false; // No extension but extensions are specified
+        };
+        let ext = ext.to_string_lossy();
+        Self::split_list(include_extensions).any(|allowed| allowed.trim_start_matches('.') == ext)
+    }
+
+    fn is_binary(bytes: &[u8]) -> bool {
+        bytes[..bytes.len().min(BINARY_CHECK_BYTES)].contains(&0)
+    }
+
+    /// Lines (first and last, 0-based) of the matches of a file, at most `cap`
+    fn matching_lines(content: &str, lines: &[&str], pattern: &Regex, multiline: bool, cap: usize) -> Vec<(usize, usize)> {
+        if !multiline {
+            return lines.iter()
+                .enumerate()
+                .filter(|(_, line)| pattern.is_match(line))
+                .map(|(index, _)| (index, index))
+                .take(cap)
+                .collect();
+        }
+
+        let line_starts: Vec<usize> = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        let last_line = lines.len().saturating_sub(1);
+        let line_of = |offset: usize| (line_starts.partition_point(|&start| start <= offset) - 1).min(last_line);
+
+        let mut matches: Vec<(usize, usize)> = Vec::new();
+        for found in pattern.find_iter(content) {
+            let first = line_of(found.start());
+            let last = line_of(found.end().saturating_sub(1).max(found.start()));
+            // several matches on the same lines are reported once
+            if matches.last().map_or(false, |&(_, previous)| first <= previous) {
+                continue;
+            }
+            matches.push((first, last));
+            if matches.len() >= cap {
+                break;
             }
         }
-        
-        true
+        matches
     }
 
-    fn search_file_content(&self, file_path: &Path, pattern: &Regex, params: &FindToolParams) -> Vec<SearchResult> {
-        let mut results = Vec::new();
-        
-        let file = match fs::File::open(file_path) {
-            Ok(file) => file,
-            Err(_) => return results,
+    fn search_file_content(file_path: &Path, pattern: &Regex, params: &FindToolParams) -> Vec<SearchResult> {
+        let bytes = match fs::read(file_path) {
+            Ok(bytes) => bytes,
+            Err(_) => return Vec::new(),
         };
-        
-        let reader = BufReader::new(file);
-        let lines: Vec<String> = reader.lines().collect::<Result<Vec<_>, _>>().unwrap_or_default();
-        
-        for (line_num, line) in lines.iter().enumerate() {
-            if pattern.is_match(line) {
-                let line_number = (line_num + 1) as u32;
-                let context_lines = params.context_lines.unwrap_or(0);
-                
-                let mut context_before = Vec::new();
-                let mut context_after = Vec::new();
-                
-                if context_lines > 0 {
-                    let start = line_num.saturating_sub(context_lines as usize);
-                    let end = std::cmp::min(line_num + context_lines as usize + 1, lines.len());
-                    
-                    context_before = lines[start..line_num].iter().cloned().collect();
-                    context_after = lines[line_num + 1..end].iter().cloned().collect();
-                }
-                
-                results.push(SearchResult {
+        if Self::is_binary(&bytes) {
+            return Vec::new();
+        }
+
+        let content = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = content.lines().collect();
+        if lines.is_empty() {
+            return Vec::new();
+        }
+
+        let cap = params.max_matches_per_file
+            .map_or(params.max_results, |cap| cap.min(params.max_results)) as usize;
+        let context_lines = params.context_lines.unwrap_or(0) as usize;
+
+        Self::matching_lines(&content, &lines, pattern, params.multiline, cap)
+            .into_iter()
+            .map(|(first, last)| {
+                let (context_before, context_after) = if context_lines > 0 {
+                    let start = first.saturating_sub(context_lines);
+                    let end = std::cmp::min(last + context_lines + 1, lines.len());
+                    (
+                        lines[start..first].iter().map(|line| line.to_string()).collect(),
+                        lines[last + 1..end].iter().map(|line| line.to_string()).collect(),
+                    )
+                } else {
+                    (Vec::new(), Vec::new())
+                };
+
+                SearchResult {
                     file_path: file_path.to_string_lossy().to_string(),
-                    line_number: if params.show_line_numbers { Some(line_number) } else { None },
-                    line_content: Some(line.clone()),
+                    line_number: if params.show_line_numbers { Some((first + 1) as u32) } else { None },
+                    line_content: Some(lines[first..=last].join("\n")),
                     context_before,
                     context_after,
                     match_type: "content".to_string(),
-                });
-                
-                if results.len() >= params.max_results as usize {
-                    break;
                 }
-            }
-        }
-        
-        results
+            })
+            .collect()
     }
 
-    fn search_filename(&self, file_path: &Path, pattern: &Regex) -> Option<SearchResult> {
+    fn search_filename(file_path: &Path, pattern: &Regex) -> Option<SearchResult> {
         let filename = file_path.file_name()?.to_string_lossy();
-        
+
         if pattern.is_match(&filename) {
             Some(SearchResult {
                 file_path: file_path.to_string_lossy().to_string(),
@@ -108,47 +184,134 @@ impl FindTool {
             None
         }
     }
+
+    fn search_file(file_path: &Path, pattern: &Regex, params: &FindToolParams) -> Vec<SearchResult> {
+        let mut results = Vec::new();
+        if matches!(params.find_type, FindType::Filename | FindType::Both) {
+            results.extend(Self::search_filename(file_path, pattern));
+        }
+        if matches!(params.find_type, FindType::Content | FindType::Both) {
+            results.extend(Self::search_file_content(file_path, pattern, params));
+        }
+        results
+    }
+
+    /// Search the files of a batch on several threads, the results keep the order of the files
+    fn search_batch(batch: &[Candidate], workers: usize, pattern: &Regex, params: &FindToolParams) -> Vec<SearchResult> {
+        let next = AtomicUsize::new(0);
+        let mut found: Vec<(usize, Vec<SearchResult>)> = std::thread::scope(|scope| {
+            let next = &next;
+            let handles: Vec<_> = (0..workers.min(batch.len()))
+                .map(|_| scope.spawn(move || {
+                    let mut found = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(candidate) = batch.get(index) else {
+                            break;
+                        };
+                        found.push((index, Self::search_file(&candidate.path, pattern, params)));
+                    }
+                    found
+                }))
+                .collect();
+            handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+        });
+        found.sort_by_key(|(index, _)| *index);
+        found.into_iter().flat_map(|(_, results)| results).collect()
+    }
+
+    /// Walk the directory on all cores, honoring .gitignore, .krokitignore and the hidden
+    /// files. Results are sorted with the most recently modified files first, the files are
+    /// searched in that order so that the cap keeps the most recent ones.
+    fn search(search_path: &str, pattern: &Regex, params: &FindToolParams) -> Result<Vec<SearchResult>, String> {
+        let root = Path::new(search_path);
+        let include_globs = Self::build_globs(root, &params.include_globs, false)
+            .map_err(|e| format!("Invalid include glob: {}", e))?;
+        let exclude_globs = Self::build_globs(root, &params.exclude_patterns, true)
+            .map_err(|e| format!("Invalid exclude pattern: {}", e))?;
+
+        let respect_ignore = !params.no_ignore;
+        let mut walker = WalkBuilder::new(root);
+        walker
+            .hidden(!params.hidden)
+            .ignore(respect_ignore)
+            .git_ignore(respect_ignore)
+            .git_global(respect_ignore)
+            .git_exclude(respect_ignore)
+            .parents(respect_ignore)
+            // .gitignore also applies outside of a git repository
+            .require_git(false)
+            .follow_links(false)
+            .overrides(exclude_globs);
+        if respect_ignore {
+            walker.add_custom_ignore_filename(KROKIT_IGNORE);
+        }
+
+        let candidates: Mutex<Vec<Candidate>> = Mutex::new(Vec::new());
+        let (candidates_ref, include_globs) = (&candidates, &include_globs);
+
+        walker.build_parallel().run(|| {
+            Box::new(move |entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                if !entry.file_type().map_or(false, |file_type| file_type.is_file()) {
+                    return WalkState::Continue;
+                }
+                if Self::should_include_file(entry.path(), include_globs, &params.include_extensions) {
+                    candidates_ref.lock().unwrap().push(Candidate {
+                        modified: entry.metadata().ok().and_then(|metadata| metadata.modified().ok()),
+                        path: entry.into_path(),
+                    });
+                }
+                WalkState::Continue
+            })
+        });
+
+        let mut candidates = candidates.into_inner().unwrap();
+        candidates.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+
+        let max_results = params.max_results as usize;
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let mut results: Vec<SearchResult> = Vec::new();
+        for batch in candidates.chunks(workers * SEARCH_BATCH_PER_CORE) {
+            results.extend(Self::search_batch(batch, workers, pattern, params));
+            if results.len() >= max_results {
+                break;
+            }
+        }
+        results.truncate(max_results);
+        Ok(results)
+    }
 }
 
 #[tool(name = "find", description = r#"A high-performance search utility for locating files or specific text within files across the project.
 
 **Core Functionality:**
-- Employs regular expressions for powerful content searches, allowing for complex pattern matching.
+- Employs regular expressions for powerful content searches, allowing for complex pattern matching. Set `multiline` to match across lines.
 - Can also locate files based on a pattern in their name.
 - Use the `find_type` parameter (`'content'`, `'filename'`, or `'both'`) to control the search mode.
 
 **Filtering and Scope:**
-- Narrow your search to specific file types by providing a comma-separated list of extensions to `include_extensions` (e.g., 'rs,js,py').
-- Exclude irrelevant directories and files (like `target` or `.git`) using the `exclude_patterns` parameter to speed up the search.
+- Files ignored by `.gitignore` or `.krokitignore` (like `target` or `node_modules`) and hidden files are skipped, set `no_ignore` or `hidden` to search them too. Binary files are never searched.
+- Narrow your search to specific file types by providing a comma-separated list of extensions to `include_extensions` (e.g., 'rs,js,py'), or of globs to `include_globs` (e.g., 'src/**/*.rs').
+- Exclude more files or directories with globs in `exclude_patterns` (e.g., 'vendor,*.min.js').
+- Use `max_matches_per_file` to keep a single file from filling the results.
 
 **Output:**
-- Returns a list of matching file paths, sorted with the most recently modified files appearing first. This helps prioritize recently changed files."#, capabilities = [ToolCapability::Read])]
+- Returns a list of matches, sorted with the most recently modified files appearing first. This helps prioritize recently changed files."#, capabilities = [ToolCapability::Read])]
 
 impl FindTool {
     async fn execute(&self, params: FindToolParams) -> ToolResult {
         let mut meta = HashMap::new();
         meta.insert("pattern".to_string(), json!(params.pattern));
-        let default_path = ".".to_string();
-        let search_path = params.path.as_ref().unwrap_or(&default_path);
+        let search_path = params.path.clone().unwrap_or_else(|| ".".to_string());
         meta.insert("path".to_string(), json!(search_path));
         meta.insert("case_sensitive".to_string(), json!(params.case_sensitive));
         meta.insert("max_results".to_string(), json!(params.max_results));
         meta.insert("find_type".to_string(), json!(format!("{:?}", params.find_type)));
 
-        // Create regex pattern
-        let regex_flags = if params.case_sensitive {
-            ""
-        } else {
-            "(?i)"
-        };
-        
-        let pattern_str = if params.whole_word {
-            format!("{}\\b{}\\b", regex_flags, regex::escape(&params.pattern))
-        } else {
-            format!("{}{}", regex_flags, params.pattern)
-        };
-
-        let pattern = match Regex::new(&pattern_str) {
+        let pattern = match Self::build_regex(&params) {
             Ok(regex) => regex,
             Err(e) => {
                 return ToolResult::Error {
@@ -158,55 +321,13 @@ impl FindTool {
             }
         };
 
-        let mut all_results = Vec::new();
-
-        // Walk through directory
-        for entry in WalkDir::new(search_path)
-            .follow_links(false)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            
-            // Skip directories for content search
-            if path.is_dir() {
-                continue;
-            }
-
-            // Apply file filters
-            if !self.should_include_file(path, &params.include_extensions, &params.exclude_patterns) {
-                continue;
-            }
-
-            // Search based on find_type
-            match params.find_type {
-                FindType::Content => {
-                    let mut content_results = self.search_file_content(path, &pattern, &params);
-                    all_results.append(&mut content_results);
-                },
-                FindType::Filename => {
-                    if let Some(filename_result) = self.search_filename(path, &pattern) {
-                        all_results.push(filename_result);
-                    }
-                },
-                FindType::Both => {
-                    // Search filename first
-                    if let Some(filename_result) = self.search_filename(path, &pattern) {
-                        all_results.push(filename_result);
-                    }
-                    // Then search content
-                    let mut content_results = self.search_file_content(path, &pattern, &params);
-                    all_results.append(&mut content_results);
-                }
-            }
-
-            if all_results.len() >= params.max_results as usize {
-                break;
-            }
-        }
-
-        // Truncate results to max_results
-        all_results.truncate(params.max_results as usize);
+        // the walk blocks its threads until done
+        let search = tokio::task::spawn_blocking(move || Self::search(&search_path, &pattern, &params)).await;
+        let all_results = match search {
+            Ok(Ok(results)) => results,
+            Ok(Err(error)) => return ToolResult::Error { error, metadata: Some(meta) },
+            Err(e) => return ToolResult::Error { error: format!("Search failed: {}", e), metadata: Some(meta) },
+        };
 
         meta.insert("results_count".to_string(), json!(all_results.len()));
 
@@ -5,4 +5,4 @@ pub mod find;
 mod tests;
 
 pub use structs::{FindToolParams, FindType, SearchResult};
-pub use find::FindTool;
+pub use find::{FindTool, KROKIT_IGNORE};
@@ -11,12 +11,18 @@ pub struct FindToolParams {
     /// File extensions to include (e.g., "rs,js,py")
     #[serde(default)]
     pub include_extensions: Option<String>,
-    /// File patterns to exclude (e.g., "target,node_modules,.git")
+    /// Glob patterns of the files to include, relative to the search directory (e.g., "src/**/*.rs,*.toml")
+    #[serde(default)]
+    pub include_globs: Option<String>,
+    /// Glob patterns of the files and directories to exclude (e.g., "vendor,*.min.js,tests/fixtures/**")
     #[serde(default)]
     pub exclude_patterns: Option<String>,
     /// Maximum number of results to return
     #[serde(default = "default_max_results")]
     pub max_results: u32,
+    /// Maximum number of matches reported for a single file
+    #[serde(default)]
+    pub max_matches_per_file: Option<u32>,
     /// Whether to use case-sensitive search
     #[serde(default)]
     pub case_sensitive: bool,
@@ -32,6 +38,37 @@ pub struct FindToolParams {
     /// Use whole word matching
     #[serde(default)]
     pub whole_word: bool,
+    /// Let the pattern match across lines ('.' also matches a newline)
+    #[serde(default)]
+    pub multiline: bool,
+    /// Also search hidden files and directories (starting with '.')
+    #[serde(default)]
+    pub hidden: bool,
+    /// Also search the files ignored by .gitignore and .krokitignore
+    #[serde(default)]
+    pub no_ignore: bool,
+}
+
+impl Default for FindToolParams {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            path: None,
+            include_extensions: None,
+            include_globs: None,
+            exclude_patterns: None,
+            max_results: default_max_results(),
+            max_matches_per_file: None,
+            case_sensitive: false,
+            find_type: default_find_type(),
+            show_line_numbers: default_show_line_numbers(),
+            context_lines: None,
+            whole_word: false,
+            multiline: false,
+            hidden: false,
+            no_ignore: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -55,4 +92,4 @@ pub struct SearchResult {
     pub context_before: Vec<String>,
     pub context_after: Vec<String>,
     pub match_type: String,  // "content" or "filename"
-}
\ No newline at end of file
+}
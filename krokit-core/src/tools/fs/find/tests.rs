@@ -61,6 +61,7 @@ fn main() {
         show_line_numbers: true,
         context_lines: None,
         whole_word: false,
+        ..Default::default()
     };
 
     let result = find_tool.execute(params, None).await;
@@ -87,6 +88,7 @@ fn main() {
         show_line_numbers: true,
         context_lines: Some(1),
         whole_word: false,
+        ..Default::default()
     };
 
     let result = find_tool.execute(params, None).await;
@@ -126,6 +128,7 @@ async fn test_find_tool_filename_search() {
         show_line_numbers: false,
         context_lines: None,
         whole_word: false,
+        ..Default::default()
     };
 
     let result = find_tool.execute(params, None).await;
@@ -179,6 +182,7 @@ async fn test_find_tool_with_filters() {
         show_line_numbers: true,
         context_lines: None,
         whole_word: false,
+        ..Default::default()
     };
 
     let result = find_tool.execute(params, None).await;
@@ -228,6 +232,7 @@ fn calculate_guest_score() -> u32 {
         show_line_numbers: true,
         context_lines: None,
         whole_word: false,
+        ..Default::default()
     };
 
     let result = find_tool.execute(params, None).await;
@@ -268,6 +273,7 @@ async fn test_find_tool_invalid_regex() {
         show_line_numbers: true,
         context_lines: None,
         whole_word: false,
+        ..Default::default()
     };
 
     let result = find_tool.execute(params, None).await;
@@ -279,4 +285,132 @@ async fn test_find_tool_invalid_regex() {
             assert!(error.contains("Invalid regex pattern"), "Should indicate regex error");
         }
     }
-}
\ No newline at end of file
+}
+
+fn search_results(result: crate::tools::ToolResult) -> Vec<super::structs::SearchResult> {
+    match result {
+        crate::tools::ToolResult::Success { output, .. } => serde_json::from_str(&output).expect("Should parse JSON results"),
+        crate::tools::ToolResult::Error { error, .. } => panic!("Find tool should succeed, got error: {}", error),
+    }
+}
+
+#[tokio::test]
+async fn test_find_tool_respects_ignore_files() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+
+    fs::create_dir_all(temp_path.join("src")).unwrap();
+    fs::create_dir_all(temp_path.join("target/debug")).unwrap();
+    fs::create_dir_all(temp_path.join("vendor")).unwrap();
+    fs::create_dir_all(temp_path.join(".cache")).unwrap();
+    fs::write(temp_path.join(".gitignore"), "target/\n").unwrap();
+    fs::write(temp_path.join(".krokitignore"), "vendor/\n").unwrap();
+    fs::write(temp_path.join("src/lib.rs"), "const NEEDLE: u32 = 1;").unwrap();
+    fs::write(temp_path.join("target/debug/build.rs"), "const NEEDLE: u32 = 2;").unwrap();
+    fs::write(temp_path.join("vendor/dep.rs"), "const NEEDLE: u32 = 3;").unwrap();
+    fs::write(temp_path.join(".cache/cached.rs"), "const NEEDLE: u32 = 4;").unwrap();
+    fs::write(temp_path.join("data.bin"), b"NEEDLE\0\x01\x02").unwrap();
+
+    let find_tool = FindTool::new();
+    let params = FindToolParams {
+        pattern: "NEEDLE".to_string(),
+        path: Some(temp_path.to_string_lossy().to_string()),
+        ..Default::default()
+    };
+    let results = search_results(find_tool.execute(params.clone(), None).await);
+    assert_eq!(results.len(), 1, "Only src/lib.rs should match: {:?}", results);
+    assert!(results[0].file_path.ends_with("lib.rs"));
+
+    // ignored and hidden files are searched on request, binary files never are
+    let params = FindToolParams { no_ignore: true, hidden: true, ..params };
+    let results = search_results(find_tool.execute(params, None).await);
+    assert_eq!(results.len(), 4, "All the text files should match: {:?}", results);
+    assert!(results.iter().all(|result| !result.file_path.ends_with("data.bin")));
+}
+
+#[tokio::test]
+async fn test_find_tool_globs() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+
+    fs::create_dir_all(temp_path.join("src/generated")).unwrap();
+    fs::create_dir_all(temp_path.join("docs")).unwrap();
+    fs::write(temp_path.join("src/main.rs"), "// TODO: main").unwrap();
+    fs::write(temp_path.join("src/generated/api.rs"), "// TODO: api").unwrap();
+    fs::write(temp_path.join("docs/guide.md"), "TODO: guide").unwrap();
+
+    let find_tool = FindTool::new();
+    let params = FindToolParams {
+        pattern: "TODO".to_string(),
+        path: Some(temp_path.to_string_lossy().to_string()),
+        include_globs: Some("src/**/*.rs".to_string()),
+        exclude_patterns: Some("generated".to_string()),
+        ..Default::default()
+    };
+    let results = search_results(find_tool.execute(params, None).await);
+    assert_eq!(results.len(), 1, "Only src/main.rs should match: {:?}", results);
+    assert!(results[0].file_path.ends_with("main.rs"));
+}
+
+#[tokio::test]
+async fn test_find_tool_multiline_and_per_file_cap() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+
+    fs::write(temp_path.join("lib.rs"), "#[derive(Debug)]\npub struct Config {\n    name: String,\n}\n").unwrap();
+    fs::write(temp_path.join("many.txt"), "match\nmatch\nmatch\nmatch\n").unwrap();
+
+    let find_tool = FindTool::new();
+    let params = FindToolParams {
+        pattern: r"derive\(Debug\)\]\s*pub struct".to_string(),
+        path: Some(temp_path.to_string_lossy().to_string()),
+        multiline: true,
+        ..Default::default()
+    };
+    let results = search_results(find_tool.execute(params, None).await);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].line_number, Some(1));
+    assert_eq!(results[0].line_content.as_deref(), Some("#[derive(Debug)]\npub struct Config {"));
+
+    let params = FindToolParams {
+        pattern: "match".to_string(),
+        path: Some(temp_path.to_string_lossy().to_string()),
+        max_matches_per_file: Some(2),
+        ..Default::default()
+    };
+    let results = search_results(find_tool.execute(params, None).await);
+    assert_eq!(results.len(), 2);
+}
+
+#[tokio::test]
+async fn test_find_tool_cap_keeps_the_most_recent_files() {
+    use std::time::{Duration, SystemTime};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+
+    // more files than a batch, the most recent ones are spread in the tree
+    let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    for i in 0..200 {
+        let path = temp_path.join(format!("dir{}/file{}.txt", i % 7, i));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "needle").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(epoch + Duration::from_secs(i)).unwrap();
+    }
+
+    let find_tool = FindTool::new();
+    let params = FindToolParams {
+        pattern: "needle".to_string(),
+        path: Some(temp_path.to_string_lossy().to_string()),
+        max_results: 3,
+        ..Default::default()
+    };
+    for _ in 0..3 {
+        let results = search_results(find_tool.execute(params.clone(), None).await);
+        let files: Vec<String> = results.iter()
+            .map(|result| std::path::Path::new(&result.file_path).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(files, vec!["file199.txt", "file198.txt", "file197.txt"]);
+    }
+}
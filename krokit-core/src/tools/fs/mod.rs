@@ -1,18 +1,22 @@
+pub mod checkpoint;
 pub mod edit;
 pub mod find;
 pub mod ls;
 pub mod multiedit;
 pub mod operation_log;
+pub mod patch;
 pub mod read;
 pub mod write;
 
 #[cfg(test)]
 mod tests;
 
+pub use checkpoint::{CheckpointError, CheckpointLog, FileCheckpoint, FileCheckpoints};
 pub use edit::EditTool;
 pub use find::FindTool;
 pub use ls::LsTool;
 pub use multiedit::MultiEditTool;
 pub use operation_log::{FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
+pub use patch::PatchTool;
 pub use read::ReadTool;
 pub use write::WriteTool;
\ No newline at end of file
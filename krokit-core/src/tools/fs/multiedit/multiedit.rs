@@ -50,6 +50,10 @@ impl MultiEditTool {
         
         // Only write to file if not preview mode
         if !preview {
+            // Keep the previous content so the change can be undone
+            if let Err(e) = self.operation_log.checkpoints.snapshot(&params.file_path).await {
+                tracing::warn!(target: "tools::checkpoint", error = %e, path = %params.file_path, "failed to snapshot file");
+            }
             self.edit_tool.commit_edit(&params.file_path, &current_content)?;
         }
 
@@ -3,6 +3,8 @@ use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::checkpoint::FileCheckpoints;
+
 /// Represents a file system operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FsOperation {
@@ -18,6 +20,7 @@ pub enum FsOperationType {
     Write,
     Edit,
     MultiEdit,
+    Patch,
 }
 
 /// Shared log for tracking file system operations
@@ -25,6 +28,7 @@ pub enum FsOperationType {
 pub struct FsOperationLog {
     operations: RwLock<Vec<FsOperation>>,
     read_files: RwLock<HashSet<String>>, // Tracks which files have been read
+    pub checkpoints: FileCheckpoints,    // Previous content of the modified files, per turn
 }
 
 impl FsOperationLog {
@@ -33,6 +37,7 @@ impl FsOperationLog {
         Self {
             operations: RwLock::new(Vec::new()),
             read_files: RwLock::new(HashSet::new()),
+            checkpoints: FileCheckpoints::new(),
         }
     }
 
@@ -108,6 +113,16 @@ impl FsOperationLog {
         }
     }
 
+    /// Replace the log with previously saved operations (used when resuming a session)
+    pub async fn restore(&self, operations: Vec<FsOperation>) {
+        let read: HashSet<String> = operations.iter()
+            .filter(|op| op.operation_type == FsOperationType::Read)
+            .map(|op| op.file_path.clone())
+            .collect();
+        *self.operations.write().await = operations;
+        *self.read_files.write().await = read;
+    }
+
     /// Get summary statistics
     pub async fn get_summary(&self) -> FsOperationSummary {
         let operations = self.operations.read().await;
@@ -117,6 +132,7 @@ impl FsOperationLog {
         let mut write_count = 0;
         let mut edit_count = 0;
         let mut multiedit_count = 0;
+        let mut patch_count = 0;
 
         for op in operations.iter() {
             match op.operation_type {
@@ -124,6 +140,7 @@ impl FsOperationLog {
                 FsOperationType::Write => write_count += 1,
                 FsOperationType::Edit => edit_count += 1,
                 FsOperationType::MultiEdit => multiedit_count += 1,
+                FsOperationType::Patch => patch_count += 1,
             }
         }
 
@@ -133,6 +150,7 @@ impl FsOperationLog {
             write_count,
             edit_count,
             multiedit_count,
+            patch_count,
             unique_files_read: read_files.len(),
         }
     }
@@ -146,6 +164,7 @@ pub struct FsOperationSummary {
     pub write_count: usize,
     pub edit_count: usize,
     pub multiedit_count: usize,
+    pub patch_count: usize,
     pub unique_files_read: usize,
 }
 
@@ -207,19 +226,21 @@ mod tests {
         log.log_operation(FsOperationType::Edit, "file1.txt".to_string()).await;
         log.log_operation(FsOperationType::Write, "file2.txt".to_string()).await;
         log.log_operation(FsOperationType::MultiEdit, "file1.txt".to_string()).await;
+        log.log_operation(FsOperationType::Patch, "file2.txt".to_string()).await;
         
         let operations = log.get_all_operations().await;
-        assert_eq!(operations.len(), 4);
+        assert_eq!(operations.len(), 5);
         
         let file1_ops = log.get_file_operations("file1.txt").await;
         assert_eq!(file1_ops.len(), 3);
         
         let summary = log.get_summary().await;
-        assert_eq!(summary.total_operations, 4);
+        assert_eq!(summary.total_operations, 5);
         assert_eq!(summary.read_count, 1);
         assert_eq!(summary.edit_count, 1);
         assert_eq!(summary.write_count, 1);
         assert_eq!(summary.multiedit_count, 1);
+        assert_eq!(summary.patch_count, 1);
         assert_eq!(summary.unique_files_read, 1);
     }
 
@@ -235,4 +256,18 @@ mod tests {
         assert!(log.get_all_operations().await.is_empty());
         assert!(!log.has_been_read("test.txt").await);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_restore_log() {
+        let log = FsOperationLog::new();
+        log.log_operation(FsOperationType::Read, "a.txt".to_string()).await;
+        log.log_operation(FsOperationType::Edit, "a.txt".to_string()).await;
+        let saved = log.get_all_operations().await;
+
+        let restored = FsOperationLog::new();
+        restored.restore(saved).await;
+        assert_eq!(restored.get_all_operations().await.len(), 2);
+        assert!(restored.has_been_read("a.txt").await);
+        assert!(restored.validate_edit_permission("a.txt").await.is_ok());
+    }
+}
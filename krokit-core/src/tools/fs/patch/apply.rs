@@ -0,0 +1,113 @@
+use super::structs::{Hunk, HunkLine};
+
+/// How strictly the lines of a hunk are compared to the lines of the file, from the
+/// strictest. A hunk is placed with the first level that finds it.
+#[derive(Debug, Clone, Copy)]
+enum Fuzz {
+    Exact,
+    TrailingWhitespace,
+    Whitespace,
+}
+
+impl Fuzz {
+    const LEVELS: [Fuzz; 3] = [Fuzz::Exact, Fuzz::TrailingWhitespace, Fuzz::Whitespace];
+
+    fn matches(self, file_line: &str, hunk_line: &str) -> bool {
+        match self {
+            Fuzz::Exact => file_line == hunk_line,
+            Fuzz::TrailingWhitespace => file_line.trim_end() == hunk_line.trim_end(),
+            Fuzz::Whitespace => file_line.trim() == hunk_line.trim(),
+        }
+    }
+}
+
+/// Apply the hunks of a file in order. Each hunk is searched after the previous one,
+/// closest to the line its header gives, so that line numbers that drifted or lines
+/// whose indentation changed still apply. The context lines keep the text of the file,
+/// and its line endings and final newline are preserved.
+pub fn apply_hunks(content: &str, hunks: &[Hunk], path: &str) -> Result<String, String> {
+    let line_ending = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let final_newline = content.is_empty() || content.ends_with('\n');
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut result: Vec<String> = Vec::with_capacity(lines.len());
+    let mut cursor = 0;
+    // how far the hunks were found from their header line
+    let mut drift: isize = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let old = hunk.old_lines();
+        let expected = hunk.old_start
+            .map(|start| (start as isize - 1 + drift).max(0) as usize);
+        let position = locate(&lines, cursor, &old, expected, hunk)
+            .map_err(|reason| format!("Hunk #{} of {} does not apply: {}", index + 1, path, reason))?;
+        if let Some(start) = hunk.old_start {
+            drift = position as isize - (start as isize - 1);
+        }
+
+        result.extend(lines[cursor..position].iter().map(|line| line.to_string()));
+        let mut current = position;
+        for line in &hunk.lines {
+            match line {
+                HunkLine::Context(_) => {
+                    result.push(lines[current].to_string());
+                    current += 1;
+                }
+                HunkLine::Remove(_) => current += 1,
+                HunkLine::Add(text) => result.push(text.clone()),
+            }
+        }
+        cursor = current;
+    }
+    result.extend(lines[cursor..].iter().map(|line| line.to_string()));
+
+    let mut patched = result.join(line_ending);
+    if final_newline && !result.is_empty() {
+        patched.push_str(line_ending);
+    }
+    Ok(patched)
+}
+
+/// Index of the line where the old lines of the hunk start, at or after the cursor
+fn locate(lines: &[&str], cursor: usize, old: &[&str], expected: Option<usize>, hunk: &Hunk) -> Result<usize, String> {
+    let mut from = cursor;
+    if let Some(anchor) = &hunk.anchor {
+        let anchor = anchor.trim();
+        let found = lines[cursor..].iter().position(|line| line.trim() == anchor)
+            .or_else(|| lines[cursor..].iter().position(|line| line.contains(anchor)))
+            .ok_or_else(|| format!("the line '{}' above it was not found", anchor))?;
+        from = cursor + found + 1;
+    }
+
+    if old.is_empty() {
+        // pure insertion, at the end of the file or where the header says
+        let position = if hunk.at_end {
+            lines.len()
+        } else {
+            expected.unwrap_or(if hunk.anchor.is_some() { from } else { lines.len() })
+        };
+        return Ok(position.clamp(from, lines.len()));
+    }
+    if old.len() > lines.len().saturating_sub(from) {
+        return Err(format!("the file has fewer lines than the hunk expects:\n{}", old.join("\n")));
+    }
+
+    for fuzz in Fuzz::LEVELS {
+        let candidates: Vec<usize> = (from..=lines.len() - old.len())
+            .filter(|&start| old.iter().enumerate().all(|(offset, line)| fuzz.matches(lines[start + offset], line)))
+            .collect();
+        let best = if hunk.at_end {
+            candidates.iter().copied().find(|&start| start + old.len() == lines.len())
+                .or_else(|| candidates.last().copied())
+        } else {
+            match expected {
+                Some(expected) => candidates.iter().copied().min_by_key(|&start| start.abs_diff(expected)),
+                None => candidates.first().copied(),
+            }
+        };
+        if let Some(best) = best {
+            return Ok(best);
+        }
+    }
+    Err(format!("these lines were not found in the file:\n{}", old.join("\n")))
+}
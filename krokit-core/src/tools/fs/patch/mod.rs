@@ -0,0 +1,12 @@
+pub mod structs;
+pub mod parser;
+pub mod apply;
+pub mod patch;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{PatchToolParams, FilePatch, FileChange, Hunk, HunkLine};
+pub use parser::parse_patch;
+pub use apply::apply_hunks;
+pub use patch::PatchTool;
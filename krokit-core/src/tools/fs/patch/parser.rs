@@ -0,0 +1,225 @@
+use super::structs::{FileChange, FilePatch, Hunk, HunkLine};
+
+const BEGIN_PATCH: &str = "*** Begin Patch";
+const END_PATCH: &str = "*** End Patch";
+const END_OF_FILE: &str = "*** End of File";
+
+/// Parse a unified diff or a `*** Begin Patch` envelope into the changes of its files
+pub fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let patches = if lines.iter().any(|line| line.trim() == BEGIN_PATCH) {
+        parse_envelope(&lines)?
+    } else {
+        parse_unified(&lines)?
+    };
+    if patches.is_empty() {
+        return Err("No file change found in the patch: expected a unified diff (`--- a/file`, `+++ b/file` and `@@` hunks) or a `*** Begin Patch` envelope".to_string());
+    }
+    Ok(patches)
+}
+
+/// Unified diff, the header lines of git (`diff --git`, `index`, modes) and the text
+/// around the files are ignored
+fn parse_unified(lines: &[&str]) -> Result<Vec<FilePatch>, String> {
+    let mut patches = Vec::new();
+    // a git rename without content change has no `---`/`+++` header
+    let mut rename: (Option<String>, Option<String>) = (None, None);
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        if line.starts_with("diff --git ") {
+            flush_rename(&mut rename, &mut patches);
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            rename.0 = Some(from.trim().to_string());
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            rename.1 = Some(to.trim().to_string());
+        } else if line.starts_with("--- ") && lines.get(i + 1).map_or(false, |next| next.starts_with("+++ ")) {
+            let old = header_path(&line[4..]);
+            let new = header_path(&lines[i + 1][4..]);
+            rename = (None, None);
+            i += 2;
+
+            let mut hunks = Vec::new();
+            while i < lines.len() && lines[i].starts_with("@@") {
+                let old_start = parse_old_start(lines[i]);
+                i += 1;
+                let mut hunk = Hunk { old_start, ..Default::default() };
+                i = parse_hunk_lines(lines, i, &mut hunk);
+                hunks.push(hunk);
+            }
+
+            let patch = match (old, new) {
+                (None, Some(path)) => {
+                    let lines = hunks.iter()
+                        .flat_map(|hunk| &hunk.lines)
+                        .filter_map(|line| match line {
+                            HunkLine::Add(text) => Some(text.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    FilePatch { path, change: FileChange::Add { lines } }
+                }
+                (Some(path), None) => FilePatch { path, change: FileChange::Delete },
+                (Some(path), Some(new)) => {
+                    let move_to = (new != path).then_some(new);
+                    FilePatch { path, change: FileChange::Update { hunks, move_to } }
+                }
+                (None, None) => return Err("Both files of a diff header are /dev/null".to_string()),
+            };
+            patches.push(patch);
+            continue;
+        }
+        i += 1;
+    }
+    flush_rename(&mut rename, &mut patches);
+    Ok(patches)
+}
+
+fn flush_rename(rename: &mut (Option<String>, Option<String>), patches: &mut Vec<FilePatch>) {
+    if let (Some(from), Some(to)) = std::mem::take(rename) {
+        patches.push(FilePatch { path: from, change: FileChange::Update { hunks: Vec::new(), move_to: Some(to) } });
+    }
+}
+
+/// Path of a `---` or `+++` line without the `a/` and `b/` prefixes of git nor the
+/// timestamp of diff, None for /dev/null
+fn header_path(header: &str) -> Option<String> {
+    let path = header.split('\t').next().unwrap_or(header).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path.strip_prefix("a/").or_else(|| path.strip_prefix("b/")).unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Start of the old range of a `@@ -12,5 +12,6 @@` header
+fn parse_old_start(header: &str) -> Option<usize> {
+    let range = header.split_whitespace().find_map(|part| part.strip_prefix('-'))?;
+    let start: usize = range.split(',').next()?.parse().ok()?;
+    // an empty range starts after its line, `-0,0` on an empty file
+    let empty = range.split(',').nth(1) == Some("0");
+    Some(if empty { start + 1 } else { start.max(1) })
+}
+
+/// Lines of a unified hunk up to the next hunk or file. The line counts of the header are
+/// not trusted, they are often wrong in hand written diffs.
+fn parse_hunk_lines(lines: &[&str], mut i: usize, hunk: &mut Hunk) -> usize {
+    while i < lines.len() {
+        let line = lines[i];
+        let next_file = line.starts_with("--- ") && lines.get(i + 1).map_or(false, |next| next.starts_with("+++ "));
+        if line.starts_with("@@") || line.starts_with("diff --git ") || next_file {
+            break;
+        }
+        match hunk_line(line) {
+            Some(Some(parsed)) => hunk.lines.push(parsed),
+            Some(None) => {}
+            None => break,
+        }
+        i += 1;
+    }
+    trim_trailing_blank_context(hunk);
+    i
+}
+
+/// Line of a hunk, Some(None) for the lines to skip, None for a line that is not part of one
+fn hunk_line(line: &str) -> Option<Option<HunkLine>> {
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    if line.starts_with('\\') {
+        // "\ No newline at end of file", the trailing newline of the file is kept as is
+        return Some(None);
+    }
+    match line.chars().next() {
+        Some(' ') => Some(Some(HunkLine::Context(line[1..].to_string()))),
+        Some('-') => Some(Some(HunkLine::Remove(line[1..].to_string()))),
+        Some('+') => Some(Some(HunkLine::Add(line[1..].to_string()))),
+        // editors strip the space of empty context lines
+        None => Some(Some(HunkLine::Context(String::new()))),
+        _ => None,
+    }
+}
+
+/// Blank lines after the last change are separators of the patch rather than context
+fn trim_trailing_blank_context(hunk: &mut Hunk) {
+    while matches!(hunk.lines.last(), Some(HunkLine::Context(text)) if text.trim().is_empty()) {
+        hunk.lines.pop();
+    }
+}
+
+/// Envelope of `*** Add File:`, `*** Delete File:` and `*** Update File:` sections between
+/// `*** Begin Patch` and `*** End Patch`
+fn parse_envelope(lines: &[&str]) -> Result<Vec<FilePatch>, String> {
+    let mut patches = Vec::new();
+    let start = lines.iter().position(|line| line.trim() == BEGIN_PATCH).unwrap_or(0) + 1;
+    let mut i = start;
+
+    while i < lines.len() {
+        let line = lines[i].trim_end();
+        i += 1;
+        if line.trim() == END_PATCH {
+            break;
+        }
+        if let Some(path) = line.strip_prefix("*** Add File:") {
+            let mut added = Vec::new();
+            while i < lines.len() && !lines[i].starts_with("***") {
+                let text = lines[i].strip_suffix('\r').unwrap_or(lines[i]);
+                added.push(text.strip_prefix('+').unwrap_or(text).to_string());
+                i += 1;
+            }
+            patches.push(FilePatch { path: path.trim().to_string(), change: FileChange::Add { lines: added } });
+        } else if let Some(path) = line.strip_prefix("*** Delete File:") {
+            patches.push(FilePatch { path: path.trim().to_string(), change: FileChange::Delete });
+        } else if let Some(path) = line.strip_prefix("*** Update File:") {
+            let mut move_to = None;
+            if let Some(to) = lines.get(i).and_then(|line| line.strip_prefix("*** Move to:")) {
+                move_to = Some(to.trim().to_string());
+                i += 1;
+            }
+            let mut hunks: Vec<Hunk> = Vec::new();
+            while i < lines.len() {
+                let line = lines[i];
+                if line.trim_end() == END_OF_FILE {
+                    if let Some(hunk) = hunks.last_mut() {
+                        hunk.at_end = true;
+                    }
+                    i += 1;
+                    continue;
+                }
+                if line.starts_with("***") {
+                    break;
+                }
+                if let Some(header) = line.strip_prefix("@@") {
+                    hunks.push(envelope_hunk(line, header));
+                } else {
+                    match hunk_line(line) {
+                        Some(Some(parsed)) => {
+                            if hunks.is_empty() {
+                                hunks.push(Hunk::default());
+                            }
+                            hunks.last_mut().unwrap().lines.push(parsed);
+                        }
+                        Some(None) => {}
+                        None => return Err(format!("Invalid line in the changes of {}: '{}', lines start with ' ', '-' or '+'", path.trim(), line)),
+                    }
+                }
+                i += 1;
+            }
+            hunks.iter_mut().for_each(trim_trailing_blank_context);
+            hunks.retain(|hunk| !hunk.lines.is_empty());
+            patches.push(FilePatch { path: path.trim().to_string(), change: FileChange::Update { hunks, move_to } });
+        } else if !line.trim().is_empty() {
+            return Err(format!("Invalid line in the patch: '{}', expected `*** Add File:`, `*** Delete File:`, `*** Update File:` or `*** End Patch`", line));
+        }
+    }
+    Ok(patches)
+}
+
+/// Hunk of an envelope, its header is either `@@ line above the change` or a unified one
+fn envelope_hunk(line: &str, header: &str) -> Hunk {
+    let header = header.trim();
+    if header.starts_with('-') && header.ends_with("@@") {
+        return Hunk { old_start: parse_old_start(line), ..Default::default() };
+    }
+    let anchor = (!header.is_empty()).then(|| header.to_string());
+    Hunk { anchor, ..Default::default() }
+}
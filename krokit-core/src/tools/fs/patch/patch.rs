@@ -0,0 +1,256 @@
+use super::apply::apply_hunks;
+use super::parser::parse_patch;
+use super::structs::{FileChange, FilePatch, PatchToolParams};
+use super::super::{EditTool, FsOperationLog, FsOperationType};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Content of a file before and after the patch, None when it does not exist
+#[derive(Debug, Clone)]
+struct PlannedFile {
+    path: String,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+impl PlannedFile {
+    fn action(&self) -> &'static str {
+        match (&self.before, &self.after) {
+            (None, _) => "add",
+            (Some(_), None) => "delete",
+            (Some(_), Some(_)) => "update",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PatchTool {
+    operation_log: Arc<FsOperationLog>,
+    edit_tool: EditTool,
+}
+
+impl PatchTool {
+    pub fn new(operation_log: Arc<FsOperationLog>) -> Self {
+        let edit_tool = EditTool::new(operation_log.clone());
+        Self { operation_log, edit_tool }
+    }
+
+    /// Content of every file touched by the patch once applied, computed in memory. Nothing
+    /// is planned when a single file change fails.
+    async fn plan(&self, patches: &[FilePatch]) -> Result<Vec<PlannedFile>, String> {
+        let mut planned: Vec<PlannedFile> = Vec::new();
+
+        for patch in patches {
+            let current = Self::current(&planned, &patch.path)?;
+            match &patch.change {
+                FileChange::Add { lines } => {
+                    if current.is_some() {
+                        return Err(format!("Cannot add {}: the file already exists, update it instead", patch.path));
+                    }
+                    let mut content = lines.join("\n");
+                    if !lines.is_empty() {
+                        content.push('\n');
+                    }
+                    Self::set(&mut planned, &patch.path, None, Some(content));
+                }
+                FileChange::Delete => {
+                    if current.is_none() {
+                        return Err(format!("Cannot delete {}: the file does not exist", patch.path));
+                    }
+                    self.validate_read(&planned, &patch.path).await?;
+                    Self::set(&mut planned, &patch.path, current, None);
+                }
+                FileChange::Update { hunks, move_to } => {
+                    let Some(content) = current.clone() else {
+                        return Err(format!("Cannot update {}: the file does not exist", patch.path));
+                    };
+                    self.validate_read(&planned, &patch.path).await?;
+                    let patched = apply_hunks(&content, hunks, &patch.path)?;
+                    match move_to {
+                        Some(destination) if destination != &patch.path => {
+                            if Self::current(&planned, destination)?.is_some() {
+                                return Err(format!("Cannot move {} to {}: the destination already exists", patch.path, destination));
+                            }
+                            Self::set(&mut planned, destination, None, Some(patched));
+                            Self::set(&mut planned, &patch.path, current, None);
+                        }
+                        _ => Self::set(&mut planned, &patch.path, current, Some(patched)),
+                    }
+                }
+            }
+        }
+        Ok(planned)
+    }
+
+    /// Content of a file as the previous changes of the patch leave it
+    fn current(planned: &[PlannedFile], path: &str) -> Result<Option<String>, String> {
+        if let Some(file) = planned.iter().find(|file| file.path == path) {
+            return Ok(file.after.clone());
+        }
+        let file = Path::new(path);
+        if !file.exists() {
+            return Ok(None);
+        }
+        if file.is_dir() {
+            return Err(format!("{} is a directory", path));
+        }
+        fs::read_to_string(file).map(Some).map_err(|e| format!("Cannot read {}: {}", path, e))
+    }
+
+    /// Record the new content of a file, keeping its content before the first change
+    fn set(planned: &mut Vec<PlannedFile>, path: &str, before: Option<String>, after: Option<String>) {
+        match planned.iter_mut().find(|file| file.path == path) {
+            Some(file) => file.after = after,
+            None => planned.push(PlannedFile { path: path.to_string(), before, after }),
+        }
+    }
+
+    /// Existing files are only changed once read, unless the patch created them
+    async fn validate_read(&self, planned: &[PlannedFile], path: &str) -> Result<(), String> {
+        if planned.iter().any(|file| file.path == path && file.before.is_none()) {
+            return Ok(());
+        }
+        if self.operation_log.validate_edit_permission(path).await.is_ok() {
+            return Ok(());
+        }
+        // the file may have been read by its absolute path
+        let absolute = std::env::current_dir()
+            .map(|cwd| cwd.join(path).to_string_lossy().to_string())
+            .unwrap_or_default();
+        if self.operation_log.has_been_read(&absolute).await {
+            return Ok(());
+        }
+        self.operation_log.validate_edit_permission(path).await
+    }
+
+    /// Write all the planned files, or none: when a write fails the files already written
+    /// get their previous content back
+    async fn commit(&self, planned: &[PlannedFile]) -> Result<(), String> {
+        for file in planned {
+            // Keep the previous content so the change can be undone
+            if let Err(e) = self.operation_log.checkpoints.snapshot(&file.path).await {
+                tracing::warn!(target: "tools::checkpoint", error = %e, path = %file.path, "failed to snapshot file");
+            }
+        }
+
+        for (index, file) in planned.iter().enumerate() {
+            if let Err(e) = Self::write(&file.path, file.after.as_deref()) {
+                for written in planned[..index].iter().rev() {
+                    if let Err(e) = Self::write(&written.path, written.before.as_deref()) {
+                        tracing::warn!(target: "tools::patch", error = %e, path = %written.path, "failed to restore file");
+                    }
+                }
+                return Err(format!("Cannot write {}: {}. No file was changed", file.path, e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace a file through a temporary file renamed over it, or remove it
+    fn write(path: &str, content: Option<&str>) -> io::Result<()> {
+        let path = Path::new(path);
+        let Some(content) = content else {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            };
+        };
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let temporary = path.with_file_name(format!(".{}.krokit-patch", file_name));
+        fs::write(&temporary, content)?;
+        if let Ok(metadata) = fs::metadata(path) {
+            let _ = fs::set_permissions(&temporary, metadata.permissions());
+        }
+        fs::rename(&temporary, path).inspect_err(|_| {
+            let _ = fs::remove_file(&temporary);
+        })
+    }
+
+    fn render(&self, planned: &[PlannedFile]) -> String {
+        planned.iter()
+            .map(|file| {
+                let diff = self.edit_tool.myers_diff(
+                    file.before.as_deref().unwrap_or(""),
+                    file.after.as_deref().unwrap_or(""));
+                format!("{} {}\n{}", file.action(), file.path, diff)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[tool(name = "patch", description = r#"Applies a patch to one or several files at once, as a single atomic change: either every file is changed or none is. Use it for changes spanning several files, or for large changes that are easier to describe as a diff than as find-and-replace operations.
+
+**Formats:**
+- A unified diff, as printed by `git diff` or `diff -u`: `--- a/path` and `+++ b/path` headers followed by `@@ -12,5 +12,6 @@` hunks. `/dev/null` as the old file adds a file, as the new file deletes it, and different paths rename it.
+- A patch envelope:
+```
+*** Begin Patch
+*** Update File: src/lib.rs
+@@ fn parse(input: &str) {
+-    let value = input.trim();
++    let value = input.trim().to_lowercase();
+*** Add File: src/util.rs
++pub fn helper() {}
+*** Delete File: src/old.rs
+*** End Patch
+```
+  In `*** Update File:` sections, `@@` optionally names a line above the hunk, `*** Move to: path` renames the file, and `*** End of File` marks a hunk at the end of the file.
+
+**Matching:**
+- Hunk lines start with ' ' (context), '-' (removed) or '+' (added). Give about 3 lines of context around each change.
+- Hunks are searched near their line numbers, which may be approximate, and the whitespace at the end or the start of the lines is ignored when the exact lines are not found.
+
+**Critical Considerations:**
+- You must first use the `read` tool on every file the patch updates or deletes.
+- The files added must not exist yet."#, capabilities = [ToolCapability::Read, ToolCapability::Write])]
+impl PatchTool {
+    async fn execute_preview(&self, params: PatchToolParams) -> Option<ToolResult> {
+        Some(self.execute_internal(params, true).await)
+    }
+
+    async fn execute(&self, params: PatchToolParams) -> ToolResult {
+        self.execute_internal(params, false).await
+    }
+
+    async fn execute_internal(&self, params: PatchToolParams, preview: bool) -> ToolResult {
+        let patches = match parse_patch(&params.patch) {
+            Ok(patches) => patches,
+            Err(e) => return ToolResult::error(e),
+        };
+        let planned = match self.plan(&patches).await {
+            Ok(planned) => planned,
+            Err(e) => return ToolResult::error(format!("Patch not applied, no file was changed: {}", e)),
+        };
+
+        if !preview {
+            if let Err(e) = self.commit(&planned).await {
+                return ToolResult::error(e);
+            }
+            for file in &planned {
+                self.operation_log.log_operation(FsOperationType::Patch, file.path.clone()).await;
+            }
+        }
+
+        let mut meta = HashMap::new();
+        let files: Vec<serde_json::Value> = planned.iter()
+            .map(|file| json!({ "path": file.path, "action": file.action() }))
+            .collect();
+        meta.insert("files".to_string(), json!(files));
+        meta.insert("files_count".to_string(), json!(planned.len()));
+        meta.insert("preview_mode".to_string(), json!(preview));
+
+        ToolResult::Success {
+            output: self.render(&planned),
+            metadata: Some(meta),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct PatchToolParams {
+    /// The patch to apply: a unified diff as printed by `git diff`, or a `*** Begin Patch` envelope. It may change several files.
+    pub patch: String,
+}
+
+/// Change of one file in a patch
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePatch {
+    pub path: String,
+    pub change: FileChange,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileChange {
+    /// New file with these lines
+    Add { lines: Vec<String> },
+    Delete,
+    /// Hunks applied in order, then the file is renamed when `move_to` is set
+    Update { hunks: Vec<Hunk>, move_to: Option<String> },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hunk {
+    /// First line of the hunk in the original file, when the patch gives it
+    pub old_start: Option<usize>,
+    /// Line above the hunk, from the `@@ anchor` header of an envelope
+    pub anchor: Option<String>,
+    pub lines: Vec<HunkLine>,
+    /// The hunk ends at the end of the file
+    pub at_end: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl Hunk {
+    /// Lines the hunk expects in the file
+    pub fn old_lines(&self) -> Vec<&str> {
+        self.lines.iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,222 @@
+use super::apply::apply_hunks;
+use super::parser::parse_patch;
+use super::patch::PatchTool;
+use super::structs::{FileChange, Hunk, HunkLine, PatchToolParams};
+use crate::tools::{FsOperationLog, FsOperationType, Tool, ToolCapability, ToolResult};
+use krokit_llm::ToolDescription;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn hunks(patch: &str) -> Vec<Hunk> {
+    match parse_patch(patch).unwrap().remove(0).change {
+        FileChange::Update { hunks, .. } => hunks,
+        other => panic!("expected an update: {:?}", other),
+    }
+}
+
+#[test]
+fn test_patch_tool_permissions() {
+    let tool = PatchTool::new(Arc::new(FsOperationLog::new()));
+    assert_eq!(&tool.name(), "patch");
+    let perms = tool.capabilities();
+    assert!(perms.contains(&ToolCapability::Read));
+    assert!(perms.contains(&ToolCapability::Write));
+    assert_eq!(perms.len(), 2);
+}
+
+#[test]
+fn test_parse_git_diff() {
+    let patch = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a9c2f0e 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    old();
++    new();
+ }
+diff --git a/notes.txt b/notes.txt
+new file mode 100644
+--- /dev/null
++++ b/notes.txt
+@@ -0,0 +1,2 @@
++first
++second
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+--- a/old.rs
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+diff --git a/a.rs b/b.rs
+similarity index 100%
+rename from a.rs
+rename to b.rs
+";
+    let patches = parse_patch(patch).unwrap();
+    assert_eq!(patches.len(), 4);
+
+    assert_eq!(patches[0].path, "src/lib.rs");
+    assert_eq!(patches[0].change, FileChange::Update {
+        hunks: vec![Hunk {
+            old_start: Some(1),
+            anchor: None,
+            lines: vec![
+                HunkLine::Context("fn main() {".to_string()),
+                HunkLine::Remove("    old();".to_string()),
+                HunkLine::Add("    new();".to_string()),
+                HunkLine::Context("}".to_string()),
+            ],
+            at_end: false,
+        }],
+        move_to: None,
+    });
+    assert_eq!(patches[1].change, FileChange::Add { lines: vec!["first".to_string(), "second".to_string()] });
+    assert_eq!((patches[2].path.as_str(), &patches[2].change), ("old.rs", &FileChange::Delete));
+    assert_eq!(patches[3].change, FileChange::Update { hunks: vec![], move_to: Some("b.rs".to_string()) });
+}
+
+#[test]
+fn test_parse_envelope() {
+    let patch = "\
+Here is the change:
+*** Begin Patch
+*** Update File: src/app.py
+*** Move to: src/main.py
+@@ def run():
+-    return 1
++    return 2
+@@
++# end
+*** End of File
+*** Add File: README.md
++# Title
++
+*** Delete File: setup.cfg
+*** End Patch";
+    let patches = parse_patch(patch).unwrap();
+    assert_eq!(patches.len(), 3);
+    match &patches[0].change {
+        FileChange::Update { hunks, move_to } => {
+            assert_eq!(move_to.as_deref(), Some("src/main.py"));
+            assert_eq!(hunks.len(), 2);
+            assert_eq!(hunks[0].anchor.as_deref(), Some("def run():"));
+            assert!(hunks[1].at_end);
+        }
+        other => panic!("expected an update: {:?}", other),
+    }
+    assert_eq!(patches[1].change, FileChange::Add { lines: vec!["# Title".to_string(), String::new()] });
+    assert_eq!(patches[2].change, FileChange::Delete);
+
+    assert!(parse_patch("just some text").is_err());
+    assert!(parse_patch("*** Begin Patch\n*** Rewrite File: a\n*** End Patch").is_err());
+}
+
+#[test]
+fn test_apply_finds_hunks_that_drifted() {
+    let content = "header\nextra\nfn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n";
+    // the line numbers are off by one and the indentation of the context changed
+    let patch = "\
+--- a/f.rs
++++ b/f.rs
+@@ -2,3 +2,3 @@
+ fn a() {
+-    one();
++    uno();
+ }
+@@ -6,3 +6,3 @@
+ fn b() {
+-  two();
++    dos();
+ }
+";
+    let patched = apply_hunks(content, &hunks(patch), "f.rs").unwrap();
+    assert_eq!(patched, "header\nextra\nfn a() {\n    uno();\n}\n\nfn b() {\n    dos();\n}\n");
+
+    let missing = "--- a/f.rs\n+++ b/f.rs\n@@ -1 +1 @@\n-fn c() {\n+fn d() {\n";
+    let error = apply_hunks(content, &hunks(missing), "f.rs").unwrap_err();
+    assert!(error.contains("Hunk #1 of f.rs") && error.contains("fn c() {"), "{}", error);
+}
+
+#[test]
+fn test_apply_keeps_line_endings() {
+    let patch = "*** Begin Patch\n*** Update File: f.txt\n@@ a\n-b\n+B\n*** End Patch";
+    assert_eq!(apply_hunks("a\r\nb\r\nc\r\n", &hunks(patch), "f.txt").unwrap(), "a\r\nB\r\nc\r\n");
+    assert_eq!(apply_hunks("a\nb\nc", &hunks(patch), "f.txt").unwrap(), "a\nB\nc");
+
+    let at_end = "*** Begin Patch\n*** Update File: f.txt\n@@\n+d\n*** End of File\n*** End Patch";
+    assert_eq!(apply_hunks("a\nb\nc\n", &hunks(at_end), "f.txt").unwrap(), "a\nb\nc\nd\n");
+}
+
+#[tokio::test]
+async fn test_patch_requires_read() {
+    let dir = tempdir().unwrap();
+    let file = path_str(&dir.path().join("main.rs"));
+    fs::write(&file, "fn main() {}\n").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    let tool = PatchTool::new(log.clone());
+    let params = PatchToolParams {
+        patch: format!("--- {0}\n+++ {0}\n@@ -1 +1 @@\n-fn main() {{}}\n+fn main() {{ run(); }}\n", file),
+    };
+
+    let result = tool.execute(params.clone(), None).await;
+    assert!(matches!(&result, ToolResult::Error { error, .. } if error.contains("must be read first")), "{:?}", result);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn main() {}\n");
+
+    log.log_operation(FsOperationType::Read, file.clone()).await;
+    let preview = tool.execute_preview(params.clone()).await.unwrap();
+    assert!(preview.is_success());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn main() {}\n", "the preview does not write");
+
+    assert!(tool.execute(params, None).await.is_success());
+    assert_eq!(fs::read_to_string(&file).unwrap(), "fn main() { run(); }\n");
+    assert_eq!(log.get_summary().await.patch_count, 1);
+}
+
+#[tokio::test]
+async fn test_patch_changes_all_files_or_none() {
+    let dir = tempdir().unwrap();
+    let first = path_str(&dir.path().join("first.txt"));
+    let second = path_str(&dir.path().join("second.txt"));
+    let added = path_str(&dir.path().join("new/added.txt"));
+    fs::write(&first, "one\ntwo\n").unwrap();
+    fs::write(&second, "three\nfour\n").unwrap();
+
+    let log = Arc::new(FsOperationLog::new());
+    log.log_operation(FsOperationType::Read, first.clone()).await;
+    log.log_operation(FsOperationType::Read, second.clone()).await;
+    let tool = PatchTool::new(log.clone());
+
+    // the second file does not match, the first one is left untouched
+    let failing = format!("*** Begin Patch\n*** Update File: {}\n-one\n+ONE\n*** Update File: {}\n-five\n+FIVE\n*** Add File: {}\n+hello\n*** End Patch", first, second, added);
+    let result = tool.execute(PatchToolParams { patch: failing }, None).await;
+    assert!(matches!(&result, ToolResult::Error { error, .. } if error.contains("no file was changed")), "{:?}", result);
+    assert_eq!(fs::read_to_string(&first).unwrap(), "one\ntwo\n");
+    assert!(!Path::new(&added).exists());
+
+    let patch = format!("*** Begin Patch\n*** Update File: {}\n-one\n+ONE\n*** Delete File: {}\n*** Add File: {}\n+hello\n*** End Patch", first, second, added);
+    let result = tool.execute(PatchToolParams { patch }, None).await;
+    assert!(result.is_success(), "{:?}", result);
+    assert_eq!(fs::read_to_string(&first).unwrap(), "ONE\ntwo\n");
+    assert!(!Path::new(&second).exists());
+    assert_eq!(fs::read_to_string(&added).unwrap(), "hello\n");
+
+    // adding over an existing file is refused
+    let patch = format!("*** Begin Patch\n*** Add File: {}\n+again\n*** End Patch", added);
+    assert!(!tool.execute(PatchToolParams { patch }, None).await.is_success());
+    assert_eq!(fs::read_to_string(&added).unwrap(), "hello\n");
+
+    // the whole patch is undone at once
+    assert_eq!(log.checkpoints.undo(1).await.unwrap().len(), 3);
+    assert_eq!(fs::read_to_string(&first).unwrap(), "one\ntwo\n");
+    assert_eq!(fs::read_to_string(&second).unwrap(), "three\nfour\n");
+    assert!(!Path::new(&added).exists());
+}
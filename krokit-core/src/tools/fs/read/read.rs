@@ -1,6 +1,8 @@
 use crate::tools::{ToolResult, tool};
 use super::structs::ReadToolParams;
 use super::super::{FsOperationLog, FsOperationType};
+use krokit_llm::ChatMessageContentPart;
+use krokit_llm::image::{image_part_from_file, is_image_path, parse_data_url};
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
@@ -106,6 +108,26 @@ impl ReadTool {
         }
     }
 
+    /// Images are not returned as text, they are attached to the conversation for the
+    /// models that can see them
+    fn read_image(&self, params: &ReadToolParams) -> ToolResult {
+        let part = match image_part_from_file(&params.path) {
+            Ok(ChatMessageContentPart::Image(image)) => image,
+            Ok(_) => return ToolResult::error(format!("Failed to read image: {}", params.path)),
+            Err(e) => return ToolResult::error(format!("Failed to read image: {}", e)),
+        };
+        let size = fs::metadata(&params.path).map(|meta| meta.len()).unwrap_or(0);
+        let media_type = parse_data_url(&part.image_url.url).map(|(media_type, _)| media_type.to_string()).unwrap_or_default();
+
+        let mut meta = HashMap::new();
+        meta.insert("path".to_string(), json!(params.path));
+        meta.insert("image".to_string(), json!(part.image_url.url));
+        ToolResult::success_with_metadata(
+            format!("Image {} ({}, {} bytes), attached to the next message", params.path, media_type, size),
+            meta,
+        )
+    }
+
     fn format_lines(&self, lines: Vec<(u32, String)>, show_line_numbers: bool) -> String {
         if show_line_numbers {
             lines
@@ -129,6 +151,7 @@ impl ReadTool {
 - An absolute `path` to the file is required.
 - For large files, you can read a specific portion by specifying `line_start` and `line_end`. If omitted, the entire file is read (within system limits).
 - The output is formatted with line numbers for easy reference, which is crucial context for subsequent `edit` operations.
+- Images (png, jpeg, gif, webp) are attached to the conversation so that you can look at them, e.g. screenshots or diagrams.
 
 **Best Practices:**
 - When investigating a task, it is often effective to read multiple potentially relevant files in a single turn to build a complete understanding of the context."#, capabilities = [Read])]
@@ -146,6 +169,11 @@ impl ReadTool {
             return ToolResult::error(format!("Path is not a file: {}", params.path));
         }
 
+        if is_image_path(path) {
+            self.operation_log.log_operation(FsOperationType::Read, params.path.clone()).await;
+            return self.read_image(&params);
+        }
+
         // Read the file
         match self.read_file_content(&params) {
             Ok(content) => {
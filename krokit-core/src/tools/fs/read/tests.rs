@@ -167,4 +167,36 @@ async fn test_read_tool_line_range_reading() {
                    "Should indicate file not found error, got: {}", error);
         }
     }
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_read_tool_returns_images() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let image_path = temp_dir.path().join("screenshot.png");
+    fs::write(&image_path, [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D]).expect("Failed to write image");
+
+    let read_tool = ReadTool::new(Arc::new(FsOperationLog::new()));
+    let params = ReadToolParams {
+        path: image_path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        show_line_numbers: true,
+    };
+
+    let result = read_tool.execute(params, None).await;
+    assert!(result.is_success(), "{}", result);
+    assert!(result.to_string().contains("image/png, 12 bytes"), "{}", result);
+    let Some(krokit_llm::ChatMessageContentPart::Image(image)) = result.image() else {
+        panic!("the image should be returned");
+    };
+    assert!(image.image_url.url.starts_with("data:image/png;base64,"));
+
+    // not an image, whatever the extension
+    fs::write(&image_path, "not an image").expect("Failed to write file");
+    let params = ReadToolParams {
+        path: image_path.to_string_lossy().to_string(),
+        line_start: None,
+        line_end: None,
+        show_line_numbers: false,
+    };
+    assert!(read_tool.execute(params, None).await.is_error());
+}
@@ -11,7 +11,7 @@ mod integration_tests {
         find::structs::FindToolParams,
         write::structs::WriteToolParams,
         read::structs::ReadToolParams,
-        edit::structs::EditToolParams,
+        edit::structs::{EditToolParams, MatchMode},
         multiedit::structs::{MultiEditToolParams, EditOperation}
     };
 
@@ -84,6 +84,7 @@ mod integration_tests {
             old_string: "Hello, World!".to_string(),
             new_string: "Hello, Universe!".to_string(),
             replace_all: false,
+            match_mode: MatchMode::Exact,
         }, None).await;
         assert!(edit_result.is_success());
         
@@ -156,6 +157,7 @@ mod integration_tests {
             old_string: "Content".to_string(),
             new_string: "Modified content".to_string(),
             replace_all: false,
+            match_mode: MatchMode::Exact,
         }, None).await;
         assert!(edit_result.is_error());
         if let crate::tools::types::ToolResult::Error { error, .. } = edit_result {
@@ -191,6 +193,7 @@ mod integration_tests {
             old_string: "Content".to_string(),
             new_string: "Modified content".to_string(),
             replace_all: false,
+            match_mode: MatchMode::Exact,
         }, None).await;
         assert!(edit_result.is_success());
         
@@ -261,6 +264,7 @@ mod integration_tests {
             show_line_numbers: false,
             context_lines: None,
             whole_word: false,
+            ..Default::default()
         }, None).await;
         assert!(find_result.is_success());
         
@@ -280,6 +284,7 @@ mod integration_tests {
             old_string: r#""version": "1.0""#.to_string(),
             new_string: r#""version": "2.0""#.to_string(),
             replace_all: false,
+            match_mode: MatchMode::Exact,
         }, None).await;
         assert!(edit_result.is_success());
         
@@ -298,6 +303,7 @@ mod integration_tests {
             old_string: "Hello, Python!".to_string(),
             new_string: "Hello, World from Python!".to_string(),
             replace_all: false,
+            match_mode: MatchMode::Exact,
         }, None).await;
         assert!(edit_result.is_success());
         
@@ -65,6 +65,11 @@ impl WriteTool {
     }
 
     async fn execute(&self, params: WriteToolParams) -> ToolResult {
+        // Keep the previous content so the change can be undone
+        if let Err(e) = self.operation_log.checkpoints.snapshot(&params.path).await {
+            tracing::warn!(target: "tools::checkpoint", error = %e, path = %params.path, "failed to snapshot file");
+        }
+
         match self.perform_write(&params) {
             Ok(message) => {
                 // Log the write operation
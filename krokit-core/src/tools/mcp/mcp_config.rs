@@ -1,4 +1,5 @@
 use crate::tools::McpClient;
+use krokit_llm::HttpSettings;
 use serde::{Serialize, Deserialize};
 
 use super::{StdioClient, HttpClient, SseClient};
@@ -9,9 +10,19 @@ pub enum McpConfig {
     #[serde(rename = "stdio")]
     Stdio { command: String, args: Vec<String> },
     #[serde(rename = "http")]
-    Http { url: String, bearer_token: Option<String> },
+    Http {
+        url: String,
+        bearer_token: Option<String>,
+        /// proxy, CA and timeouts of the connection to the server
+        #[serde(default, skip_serializing_if = "HttpSettings::is_default")]
+        http: HttpSettings,
+    },
     #[serde(rename = "sse")]
-    Sse { url: String },
+    Sse {
+        url: String,
+        #[serde(default, skip_serializing_if = "HttpSettings::is_default")]
+        http: HttpSettings,
+    },
 }
 
 /// Factory function to create an MCP client from configuration
@@ -20,11 +31,11 @@ pub fn create_mcp_client(config: McpConfig) -> Box<dyn McpClient> {
         McpConfig::Stdio { command, args } => {
             Box::new(StdioClient::new(command, args))
         }
-        McpConfig::Http { url, bearer_token } => {
-            Box::new(HttpClient::new_with_auth(url, bearer_token))
+        McpConfig::Http { url, bearer_token, http } => {
+            Box::new(HttpClient::new_with_auth(url, bearer_token).with_http(http))
         }
-        McpConfig::Sse { url } => {
-            Box::new(SseClient::new(url))
+        McpConfig::Sse { url, http } => {
+            Box::new(SseClient::new(url).with_http(http))
         }
     }
 }
\ No newline at end of file
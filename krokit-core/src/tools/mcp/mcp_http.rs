@@ -6,6 +6,7 @@ use rmcp::{
     RoleClient,
 };
 use std::borrow::Cow;
+use krokit_llm::HttpSettings;
 
 use crate::tools::{ToolResult, ToolCall};
 use super::mcp::{McpClient, McpToolDescription};
@@ -13,6 +14,7 @@ use super::mcp::{McpClient, McpToolDescription};
 pub struct HttpClient {
     url: String,
     bearer_token: Option<String>,
+    http: HttpSettings,
     service: Option<RunningService<RoleClient, InitializeRequestParam>>,
 }
 
@@ -25,9 +27,16 @@ impl HttpClient {
         Self {
             url,
             bearer_token,
+            http: HttpSettings::default(),
             service: None,
         }
     }
+
+    /// Connect through the proxy, CA and timeouts of the settings
+    pub fn with_http(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
 }
 
 #[async_trait]
@@ -38,27 +47,24 @@ impl McpClient for HttpClient {
             return Ok(());
         }
         
-        let transport = if let Some(token) = &self.bearer_token {
-            // Create a custom reqwest client with default bearer token
+        let mut builder = self.http.client_builder()?;
+        if let Some(token) = &self.bearer_token {
+            // default bearer token on every request
             let mut default_headers = reqwest::header::HeaderMap::new();
             default_headers.insert(
                 reqwest::header::AUTHORIZATION,
                 reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))?
             );
-            let client = reqwest::Client::builder()
-                .default_headers(default_headers)
-                .build()?;
-            
-            StreamableHttpClientTransport::with_client(
-                client,
-                rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig {
-                    uri: self.url.clone().into(),
-                    ..Default::default()
-                }
-            )
-        } else {
-            StreamableHttpClientTransport::from_uri(self.url.as_str())
-        };
+            builder = builder.default_headers(default_headers);
+        }
+
+        let transport = StreamableHttpClientTransport::with_client(
+            builder.build()?,
+            rmcp::transport::streamable_http_client::StreamableHttpClientTransportConfig {
+                uri: self.url.clone().into(),
+                ..Default::default()
+            }
+        );
 
         let client_info = ClientInfo {
             protocol_version: Default::default(),
@@ -9,6 +9,7 @@ use std::sync::{Arc, Mutex};
 use reqwest;
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
+use krokit_llm::HttpSettings;
 
 #[derive(Serialize)]
 struct ClientRegistrationRequest {
@@ -25,12 +26,17 @@ struct ClientRegistrationResponse {
 }
 
 pub async fn signin_oauth(base_url: &str) -> anyhow::Result<String> {
+    signin_oauth_with(base_url, &HttpSettings::default()).await
+}
+
+/// OAuth sign in, the requests to the server go through the proxy and CA of the settings
+pub async fn signin_oauth_with(base_url: &str, http: &HttpSettings) -> anyhow::Result<String> {
     // Extract the root domain for .well-known endpoint (OAuth standard)
     let url = Url::parse(base_url)?;
     let root_url = format!("{}://{}", url.scheme(), url.host_str().unwrap_or(""));
     let well_known_url = format!("{}/.well-known/oauth-authorization-server", root_url);
     
-    let client = reqwest::Client::new();
+    let client = http.build_client().map_err(|e| anyhow::anyhow!("{}", e))?;
     let oauth_metadata: serde_json::Value = client
         .get(&well_known_url)
         .send()
@@ -2,16 +2,18 @@ use async_trait::async_trait;
 use rmcp::{
     model::{CallToolRequestParam, ClientCapabilities, ClientInfo, Implementation, InitializeRequestParam},
     service::{ServiceExt, RunningService},
-    transport::SseClientTransport,
+    transport::{SseClientTransport, sse_client::SseClientConfig},
     RoleClient,
 };
 use std::borrow::Cow;
+use krokit_llm::HttpSettings;
 
 use crate::tools::{ToolResult, ToolCall};
 use super::mcp::{McpClient, McpToolDescription};
 
 pub struct SseClient {
     url: String,
+    http: HttpSettings,
     service: Option<RunningService<RoleClient, InitializeRequestParam>>,
 }
 
@@ -19,9 +21,16 @@ impl SseClient {
     pub fn new(url: String) -> Self {
         Self {
             url,
+            http: HttpSettings::default(),
             service: None,
         }
     }
+
+    /// Connect through the proxy, CA and timeouts of the settings
+    pub fn with_http(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
 }
 
 #[async_trait]
@@ -32,7 +41,13 @@ impl McpClient for SseClient {
             return Ok(());
         }
         
-        let transport = SseClientTransport::start(self.url.as_str()).await?;
+        let transport = SseClientTransport::start_with_client(
+            self.http.build_client()?,
+            SseClientConfig {
+                sse_endpoint: self.url.clone().into(),
+                ..Default::default()
+            }
+        ).await?;
         let client_info = ClientInfo {
             protocol_version: Default::default(),
             capabilities: ClientCapabilities::default(),
@@ -148,7 +148,8 @@ mod tests {
         // Test HTTP config
         let http_config = McpConfig::Http {
             url: "http://localhost:8080".to_string(),
-            bearer_token: None
+            bearer_token: None,
+            http: Default::default(),
         };
         let _http_client = create_mcp_client(http_config);
         println!("✅ Successfully created HttpClient via factory");
@@ -156,6 +157,7 @@ mod tests {
         // Test SSE config
         let sse_config = McpConfig::Sse {
             url: "http://localhost:8080/sse".to_string(),
+            http: Default::default(),
         };
         let _sse_client = create_mcp_client(sse_config);
         println!("✅ Successfully created SseClient via factory");
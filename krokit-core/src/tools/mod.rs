@@ -5,16 +5,20 @@ pub mod fs;
 pub mod fetch;
 pub mod bash;
 pub mod mcp;
+pub mod symbols;
+pub mod semantic_search;
 
 #[cfg(test)]
 mod tests_llm;
 
 pub use krokit_macros::tool;
-pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams};
+pub use types::{Tool, ToolCall, ToolResult, ToolError, ToolCapability, AnyTool, AnyToolBox, ToolEmptyParams, ProgressSink};
 
 // Re-export all tools
 pub use bash::BashTool;
 pub use fetch::FetchTool;
-pub use fs::{EditTool, FindTool, LsTool, MultiEditTool, ReadTool, WriteTool, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary};
+pub use fs::{EditTool, FindTool, LsTool, MultiEditTool, PatchTool, ReadTool, WriteTool, FsOperationLog, FsOperationType, FsOperation, FsOperationSummary, FileCheckpoints, CheckpointLog, CheckpointError};
+pub use symbols::SymbolsTool;
+pub use semantic_search::SemanticSearchTool;
 pub use todo::{TodoReadTool, TodoWriteTool, TodoStorage, TodoItem, TodoStatus, TodoWriteParams, TodoItemInput};
 pub use mcp::{McpClient, McpToolDescription, McpConfig, create_mcp_client, get_mcp_tools, StdioClient, HttpClient, SseClient};
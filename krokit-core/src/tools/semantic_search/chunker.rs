@@ -0,0 +1,104 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::tools::symbols::extract_symbols;
+use super::embedder::fnv1a;
+
+/// Longest chunk, larger definitions are split in windows of this size
+pub const MAX_CHUNK_LINES: u32 = 60;
+
+/// Longest text sent to the embedding model for a chunk
+const MAX_EMBEDDED_CHARS: usize = 6000;
+
+/// Range of lines of a file embedded as one vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chunk {
+    /// first and last line of the chunk, 1-based
+    pub start_line: u32,
+    pub end_line: u32,
+    /// qualified name of the definition, None for the code between definitions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// hash of the embedded text, the vector of an unchanged chunk is reused
+    pub hash: u64,
+}
+
+/// Chunks of a source file with the text to embed for each of them. Definitions found by
+/// tree-sitter make a chunk each (the definitions of a large type or module one by one),
+/// the code between them is cut in windows.
+pub fn chunk_file(path: &Path, source: &str) -> Vec<(Chunk, String)> {
+    let lines: Vec<&str> = source.lines().collect();
+    let total = lines.len() as u32;
+
+    let mut ranges: Vec<(u32, u32, Option<String>)> = Vec::new();
+    // last line already in a chunk
+    let mut covered = 0;
+    for symbol in extract_symbols(path, source).unwrap_or_default() {
+        if symbol.line <= covered || symbol.end_line > total {
+            continue;
+        }
+        if symbol.end_line - symbol.line < MAX_CHUNK_LINES {
+            ranges.push((symbol.line, symbol.end_line, Some(symbol.qualified_name())));
+            covered = symbol.end_line;
+        } else if symbol.kind.is_callable() {
+            for start in (symbol.line..=symbol.end_line).step_by(MAX_CHUNK_LINES as usize) {
+                let end = (start + MAX_CHUNK_LINES - 1).min(symbol.end_line);
+                ranges.push((start, end, Some(symbol.qualified_name())));
+            }
+            covered = symbol.end_line;
+        }
+    }
+
+    let mut gaps = Vec::new();
+    let mut next = 1;
+    for (start, end, _) in &ranges {
+        gaps.extend(windows(&lines, next, start - 1));
+        next = end + 1;
+    }
+    gaps.extend(windows(&lines, next, total));
+    ranges.extend(gaps.into_iter().map(|(start, end)| (start, end, None)));
+    ranges.sort_by_key(|(start, ..)| *start);
+
+    ranges.into_iter()
+        .map(|(start_line, end_line, symbol)| {
+            let text = lines[start_line as usize - 1..end_line as usize].join("\n");
+            let text = embedded_text(path, symbol.as_deref(), &text);
+            let chunk = Chunk { start_line, end_line, symbol, hash: fnv1a(text.as_bytes()) };
+            (chunk, text)
+        })
+        .collect()
+}
+
+/// Windows of the lines from..=to, without their blank lines at both ends
+fn windows(lines: &[&str], from: u32, to: u32) -> Vec<(u32, u32)> {
+    let is_blank = |line: u32| lines[line as usize - 1].trim().is_empty();
+    let mut windows = Vec::new();
+    if from > to {
+        return windows;
+    }
+    for start in (from..=to).step_by(MAX_CHUNK_LINES as usize) {
+        let mut start = start;
+        let mut end = (start + MAX_CHUNK_LINES - 1).min(to);
+        while start <= end && is_blank(start) {
+            start += 1;
+        }
+        while end > start && is_blank(end) {
+            end -= 1;
+        }
+        if start <= end {
+            windows.push((start, end));
+        }
+    }
+    windows
+}
+
+/// The path and the name of the definition give the model context the code lacks
+fn embedded_text(path: &Path, symbol: Option<&str>, text: &str) -> String {
+    let mut embedded = match symbol {
+        Some(symbol) => format!("{} {}\n{}", path.display(), symbol, text),
+        None => format!("{}\n{}", path.display(), text),
+    };
+    if let Some((end, _)) = embedded.char_indices().nth(MAX_EMBEDDED_CHARS) {
+        embedded.truncate(end);
+    }
+    embedded
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use krokit_llm::LlmClient;
+
+/// Texts sent to the embedding model in one request
+const EMBEDDING_BATCH: usize = 64;
+
+/// Turns texts into vectors of unit length, so that their dot product is their cosine
+/// similarity
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Model of the vectors, an index built by another model is built again
+    fn id(&self) -> String;
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, String>;
+}
+
+/// Embedding model of a provider
+pub struct LlmEmbedder {
+    llm: Arc<LlmClient>,
+    model: String,
+}
+
+impl LlmEmbedder {
+    pub fn new(llm: Arc<LlmClient>, model: String) -> Self {
+        Self { llm, model }
+    }
+}
+
+#[async_trait]
+impl Embedder for LlmEmbedder {
+    fn id(&self) -> String {
+        format!("{}/{}", self.llm.provider_name(), self.model)
+    }
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBEDDING_BATCH) {
+            let embeddings = self.llm.embeddings(&self.model, batch.to_vec()).await
+                .map_err(|e| format!("Embedding with {} failed: {}", self.id(), e))?;
+            vectors.extend(embeddings.into_iter().map(normalized));
+        }
+        Ok(vectors)
+    }
+}
+
+/// Local stand-in for an embedding model, without network nor model files: the words
+/// of the text (identifiers split on their case and underscores, reduced to their stem)
+/// are hashed into the dimensions of the vector. Texts sharing words are close.
+pub struct HashEmbedder {
+    dimensions: usize,
+}
+
+impl Default for HashEmbedder {
+    fn default() -> Self {
+        Self { dimensions: 384 }
+    }
+}
+
+impl HashEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions: dimensions.max(1) }
+    }
+
+    pub fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for word in words(text) {
+            *counts.entry(word).or_default() += 1;
+        }
+
+        let mut vector = vec![0.0; self.dimensions];
+        for (word, count) in counts {
+            let hash = fnv1a(word.as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[(hash % self.dimensions as u64) as usize] += sign * (1.0 + (count as f32).ln());
+        }
+        normalized(vector)
+    }
+}
+
+#[async_trait]
+impl Embedder for HashEmbedder {
+    fn id(&self) -> String {
+        format!("local/hash-{}", self.dimensions)
+    }
+
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+}
+
+/// Words too common in code and in questions to tell chunks apart
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "from", "with", "where", "what", "how", "this", "that",
+    "let", "mut", "pub", "self", "use", "return", "new", "def", "func", "function", "const", "var",
+];
+
+/// Lowercase stems of the words of a text, "refreshAccessTokens" gives "refresh",
+/// "access" and "token"
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for identifier in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut word = String::new();
+        let mut previous_lowercase = false;
+        for c in identifier.chars() {
+            if c.is_uppercase() && previous_lowercase {
+                words.push(std::mem::take(&mut word));
+            }
+            previous_lowercase = c.is_lowercase() || c.is_ascii_digit();
+            word.extend(c.to_lowercase());
+        }
+        words.push(word);
+    }
+    words.into_iter()
+        .filter(|word| word.chars().count() > 2 && !STOP_WORDS.contains(&word.as_str()))
+        .map(stem)
+        .collect()
+}
+
+fn stem(word: String) -> String {
+    if word.ends_with("ss") {
+        return word;
+    }
+    for suffix in ["ing", "ed", "es", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix).filter(|stem| stem.chars().count() >= 4) {
+            return stem.to_string();
+        }
+    }
+    word
+}
+
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+    vector
+}
+
+/// FNV-1a, stable across builds unlike the std hasher, the hashes are kept on disk
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use ignore::{WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use crate::tools::fs::find::KROKIT_IGNORE;
+use crate::tools::symbols::SymbolLanguage;
+use super::chunker::{chunk_file, Chunk};
+use super::embedder::Embedder;
+
+/// Larger files are generated or vendored code, they are not indexed
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedChunk {
+    #[serde(flatten)]
+    chunk: Chunk,
+    vector: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    modified: SystemTime,
+    len: u64,
+    chunks: Vec<IndexedChunk>,
+}
+
+/// Source file read and chunked by a scan, new or changed since the last update
+struct ScannedFile {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    chunks: Vec<(Chunk, String)>,
+}
+
+/// What an update did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdateStats {
+    pub files: usize,
+    pub chunks: usize,
+    /// chunks sent to the embedding model, the others kept their vector
+    pub embedded: usize,
+}
+
+pub struct SearchHit<'a> {
+    pub path: &'a Path,
+    pub chunk: &'a Chunk,
+    pub score: f32,
+}
+
+/// Vectors of the chunks of the source files of a directory, the paths are relative to
+/// it. It is kept on disk and updated incrementally: only the files whose modification
+/// time or size changed are chunked again, and only their new chunks are embedded.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SemanticIndex {
+    embedder: String,
+    files: HashMap<PathBuf, IndexedFile>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl SemanticIndex {
+    /// Location of the index of a directory
+    pub fn index_path(root: &Path) -> PathBuf {
+        root.join(".krokit").join("semantic_index.json")
+    }
+
+    /// Index saved at the path, an empty one when there is none or when it was built
+    /// by another embedding model
+    pub fn load(path: impl Into<PathBuf>, embedder: &str) -> Self {
+        let path = path.into();
+        let mut index: SemanticIndex = fs::read_to_string(&path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .filter(|index: &SemanticIndex| index.embedder == embedder)
+            .unwrap_or_default();
+        index.embedder = embedder.to_string();
+        index.path = Some(path);
+        index
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn files_count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn chunks_count(&self) -> usize {
+        self.files.values().map(|file| file.chunks.len()).sum()
+    }
+
+    /// Bring the index up to date with the source files of the root directory
+    pub async fn update(&mut self, root: &Path, embedder: &dyn Embedder) -> Result<UpdateStats, String> {
+        let known: HashMap<PathBuf, (SystemTime, u64)> = self.files.iter()
+            .map(|(path, file)| (path.clone(), (file.modified, file.len)))
+            .collect();
+        // reading and parsing a whole project blocks its threads until done
+        let scan_root = root.to_path_buf();
+        let (present, scanned) = tokio::task::spawn_blocking(move || scan(&scan_root, &known)).await
+            .map_err(|e| format!("Indexing failed: {}", e))?;
+
+        // the unchanged chunks of an edited file keep their vector, even when their lines moved
+        let mut vectors: HashMap<u64, Vec<f32>> = HashMap::new();
+        let wanted: HashSet<u64> = scanned.iter()
+            .flat_map(|file| file.chunks.iter().map(|(chunk, _)| chunk.hash))
+            .collect();
+        for chunk in self.files.values().flat_map(|file| &file.chunks) {
+            if wanted.contains(&chunk.chunk.hash) {
+                vectors.entry(chunk.chunk.hash).or_insert_with(|| chunk.vector.clone());
+            }
+        }
+        self.files.retain(|path, _| present.contains(path));
+
+        let mut texts = Vec::new();
+        let mut hashes = Vec::new();
+        let mut queued = HashSet::new();
+        for (chunk, text) in scanned.iter().flat_map(|file| &file.chunks) {
+            if !vectors.contains_key(&chunk.hash) && queued.insert(chunk.hash) {
+                hashes.push(chunk.hash);
+                texts.push(text.clone());
+            }
+        }
+        let embedded = texts.len();
+        let embeddings = embedder.embed(texts).await?;
+        if embeddings.len() != embedded {
+            return Err(format!("The embedding model returned {} vectors for {} chunks", embeddings.len(), embedded));
+        }
+        vectors.extend(hashes.into_iter().zip(embeddings));
+
+        for file in scanned {
+            let chunks = file.chunks.into_iter()
+                .filter_map(|(chunk, _)| {
+                    let vector = vectors.get(&chunk.hash)?.clone();
+                    Some(IndexedChunk { chunk, vector })
+                })
+                .collect();
+            self.files.insert(file.path, IndexedFile { modified: file.modified, len: file.len, chunks });
+        }
+
+        Ok(UpdateStats {
+            files: self.files_count(),
+            chunks: self.chunks_count(),
+            embedded,
+        })
+    }
+
+    /// Chunks closest to the query vector, under a path relative to the root (all of them
+    /// when empty), best first
+    pub fn search(&self, query: &[f32], under: &Path, max_results: usize) -> Vec<SearchHit<'_>> {
+        let mut hits: Vec<SearchHit> = self.files.iter()
+            .filter(|(path, _)| path.starts_with(under))
+            .flat_map(|(path, file)| file.chunks.iter().map(move |chunk| SearchHit {
+                path: path.as_path(),
+                chunk: &chunk.chunk,
+                score: chunk.vector.iter().zip(query).map(|(a, b)| a * b).sum(),
+            }))
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score)
+            .then_with(|| a.path.cmp(b.path))
+            .then_with(|| a.chunk.start_line.cmp(&b.chunk.start_line)));
+        hits.truncate(max_results);
+        hits
+    }
+}
+
+/// Walk the source files of the root, honoring .gitignore and .krokitignore, and chunk the
+/// ones that are not known with the same modification time and size. Returns the paths of
+/// all the files found with the files chunked.
+fn scan(root: &Path, known: &HashMap<PathBuf, (SystemTime, u64)>) -> (HashSet<PathBuf>, Vec<ScannedFile>) {
+    let present: Mutex<HashSet<PathBuf>> = Mutex::new(HashSet::new());
+    let scanned: Mutex<Vec<ScannedFile>> = Mutex::new(Vec::new());
+    let (present_ref, scanned_ref) = (&present, &scanned);
+
+    WalkBuilder::new(root)
+        .require_git(false)
+        .add_custom_ignore_filename(KROKIT_IGNORE)
+        .build_parallel()
+        .run(|| {
+            Box::new(move |entry| {
+                let Ok(entry) = entry else {
+                    return WalkState::Continue;
+                };
+                let source_file = entry.file_type().map_or(false, |file_type| file_type.is_file())
+                    && SymbolLanguage::from_path(entry.path()).is_some();
+                let Some(metadata) = source_file.then(|| entry.metadata().ok()).flatten() else {
+                    return WalkState::Continue;
+                };
+                let (Ok(modified), len) = (metadata.modified(), metadata.len()) else {
+                    return WalkState::Continue;
+                };
+                if len > MAX_FILE_BYTES {
+                    return WalkState::Continue;
+                }
+
+                let path = entry.path().strip_prefix(root).unwrap_or(entry.path()).to_path_buf();
+                present_ref.lock().unwrap().insert(path.clone());
+                if known.get(&path) == Some(&(modified, len)) {
+                    return WalkState::Continue;
+                }
+                if let Ok(bytes) = fs::read(entry.path()) {
+                    let chunks = chunk_file(&path, &String::from_utf8_lossy(&bytes));
+                    scanned_ref.lock().unwrap().push(ScannedFile { path, modified, len, chunks });
+                }
+                WalkState::Continue
+            })
+        });
+
+    (present.into_inner().unwrap(), scanned.into_inner().unwrap())
+}
@@ -0,0 +1,14 @@
+pub mod structs;
+pub mod chunker;
+pub mod embedder;
+pub mod index;
+pub mod semantic_search;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::SemanticSearchToolParams;
+pub use chunker::{chunk_file, Chunk};
+pub use embedder::{Embedder, HashEmbedder, LlmEmbedder};
+pub use index::{SemanticIndex, SearchHit, UpdateStats};
+pub use semantic_search::SemanticSearchTool;
@@ -0,0 +1,162 @@
+use super::embedder::{Embedder, HashEmbedder, LlmEmbedder};
+use super::index::SemanticIndex;
+use super::structs::SemanticSearchToolParams;
+use crate::config::config::KrokitConfig;
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Lines of each result shown in the output
+const PREVIEW_LINES: usize = 12;
+
+pub struct SemanticSearchTool {
+    embedder: Arc<dyn Embedder>,
+    root: PathBuf,
+    /// loaded from disk on the first search
+    index: Arc<Mutex<Option<SemanticIndex>>>,
+}
+
+impl SemanticSearchTool {
+    /// Search the current directory with the given embedding model
+    pub fn new(embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            embedder,
+            root: PathBuf::from("."),
+            index: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Search with the local stand-in model, without network
+    pub fn local() -> Self {
+        Self::new(Arc::new(HashEmbedder::default()))
+    }
+
+    /// Semantic search with the embeddings of the krokit config, the local model when
+    /// they are not configured or their provider cannot be created
+    pub fn from_config() -> Self {
+        let llm = KrokitConfig::load().ok()
+            .and_then(|config| config.embeddings_llm().ok().flatten());
+        match llm {
+            Some((llm, model)) => Self::new(Arc::new(LlmEmbedder::new(Arc::new(llm), model))),
+            None => Self::local(),
+        }
+    }
+
+    /// Index and search another directory than the current one
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Path relative to the root, as the index stores them. Both are canonicalized, so
+    /// that an absolute path is found under a relative root and the other way around
+    fn relative(&self, path: &str) -> PathBuf {
+        let canonical = (fs::canonicalize(&self.root), fs::canonicalize(self.root.join(path)));
+        if let (Ok(root), Ok(path)) = canonical {
+            if let Ok(relative) = path.strip_prefix(&root) {
+                return relative.to_path_buf();
+            }
+        }
+        let path = Path::new(path);
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        let path = path.strip_prefix(".").unwrap_or(path);
+        path.to_path_buf()
+    }
+
+    fn display_path(&self, path: &Path) -> PathBuf {
+        if self.root == Path::new(".") {
+            path.to_path_buf()
+        } else {
+            self.root.join(path)
+        }
+    }
+
+    /// Numbered first lines of a result, read from the file
+    fn preview(path: &Path, start_line: u32, end_line: u32) -> String {
+        let Ok(content) = fs::read_to_string(path) else {
+            return String::new();
+        };
+        let count = ((end_line - start_line + 1) as usize).min(PREVIEW_LINES);
+        let mut preview: Vec<String> = content.lines()
+            .enumerate()
+            .skip(start_line as usize - 1)
+            .take(count)
+            .map(|(number, line)| format!("{:>5} {}", number + 1, line))
+            .collect();
+        if end_line - start_line + 1 > count as u32 {
+            preview.push("      ...".to_string());
+        }
+        preview.join("\n")
+    }
+}
+
+#[tool(name = "semantic_search", description = r#"Finds the code related to a question or a concept. Use it for questions like "where do we handle token refresh" or "how are the retries of failed requests scheduled", when you do not know the exact names to search for with `find` or `symbols`.
+
+**Functionality:**
+- The source files (Rust, Python, JavaScript, TypeScript, Go) are split in chunks at the boundaries of their functions, types and modules, and each chunk is embedded as a vector.
+- The embedding model is the one configured in `embeddings`, which also finds code that does not contain the words of the query. Without it, a local model compares the words of the query with the words of the code, identifiers included (`refreshToken` holds "refresh" and "token"), so use the words the code is likely to contain. The `embedder` of the result metadata tells which model is used.
+- Returns the chunks closest to the query, best first, as `path:start-end name (score)` followed by their first lines.
+- The index is kept in `.krokit/semantic_index.json` and updated on each search, only the files changed since the last one are embedded again.
+
+**Notes:**
+- Describe what the code does rather than guessing identifiers.
+- Restrict the search to a file or a directory with `path`.
+- Files ignored by `.gitignore` or `.krokitignore` and hidden files are not indexed.
+- The results are candidates, read the files to confirm them."#, capabilities = [ToolCapability::Read, ToolCapability::Network])]
+impl SemanticSearchTool {
+    async fn execute(&self, params: SemanticSearchToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        meta.insert("query".to_string(), json!(params.query));
+        meta.insert("embedder".to_string(), json!(self.embedder.id()));
+        if params.query.trim().is_empty() {
+            return ToolResult::Error { error: "The query is empty".to_string(), metadata: Some(meta) };
+        }
+
+        // one search at a time updates the index
+        let mut index = self.index.lock().await;
+        let index = index.get_or_insert_with(|| {
+            SemanticIndex::load(SemanticIndex::index_path(&self.root), &self.embedder.id())
+        });
+        let stats = match index.update(&self.root, self.embedder.as_ref()).await {
+            Ok(stats) => stats,
+            Err(error) => return ToolResult::Error { error, metadata: Some(meta) },
+        };
+        meta.insert("files_indexed".to_string(), json!(stats.files));
+        meta.insert("chunks_embedded".to_string(), json!(stats.embedded));
+        if stats.embedded > 0 {
+            // a search still works without its index on disk, it is only slower next time
+            if let Err(e) = index.save() {
+                meta.insert("save_error".to_string(), json!(e.to_string()));
+            }
+        }
+
+        let query = match self.embedder.embed(vec![params.query.clone()]).await {
+            Ok(mut vectors) if !vectors.is_empty() => vectors.remove(0),
+            Ok(_) => return ToolResult::Error { error: "The embedding model returned no vector".to_string(), metadata: Some(meta) },
+            Err(error) => return ToolResult::Error { error, metadata: Some(meta) },
+        };
+        let under = params.path.as_deref().map(|path| self.relative(path)).unwrap_or_default();
+        let hits = index.search(&query, &under, params.max_results as usize);
+        meta.insert("results_count".to_string(), json!(hits.len()));
+
+        if hits.is_empty() {
+            let output = format!("No code indexed under {} ({} files indexed)", params.path.as_deref().unwrap_or("."), stats.files);
+            return ToolResult::Success { output, metadata: Some(meta) };
+        }
+        let output = hits.iter()
+            .map(|hit| {
+                let path = self.display_path(hit.path);
+                let name = hit.chunk.symbol.as_deref().unwrap_or("-");
+                format!("{}:{}-{} {} ({:.2})\n{}",
+                    path.display(), hit.chunk.start_line, hit.chunk.end_line, name, hit.score,
+                    Self::preview(&self.root.join(hit.path), hit.chunk.start_line, hit.chunk.end_line))
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        ToolResult::Success { output, metadata: Some(meta) }
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SemanticSearchToolParams {
+    /// What the code does, in natural language (e.g., "where are the auth tokens refreshed")
+    pub query: String,
+    /// Only the results under this file or directory (defaults to the whole project)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Maximum number of code chunks to return
+    #[serde(default = "default_max_results")]
+    pub max_results: u32,
+}
+
+fn default_max_results() -> u32 { 10 }
@@ -0,0 +1,177 @@
+use super::chunker::chunk_file;
+use super::embedder::{Embedder, HashEmbedder};
+use super::index::SemanticIndex;
+use super::semantic_search::SemanticSearchTool;
+use super::structs::SemanticSearchToolParams;
+use crate::tools::{Tool, ToolResult};
+use krokit_llm::ToolDescription;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use std::fs;
+
+const AUTH_SOURCE: &str = r#"use std::time::Instant;
+
+pub struct TokenStore {
+    access_token: String,
+    expires_at: Instant,
+}
+
+impl TokenStore {
+    /// Refresh the access token when it expired
+    pub fn refresh_token(&mut self) {
+        if self.expires_at < Instant::now() {
+            self.access_token = fetch_new_token();
+        }
+    }
+}
+
+fn fetch_new_token() -> String {
+    String::from("token")
+}
+"#;
+
+const RETRY_SOURCE: &str = r#"pub fn retry_request(attempts: u32) -> u32 {
+    let mut delay = 1;
+    for _ in 0..attempts {
+        delay *= 2;
+    }
+    delay
+}
+"#;
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+#[test]
+fn test_chunks_follow_definitions() {
+    let chunks = chunk_file(Path::new("auth.rs"), AUTH_SOURCE);
+    let ranges: Vec<(u32, u32, Option<&str>)> = chunks.iter()
+        .map(|(chunk, _)| (chunk.start_line, chunk.end_line, chunk.symbol.as_deref()))
+        .collect();
+    assert_eq!(ranges, vec![
+        (1, 1, None),
+        (3, 6, Some("TokenStore")),
+        (8, 15, Some("TokenStore")),
+        (17, 19, Some("fetch_new_token")),
+    ]);
+
+    let (_, text) = &chunks[2];
+    assert!(text.starts_with("auth.rs TokenStore\nimpl TokenStore {"), "path and name give context: {}", text);
+}
+
+#[test]
+fn test_large_definitions_are_split() {
+    let mut source = String::from("impl Big {\n");
+    for i in 0..50 {
+        source.push_str(&format!("    fn method_{}(&self) {{\n        println!(\"{}\");\n    }}\n", i, i));
+    }
+    source.push_str("}\n");
+    let chunks = chunk_file(Path::new("big.rs"), &source);
+
+    // the impl is too large, its methods are chunked one by one
+    assert_eq!(chunks.len(), 52);
+    assert_eq!(chunks[1].0.symbol.as_deref(), Some("Big::method_0"));
+    // its first and last lines are left between the methods
+    assert_eq!((chunks[0].0.start_line, chunks[0].0.symbol.as_deref()), (1, None));
+    assert_eq!((chunks[51].0.start_line, chunks[51].0.symbol.as_deref()), (152, None));
+
+    let mut function = String::from("fn long() {\n");
+    function.push_str(&"    step();\n".repeat(100));
+    function.push_str("}\n");
+    let chunks = chunk_file(Path::new("long.rs"), &function);
+    assert_eq!(chunks.iter().map(|(chunk, _)| (chunk.start_line, chunk.end_line)).collect::<Vec<_>>(),
+        vec![(1, 60), (61, 102)]);
+}
+
+#[test]
+fn test_hash_embedder_matches_the_words_of_the_code() {
+    let embedder = HashEmbedder::default();
+    let query = embedder.embed_text("where do we handle token refresh");
+    let auth = embedder.embed_text("fn refreshAccessTokens(&mut self)");
+    let retry = embedder.embed_text("fn retry_request(attempts: u32)");
+
+    assert!((dot(&auth, &auth) - 1.0).abs() < 1e-5, "vectors have a unit length");
+    assert!(dot(&query, &auth) > dot(&query, &retry));
+    assert_eq!(embedder.embed_text(""), vec![0.0; 384]);
+}
+
+#[tokio::test]
+async fn test_index_embeds_only_what_changed() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/auth.rs"), AUTH_SOURCE).unwrap();
+    fs::write(root.join("src/retry.rs"), RETRY_SOURCE).unwrap();
+    fs::write(root.join("notes.txt"), "not source code").unwrap();
+
+    let embedder = HashEmbedder::default();
+    let path = SemanticIndex::index_path(root);
+    let mut index = SemanticIndex::load(&path, &embedder.id());
+    let stats = index.update(root, &embedder).await.unwrap();
+    assert_eq!((stats.files, stats.chunks, stats.embedded), (2, 5, 5));
+    index.save().unwrap();
+
+    // nothing changed, nothing embedded
+    let mut index = SemanticIndex::load(&path, &embedder.id());
+    assert_eq!(index.update(root, &embedder).await.unwrap().embedded, 0);
+
+    // only the new function is embedded, the other chunks moved down a line
+    fs::write(root.join("src/auth.rs"), format!("fn login() {{}}\n{}", AUTH_SOURCE)).unwrap();
+    fs::remove_file(root.join("src/retry.rs")).unwrap();
+    let stats = index.update(root, &embedder).await.unwrap();
+    assert_eq!((stats.files, stats.chunks, stats.embedded), (1, 5, 1));
+
+    // another model starts over
+    let other = HashEmbedder::new(64);
+    let mut index = SemanticIndex::load(&path, &other.id());
+    assert_eq!(index.update(root, &other).await.unwrap().embedded, 5);
+}
+
+#[tokio::test]
+async fn test_semantic_search_tool() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join("src/net")).unwrap();
+    fs::write(root.join("src/auth.rs"), AUTH_SOURCE).unwrap();
+    fs::write(root.join("src/net/retry.rs"), RETRY_SOURCE).unwrap();
+
+    let tool = SemanticSearchTool::new(Arc::new(HashEmbedder::default())).with_root(root);
+    assert_eq!(&tool.name(), "semantic_search");
+
+    let params = |query: &str, path: Option<&str>| SemanticSearchToolParams {
+        query: query.to_string(),
+        path: path.map(str::to_string),
+        max_results: 2,
+    };
+
+    let output = match tool.execute(params("where do we handle token refresh", None), None).await {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("search should succeed: {:?}", other),
+    };
+    let first = output.lines().next().unwrap();
+    assert!(first.contains("auth.rs:8-15 TokenStore"), "best match first: {}", output);
+    assert!(output.contains("fn refresh_token(&mut self)"), "results show the code: {}", output);
+    assert!(root.join(".krokit/semantic_index.json").exists());
+
+    let output = match tool.execute(params("token refresh", Some("src/net")), None).await {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("search should succeed: {:?}", other),
+    };
+    assert!(output.contains("retry.rs:1-7 retry_request"));
+    assert!(!output.contains("auth.rs"), "results are restricted to the path: {}", output);
+
+    // an absolute path is found under a root written differently
+    let tool = SemanticSearchTool::new(Arc::new(HashEmbedder::default())).with_root(root.join("src/.."));
+    let absolute = root.join("src/net").to_string_lossy().to_string();
+    let output = match tool.execute(params("token refresh", Some(&absolute)), None).await {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("search should succeed: {:?}", other),
+    };
+    assert!(output.contains("retry.rs:1-7 retry_request"), "{}", output);
+    assert!(!output.contains("auth.rs"), "results are restricted to the path: {}", output);
+
+    let result = tool.execute(params("  ", None), None).await;
+    assert!(matches!(result, ToolResult::Error { .. }));
+}
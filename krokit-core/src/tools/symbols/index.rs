@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use ignore::{WalkBuilder, WalkState};
+use crate::tools::fs::find::KROKIT_IGNORE;
+use super::parser::{extract_symbols, SymbolLanguage};
+use super::structs::Symbol;
+
+struct IndexedFile {
+    modified: SystemTime,
+    len: u64,
+    symbols: Arc<Vec<Symbol>>,
+}
+
+/// Symbols of the source files, a file is parsed again only when its modification time
+/// or its size changed since it was indexed
+#[derive(Default)]
+pub struct SymbolIndex {
+    files: Mutex<HashMap<PathBuf, IndexedFile>>,
+}
+
+/// Paths are stored without the leading "./" of the walk of the current directory
+fn normalize(path: &Path) -> PathBuf {
+    if path == Path::new(".") {
+        return PathBuf::new();
+    }
+    path.strip_prefix("./").unwrap_or(path).to_path_buf()
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of files indexed
+    pub fn len(&self) -> usize {
+        self.files.lock().unwrap().len()
+    }
+
+    /// Symbols of a file, None when its language is not supported or it cannot be read
+    pub fn file_symbols(&self, path: &Path) -> Option<Arc<Vec<Symbol>>> {
+        SymbolLanguage::from_path(path)?;
+        let path = normalize(path);
+        let metadata = fs::metadata(&path).ok()?;
+        let modified = metadata.modified().ok()?;
+
+        if let Some(indexed) = self.files.lock().unwrap().get(&path) {
+            if indexed.modified == modified && indexed.len == metadata.len() {
+                return Some(indexed.symbols.clone());
+            }
+        }
+
+        let bytes = fs::read(&path).ok()?;
+        let symbols = Arc::new(extract_symbols(&path, &String::from_utf8_lossy(&bytes))?);
+        self.files.lock().unwrap().insert(path, IndexedFile {
+            modified,
+            len: metadata.len(),
+            symbols: symbols.clone(),
+        });
+        Some(symbols)
+    }
+
+    /// Symbols of the source files of a directory (or of a single file), sorted by path.
+    /// Files ignored by .gitignore or .krokitignore and hidden files are skipped, files
+    /// deleted since the last call are dropped from the index.
+    pub fn directory_symbols(&self, root: &Path) -> Vec<(PathBuf, Arc<Vec<Symbol>>)> {
+        if root.is_file() {
+            return self.file_symbols(root)
+                .map(|symbols| vec![(normalize(root), symbols)])
+                .unwrap_or_default();
+        }
+
+        let found: Mutex<Vec<(PathBuf, Arc<Vec<Symbol>>)>> = Mutex::new(Vec::new());
+        let found_ref = &found;
+        WalkBuilder::new(root)
+            .require_git(false)
+            .add_custom_ignore_filename(KROKIT_IGNORE)
+            .build_parallel()
+            .run(|| {
+                Box::new(move |entry| {
+                    let Ok(entry) = entry else {
+                        return WalkState::Continue;
+                    };
+                    if entry.file_type().map_or(false, |file_type| file_type.is_file()) {
+                        if let Some(symbols) = self.file_symbols(entry.path()) {
+                            found_ref.lock().unwrap().push((normalize(entry.path()), symbols));
+                        }
+                    }
+                    WalkState::Continue
+                })
+            });
+
+        let mut found = found.into_inner().unwrap();
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let root = normalize(root);
+        let seen: HashSet<&PathBuf> = found.iter().map(|(path, _)| path).collect();
+        self.files.lock().unwrap()
+            .retain(|path, _| !path.starts_with(&root) || seen.contains(path));
+        found
+    }
+}
@@ -0,0 +1,12 @@
+pub mod structs;
+pub mod parser;
+pub mod index;
+pub mod symbols;
+
+#[cfg(test)]
+mod tests;
+
+pub use structs::{SymbolsToolParams, SymbolsAction, Symbol, SymbolKind};
+pub use parser::{extract_symbols, SymbolLanguage};
+pub use index::SymbolIndex;
+pub use symbols::SymbolsTool;
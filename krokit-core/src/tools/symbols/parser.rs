@@ -0,0 +1,187 @@
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+use super::structs::{Symbol, SymbolKind};
+
+/// Longest signature kept, in characters
+const MAX_SIGNATURE_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl SymbolLanguage {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "py" | "pyi" => Some(Self::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "go" => Some(Self::Go),
+            _ => None,
+        }
+    }
+
+    fn grammar(&self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+}
+
+/// Definitions of a source file in the order of the file, None when the language is
+/// not supported
+pub fn extract_symbols(path: &Path, source: &str) -> Option<Vec<Symbol>> {
+    let language = SymbolLanguage::from_path(path)?;
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar()).ok()?;
+    let tree = parser.parse(source, None)?;
+
+    let mut symbols = Vec::new();
+    collect(language, tree.root_node(), source.as_bytes(), None, &mut symbols);
+    Some(symbols)
+}
+
+/// Type or module a definition is nested in
+struct Scope<'a> {
+    name: &'a str,
+    kind: SymbolKind,
+}
+
+fn collect(language: SymbolLanguage, node: Node, source: &[u8], scope: Option<&Scope>, symbols: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let Some(symbol) = definition(language, child, source, scope) else {
+            collect(language, child, source, scope, symbols);
+            continue;
+        };
+        // definitions local to a function body are not part of the outline
+        if !symbol.kind.is_callable() {
+            let name = symbol.name.clone();
+            let kind = symbol.kind;
+            symbols.push(symbol);
+            collect(language, child, source, Some(&Scope { name: &name, kind }), symbols);
+        } else {
+            symbols.push(symbol);
+        }
+    }
+}
+
+fn text<'a>(node: Node, source: &'a [u8]) -> &'a str {
+    node.utf8_text(source).unwrap_or_default()
+}
+
+fn field_text<'a>(node: Node, field: &str, source: &'a [u8]) -> Option<&'a str> {
+    node.child_by_field_name(field).map(|child| text(child, source))
+}
+
+/// Type name without its generics or pointer, e.g. "*Config[T]" -> "Config"
+fn bare_type(name: &str) -> &str {
+    let name = name.trim().trim_start_matches(['*', '&']);
+    let end = name.find(['<', '[', ' ', '(']).unwrap_or(name.len());
+    &name[..end]
+}
+
+fn signature(node: Node, source: &[u8]) -> String {
+    let first_line = text(node, source).lines().next().unwrap_or_default();
+    let signature = first_line.trim().trim_end_matches('{').trim_end();
+    match signature.char_indices().nth(MAX_SIGNATURE_CHARS) {
+        Some((end, _)) => format!("{}...", &signature[..end]),
+        None => signature.to_string(),
+    }
+}
+
+/// Kind and name of the definition of a node, if it is one
+fn definition(language: SymbolLanguage, node: Node, source: &[u8], scope: Option<&Scope>) -> Option<Symbol> {
+    let in_type = scope.map_or(false, |scope| !matches!(scope.kind, SymbolKind::Module));
+    let function = if in_type { SymbolKind::Method } else { SymbolKind::Function };
+    let name = |field: &str| field_text(node, field, source).map(str::to_string);
+
+    let (kind, name) = match language {
+        SymbolLanguage::Rust => match node.kind() {
+            "function_item" => (function, name("name")?),
+            "function_signature_item" => (SymbolKind::Method, name("name")?),
+            "struct_item" | "union_item" => (SymbolKind::Struct, name("name")?),
+            "enum_item" => (SymbolKind::Enum, name("name")?),
+            "trait_item" => (SymbolKind::Trait, name("name")?),
+            "type_item" => (SymbolKind::Type, name("name")?),
+            "mod_item" => (SymbolKind::Module, name("name")?),
+            "const_item" | "static_item" => (SymbolKind::Constant, name("name")?),
+            "macro_definition" => (SymbolKind::Macro, name("name")?),
+            "impl_item" => (SymbolKind::Impl, bare_type(field_text(node, "type", source)?).to_string()),
+            _ => return None,
+        },
+        SymbolLanguage::Python => match node.kind() {
+            "function_definition" => (function, name("name")?),
+            "class_definition" => (SymbolKind::Class, name("name")?),
+            _ => return None,
+        },
+        SymbolLanguage::JavaScript | SymbolLanguage::TypeScript | SymbolLanguage::Tsx => match node.kind() {
+            "function_declaration" | "generator_function_declaration" | "function_signature" => (function, name("name")?),
+            "method_definition" | "method_signature" | "abstract_method_signature" => (SymbolKind::Method, name("name")?),
+            "class_declaration" | "abstract_class_declaration" => (SymbolKind::Class, name("name")?),
+            "interface_declaration" => (SymbolKind::Interface, name("name")?),
+            "type_alias_declaration" => (SymbolKind::Type, name("name")?),
+            "enum_declaration" => (SymbolKind::Enum, name("name")?),
+            "internal_module" | "module" => (SymbolKind::Module, name("name")?),
+            // const handler = () => {...}
+            "variable_declarator" => {
+                let value = node.child_by_field_name("value")?;
+                if !matches!(value.kind(), "arrow_function" | "function_expression" | "function" | "generator_function") {
+                    return None;
+                }
+                (function, name("name")?)
+            }
+            _ => return None,
+        },
+        SymbolLanguage::Go => match node.kind() {
+            "function_declaration" => (SymbolKind::Function, name("name")?),
+            "method_elem" | "method_spec" => (SymbolKind::Method, name("name")?),
+            "method_declaration" => {
+                // the receiver, e.g. (c *Config), is the parent
+                let receiver = node.child_by_field_name("receiver")?;
+                let mut cursor = receiver.walk();
+                let receiver_type = receiver.named_children(&mut cursor)
+                    .find_map(|parameter| field_text(parameter, "type", source))
+                    .map(|receiver_type| bare_type(receiver_type).to_string());
+                return Some(Symbol {
+                    name: name("name")?,
+                    kind: SymbolKind::Method,
+                    parent: receiver_type,
+                    line: node.start_position().row as u32 + 1,
+                    end_line: node.end_position().row as u32 + 1,
+                    signature: signature(node, source),
+                });
+            }
+            "type_spec" | "type_alias" => {
+                let kind = match node.child_by_field_name("type").map(|type_node| type_node.kind()) {
+                    Some("struct_type") => SymbolKind::Struct,
+                    Some("interface_type") => SymbolKind::Interface,
+                    _ => SymbolKind::Type,
+                };
+                (kind, name("name")?)
+            }
+            _ => return None,
+        },
+    };
+
+    Some(Symbol {
+        name,
+        kind,
+        parent: scope.map(|scope| scope.name.to_string()),
+        line: node.start_position().row as u32 + 1,
+        end_line: node.end_position().row as u32 + 1,
+        signature: signature(node, source),
+    })
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SymbolsToolParams {
+    /// What to do: "find" where a symbol is defined, list the symbols of a "file", or "outline" a directory
+    pub action: SymbolsAction,
+    /// Name of the symbol to find, exact or a part of it (for "find")
+    #[serde(default)]
+    pub name: Option<String>,
+    /// File or directory (defaults to current directory)
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Only these kinds, comma separated (e.g., "function,method,struct")
+    #[serde(default)]
+    pub kinds: Option<String>,
+    /// Maximum number of symbols to return
+    #[serde(default = "default_max_results")]
+    pub max_results: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[schemars(inline)]
+pub enum SymbolsAction {
+    Find,
+    File,
+    Outline,
+}
+
+fn default_max_results() -> u32 { 100 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Impl,
+    Class,
+    Interface,
+    Type,
+    Module,
+    Constant,
+    Macro,
+}
+
+impl SymbolKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Method => "method",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Trait => "trait",
+            SymbolKind::Impl => "impl",
+            SymbolKind::Class => "class",
+            SymbolKind::Interface => "interface",
+            SymbolKind::Type => "type",
+            SymbolKind::Module => "module",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Macro => "macro",
+        }
+    }
+
+    /// Functions and methods, their bodies are not searched for more definitions
+    pub fn is_callable(&self) -> bool {
+        matches!(self, SymbolKind::Function | SymbolKind::Method)
+    }
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A definition found in a source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// type, impl, trait, class or module the symbol is defined in
+    pub parent: Option<String>,
+    /// first and last line of the definition, 1-based
+    pub line: u32,
+    pub end_line: u32,
+    /// first line of the definition
+    pub signature: String,
+}
+
+impl Symbol {
+    /// Name with its parent, e.g. "LlmClient::chat"
+    pub fn qualified_name(&self) -> String {
+        match &self.parent {
+            Some(parent) => format!("{}::{}", parent, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
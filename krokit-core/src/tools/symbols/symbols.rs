@@ -0,0 +1,181 @@
+use super::index::SymbolIndex;
+use super::structs::{Symbol, SymbolsAction, SymbolsToolParams};
+use crate::tools::{tool, ToolResult};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub struct SymbolsTool {
+    index: Arc<SymbolIndex>,
+}
+
+impl SymbolsTool {
+    pub fn new() -> Self {
+        Self::with_index(Arc::new(SymbolIndex::new()))
+    }
+
+    /// Share the index, and the files already parsed, with other tools
+    pub fn with_index(index: Arc<SymbolIndex>) -> Self {
+        Self { index }
+    }
+
+    fn kind_filter(kinds: &Option<String>) -> impl Fn(&Symbol) -> bool {
+        let kinds: Vec<String> = kinds.iter()
+            .flat_map(|kinds| kinds.split(','))
+            .map(|kind| kind.trim().to_lowercase())
+            .filter(|kind| !kind.is_empty())
+            .collect();
+        move |symbol: &Symbol| kinds.is_empty() || kinds.iter().any(|kind| kind == symbol.kind.name())
+    }
+
+    fn format_symbol(symbol: &Symbol, indent: usize) -> String {
+        format!("{:>5}-{:<5} {}{} {}: {}",
+            symbol.line, symbol.end_line, "  ".repeat(indent), symbol.kind, symbol.name, symbol.signature)
+    }
+
+    /// Symbols named like the query: exact names first, then the names equal ignoring the
+    /// case, then the names containing it
+    fn find(index: &SymbolIndex, root: &Path, query: &str, params: &SymbolsToolParams) -> (String, usize) {
+        let keep = Self::kind_filter(&params.kinds);
+        let lowercase_query = query.to_lowercase();
+
+        let files = index.directory_symbols(root);
+        let mut matches: Vec<(u8, &PathBuf, &Symbol)> = Vec::new();
+        for (path, symbols) in &files {
+            for symbol in symbols.iter().filter(|symbol| keep(*symbol)) {
+                let rank = if symbol.name == query || symbol.qualified_name() == query {
+                    0
+                } else if symbol.name.to_lowercase() == lowercase_query {
+                    1
+                } else if symbol.name.to_lowercase().contains(&lowercase_query) {
+                    2
+                } else {
+                    continue;
+                };
+                matches.push((rank, path, symbol));
+            }
+        }
+        // stable sort, keeps the path order between equal ranks
+        matches.sort_by_key(|(rank, ..)| *rank);
+        matches.truncate(params.max_results as usize);
+
+        if matches.is_empty() {
+            return (format!("No symbol matching '{}' found in {}", query, root.display()), 0);
+        }
+        let output = matches.iter()
+            .map(|(_, path, symbol)| format!("{}:{} {} {}: {}",
+                path.display(), symbol.line, symbol.kind, symbol.qualified_name(), symbol.signature))
+            .collect::<Vec<_>>()
+            .join("\n");
+        (output, matches.len())
+    }
+
+    /// All the symbols of a file, nested ones indented under their parent
+    fn file(index: &SymbolIndex, path: &Path, params: &SymbolsToolParams) -> Result<(String, usize), String> {
+        if !path.is_file() {
+            return Err(format!("{} is not a file", path.display()));
+        }
+        let symbols = index.file_symbols(path)
+            .ok_or_else(|| format!("Language of {} is not supported (rust, python, javascript, typescript, go)", path.display()))?;
+        let keep = Self::kind_filter(&params.kinds);
+
+        let mut lines = vec![path.display().to_string()];
+        // last lines of the parents still open around the symbol
+        let mut parents: Vec<u32> = Vec::new();
+        for symbol in symbols.iter() {
+            parents.retain(|end_line| *end_line >= symbol.line);
+            if keep(symbol) {
+                lines.push(Self::format_symbol(symbol, parents.len()));
+            }
+            if !symbol.kind.is_callable() {
+                parents.push(symbol.end_line);
+            }
+            if lines.len() > params.max_results as usize {
+                break;
+            }
+        }
+        let count = lines.len() - 1;
+        Ok((lines.join("\n"), count))
+    }
+
+    /// Top level symbols of the files of a directory
+    fn outline(index: &SymbolIndex, root: &Path, params: &SymbolsToolParams) -> (String, usize) {
+        let keep = Self::kind_filter(&params.kinds);
+        let mut lines = Vec::new();
+        let mut count = 0;
+        for (path, symbols) in index.directory_symbols(root) {
+            let top_level: Vec<&Symbol> = symbols.iter()
+                .filter(|symbol| symbol.parent.is_none() && keep(*symbol))
+                .collect();
+            if top_level.is_empty() {
+                continue;
+            }
+            lines.push(path.display().to_string());
+            for symbol in top_level {
+                if count >= params.max_results as usize {
+                    lines.push("... more symbols, raise max_results or outline a sub directory".to_string());
+                    return (lines.join("\n"), count);
+                }
+                lines.push(Self::format_symbol(symbol, 1));
+                count += 1;
+            }
+        }
+        if lines.is_empty() {
+            return (format!("No symbol found in {}", root.display()), 0);
+        }
+        (lines.join("\n"), count)
+    }
+
+    fn run(index: &SymbolIndex, params: &SymbolsToolParams) -> Result<(String, usize), String> {
+        let path = PathBuf::from(params.path.as_deref().unwrap_or("."));
+        if !path.exists() {
+            return Err(format!("Path does not exist: {}", path.display()));
+        }
+        match params.action {
+            SymbolsAction::Find => {
+                let name = params.name.as_deref().map(str::trim).filter(|name| !name.is_empty())
+                    .ok_or("The name of the symbol is required to find it")?;
+                Ok(Self::find(index, &path, name, params))
+            }
+            SymbolsAction::File => Self::file(index, &path, params),
+            SymbolsAction::Outline if path.is_file() => Self::file(index, &path, params),
+            SymbolsAction::Outline => Ok(Self::outline(index, &path, params)),
+        }
+    }
+}
+
+#[tool(name = "symbols", description = r#"Finds the definitions of the code (functions, methods, structs, classes, enums, traits, interfaces, impls, types, modules) without reading whole files. Supports Rust, Python, JavaScript, TypeScript and Go.
+
+**Actions:**
+- `find`: where is a symbol defined, `name` is an exact name (e.g. 'create_provider', 'LlmClient::chat') or a part of it. Returns `path:line kind name: signature`, exact matches first.
+- `file`: all the symbols of the file `path` with their line ranges, methods nested under their type. Use it before reading a large file, then read only the lines you need.
+- `outline`: the top level symbols of every source file under the directory `path`, to discover a module.
+
+**Notes:**
+- Files ignored by `.gitignore` or `.krokitignore` and hidden files are skipped.
+- Restrict the kinds with `kinds` (e.g. 'struct,enum' or 'function,method').
+- Files are parsed once and again only when they change."#, capabilities = [ToolCapability::Read])]
+impl SymbolsTool {
+    async fn execute(&self, params: SymbolsToolParams) -> ToolResult {
+        let mut meta = HashMap::new();
+        meta.insert("action".to_string(), json!(params.action));
+        meta.insert("path".to_string(), json!(params.path.clone().unwrap_or_else(|| ".".to_string())));
+        if let Some(name) = &params.name {
+            meta.insert("name".to_string(), json!(name));
+        }
+
+        // parsing a whole project blocks its threads until done
+        let index = self.index.clone();
+        let result = tokio::task::spawn_blocking(move || Self::run(&index, &params)).await;
+        match result {
+            Ok(Ok((output, count))) => {
+                meta.insert("results_count".to_string(), json!(count));
+                meta.insert("files_indexed".to_string(), json!(self.index.len()));
+                ToolResult::Success { output, metadata: Some(meta) }
+            }
+            Ok(Err(error)) => ToolResult::Error { error, metadata: Some(meta) },
+            Err(e) => ToolResult::Error { error: format!("Symbol search failed: {}", e), metadata: Some(meta) },
+        }
+    }
+}
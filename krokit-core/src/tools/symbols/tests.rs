@@ -0,0 +1,175 @@
+use super::index::SymbolIndex;
+use super::parser::extract_symbols;
+use super::structs::{SymbolKind, SymbolsAction, SymbolsToolParams};
+use super::symbols::SymbolsTool;
+use crate::tools::{Tool, ToolResult};
+use krokit_llm::ToolDescription;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use std::fs;
+
+fn summary(path: &str, source: &str) -> Vec<(SymbolKind, String, Option<String>)> {
+    extract_symbols(Path::new(path), source)
+        .expect("language should be supported")
+        .into_iter()
+        .map(|symbol| (symbol.kind, symbol.name, symbol.parent))
+        .collect()
+}
+
+#[test]
+fn test_rust_symbols() {
+    let source = r#"
+pub struct Config {
+    name: String,
+}
+
+impl Config {
+    pub fn new(name: String) -> Self {
+        fn helper() {}
+        Self { name }
+    }
+}
+
+pub trait Named {
+    fn name(&self) -> &str;
+}
+
+impl<T: Named> Named for Vec<T> {
+    fn name(&self) -> &str { "vec" }
+}
+
+pub enum Mode { Fast, Slow }
+const MAX: usize = 3;
+mod inner {
+    pub fn run() {}
+}
+"#;
+    let symbols = summary("lib.rs", source);
+    assert_eq!(symbols, vec![
+        (SymbolKind::Struct, "Config".to_string(), None),
+        (SymbolKind::Impl, "Config".to_string(), None),
+        (SymbolKind::Method, "new".to_string(), Some("Config".to_string())),
+        (SymbolKind::Trait, "Named".to_string(), None),
+        (SymbolKind::Method, "name".to_string(), Some("Named".to_string())),
+        (SymbolKind::Impl, "Vec".to_string(), None),
+        (SymbolKind::Method, "name".to_string(), Some("Vec".to_string())),
+        (SymbolKind::Enum, "Mode".to_string(), None),
+        (SymbolKind::Constant, "MAX".to_string(), None),
+        (SymbolKind::Module, "inner".to_string(), None),
+        (SymbolKind::Function, "run".to_string(), Some("inner".to_string())),
+    ]);
+
+    let symbols = extract_symbols(Path::new("lib.rs"), source).unwrap();
+    let new = symbols.iter().find(|symbol| symbol.name == "new").unwrap();
+    assert_eq!((new.line, new.end_line), (7, 10));
+    assert_eq!(new.signature, "pub fn new(name: String) -> Self");
+    assert_eq!(new.qualified_name(), "Config::new");
+}
+
+#[test]
+fn test_python_typescript_and_go_symbols() {
+    let python = "class User:\n    def greet(self):\n        pass\n\n@cache\ndef load():\n    pass\n";
+    assert_eq!(summary("user.py", python), vec![
+        (SymbolKind::Class, "User".to_string(), None),
+        (SymbolKind::Method, "greet".to_string(), Some("User".to_string())),
+        (SymbolKind::Function, "load".to_string(), None),
+    ]);
+
+    let typescript = r#"
+export interface Shape { area(): number; }
+export class Circle implements Shape {
+    area() { return 3; }
+}
+export const handler = async () => {};
+type Id = string;
+"#;
+    assert_eq!(summary("shapes.ts", typescript), vec![
+        (SymbolKind::Interface, "Shape".to_string(), None),
+        (SymbolKind::Method, "area".to_string(), Some("Shape".to_string())),
+        (SymbolKind::Class, "Circle".to_string(), None),
+        (SymbolKind::Method, "area".to_string(), Some("Circle".to_string())),
+        (SymbolKind::Function, "handler".to_string(), None),
+        (SymbolKind::Type, "Id".to_string(), None),
+    ]);
+
+    let go = "package main\n\ntype Server struct {\n\tport int\n}\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n\nfunc main() {}\n";
+    assert_eq!(summary("main.go", go), vec![
+        (SymbolKind::Struct, "Server".to_string(), None),
+        (SymbolKind::Method, "Start".to_string(), Some("Server".to_string())),
+        (SymbolKind::Function, "main".to_string(), None),
+    ]);
+
+    assert!(extract_symbols(Path::new("notes.txt"), "hello").is_none());
+}
+
+#[test]
+fn test_index_is_updated_when_files_change() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let file = temp_dir.path().join("lib.rs");
+    fs::write(&file, "fn one() {}\n").unwrap();
+
+    let index = SymbolIndex::new();
+    let first = index.file_symbols(&file).unwrap();
+    assert!(Arc::ptr_eq(&first, &index.file_symbols(&file).unwrap()), "unchanged files are not parsed again");
+
+    fs::write(&file, "fn one() {}\nfn two() {}\n").unwrap();
+    let second = index.file_symbols(&file).unwrap();
+    assert_eq!(second.len(), 2);
+
+    // deleted files leave the index
+    fs::write(temp_dir.path().join("other.rs"), "struct Other;\n").unwrap();
+    assert_eq!(index.directory_symbols(temp_dir.path()).len(), 2);
+    fs::remove_file(&file).unwrap();
+    assert_eq!(index.directory_symbols(temp_dir.path()).len(), 1);
+    assert_eq!(index.len(), 1);
+}
+
+#[tokio::test]
+async fn test_symbols_tool_actions() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let temp_path = temp_dir.path();
+    fs::create_dir_all(temp_path.join("src")).unwrap();
+    fs::create_dir_all(temp_path.join("target")).unwrap();
+    fs::write(temp_path.join(".gitignore"), "target/\n").unwrap();
+    fs::write(temp_path.join("src/client.rs"), "pub struct Client;\n\nimpl Client {\n    pub fn connect(&self) {}\n}\n").unwrap();
+    fs::write(temp_path.join("src/main.rs"), "fn main() {}\nfn connect_all() {}\n").unwrap();
+    fs::write(temp_path.join("target/gen.rs"), "fn connect() {}\n").unwrap();
+
+    let tool = SymbolsTool::new();
+    assert_eq!(&tool.name(), "symbols");
+
+    let params = |action, name: Option<&str>, path: &Path| SymbolsToolParams {
+        action,
+        name: name.map(str::to_string),
+        path: Some(path.to_string_lossy().to_string()),
+        kinds: None,
+        max_results: 100,
+    };
+
+    let output = match tool.execute(params(SymbolsAction::Find, Some("connect"), temp_path), None).await {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("find should succeed: {:?}", other),
+    };
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2, "target is ignored: {}", output);
+    assert!(lines[0].contains("client.rs:4 method Client::connect"), "exact match first: {}", output);
+    assert!(lines[1].contains("connect_all"));
+
+    let output = match tool.execute(params(SymbolsAction::File, None, &temp_path.join("src/client.rs")), None).await {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("file should succeed: {:?}", other),
+    };
+    assert!(output.contains("struct Client"));
+    assert!(output.contains("    4-4       method connect"), "methods are nested: {}", output);
+
+    let output = match tool.execute(params(SymbolsAction::Outline, None, temp_path), None).await {
+        ToolResult::Success { output, .. } => output,
+        other => panic!("outline should succeed: {:?}", other),
+    };
+    assert!(output.contains("function main"));
+    assert!(!output.contains("method connect"), "only top level symbols: {}", output);
+
+    let result = tool.execute(params(SymbolsAction::Find, None, temp_path), None).await;
+    assert!(matches!(result, ToolResult::Error { .. }));
+}
@@ -1,7 +1,8 @@
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use schemars::JsonSchema;
-use krokit_llm::{ChatCompletionFunction, ChatCompletionTool, ChatCompletionToolType, ToolBox, ToolDescription};
+use krokit_llm::{ChatCompletionFunction, ChatCompletionTool, ChatCompletionToolType, ChatMessageContentPart, ToolBox, ToolDescription};
+use krokit_llm::image::image_url_part;
 use tokio_util::sync::CancellationToken;
 use std::collections::HashMap;
 use std::fmt;
@@ -111,8 +112,32 @@ impl ToolResult {
     pub fn is_denied(&self) -> bool {
         matches!(self, Self::Denied)
     }
+
+    /// Image returned by the tool (as a data url in the "image" metadata), it cannot be
+    /// part of a tool message and is sent to the model after the results of the tools
+    pub fn image(&self) -> Option<ChatMessageContentPart> {
+        let Self::Success { metadata: Some(metadata), .. } = self else {
+            return None;
+        };
+        let url = metadata.get("image")?.as_str()?;
+        Some(image_url_part(url.to_string()))
+    }
+
+    /// Copy of the result with the size of its image instead of its data, to be logged
+    pub fn without_image(&self) -> Self {
+        let mut result = self.clone();
+        if let Self::Success { metadata: Some(metadata), .. } = &mut result {
+            if let Some(size) = metadata.get("image").and_then(|image| image.as_str()).map(str::len) {
+                metadata.insert("image".to_string(), serde_json::json!(format!("<data url of {} bytes>", size)));
+            }
+        }
+        result
+    }
 }
 
+/// Callback receiving the output of a tool while it runs, for the tools that support streaming
+pub type ProgressSink = Arc<dyn Fn(String) + Send + Sync>;
+
 #[async_trait]
 pub trait Tool: ToolDescription + Send + Sync {
     type Params: DeserializeOwned + JsonSchema + Send + Sync;
@@ -123,6 +148,12 @@ pub trait Tool: ToolDescription + Send + Sync {
     /// parameters are specific for each tool
     async fn execute(&self, params: Self::Params, cancel_token: Option<CancellationToken>) -> ToolResult;
 
+    /// execute the tool, sending its output to `progress` as it comes.
+    /// Default implementation ignores the sink (no streaming)
+    async fn execute_with_progress(&self, params: Self::Params, cancel_token: Option<CancellationToken>, progress: Option<ProgressSink>) -> ToolResult {
+        self.execute(params, cancel_token).await
+    }
+
     /// execute the tool in preview mode - shows what would happen without making changes
     /// Default implementation returns None (no preview available)
     async fn execute_preview(&self, params: Self::Params) -> Option<ToolResult> {
@@ -150,6 +181,11 @@ pub trait AnyTool: ToolDescription + Send + Sync {
     
     async fn execute_json(&self, params: serde_json::Value, cancel_token: Option<CancellationToken>) -> ToolResult;
     async fn execute_preview_json(&self, params: serde_json::Value) -> Option<ToolResult>;
+
+    /// execute_json with an optional progress sink, ignored by the tools that do not stream
+    async fn execute_json_with_progress(&self, params: serde_json::Value, cancel_token: Option<CancellationToken>, progress: Option<ProgressSink>) -> ToolResult {
+        self.execute_json(params, cancel_token).await
+    }
 }
 
 /// Auto-implement AnyTool
@@ -174,6 +210,15 @@ where
         
         self.execute_preview(typed_params).await
     }
+
+    async fn execute_json_with_progress(&self, params: serde_json::Value, cancel_token: Option<CancellationToken>, progress: Option<ProgressSink>) -> ToolResult {
+        let typed_params: <T as Tool>::Params = match serde_json::from_value(params) {
+            Ok(p) => p,
+            Err(e) => return ToolResult::error(format!("Parameter deserialization failed: {}", e))
+        };
+
+        self.execute_with_progress(typed_params, cancel_token, progress).await
+    }
 }
 
 pub type ToolError = Box<dyn std::error::Error + Send + Sync>;
@@ -0,0 +1,8 @@
+pub mod usage;
+pub mod pricing;
+
+#[cfg(test)]
+mod tests;
+
+pub use usage::{TokenUsage, CallUsage, UsageTracker};
+pub use pricing::{ModelPricing, PricingTable};
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::config::config::KrokitConfig;
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input: f64,
+    pub output: f64,
+    /// price of prompt tokens read from the provider cache, same as input if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input: Option<f64>,
+}
+
+impl ModelPricing {
+    pub const fn new(input: f64, output: f64, cached_input: Option<f64>) -> Self {
+        Self { input, output, cached_input }
+    }
+
+    /// Cost in USD of a call. Cached tokens are part of the prompt tokens.
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64, cached_tokens: u64) -> f64 {
+        let cached = cached_tokens.min(prompt_tokens);
+        let uncached = prompt_tokens - cached;
+        (uncached as f64 * self.input
+            + cached as f64 * self.cached_input.unwrap_or(self.input)
+            + completion_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// Best effort public prices, matched as a substring of the model name (first match wins)
+const DEFAULT_PRICES: &[(&str, ModelPricing)] = &[
+    ("claude-opus", ModelPricing::new(15.0, 75.0, Some(1.5))),
+    ("claude-3-opus", ModelPricing::new(15.0, 75.0, Some(1.5))),
+    ("claude-sonnet", ModelPricing::new(3.0, 15.0, Some(0.3))),
+    ("claude-3-7-sonnet", ModelPricing::new(3.0, 15.0, Some(0.3))),
+    ("claude-3-5-sonnet", ModelPricing::new(3.0, 15.0, Some(0.3))),
+    ("claude-3-5-haiku", ModelPricing::new(0.8, 4.0, Some(0.08))),
+    ("claude-haiku", ModelPricing::new(1.0, 5.0, Some(0.1))),
+    ("gpt-5-nano", ModelPricing::new(0.05, 0.4, Some(0.005))),
+    ("gpt-5-mini", ModelPricing::new(0.25, 2.0, Some(0.025))),
+    ("gpt-5", ModelPricing::new(1.25, 10.0, Some(0.125))),
+    ("gpt-4.1-nano", ModelPricing::new(0.1, 0.4, Some(0.025))),
+    ("gpt-4.1-mini", ModelPricing::new(0.4, 1.6, Some(0.1))),
+    ("gpt-4.1", ModelPricing::new(2.0, 8.0, Some(0.5))),
+    ("gpt-4o-mini", ModelPricing::new(0.15, 0.6, Some(0.075))),
+    ("gpt-4o", ModelPricing::new(2.5, 10.0, Some(1.25))),
+    ("o4-mini", ModelPricing::new(1.1, 4.4, Some(0.275))),
+    ("o3-mini", ModelPricing::new(1.1, 4.4, Some(0.55))),
+    ("o3", ModelPricing::new(2.0, 8.0, Some(0.5))),
+    ("gemini-2.5-pro", ModelPricing::new(1.25, 10.0, Some(0.31))),
+    ("gemini-2.5-flash-lite", ModelPricing::new(0.1, 0.4, Some(0.025))),
+    ("gemini-2.5-flash", ModelPricing::new(0.3, 2.5, Some(0.075))),
+    ("mistral-large", ModelPricing::new(2.0, 6.0, None)),
+    ("mistral-medium", ModelPricing::new(0.4, 2.0, None)),
+    ("mistral-small", ModelPricing::new(0.1, 0.3, None)),
+    ("codestral", ModelPricing::new(0.3, 0.9, None)),
+    ("devstral-medium", ModelPricing::new(0.4, 2.0, None)),
+    ("devstral", ModelPricing::new(0.1, 0.3, None)),
+    ("deepseek-reasoner", ModelPricing::new(0.55, 2.19, Some(0.14))),
+    ("deepseek-chat", ModelPricing::new(0.27, 1.1, Some(0.07))),
+];
+
+/// Providers running models locally, they are always free
+const FREE_PROVIDERS: &[&str] = &["ollama"];
+
+/// Pricing lookup. Overrides are keyed by "provider/model", "model" or "provider"
+/// (most specific first) and take precedence over the built-in prices.
+#[derive(Debug, Clone, Default)]
+pub struct PricingTable {
+    overrides: HashMap<String, ModelPricing>,
+}
+
+impl PricingTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Built-in prices plus the overrides of the krokit config (if any)
+    pub fn from_config() -> Self {
+        let overrides = KrokitConfig::load()
+            .map(|config| config.pricing)
+            .unwrap_or_default();
+        Self { overrides }
+    }
+
+    pub fn with_override(mut self, key: &str, pricing: ModelPricing) -> Self {
+        self.overrides.insert(key.to_string(), pricing);
+        self
+    }
+
+    /// Price of a model, None if unknown
+    pub fn get(&self, provider: &str, model: &str) -> Option<ModelPricing> {
+        let keys = [format!("{}/{}", provider, model), model.to_string(), provider.to_string()];
+        if let Some(pricing) = keys.iter().find_map(|key| self.overrides.get(key)) {
+            return Some(*pricing);
+        }
+
+        if FREE_PROVIDERS.contains(&provider) {
+            return Some(ModelPricing::new(0.0, 0.0, None));
+        }
+
+        let model = model.to_lowercase();
+        DEFAULT_PRICES.iter()
+            .find(|(pattern, _)| model.contains(pattern))
+            .map(|(_, pricing)| *pricing)
+    }
+}
@@ -0,0 +1,85 @@
+use super::{CallUsage, ModelPricing, PricingTable, TokenUsage, UsageTracker};
+use super::usage::format_tokens;
+
+fn call(provider: &str, model: &str, prompt: u64, completion: u64, cached: u64) -> CallUsage {
+    CallUsage {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        tokens: TokenUsage { prompt_tokens: prompt, completion_tokens: completion, cached_tokens: cached, cost: None },
+    }
+}
+
+#[test]
+fn test_cost_with_cached_tokens() {
+    let pricing = ModelPricing::new(3.0, 15.0, Some(0.3));
+    // 1M prompt tokens of which half are cached, 100k completion tokens
+    let cost = pricing.cost(1_000_000, 100_000, 500_000);
+    assert!((cost - (1.5 + 0.15 + 1.5)).abs() < 1e-9);
+
+    // cached price defaults to the input price
+    let pricing = ModelPricing::new(2.0, 4.0, None);
+    assert!((pricing.cost(1_000_000, 0, 1_000_000) - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_override_precedence() {
+    let table = PricingTable::new()
+        .with_override("openrouter", ModelPricing::new(1.0, 1.0, None))
+        .with_override("gpt-4o", ModelPricing::new(2.0, 2.0, None))
+        .with_override("openrouter/gpt-4o", ModelPricing::new(3.0, 3.0, None));
+
+    assert_eq!(table.get("openrouter", "gpt-4o").unwrap().input, 3.0);
+    assert_eq!(table.get("openai", "gpt-4o").unwrap().input, 2.0);
+    assert_eq!(table.get("openrouter", "some-model").unwrap().input, 1.0);
+}
+
+#[test]
+fn test_builtin_prices() {
+    let table = PricingTable::new();
+    assert_eq!(table.get("openai", "gpt-4o-mini").unwrap().input, 0.15);
+    assert_eq!(table.get("anthropic", "claude-sonnet-4-20250514").unwrap().output, 15.0);
+    assert_eq!(table.get("ollama", "gpt-oss:20b").unwrap().cost(1000, 1000, 0), 0.0);
+    assert!(table.get("openai", "unknown-model").is_none());
+}
+
+#[test]
+fn test_tracker_accumulates_per_turn_and_session() {
+    let mut tracker = UsageTracker::new(PricingTable::new());
+    let priced = tracker.record(&call("openai", "gpt-4o", 1000, 100, 0));
+    assert!(priced.cost.is_some());
+    tracker.record(&call("openai", "gpt-4o", 2000, 200, 1000));
+    assert_eq!(tracker.turn.prompt_tokens, 3000);
+    assert_eq!(tracker.turn.cached_tokens, 1000);
+    assert_eq!(tracker.calls, 2);
+
+    tracker.new_turn();
+    tracker.record(&call("openai", "gpt-4o", 500, 50, 0));
+    assert_eq!(tracker.turn.prompt_tokens, 500);
+    assert_eq!(tracker.session.prompt_tokens, 3500);
+    assert_eq!(tracker.session.completion_tokens, 350);
+}
+
+#[test]
+fn test_unknown_model_has_no_cost() {
+    let mut tracker = UsageTracker::new(PricingTable::new());
+    let priced = tracker.record(&call("custom", "my-model", 1000, 100, 0));
+    assert!(priced.cost.is_none());
+    assert!(tracker.session.cost.is_none());
+    assert_eq!(tracker.session.total_tokens(), 1100);
+
+    // a priced call afterwards still gives a partial cost
+    tracker.record(&call("openai", "gpt-4o", 1000, 100, 0));
+    assert!(tracker.session.cost.is_some());
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(format_tokens(950), "950");
+    assert_eq!(format_tokens(12_345), "12.3k");
+    assert_eq!(format_tokens(1_200_000), "1.2M");
+
+    let usage = TokenUsage { prompt_tokens: 12_300, completion_tokens: 1_200, cached_tokens: 8_000, cost: Some(0.0421) };
+    assert_eq!(usage.format_short(), "12.3k in (8.0k cached) · 1.2k out · $0.0421");
+    let usage = TokenUsage { prompt_tokens: 10, completion_tokens: 5, cached_tokens: 0, cost: None };
+    assert_eq!(usage.format_short(), "10 in · 5 out");
+}
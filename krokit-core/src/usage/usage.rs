@@ -0,0 +1,127 @@
+use openai_dive::v1::resources::shared::Usage;
+use serde::{Deserialize, Serialize};
+
+use super::pricing::PricingTable;
+
+/// Tokens consumed by one or several llm calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// prompt tokens, including the cached ones
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// prompt tokens read from the provider cache
+    pub cached_tokens: u64,
+    /// cost in USD of the calls that could be priced, None if none could
+    pub cost: Option<f64>,
+}
+
+impl TokenUsage {
+    /// Token counts reported by the provider (not priced)
+    pub fn from_usage(usage: &Usage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens.unwrap_or(0) as u64,
+            completion_tokens: usage.completion_tokens.unwrap_or(0) as u64,
+            cached_tokens: usage.prompt_tokens_details.as_ref()
+                .and_then(|details| details.cached_tokens)
+                .unwrap_or(0) as u64,
+            cost: None,
+        }
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens() == 0
+    }
+
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cached_tokens += other.cached_tokens;
+        self.cost = match (self.cost, other.cost) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// One line summary, e.g. "12.3k in (8.0k cached) · 1.2k out · $0.0421"
+    pub fn format_short(&self) -> String {
+        let mut out = format!("{} in", format_tokens(self.prompt_tokens));
+        if self.cached_tokens > 0 {
+            out.push_str(&format!(" ({} cached)", format_tokens(self.cached_tokens)));
+        }
+        out.push_str(&format!(" · {} out", format_tokens(self.completion_tokens)));
+        if let Some(cost) = self.cost {
+            out.push_str(&format!(" · ${:.4}", cost));
+        }
+        out
+    }
+}
+
+/// Human friendly token count: 950, 12.3k, 1.2M
+pub fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        t if t >= 1_000_000 => format!("{:.1}M", t as f64 / 1_000_000.0),
+        t if t >= 1_000 => format!("{:.1}k", t as f64 / 1_000.0),
+        t => t.to_string(),
+    }
+}
+
+/// Usage of a single brain call, as reported by the brain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallUsage {
+    pub provider: String,
+    pub model: String,
+    pub tokens: TokenUsage,
+}
+
+impl CallUsage {
+    pub fn new(provider: &str, model: &str, usage: &Usage) -> Self {
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            tokens: TokenUsage::from_usage(usage),
+        }
+    }
+}
+
+/// Accumulates the usage of the brain calls, per turn and for the whole session
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    pricing: PricingTable,
+    pub turn: TokenUsage,
+    pub session: TokenUsage,
+    pub calls: usize,
+}
+
+impl UsageTracker {
+    pub fn new(pricing: PricingTable) -> Self {
+        Self {
+            pricing,
+            ..Default::default()
+        }
+    }
+
+    /// Price the call and add it to the totals, returns the priced call usage
+    pub fn record(&mut self, call: &CallUsage) -> TokenUsage {
+        let mut tokens = call.tokens;
+        tokens.cost = self.pricing.get(&call.provider, &call.model)
+            .map(|pricing| pricing.cost(tokens.prompt_tokens, tokens.completion_tokens, tokens.cached_tokens));
+        self.turn.add(&tokens);
+        self.session.add(&tokens);
+        self.calls += 1;
+        tokens
+    }
+
+    /// Called when the user sends a new message
+    pub fn new_turn(&mut self) {
+        self.turn = TokenUsage::default();
+    }
+
+    /// Continue counting from the totals of a previous session
+    pub fn restore(&mut self, session: TokenUsage) {
+        self.session = session;
+    }
+}
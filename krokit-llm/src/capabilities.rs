@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// context window used when the model is unknown
+pub const DEFAULT_CONTEXT_WINDOW: u32 = 32_768;
+
+/// What a model can do. Unknown values (None) are completed from the next source of
+/// the registry, and end up with the defaults of the accessors.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    /// context window in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// maximum number of tokens of an answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+    /// native function calling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<bool>,
+    /// structured output constrained by a json schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<bool>,
+    /// images in the user messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vision: Option<bool>,
+    /// thinks before answering (reasoning content or effort)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<bool>,
+}
+
+impl ModelCapabilities {
+    /// Known values of self, completed by the ones of other
+    pub fn or(self, other: ModelCapabilities) -> Self {
+        Self {
+            context_window: self.context_window.or(other.context_window),
+            max_output_tokens: self.max_output_tokens.or(other.max_output_tokens),
+            tools: self.tools.or(other.tools),
+            json_schema: self.json_schema.or(other.json_schema),
+            vision: self.vision.or(other.vision),
+            reasoning: self.reasoning.or(other.reasoning),
+        }
+    }
+
+    /// Capabilities that two models share, for requests that may be answered by either
+    pub fn common(self, other: ModelCapabilities) -> Self {
+        fn both<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            context_window: both(self.context_window, other.context_window),
+            max_output_tokens: both(self.max_output_tokens, other.max_output_tokens),
+            tools: both(self.tools, other.tools),
+            json_schema: both(self.json_schema, other.json_schema),
+            vision: both(self.vision, other.vision),
+            reasoning: both(self.reasoning, other.reasoning),
+        }
+    }
+
+    pub fn context_window(&self) -> usize {
+        self.context_window.unwrap_or(DEFAULT_CONTEXT_WINDOW) as usize
+    }
+
+    pub fn supports_tools(&self) -> bool {
+        self.tools.unwrap_or(true)
+    }
+
+    pub fn supports_json_schema(&self) -> bool {
+        self.json_schema.unwrap_or(true)
+    }
+
+    pub fn supports_vision(&self) -> bool {
+        self.vision.unwrap_or(false)
+    }
+
+    pub fn supports_reasoning(&self) -> bool {
+        self.reasoning.unwrap_or(false)
+    }
+}
+
+/// Capabilities of a model, resolved in order from: the overrides of the config
+/// ("provider/model" then "model"), the models api of the provider, what the
+/// provider knows of its models, and a built-in table of the known model families.
+#[derive(Debug, Default)]
+pub struct CapabilityRegistry {
+    overrides: HashMap<String, ModelCapabilities>,
+    reported: RwLock<HashMap<String, ModelCapabilities>>,
+    seeded: tokio::sync::OnceCell<()>,
+}
+
+impl CapabilityRegistry {
+    pub fn new(overrides: HashMap<String, ModelCapabilities>) -> Self {
+        Self {
+            overrides,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_overrides(&mut self, overrides: HashMap<String, ModelCapabilities>) {
+        self.overrides = overrides;
+    }
+
+    /// Record the capabilities reported by the models api of the provider
+    pub fn seed(&self, reported: HashMap<String, ModelCapabilities>) {
+        self.reported.write().unwrap().extend(reported);
+    }
+
+    /// Run the seeding only once, concurrent callers wait for it
+    pub async fn seed_once<F, Fut>(&self, fetch: F)
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = HashMap<String, ModelCapabilities>>,
+    {
+        self.seeded.get_or_init(|| async {
+            self.seed(fetch().await);
+        }).await;
+    }
+
+    pub fn resolve(&self, provider: &str, model: &str, provider_known: ModelCapabilities) -> ModelCapabilities {
+        let overridden = |key: &str| self.overrides.get(key).cloned().unwrap_or_default();
+        let reported = self.reported.read().unwrap().get(model).cloned().unwrap_or_default();
+        overridden(&format!("{}/{}", provider, model))
+            .or(overridden(model))
+            .or(reported)
+            .or(provider_known)
+            .or(builtin_capabilities(model))
+    }
+}
+
+/// Best effort capabilities of the known model families, from the model name
+pub fn builtin_capabilities(model: &str) -> ModelCapabilities {
+    // (pattern, context window, max output, vision, reasoning), the first match wins
+    const TABLE: &[(&str, u32, u32, bool, bool)] = &[
+        ("gpt-4.1", 1_047_576, 32_768, true, false),
+        ("gpt-5", 400_000, 128_000, true, true),
+        ("gpt-4o", 128_000, 16_384, true, false),
+        ("gpt-4-turbo", 128_000, 4_096, true, false),
+        ("o1", 200_000, 100_000, true, true),
+        ("o3", 200_000, 100_000, true, true),
+        ("o4", 200_000, 100_000, true, true),
+        ("gpt-4", 8_192, 8_192, false, false),
+        ("gpt-3.5", 16_385, 4_096, false, false),
+        ("claude-opus-4", 200_000, 32_000, true, true),
+        ("claude-sonnet-4", 200_000, 64_000, true, true),
+        ("claude-3-7", 200_000, 64_000, true, true),
+        ("claude", 200_000, 8_192, true, false),
+        ("gemini-2.5", 1_048_576, 65_536, true, true),
+        ("gemini", 1_048_576, 8_192, true, false),
+        ("codestral", 256_000, 32_000, false, false),
+        ("devstral", 128_000, 32_000, false, false),
+        ("pixtral", 128_000, 32_000, true, false),
+        ("magistral", 128_000, 40_000, false, true),
+        ("mistral-large", 128_000, 32_000, false, false),
+        ("mistral-medium", 128_000, 32_000, true, false),
+        ("mistral-small", 128_000, 32_000, true, false),
+        ("deepseek-r1", 64_000, 32_000, false, true),
+        ("deepseek", 64_000, 8_192, false, false),
+        ("kimi", 128_000, 16_384, false, false),
+        ("llama-4", 128_000, 16_384, true, false),
+        ("llama-3", 128_000, 8_192, false, false),
+        ("llama3", 128_000, 8_192, false, false),
+        ("qwen3-coder", 256_000, 65_536, false, false),
+        ("qwen3", 32_768, 32_768, false, true),
+        ("qwen", 32_768, 8_192, false, false),
+        ("gpt-oss", 128_000, 32_768, false, true),
+        ("grok-4", 256_000, 64_000, true, true),
+    ];
+    const VISION_MARKERS: &[&str] = &["vision", "-vl", "llava", "gemma-3"];
+
+    let model = model.to_lowercase();
+    // provider prefix of the routers (openai/gpt-4o)
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    let known = TABLE.iter()
+        .find(|(pattern, ..)| match *pattern {
+            // o1, o3 and o4 are prefixes, not parts of other names
+            "o1" | "o3" | "o4" => name == *pattern || name.starts_with(&format!("{}-", pattern)),
+            pattern => name.contains(pattern),
+        })
+        .map(|&(_, context_window, max_output_tokens, vision, reasoning)| ModelCapabilities {
+            context_window: Some(context_window),
+            max_output_tokens: Some(max_output_tokens),
+            tools: None,
+            json_schema: None,
+            vision: Some(vision),
+            reasoning: Some(reasoning),
+        })
+        .unwrap_or_default();
+
+    let vision = VISION_MARKERS.iter().any(|marker| name.contains(marker)).then_some(true);
+    ModelCapabilities { vision, ..Default::default() }.or(known)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_capabilities() {
+        let claude = builtin_capabilities("claude-sonnet-4-20250514");
+        assert_eq!(claude.context_window(), 200_000);
+        assert!(claude.supports_vision() && claude.supports_reasoning());
+        assert!(builtin_capabilities("openai/gpt-4o-mini").supports_vision());
+        assert!(builtin_capabilities("o3").supports_reasoning());
+        assert!(builtin_capabilities("qwen/qwen2.5-vl-72b-instruct").supports_vision());
+        assert!(!builtin_capabilities("qwen/qwen3-coder").supports_vision());
+        assert!(!builtin_capabilities("codestral-latest").supports_vision());
+
+        let unknown = builtin_capabilities("my-local-model");
+        assert_eq!(unknown.context_window(), DEFAULT_CONTEXT_WINDOW as usize);
+        assert!(unknown.supports_tools() && !unknown.supports_vision());
+    }
+
+    #[test]
+    fn test_resolution_order() {
+        let registry = CapabilityRegistry::new(HashMap::from([
+            ("ollama/qwen3-coder".to_string(), ModelCapabilities { tools: Some(false), ..Default::default() }),
+            ("qwen3-coder".to_string(), ModelCapabilities { context_window: Some(65_536), ..Default::default() }),
+        ]));
+        registry.seed(HashMap::from([
+            ("qwen3-coder".to_string(), ModelCapabilities { context_window: Some(262_144), json_schema: Some(false), ..Default::default() }),
+        ]));
+
+        let resolved = registry.resolve("ollama", "qwen3-coder", ModelCapabilities { vision: Some(true), ..Default::default() });
+        assert!(!resolved.supports_tools());
+        assert_eq!(resolved.context_window(), 65_536);
+        assert!(!resolved.supports_json_schema());
+        assert!(resolved.supports_vision());
+        assert_eq!(resolved.max_output_tokens, Some(65_536));
+
+        let resolved = registry.resolve("openrouter", "qwen3-coder", ModelCapabilities::default());
+        assert!(resolved.supports_tools());
+    }
+
+    #[test]
+    fn test_common_capabilities() {
+        let big = ModelCapabilities { context_window: Some(200_000), vision: Some(true), ..Default::default() };
+        let small = ModelCapabilities { context_window: Some(32_768), vision: Some(false), tools: Some(true), ..Default::default() };
+        let common = big.common(small);
+        assert_eq!(common.context_window, Some(32_768));
+        assert_eq!(common.vision, Some(false));
+        assert_eq!(common.tools, Some(true));
+    }
+}
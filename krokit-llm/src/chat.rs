@@ -64,6 +64,12 @@ impl ChatClient {
         }
     }
 
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = http_client;
+        self
+    }
+
     /// Build a request with authentication headers
     fn build_request(&self, method: Method, path: &str, content_type: &str) -> RequestBuilder {
         let url = format!("{}{}", self.base_url, path);
@@ -3,6 +3,8 @@ use crate::ToolCallMethod;
 
 // llm/client.rs
 use super::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo};
+use super::capabilities::{CapabilityRegistry, ModelCapabilities};
+use super::http::HttpSettings;
 use super::providers::{
     openai::OpenAIProvider,
     openai_compatible::OpenAICompatibleProvider,
@@ -10,7 +12,10 @@ use super::providers::{
     ovhcloud::OvhCloudProvider,
     anthropic::AnthropicProvider,
     ollama::OllamaProvider,
-    mistral::MistralProvider
+    mistral_native::MistralProvider,
+    gemini::GeminiProvider,
+    fallback::{FallbackProvider, RetryPolicy},
+    replay::{RecordingProvider, ReplayProvider},
 };
 use openai_dive::v1::resources::chat::ChatCompletionParametersBuilder;
 use openai_dive::v1::resources::{
@@ -22,6 +27,7 @@ use regex::Regex;
 #[derive(Debug)]
 pub struct LlmClient {
     provider: Box<dyn LlmProvider>,
+    capabilities: CapabilityRegistry,
 }
 
 /// Provider Factory related method
@@ -31,6 +37,7 @@ impl LlmClient {
     pub fn from_env_openai() -> Option<Self> {
         OpenAIProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
@@ -39,6 +46,7 @@ impl LlmClient {
     pub fn from_env_anthropic() -> Option<Self> {
         AnthropicProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
@@ -47,6 +55,7 @@ impl LlmClient {
     pub fn from_env_ollama() -> Option<Self> {
         OllamaProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
@@ -55,6 +64,7 @@ impl LlmClient {
     pub fn from_env_openrouter() -> Option<Self> {
         OpenRouterProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
@@ -63,6 +73,7 @@ impl LlmClient {
     pub fn from_env_openai_compatible() -> Option<Self> {
         OpenAICompatibleProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
@@ -71,6 +82,7 @@ impl LlmClient {
     pub fn from_env_ovhcloud() -> Option<Self> {
         OvhCloudProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
@@ -79,51 +91,138 @@ impl LlmClient {
     pub fn from_env_mistral() -> Option<Self> {
         MistralProvider::from_env().map(|provider| Self {
             provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
+        })
+    }
+
+    /// Create a Gemini provider from environment variables
+    /// Returns None if required environment variables are not set
+    pub fn from_env_gemini() -> Option<Self> {
+        GeminiProvider::from_env().map(|provider| Self {
+            provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
         })
     }
 
     pub fn openai(api_key: String) -> Self {
         Self {
             provider: Box::new(OpenAIProvider::new(api_key)),
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
     pub fn compatible(api_key: String, base_url: String) -> Self {
         Self {
             provider: Box::new(OpenAICompatibleProvider::new(api_key, base_url)),
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
     pub fn openrouter(api_key: String) -> Self {
         Self {
             provider: Box::new(OpenRouterProvider::new(api_key)),
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
     pub fn ovhcloud(api_key: String, base_url: Option<String>) -> Self {
         Self {
             provider: Box::new(OvhCloudProvider::new(api_key, base_url)),
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
-    pub fn anthropic(api_key: String) -> Self {
+    pub fn anthropic(api_key: String, thinking_budget: Option<u32>) -> Self {
         Self {
-            provider: Box::new(AnthropicProvider::new(api_key)),
+            provider: Box::new(AnthropicProvider::new(api_key).with_thinking_budget(thinking_budget)),
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
     pub fn ollama(base_url: String) -> Self {
         Self {
             provider: Box::new(OllamaProvider::new(Some(base_url))),
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
     pub fn mistral(api_key: String) -> Self {
         Self {
             provider: Box::new(MistralProvider::new(api_key)),
+            capabilities: CapabilityRegistry::default(),
+        }
+    }
+
+    pub fn gemini(api_key: String, base_url: Option<String>) -> Self {
+        let provider = match base_url {
+            Some(base_url) => GeminiProvider::with_base_url(api_key, base_url),
+            None => GeminiProvider::new(api_key),
+        };
+        Self {
+            provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
+        }
+    }
+
+    /// Chain several clients, each one is tried in order (with retries on transient
+    /// failures) until one answers. The model replaces the one of the request when set
+    pub fn fallback(chain: Vec<(LlmClient, Option<String>)>, policy: RetryPolicy) -> Self {
+        let provider = chain.into_iter()
+            .fold(FallbackProvider::new(policy), |fallback, (client, model)| {
+                fallback.with_provider(client.provider, model)
+            });
+        Self {
+            provider: Box::new(provider),
+            capabilities: CapabilityRegistry::default(),
+        }
+    }
+
+    /// Record the answers of a client to a fixture file
+    pub fn recording(client: LlmClient, path: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            provider: Box::new(RecordingProvider::new(client.provider, path)),
+            capabilities: CapabilityRegistry::default(),
+        }
+    }
+
+    /// Serve the answers of a fixture file, without network
+    pub fn replay(path: impl AsRef<std::path::Path>) -> Result<Self, LlmError> {
+        Ok(Self {
+            provider: Box::new(ReplayProvider::load(path)?),
+            capabilities: CapabilityRegistry::default(),
+        })
+    }
+
+    /// Client of a custom provider
+    pub fn from_provider(provider: Box<dyn LlmProvider>) -> Self {
+        Self {
+            provider,
+            capabilities: CapabilityRegistry::default(),
         }
     }
 
+    /// Override the capabilities of some models, keyed by "provider/model" or "model"
+    pub fn with_capabilities(mut self, overrides: std::collections::HashMap<String, ModelCapabilities>) -> Self {
+        self.capabilities.set_overrides(overrides);
+        self
+    }
+
+    /// Replay a recorded fixture file, or record it with the first provider from the
+    /// environment when KROKIT_RECORD is set. Without a fixture the test runs live with
+    /// that provider, and fails without one. Used by tests to run offline.
+    pub fn recorded(path: impl Into<std::path::PathBuf>) -> Result<Self, LlmError> {
+        let path = path.into();
+        let record = std::env::var("KROKIT_RECORD").map_or(false, |record| !record.is_empty() && record != "0");
+        if !record && path.exists() {
+            return Self::replay(&path);
+        }
+        let client = Self::first_from_env()
+            .ok_or_else(|| format!("no LLM provider available and no fixture at {}, run the test with KROKIT_RECORD=1 and a provider to record it", path.display()))?;
+        Ok(match record {
+            true => Self::recording(client, path),
+            false => client,
+        })
+    }
 
     /// Get all available LLM clients from environment variables
     /// Returns clients in order of preference for testing
@@ -134,6 +233,7 @@ impl LlmClient {
                 "openai" => return Self::from_env_openai(),
                 "mistral" => return Self::from_env_mistral(),
                 "anthropic" => return Self::from_env_anthropic(),
+                "gemini" => return Self::from_env_gemini(),
                 "openrouter" => return Self::from_env_openrouter(),
                 "openai_compatible" => return Self::from_env_openai_compatible(),
                 "ollama" => return Self::from_env_ollama(),
@@ -153,6 +253,9 @@ impl LlmClient {
         if let Some(client) = Self::from_env_anthropic() {
             return Some(client);
         }
+        if let Some(client) = Self::from_env_gemini() {
+            return Some(client);
+        }
         if let Some(client) = Self::from_env_openrouter() {
             return Some(client);
         }
@@ -174,53 +277,75 @@ impl LlmClient {
             OpenAICompatibleProvider::info(),
             OpenRouterProvider::info(),
             AnthropicProvider::info(),
+            GeminiProvider::info(),
             OpenAIProvider::info(),
         ]
     }
 
     /// Create a provider dynamically based on name and environment values
     pub fn create_provider(provider_name: &str, env_values: &std::collections::HashMap<String, String>) -> Result<Self, LlmError> {
-        match provider_name {
+        Self::create_provider_with(provider_name, env_values, &HttpSettings::default())
+    }
+
+    /// Create a provider dynamically, its requests go through a client configured
+    /// with the http settings (proxy, CA, timeouts)
+    pub fn create_provider_with(provider_name: &str, env_values: &std::collections::HashMap<String, String>, http: &HttpSettings) -> Result<Self, LlmError> {
+        let http_client = http.build_client()
+            .map_err(|e| format!("Invalid http settings for {}: {}", provider_name, e))?;
+        let provider: Box<dyn LlmProvider> = match provider_name {
             "openai" => {
                 let api_key = env_values.get("OPENAI_API_KEY")
                     .ok_or("OPENAI_API_KEY not found")?;
-                Ok(Self::openai(api_key.clone()))
+                Box::new(OpenAIProvider::new(api_key.clone()).with_http_client(http_client))
             },
             "anthropic" => {
                 let api_key = env_values.get("ANTHROPIC_API_KEY")
                     .ok_or("ANTHROPIC_API_KEY not found")?;
-                Ok(Self::anthropic(api_key.clone()))
+                let thinking_budget = env_values.get("ANTHROPIC_THINKING_BUDGET").and_then(|budget| budget.trim().parse().ok());
+                Box::new(AnthropicProvider::new(api_key.clone())
+                    .with_thinking_budget(thinking_budget)
+                    .with_http_client(http_client))
+            },
+            "gemini" => {
+                let api_key = env_values.get("GEMINI_API_KEY")
+                    .ok_or("GEMINI_API_KEY not found")?;
+                let provider = match env_values.get("GEMINI_BASE_URL").cloned().filter(|url| !url.is_empty()) {
+                    Some(base_url) => GeminiProvider::with_base_url(api_key.clone(), base_url),
+                    None => GeminiProvider::new(api_key.clone()),
+                };
+                Box::new(provider.with_http_client(http_client))
             },
             "ollama" => {
                 let base_url = env_values.get("OLLAMA_BASE_URL")
                     .cloned()
                     .unwrap_or_else(|| "http://localhost:11434/v1".to_string());
-                Ok(Self::ollama(base_url))
+                Box::new(OllamaProvider::new(Some(base_url)).with_http_client(http_client))
             },
             "mistral" => {
                 let api_key = env_values.get("MISTRAL_API_KEY")
                     .ok_or("MISTRAL_API_KEY not found")?;
-                Ok(Self::mistral(api_key.clone()))
+                Box::new(MistralProvider::new(api_key.clone()).with_http_client(http_client))
             },
             "ovhcloud" => {
                 let api_key = env_values.get("OVH_API_KEY").map_or("", |v| v);
                 let base_url = env_values.get("OVH_BASE_URL").cloned();
-                Ok(Self::ovhcloud(api_key.to_string(), base_url))
+                Box::new(OvhCloudProvider::new(api_key.to_string(), base_url).with_http_client(http_client))
             },
             "openrouter" => {
                 let api_key = env_values.get("OPENROUTER_API_KEY")
                     .ok_or("OPENROUTER_API_KEY not found")?;
-                Ok(Self::openrouter(api_key.clone()))
+                Box::new(OpenRouterProvider::new(api_key.clone()).with_http_client(http_client))
             },
             "openai_compatible" => {
                 let api_key = env_values.get("OPENAI_COMPATIBLE_API_KEY")
                     .ok_or("OPENAI_COMPATIBLE_API_KEY not found")?;
                 let base_url = env_values.get("OPENAI_COMPATIBLE_BASE_URL")
                     .ok_or("OPENAI_COMPATIBLE_BASE_URL not found")?;
-                Ok(Self::compatible(api_key.clone(), base_url.clone()))
+                Box::new(OpenAICompatibleProvider::new(api_key.clone(), base_url.clone()).with_http_client(http_client))
             },
-            _ => Err(format!("Unknown provider: {}", provider_name).into())
-        }
+            _ => return Err(format!("Unknown provider: {}", provider_name).into())
+        };
+        Ok(Self::from_provider(provider))
     }
 }
 
@@ -232,17 +357,56 @@ impl LlmClient {
     }
 
     pub async fn default_model(&self) -> Result<String, LlmError> {
-        if let Ok(model) = std::env::var("KROKIT_MODEL") {
+        if let Some(model) = std::env::var("KROKIT_MODEL").ok().filter(|model| !model.is_empty()) {
             Ok(model)
         } else {
             self.provider.default_model().await
         }
     }
 
+    /// Default model among already known models, see `LlmProvider::default_model_among`
+    pub fn default_model_among(&self, models: &[String]) -> Option<String> {
+        match std::env::var("KROKIT_MODEL").ok().filter(|model| !model.is_empty()) {
+            Some(model) => Some(model),
+            None => self.provider.default_model_among(models),
+        }
+    }
+
     pub fn provider_name(&self) -> &'static str {
         self.provider.name()
     }
 
+    /// Provider and model that actually answered the last request, differs from the
+    /// configured ones when a fallback provider was used
+    pub fn answered_by(&self) -> Option<(&'static str, String)> {
+        self.provider.answered_by()
+    }
+
+    /// Capabilities of a model, see `CapabilityRegistry` for the order of the sources.
+    /// The models api of the provider is queried once, on the first call
+    pub async fn capabilities(&self, model: &str) -> ModelCapabilities {
+        // the models api is optional, the other sources are enough without it
+        self.capabilities.seed_once(|| async {
+            self.provider.model_capabilities().await.unwrap_or_default()
+        }).await;
+        self.capabilities.resolve(self.provider.name(), model, self.provider.capabilities(model))
+    }
+
+    /// Models of the provider with their capabilities, resolved at once. The models api
+    /// is queried along with the capabilities it reports, not once per model
+    pub async fn models_with_capabilities(&self) -> Result<Vec<(String, ModelCapabilities)>, LlmError> {
+        let seed = self.capabilities.seed_once(|| async {
+            self.provider.model_capabilities().await.unwrap_or_default()
+        });
+        let (list, _) = tokio::join!(self.provider.models(), seed);
+        Ok(list?.data.into_iter()
+            .map(|model| {
+                let capabilities = self.capabilities.resolve(self.provider.name(), &model.id, self.provider.capabilities(&model.id));
+                (model.id, capabilities)
+            })
+            .collect())
+    }
+
     /// Get a reference to the underlying provider (for testing)
     pub fn provider(&self) -> &dyn LlmProvider {
         &*self.provider
@@ -252,8 +416,7 @@ impl LlmClient {
 /// Higher level chat client
 impl LlmClient {
     pub async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
-        let request = request
-            .fix_mistral_alternating();
+        let request = self.fix_alternating(request);
 
         let response = self.provider
             .chat(request)
@@ -264,12 +427,25 @@ impl LlmClient {
     }
 
     pub async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
-        let request = request
-            .fix_mistral_alternating();
+        let request = self.fix_alternating(request);
 
         self.provider.chat_stream(request).await
     }
 
+    /// The native mistral provider rewrites the conversation itself, mistral models
+    /// served by the other providers still need the fix
+    fn fix_alternating(&self, request: ChatCompletionParameters) -> ChatCompletionParameters {
+        if self.provider.name() == "mistral" {
+            return request;
+        }
+        request.fix_mistral_alternating()
+    }
+
+    /// Embeddings of the inputs, in their order
+    pub async fn embeddings(&self, model: &str, input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        self.provider.embeddings(model, input).await
+    }
+
 
 }
 
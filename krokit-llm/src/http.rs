@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::provider::LlmError;
+
+/// Settings of the http clients talking to the providers, the MCP servers and the web.
+/// Unset values keep the defaults of reqwest (which already honors HTTP(S)_PROXY and
+/// NO_PROXY from the environment, and has no timeout).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpSettings {
+    /// proxy for all the requests, e.g. "http://proxy.corp:3128" (credentials can be in the url)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// hosts that bypass the proxy, comma separated, e.g. "localhost,.corp.internal"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+    /// PEM file of root certificates trusted in addition to the system ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_cert: Option<PathBuf>,
+    /// time allowed to open the connection, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// time allowed between two reads of the response, in seconds. Streamed answers
+    /// of slow models only need this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_timeout_secs: Option<u64>,
+    /// time allowed for the whole request, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl HttpSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Known values of self, completed by the ones of other (e.g. the settings of a
+    /// provider over the global ones)
+    pub fn or(self, other: HttpSettings) -> Self {
+        Self {
+            proxy: self.proxy.or(other.proxy),
+            no_proxy: self.no_proxy.or(other.no_proxy),
+            ca_cert: self.ca_cert.or(other.ca_cert),
+            connect_timeout_secs: self.connect_timeout_secs.or(other.connect_timeout_secs),
+            read_timeout_secs: self.read_timeout_secs.or(other.read_timeout_secs),
+            timeout_secs: self.timeout_secs.or(other.timeout_secs),
+        }
+    }
+
+    /// Client builder with the settings applied, for callers that add their own
+    /// headers or timeouts on top
+    pub fn client_builder(&self) -> Result<reqwest::ClientBuilder, LlmError> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(proxy) = self.proxy.as_deref().filter(|proxy| !proxy.is_empty()) {
+            let proxy = reqwest::Proxy::all(proxy)
+                .map_err(|e| format!("invalid proxy {}: {}", proxy, e))?
+                .no_proxy(self.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_cert {
+            let pem = std::fs::read(path)
+                .map_err(|e| format!("cannot read the CA certificate {}: {}", path.display(), e))?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("invalid CA certificate {}: {}", path.display(), e))?;
+            if certificates.is_empty() {
+                return Err(format!("no certificate found in {}", path.display()).into());
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some(secs) = self.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.read_timeout_secs {
+            builder = builder.read_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        Ok(builder)
+    }
+
+    pub fn build_client(&self) -> Result<reqwest::Client, LlmError> {
+        Ok(self.client_builder()?.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_settings_override_global_ones() {
+        let global = HttpSettings {
+            proxy: Some("http://proxy.corp:3128".to_string()),
+            read_timeout_secs: Some(60),
+            ..Default::default()
+        };
+        let provider = HttpSettings {
+            read_timeout_secs: Some(600),
+            ..Default::default()
+        };
+        let merged = provider.or(global);
+        assert_eq!(merged.proxy.as_deref(), Some("http://proxy.corp:3128"));
+        assert_eq!(merged.read_timeout_secs, Some(600));
+        assert!(!merged.is_default());
+        assert!(HttpSettings::default().is_default());
+    }
+
+    #[test]
+    fn test_build_client() {
+        let settings: HttpSettings = serde_json::from_str(r#"{
+            "proxy": "http://proxy.corp:3128",
+            "no_proxy": "localhost,.corp.internal",
+            "connect_timeout_secs": 5,
+            "read_timeout_secs": 600
+        }"#).unwrap();
+        assert!(settings.build_client().is_ok());
+        assert!(HttpSettings::default().build_client().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_ca_cert_is_an_error() {
+        let settings = HttpSettings {
+            ca_cert: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        let error = settings.build_client().unwrap_err().to_string();
+        assert!(error.contains("/nonexistent/ca.pem"));
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::path::Path;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use openai_dive::v1::resources::chat::{
+    ChatMessage, ChatMessageContent, ChatMessageContentPart, ChatMessageImageContentPart, ChatMessageTextContentPart, ImageUrlType,
+};
+use crate::provider::LlmError;
+
+/// Extensions of the image files that can be attached to a prompt
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp"];
+
+/// Largest image attached, its base64 encoding stays under the 5MB limit of the apis
+pub const MAX_IMAGE_BYTES: usize = 3_750_000;
+
+/// Text of the user message holding the images returned by tools
+pub const TOOL_IMAGES: &str = "Images returned by the tools:";
+
+/// Text sent instead of the images to the models that cannot see them
+pub const IMAGE_OMITTED: &str = "[image omitted: the model does not support images]";
+
+/// Whether the path looks like an image that can be attached
+pub fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, |ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Media type of an image, from its first bytes
+pub fn image_media_type(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Image content part holding the bytes as a data url
+pub fn image_part(bytes: &[u8]) -> Result<ChatMessageContentPart, LlmError> {
+    let media_type = image_media_type(bytes)
+        .ok_or("unsupported image format, expected png, jpeg, gif or webp")?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(format!("image too large ({} bytes, at most {})", bytes.len(), MAX_IMAGE_BYTES).into());
+    }
+    Ok(image_url_part(format!("data:{};base64,{}", media_type, STANDARD.encode(bytes))))
+}
+
+/// Image content part of an url, or of a data url
+pub fn image_url_part(url: String) -> ChatMessageContentPart {
+    ChatMessageContentPart::Image(ChatMessageImageContentPart {
+        r#type: "image_url".to_string(),
+        image_url: ImageUrlType { url, detail: None },
+    })
+}
+
+/// Image content part of an image file
+pub fn image_part_from_file(path: impl AsRef<Path>) -> Result<ChatMessageContentPart, LlmError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path)
+        .map_err(|e| format!("cannot read image {}: {}", path.display(), e))?;
+    image_part(&bytes).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+pub fn text_part(text: impl Into<String>) -> ChatMessageContentPart {
+    ChatMessageContentPart::Text(ChatMessageTextContentPart {
+        r#type: "text".to_string(),
+        text: text.into(),
+    })
+}
+
+/// Content of a user message, plain text when there is no image
+pub fn user_content(text: String, images: Vec<ChatMessageContentPart>) -> ChatMessageContent {
+    if images.is_empty() {
+        return ChatMessageContent::Text(text);
+    }
+    let mut parts = vec![text_part(text)];
+    parts.extend(images);
+    ChatMessageContent::ContentPart(parts)
+}
+
+/// Media type and base64 data of a data url
+pub fn parse_data_url(url: &str) -> Option<(&str, &str)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let media_type = header.strip_suffix(";base64")?;
+    Some((media_type, data))
+}
+
+/// Urls of the images of a message content
+pub fn content_images(content: &ChatMessageContent) -> Vec<&str> {
+    match content {
+        ChatMessageContent::ContentPart(parts) => parts.iter().filter_map(|part| match part {
+            ChatMessageContentPart::Image(image) => Some(image.image_url.url.as_str()),
+            _ => None,
+        }).collect(),
+        _ => vec![],
+    }
+}
+
+pub fn has_images(messages: &[ChatMessage]) -> bool {
+    messages.iter().any(|msg| match msg {
+        ChatMessage::User { content, .. } => !content_images(content).is_empty(),
+        _ => false,
+    })
+}
+
+/// Messages with the images returned by tools, keyed by tool call id, in a user message
+/// following the results of the tools. A tool message cannot hold images, so they are
+/// only added to the request and the conversation keeps its own turns.
+pub fn with_tool_images(messages: Vec<ChatMessage>, images: &HashMap<String, ChatMessageContentPart>) -> Vec<ChatMessage> {
+    if images.is_empty() {
+        return messages;
+    }
+    let mut with_images = Vec::with_capacity(messages.len());
+    let mut pending = Vec::new();
+    let mut messages = messages.into_iter().peekable();
+    while let Some(msg) = messages.next() {
+        if let ChatMessage::Tool { tool_call_id, .. } = &msg {
+            pending.extend(images.get(tool_call_id).cloned());
+        }
+        with_images.push(msg);
+        let results_end = !matches!(messages.peek(), Some(ChatMessage::Tool { .. }));
+        if results_end && !pending.is_empty() {
+            with_images.push(ChatMessage::User {
+                content: user_content(TOOL_IMAGES.to_string(), std::mem::take(&mut pending)),
+                name: None,
+            });
+        }
+    }
+    with_images
+}
+
+/// Replace the images of the user messages by a short text, for the models without vision
+pub fn strip_images(messages: Vec<ChatMessage>) -> Vec<ChatMessage> {
+    messages.into_iter().map(|msg| match msg {
+        ChatMessage::User { content: ChatMessageContent::ContentPart(parts), name } => ChatMessage::User {
+            content: ChatMessageContent::ContentPart(parts.into_iter().map(|part| match part {
+                ChatMessageContentPart::Image(_) => text_part(IMAGE_OMITTED),
+                part => part,
+            }).collect()),
+            name,
+        },
+        msg => msg,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D];
+
+    #[test]
+    fn test_image_part_is_a_data_url() {
+        let ChatMessageContentPart::Image(image) = image_part(PNG).unwrap() else {
+            panic!("expected an image part");
+        };
+        let (media_type, data) = parse_data_url(&image.image_url.url).unwrap();
+        assert_eq!(media_type, "image/png");
+        assert_eq!(STANDARD.decode(data).unwrap(), PNG);
+
+        assert!(image_part(b"fn main() {}").is_err());
+        assert!(parse_data_url("https://example.com/cat.png").is_none());
+    }
+
+    #[test]
+    fn test_user_content() {
+        assert!(matches!(user_content("hello".to_string(), vec![]), ChatMessageContent::Text(_)));
+        let content = user_content("what is this?".to_string(), vec![image_part(PNG).unwrap()]);
+        assert_eq!(content_images(&content).len(), 1);
+
+        let stripped = strip_images(vec![ChatMessage::User { content, name: None }]);
+        assert!(!has_images(&stripped));
+        let ChatMessage::User { content: ChatMessageContent::ContentPart(parts), .. } = &stripped[0] else {
+            panic!("expected content parts");
+        };
+        assert!(matches!(&parts[1], ChatMessageContentPart::Text(text) if text.text == IMAGE_OMITTED));
+    }
+
+    #[test]
+    fn test_tool_images_follow_the_tool_results() {
+        let tool = |id: &str| ChatMessage::Tool { tool_call_id: id.to_string(), content: "done".to_string() };
+        let user = ChatMessage::User { content: ChatMessageContent::Text("thanks".to_string()), name: None };
+        let images = HashMap::from([("call_b".to_string(), image_part(PNG).unwrap())]);
+
+        let messages = with_tool_images(vec![tool("call_a"), tool("call_b"), user], &images);
+        assert_eq!(messages.len(), 4);
+        assert!(matches!(&messages[1], ChatMessage::Tool { tool_call_id, .. } if tool_call_id == "call_b"));
+        let ChatMessage::User { content, .. } = &messages[2] else {
+            panic!("expected the images after the tool results");
+        };
+        assert_eq!(content_images(content).len(), 1);
+        assert!(matches!(&messages[3], ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text == "thanks"));
+
+        assert_eq!(with_tool_images(vec![tool("call_a")], &images).len(), 1);
+    }
+
+    #[test]
+    fn test_image_paths() {
+        assert!(is_image_path(Path::new("docs/Screenshot.PNG")));
+        assert!(!is_image_path(Path::new("src/main.rs")));
+    }
+}
@@ -2,10 +2,17 @@ pub mod client;
 pub mod providers;
 pub mod provider;
 pub mod chat;
+pub mod capabilities;
+pub mod http;
+pub mod image;
 pub mod tool;
+pub mod typed;
 
 // Re-export our client
 pub use client::LlmClient;
+pub use capabilities::ModelCapabilities;
+pub use http::HttpSettings;
+pub use typed::TypedMethod;
 
 pub use tool::{
     ToolDescription, 
@@ -16,7 +23,9 @@ pub use tool::{
     AssistantResponse, 
     IntoChatMessage, 
     FunctionCallingAutoBuilder, 
-    FunctionCallingRequiredBuilder};
+    FunctionCallingRequiredBuilder,
+    ChatDelta,
+    DeltaSink};
 
 // Re-export commonly used openai_dive types for consumers
 pub use openai_dive::v1::resources::chat::{
@@ -24,6 +33,7 @@ pub use openai_dive::v1::resources::chat::{
     ChatCompletionResponse, 
     ChatMessage,
     ChatMessageContent,
+    ChatMessageContentPart,
     ChatCompletionTool,
     ChatCompletionToolType,
     ChatCompletionFunction,
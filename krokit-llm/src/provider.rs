@@ -1,8 +1,11 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
 use async_trait::async_trait;
 use futures::Stream;
 use std::error::Error;
+use std::time::Duration;
 use openai_dive::v1::endpoints::chat::Chat;
+use crate::capabilities::ModelCapabilities;
 use openai_dive::v1::resources::{
     chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
     model::ListModelResponse,
@@ -11,6 +14,60 @@ use openai_dive::v1::resources::{
 pub type LlmError = Box<dyn Error + Send + Sync>;
 pub type LlmStream = Box<dyn Stream<Item = Result<ChatCompletionChunkResponse, LlmError>> + Send + Unpin>;
 
+/// Non success HTTP response of a provider, keeps the status and the Retry-After
+/// delay so that callers can decide whether the request is worth retrying
+#[derive(Debug, Clone)]
+pub struct HttpError {
+    pub status: u16,
+    pub retry_after: Option<Duration>,
+    pub message: String,
+}
+
+impl HttpError {
+    /// Consume a failed response, the message is prefixed with the given context
+    pub async fn from_response(context: &str, response: reqwest::Response) -> Self {
+        let status = response.status().as_u16();
+        let retry_after = response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        Self {
+            status,
+            retry_after,
+            message: format!("{} {}: {}", context, status, text),
+        }
+    }
+
+    /// Rate limits, server errors and request timeouts are transient
+    pub fn is_transient(&self) -> bool {
+        is_transient_status(self.status)
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for HttpError {}
+
+pub fn is_transient_status(status: u16) -> bool {
+    matches!(status, 408 | 409 | 429) || status >= 500
+}
+
+/// Retry-After is either a number of seconds or an HTTP date
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.signed_duration_since(chrono::Utc::now());
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
 #[derive(Debug, Clone)]
 pub struct EnvVar {
     pub name: String,
@@ -49,21 +106,43 @@ pub trait LlmProvider: Send + Sync {
 
     async fn default_model(&self) -> Result<String, LlmError> {
         let models = self.models().await?; 
-        models.data
-            .first()
-            .map(|m| m.id.clone())
+        let ids: Vec<String> = models.data.into_iter().map(|m| m.id).collect();
+        self.default_model_among(&ids)
             .ok_or_else(|| "no model available".into())
     }
 
+    /// Default model among the ids listed by the models api, without network so that
+    /// it also applies to a cached list. The first one unless the provider prefers another
+    fn default_model_among(&self, models: &[String]) -> Option<String> {
+        models.first().cloned()
+    }
+
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError>;
     
     async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError>;
+
+    /// Embeddings of the inputs with an embedding model, in the order of the inputs
+    async fn embeddings(&self, _model: &str, _input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        Err(format!("{} does not provide embeddings", self.name()).into())
+    }
     
-    fn supports_functions(&self, model: String) -> bool;
-    
-    fn supports_structured_output(&self, model: String) -> bool;
+    /// What the provider knows of a model, completed by the capability registry
+    fn capabilities(&self, _model: &str) -> ModelCapabilities {
+        ModelCapabilities::default()
+    }
+
+    /// Capabilities reported by the models api, keyed by model id
+    async fn model_capabilities(&self) -> Result<HashMap<String, ModelCapabilities>, LlmError> {
+        Ok(HashMap::new())
+    }
     
     fn name(&self) -> &'static str;
+
+    /// Provider and model that answered the last request, for providers delegating to
+    /// others (None means this provider with the requested model)
+    fn answered_by(&self) -> Option<(&'static str, String)> {
+        None
+    }
     
     /// Returns provider information including environment variables
     fn info() -> ProviderInfo where Self: Sized;
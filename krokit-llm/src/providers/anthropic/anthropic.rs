@@ -1,87 +1,123 @@
-use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::capabilities::ModelCapabilities;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar, HttpError};
+use crate::providers::sse::sse_events;
+use crate::image;
 use super::api::*;
 use async_trait::async_trait;
 use reqwest::Client;
-use serde_json::json;
-use futures::{StreamExt, stream};
+use serde_json::{json, Value};
+use futures::StreamExt;
 use openai_dive::v1::resources::{
-    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatCompletionChoice, ChatCompletionChunkChoice, ToolCall, Function},
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ChatCompletionChunkChoice, DeltaToolCall, DeltaFunction, ToolCall, Function},
     model::ListModelResponse,
     shared::{FinishReason, Usage},
 };
 
+/// Smallest thinking budget accepted by the API
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// Thinking blocks of the assistant turns, by id of their first tool call. The API
+/// requires them back, signature included, while the model works through its tool
+/// calls, but the trace only keeps their text as reasoning_content.
+type ThinkingBlocks = Arc<Mutex<HashMap<String, Vec<Value>>>>;
+
 pub struct AnthropicProvider {
     api_key: String,
+    base_url: String,
     client: Client,
+    thinking_budget: Option<u32>,
+    thinking_blocks: ThinkingBlocks,
 }
 
 impl AnthropicProvider {
     pub fn new(api_key: String) -> Self {
+        Self::with_base_url(api_key, ANTHROPIC_API_BASE.to_string())
+    }
+
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
         Self {
             api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
             client: Client::new(),
+            thinking_budget: None,
+            thinking_blocks: ThinkingBlocks::default(),
         }
     }
 
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Enable extended thinking with a budget of tokens, added to max_tokens.
+    /// Without budget, thinking is only enabled by the reasoning_effort of a request.
+    pub fn with_thinking_budget(mut self, budget: Option<u32>) -> Self {
+        self.thinking_budget = budget.filter(|budget| *budget > 0);
+        self
+    }
+
     /// Create Anthropic provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env() -> Option<Self> {
+        let budget = std::env::var("ANTHROPIC_THINKING_BUDGET").ok()
+            .and_then(|budget| budget.trim().parse().ok());
         std::env::var("ANTHROPIC_API_KEY").ok().map(|api_key| {
-            Self::new(api_key)
+            Self::new(api_key).with_thinking_budget(budget)
         })
     }
 
-    async fn parse_anthropic_stream(
-        response: reqwest::Response,
-    ) -> Result<LlmStream, LlmError> {
-        let stream = response.bytes_stream();
-        
-        let parsed_stream = stream
-            .map(|chunk_result| {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        Self::parse_sse_chunk(&chunk_str)
-                    }
-                    Err(e) => vec![Err(Box::new(e) as LlmError)],
-                }
-            })
-            .flat_map(|results| stream::iter(results));
+    async fn post(&self, body: &Value, context: &str) -> Result<reqwest::Response, LlmError> {
+        let response = self.client
+            .post(&format!("{}/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
 
-        Ok(Box::new(Box::pin(parsed_stream)))
+        if !response.status().is_success() {
+            return Err(Box::new(HttpError::from_response(context, response).await));
+        }
+        Ok(response)
     }
 
-    fn parse_sse_chunk(chunk: &str) -> Vec<Result<ChatCompletionChunkResponse, LlmError>> {
-        let mut results = Vec::new();
-        let mut current_event_type: Option<String> = None;
-        let mut current_data = String::new();
-        
-        for line in chunk.lines() {
-            let line = line.trim();
-            
-            if line.starts_with("event: ") {
-                current_event_type = Some(line[7..].to_string());
-            } else if line.starts_with("data: ") {
-                current_data = line[6..].to_string();
-            } else if line.is_empty() && current_event_type.is_some() {
-                // End of SSE event
-                if let Some(event_type) = current_event_type.take() {
-                    match Self::process_anthropic_event(&event_type, &current_data) {
-                        Ok(Some(response)) => results.push(Ok(response)),
-                        Ok(None) => {}, // Non-content events like ping
-                        Err(e) => results.push(Err(e)),
+    fn parse_anthropic_stream(response: reqwest::Response, thinking_blocks: ThinkingBlocks) -> LlmStream {
+        let stream = async_stream::stream! {
+            let mut events = Box::pin(sse_events(response));
+            let mut state = AnthropicStreamState::new(thinking_blocks);
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+                if event.data.trim().is_empty() {
+                    continue;
+                }
+
+                match Self::process_anthropic_event(&mut state, event.event.as_deref().unwrap_or_default(), &event.data) {
+                    Ok(Some(chunk)) => yield Ok(chunk),
+                    Ok(None) => {}, // Non-content events like ping
+                    Err(e) => {
+                        yield Err(e);
+                        break;
                     }
-                    current_data.clear();
                 }
             }
-        }
-        
-        results
+        };
+
+        Box::new(Box::pin(stream))
     }
 
-    fn process_anthropic_event(event_type: &str, data: &str) -> Result<Option<ChatCompletionChunkResponse>, LlmError> {
+    fn process_anthropic_event(state: &mut AnthropicStreamState, event_type: &str, data: &str) -> Result<Option<ChatCompletionChunkResponse>, LlmError> {
         match serde_json::from_str::<AnthropicStreamEvent>(data) {
-            Ok(event) => Self::convert_anthropic_event_to_stream_response(event),
+            Ok(event) => state.convert(event),
             Err(e) => {
                 // Don't fail on unknown events, just skip them
                 if event_type == "ping" {
@@ -96,116 +132,73 @@ impl AnthropicProvider {
         }
     }
 
-    fn convert_anthropic_event_to_stream_response(event: AnthropicStreamEvent) -> Result<Option<ChatCompletionChunkResponse>, LlmError> {
-        match event {
-            AnthropicStreamEvent::ContentBlockDelta { delta, .. } => {
-                let content = match delta {
-                    AnthropicDelta::TextDelta { text } => Some(ChatMessageContent::Text(text)),
-                    AnthropicDelta::ThinkingDelta { thinking: _ } => return Ok(None), // Skip thinking content
-                    AnthropicDelta::InputJsonDelta { partial_json } => Some(ChatMessageContent::Text(partial_json)),
-                };
-
-                Ok(Some(ChatCompletionChunkResponse {
-                    id: Some(format!("anthropic-{}", uuid::Uuid::new_v4())),
-                    object: "chat.completion.chunk".to_string(),
-                    created: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as u32,
-                    model: "claude".to_string(),
-                    choices: vec![ChatCompletionChunkChoice {
-                        index: Some(0),
-                        delta: DeltaChatMessage::Assistant {
-                            content,
-                            reasoning_content: None,
-                            refusal: None,
-                            name: None,
-                            tool_calls: None,
-                        },
-                        finish_reason: None,
-                        logprobs: None,
-                    }],
-                    usage: None,
-                    system_fingerprint: None,
-                }))
-            }
-            AnthropicStreamEvent::MessageDelta { delta, .. } => {
-                let finish_reason = delta.stop_reason.map(|_| FinishReason::StopSequenceReached);
-
-                Ok(Some(ChatCompletionChunkResponse {
-                    id: Some(format!("anthropic-{}", uuid::Uuid::new_v4())),
-                    object: "chat.completion.chunk".to_string(),
-                    created: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as u32,
-                    model: "claude".to_string(),
-                    choices: vec![ChatCompletionChunkChoice {
-                        index: Some(0),
-                        delta: DeltaChatMessage::Assistant {
-                            content: None,
-                            reasoning_content: None,
-                            refusal: None,
-                            name: None,
-                            tool_calls: None,
-                        },
-                        finish_reason,
-                        logprobs: None,
-                    }],
-                    usage: None,
-                    system_fingerprint: None,
-                }))
-            }
-            AnthropicStreamEvent::MessageStop => {
-                Ok(Some(ChatCompletionChunkResponse {
-                    id: Some(format!("anthropic-{}", uuid::Uuid::new_v4())),
-                    object: "chat.completion.chunk".to_string(),
-                    created: std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as u32,
-                    model: "claude".to_string(),
-                    choices: vec![ChatCompletionChunkChoice {
-                        index: Some(0),
-                        delta: DeltaChatMessage::Assistant {
-                            content: None,
-                            reasoning_content: None,
-                            refusal: None,
-                            name: None,
-                            tool_calls: None,
-                        },
-                        finish_reason: Some(FinishReason::StopSequenceReached),
-                        logprobs: None,
-                    }],
-                    usage: None,
-                    system_fingerprint: None,
-                }))
-            }
-            _ => Ok(None), // Skip other events like message_start, content_block_start, ping, etc.
-        }
+    /// Budget of the thinking requested by the provider configuration or the request
+    fn thinking_budget(&self, params: &Value) -> Option<u32> {
+        let budget = self.thinking_budget.or_else(|| match params["reasoning_effort"].as_str()? {
+            "minimal" | "low" => Some(MIN_THINKING_BUDGET),
+            "medium" => Some(4096),
+            "high" => Some(16384),
+            _ => None,
+        });
+        budget.map(|budget| budget.max(MIN_THINKING_BUDGET))
     }
 
     pub(crate) fn convert_to_anthropic_format(&self, request: &ChatCompletionParameters) -> serde_json::Value {
-        let (system_messages, messages) = self.convert_messages(&request.messages);
-
+        let params = serde_json::to_value(request).unwrap_or_default();
+        let thinking = self.thinking_budget(&params)
+            .filter(|_| self.can_think(&request.messages));
+        let (system_messages, mut messages) = self.convert_messages(&request.messages, thinking.is_some());
+        mark_last_stable_message(&mut messages);
+
+        // thinking tokens are part of max_tokens
+        let max_tokens = request.max_completion_tokens.or(request.max_tokens).unwrap_or(1000);
         let mut anthropic_request = json!({
             "model": request.model,
-            "max_tokens": request.max_tokens.unwrap_or(1000),
+            "max_tokens": max_tokens + thinking.unwrap_or(0),
             "messages": messages
         });
 
+        // cache breakpoints: the tools, then the system prompt, then the trace are
+        // the prefix reused by every step of the agent
         if !system_messages.is_empty() {
-            anthropic_request["system"] = json!(system_messages.join("\n\n"));
+            anthropic_request["system"] = json!([{
+                "type": "text",
+                "text": system_messages.join("\n\n"),
+                "cache_control": { "type": "ephemeral" }
+            }]);
+        }
+
+        if let Some(tools) = request.tools.as_ref().filter(|tools| !tools.is_empty()) {
+            let mut tools = self.convert_tools(tools);
+            if let Some(last) = tools.last_mut() {
+                last["cache_control"] = json!({ "type": "ephemeral" });
+            }
+            anthropic_request["tools"] = json!(tools);
         }
 
-        if let Some(tools) = &request.tools {
-            anthropic_request["tools"] = json!(self.convert_tools(tools));
+        if let Some(budget) = thinking {
+            anthropic_request["thinking"] = json!({ "type": "enabled", "budget_tokens": budget });
         }
 
         anthropic_request
     }
 
-    fn convert_messages(&self, messages: &[ChatMessage]) -> (Vec<String>, Vec<serde_json::Value>) {
+    /// Thinking cannot be enabled in the middle of a tool loop started without it, the
+    /// last assistant message must then start with its thinking blocks
+    fn can_think(&self, messages: &[ChatMessage]) -> bool {
+        let Some(last) = messages.iter().rposition(|msg| matches!(msg, ChatMessage::Assistant { .. })) else {
+            return true;
+        };
+        let in_tool_loop = messages[last + 1..].iter().all(|msg| matches!(msg, ChatMessage::Tool { .. }));
+        match &messages[last] {
+            ChatMessage::Assistant { tool_calls: Some(calls), .. } if in_tool_loop && !calls.is_empty() => {
+                self.thinking_blocks.lock().unwrap().contains_key(&calls[0].id)
+            }
+            _ => true,
+        }
+    }
+
+    fn convert_messages(&self, messages: &[ChatMessage], thinking: bool) -> (Vec<String>, Vec<serde_json::Value>) {
         let mut system_messages = Vec::new();
         let mut converted_messages = Vec::new();
 
@@ -215,14 +208,23 @@ impl AnthropicProvider {
                     system_messages.push(self.extract_content_text(content));
                 }
                 ChatMessage::User { content, .. } => {
-                    converted_messages.push(json!({
-                        "role": "user",
-                        "content": self.extract_content_text(content)
-                    }));
+                    let blocks = self.convert_user_content(content);
+                    // images read by the tools follow their results, in the same message
+                    match converted_messages.last_mut() {
+                        Some(last) if last["role"] == "user" && last["content"].is_array() && blocks.is_array() => {
+                            if let (Some(last_blocks), Value::Array(blocks)) = (last["content"].as_array_mut(), blocks) {
+                                last_blocks.extend(blocks);
+                            }
+                        }
+                        _ => converted_messages.push(json!({
+                            "role": "user",
+                            "content": blocks
+                        })),
+                    }
                 }
                 ChatMessage::Assistant { content, tool_calls, .. } => {
                     let is_final = i == messages.len() - 1;
-                    if let Some(assistant_content) = self.build_assistant_content(content, tool_calls, is_final) {
+                    if let Some(assistant_content) = self.build_assistant_content(content, tool_calls, is_final, thinking) {
                         converted_messages.push(json!({
                             "role": "assistant",
                             "content": assistant_content
@@ -251,11 +253,18 @@ impl AnthropicProvider {
         (system_messages, converted_messages)
     }
 
-    fn build_assistant_content(&self, content: &Option<ChatMessageContent>, tool_calls: &Option<Vec<ToolCall>>, is_final: bool) -> Option<serde_json::Value> {
+    fn build_assistant_content(&self, content: &Option<ChatMessageContent>, tool_calls: &Option<Vec<ToolCall>>, is_final: bool, thinking: bool) -> Option<serde_json::Value> {
         match tool_calls {
             Some(calls) => {
                 let mut blocks = Vec::new();
-                
+
+                // Thinking blocks come first, as they were produced
+                if let Some(first) = calls.first().filter(|_| thinking) {
+                    if let Some(thinking_blocks) = self.thinking_blocks.lock().unwrap().get(&first.id) {
+                        blocks.extend(thinking_blocks.iter().cloned());
+                    }
+                }
+
                 // Add text content if present
                 if let Some(text_content) = content {
                     let text = self.extract_content_text(text_content);
@@ -263,7 +272,7 @@ impl AnthropicProvider {
                         blocks.push(json!({"type": "text", "text": text}));
                     }
                 }
-                
+
                 // Add tool_use blocks
                 for call in calls {
                     let input = serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}));
@@ -274,12 +283,12 @@ impl AnthropicProvider {
                         "input": input
                     }));
                 }
-                
+
                 Some(json!(blocks))
             }
             None => {
                 let text = content.as_ref().map(|c| self.extract_content_text(c)).unwrap_or_default();
-                
+
                 // Only allow empty content if this is the final assistant message
                 if text.is_empty() && !is_final {
                     None // Skip this empty assistant message
@@ -300,13 +309,38 @@ impl AnthropicProvider {
         }).collect()
     }
 
+    /// Text of a user message, or content blocks when it holds images
+    fn convert_user_content(&self, content: &ChatMessageContent) -> Value {
+        let ChatMessageContent::ContentPart(parts) = content else {
+            return json!(self.extract_content_text(content));
+        };
+        if image::content_images(content).is_empty() {
+            return json!(self.extract_content_text(content));
+        }
+        let blocks: Vec<Value> = parts.iter().filter_map(|part| match part {
+            ChatMessageContentPart::Text(text_part) if !text_part.text.is_empty() => {
+                Some(json!({ "type": "text", "text": text_part.text }))
+            }
+            ChatMessageContentPart::Image(image_part) => {
+                let url = &image_part.image_url.url;
+                let source = match image::parse_data_url(url) {
+                    Some((media_type, data)) => AnthropicImageSource::Base64 { media_type: media_type.to_string(), data: data.to_string() },
+                    None => AnthropicImageSource::Url { url: url.clone() },
+                };
+                Some(json!({ "type": "image", "source": source }))
+            }
+            _ => None,
+        }).collect();
+        json!(blocks)
+    }
+
     fn extract_content_text(&self, content: &ChatMessageContent) -> String {
         match content {
             ChatMessageContent::Text(text) => text.clone(),
             ChatMessageContent::ContentPart(parts) => {
                 parts.iter().filter_map(|part| {
                     match part {
-                        openai_dive::v1::resources::chat::ChatMessageContentPart::Text(text_part) => {
+                        ChatMessageContentPart::Text(text_part) => {
                             Some(text_part.text.clone())
                         }
                         _ => None, // Skip images, audio, etc.
@@ -317,10 +351,12 @@ impl AnthropicProvider {
         }
     }
 
-    fn convert_from_anthropic_format(&self, response: serde_json::Value) -> Result<ChatCompletionResponse, LlmError> {
+    pub(crate) fn convert_from_anthropic_format(&self, response: serde_json::Value) -> Result<ChatCompletionResponse, LlmError> {
         let mut text_content = Vec::new();
+        let mut reasoning = Vec::new();
+        let mut thinking_blocks = Vec::new();
         let mut tool_calls = Vec::new();
-        
+
         // Parse content array
         if let Some(content_array) = response["content"].as_array() {
             for content_block in content_array {
@@ -330,6 +366,15 @@ impl AnthropicProvider {
                             text_content.push(text.to_string());
                         }
                     }
+                    Some("thinking") => {
+                        if let Some(thinking) = content_block["thinking"].as_str() {
+                            reasoning.push(thinking.to_string());
+                        }
+                        thinking_blocks.push(content_block.clone());
+                    }
+                    Some("redacted_thinking") => {
+                        thinking_blocks.push(content_block.clone());
+                    }
                     Some("tool_use") => {
                         if let (Some(id), Some(name), Some(input)) = (
                             content_block["id"].as_str(),
@@ -350,18 +395,24 @@ impl AnthropicProvider {
                 }
             }
         }
-        
+
+        if let Some(first) = tool_calls.first().filter(|_| !thinking_blocks.is_empty()) {
+            self.thinking_blocks.lock().unwrap().insert(first.id.clone(), thinking_blocks);
+        }
+
         // Combine text content
         let combined_text = text_content.join(" ").trim().to_string();
-        let content = if combined_text.is_empty() { 
-            None 
-        } else { 
+        let content = if combined_text.is_empty() {
+            None
+        } else {
             Some(ChatMessageContent::Text(combined_text))
         };
-        
+        let reasoning = reasoning.join("\n");
+
         // Convert tool_calls to Option
         let tool_calls_option = if tool_calls.is_empty() { None } else { Some(tool_calls) };
-        
+        let usage = serde_json::from_value::<AnthropicUsage>(response["usage"].clone()).ok();
+
         Ok(ChatCompletionResponse {
             id: Some(response["id"].as_str().unwrap_or("").to_string()),
             object: "chat.completion".to_string(),
@@ -371,28 +422,221 @@ impl AnthropicProvider {
                 index: 0,
                 message: ChatMessage::Assistant {
                     content,
-                    reasoning_content: None,
+                    reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
                     refusal: None,
                     name: None,
                     audio: None,
                     tool_calls: tool_calls_option,
                 },
-                finish_reason: Some(FinishReason::StopSequenceReached),
+                finish_reason: convert_stop_reason(response["stop_reason"].as_str())
+                    .or(Some(FinishReason::StopSequenceReached)),
                 logprobs: None,
             }],
-            usage: Some(Usage {
-                prompt_tokens: Some(response["usage"]["input_tokens"].as_u64().unwrap_or(0) as u32),
-                completion_tokens: Some(response["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32),
-                total_tokens: response["usage"]["input_tokens"].as_u64().unwrap_or(0) as u32 + response["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32,
-                prompt_tokens_details: None,
-                completion_tokens_details: None,
-            }),
+            usage: usage.as_ref().and_then(convert_usage),
             service_tier: None,
             system_fingerprint: None,
         })
     }
 }
 
+/// Cache breakpoint on the last message of the trace, the next step reads everything
+/// up to it from the cache. A final assistant message is a prefill and is skipped.
+fn mark_last_stable_message(messages: &mut [Value]) {
+    let Some(message) = messages.iter_mut().rev().find(|msg| msg["role"] == "user") else {
+        return;
+    };
+    if let Some(text) = message["content"].as_str() {
+        if text.is_empty() {
+            return;
+        }
+        message["content"] = json!([{ "type": "text", "text": text }]);
+    }
+    if let Some(block) = message["content"].as_array_mut().and_then(|blocks| blocks.last_mut()) {
+        block["cache_control"] = json!({ "type": "ephemeral" });
+    }
+}
+
+fn convert_stop_reason(reason: Option<&str>) -> Option<FinishReason> {
+    let reason = match reason? {
+        "tool_use" => "tool_calls",
+        "max_tokens" => "length",
+        _ => "stop",
+    };
+    serde_json::from_value(json!(reason)).ok()
+}
+
+/// Anthropic does not count cached tokens in input_tokens, they are added back to the
+/// prompt tokens and the cache reads reported as cached tokens
+fn convert_usage(usage: &AnthropicUsage) -> Option<Usage> {
+    let cache_read = usage.cache_read_input_tokens.unwrap_or(0);
+    let prompt_tokens = usage.input_tokens.unwrap_or(0) + usage.cache_creation_input_tokens.unwrap_or(0) + cache_read;
+    serde_json::from_value(json!({
+        "prompt_tokens": prompt_tokens,
+        "completion_tokens": usage.output_tokens,
+        "total_tokens": prompt_tokens + usage.output_tokens,
+        "prompt_tokens_details": { "cached_tokens": cache_read },
+    })).ok()
+}
+
+/// Converts the events of a streamed message. The usage of the prompt comes with
+/// message_start and the output tokens with message_delta, thinking blocks are
+/// rebuilt from their deltas to be sent back with the tool results.
+pub(crate) struct AnthropicStreamState {
+    id: Option<String>,
+    model: String,
+    usage: Option<AnthropicUsage>,
+    thinking: Vec<(u32, Value)>,
+    tool_calls: u32,
+    thinking_blocks: ThinkingBlocks,
+}
+
+impl AnthropicStreamState {
+    fn new(thinking_blocks: ThinkingBlocks) -> Self {
+        Self {
+            id: None,
+            model: "claude".to_string(),
+            usage: None,
+            thinking: Vec::new(),
+            tool_calls: 0,
+            thinking_blocks,
+        }
+    }
+
+    fn thinking_block(&mut self, index: u32) -> Option<&mut Value> {
+        self.thinking.iter_mut().find(|(i, _)| *i == index).map(|(_, block)| block)
+    }
+
+    fn convert(&mut self, event: AnthropicStreamEvent) -> Result<Option<ChatCompletionChunkResponse>, LlmError> {
+        match event {
+            AnthropicStreamEvent::MessageStart { message } => {
+                self.id = Some(message.id);
+                self.model = message.model;
+                self.usage = Some(message.usage);
+                Ok(None)
+            }
+            AnthropicStreamEvent::ContentBlockStart { index, content_block } => match content_block.block_type.as_str() {
+                "thinking" => {
+                    let thinking = content_block.thinking.unwrap_or_default();
+                    self.thinking.push((index, json!({
+                        "type": "thinking",
+                        "thinking": thinking,
+                        "signature": content_block.signature.unwrap_or_default()
+                    })));
+                    Ok((!thinking.is_empty()).then(|| self.stream_chunk(Self::delta_message(None, Some(thinking), None), None)))
+                }
+                "redacted_thinking" => {
+                    self.thinking.push((index, json!({
+                        "type": "redacted_thinking",
+                        "data": content_block.data.unwrap_or_default()
+                    })));
+                    Ok(None)
+                }
+                "tool_use" => {
+                    // the thinking blocks are complete once the first tool call starts
+                    if self.tool_calls == 0 && !self.thinking.is_empty() {
+                        if let Some(id) = &content_block.id {
+                            let blocks = self.thinking.iter().map(|(_, block)| block.clone()).collect();
+                            self.thinking_blocks.lock().unwrap().insert(id.clone(), blocks);
+                        }
+                    }
+                    self.tool_calls += 1;
+
+                    // tool call starts, the arguments will follow as input_json deltas
+                    Ok(Some(self.stream_chunk(Self::delta_message(None, None, Some(vec![DeltaToolCall {
+                        index: Some(index),
+                        id: content_block.id,
+                        r#type: Some("function".to_string()),
+                        function: DeltaFunction {
+                            name: content_block.name,
+                            arguments: Some(String::new()),
+                        },
+                    }])), None)))
+                }
+                _ => Ok(None),
+            },
+            AnthropicStreamEvent::ContentBlockDelta { index, delta } => {
+                let message = match delta {
+                    AnthropicDelta::TextDelta { text } => Self::delta_message(Some(ChatMessageContent::Text(text)), None, None),
+                    AnthropicDelta::ThinkingDelta { thinking } => {
+                        if let Some(block) = self.thinking_block(index) {
+                            let text = format!("{}{}", block["thinking"].as_str().unwrap_or_default(), thinking);
+                            block["thinking"] = json!(text);
+                        }
+                        Self::delta_message(None, Some(thinking), None)
+                    }
+                    AnthropicDelta::SignatureDelta { signature } => {
+                        if let Some(block) = self.thinking_block(index) {
+                            block["signature"] = json!(signature);
+                        }
+                        return Ok(None);
+                    }
+                    AnthropicDelta::InputJsonDelta { partial_json } => Self::delta_message(None, None, Some(vec![DeltaToolCall {
+                        index: Some(index),
+                        id: None,
+                        r#type: None,
+                        function: DeltaFunction {
+                            name: None,
+                            arguments: Some(partial_json),
+                        },
+                    }])),
+                    AnthropicDelta::Unknown => return Ok(None),
+                };
+                Ok(Some(self.stream_chunk(message, None)))
+            }
+            AnthropicStreamEvent::MessageDelta { delta, usage } => {
+                // the final usage carries the output tokens, and the cache counters
+                // again on recent API versions
+                let usage = match (self.usage.take(), usage) {
+                    (Some(start), Some(end)) => Some(AnthropicUsage {
+                        input_tokens: end.input_tokens.or(start.input_tokens),
+                        output_tokens: end.output_tokens,
+                        cache_creation_input_tokens: end.cache_creation_input_tokens.or(start.cache_creation_input_tokens),
+                        cache_read_input_tokens: end.cache_read_input_tokens.or(start.cache_read_input_tokens),
+                    }),
+                    (start, end) => end.or(start),
+                };
+                let mut chunk = self.stream_chunk(Self::delta_message(None, None, None), convert_stop_reason(delta.stop_reason.as_deref()));
+                chunk.usage = usage.as_ref().and_then(convert_usage);
+                Ok(Some(chunk))
+            }
+            AnthropicStreamEvent::Error { error } => {
+                Err(LlmError::from(format!("Anthropic stream error: {}", error)))
+            }
+            _ => Ok(None), // Skip other events like message_stop, ping, etc.
+        }
+    }
+
+    fn delta_message(content: Option<ChatMessageContent>, reasoning_content: Option<String>, tool_calls: Option<Vec<DeltaToolCall>>) -> DeltaChatMessage {
+        DeltaChatMessage::Assistant {
+            content,
+            reasoning_content,
+            refusal: None,
+            name: None,
+            tool_calls,
+        }
+    }
+
+    fn stream_chunk(&self, delta: DeltaChatMessage, finish_reason: Option<FinishReason>) -> ChatCompletionChunkResponse {
+        ChatCompletionChunkResponse {
+            id: self.id.clone().or_else(|| Some(format!("anthropic-{}", uuid::Uuid::new_v4()))),
+            object: "chat.completion.chunk".to_string(),
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as u32,
+            model: self.model.clone(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta,
+                finish_reason,
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+        }
+    }
+}
+
 #[async_trait]
 impl LlmProvider for AnthropicProvider {
     async fn models(&self) -> Result<ListModelResponse, LlmError> {
@@ -440,20 +684,7 @@ impl LlmProvider for AnthropicProvider {
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
         let anthropic_request = self.convert_to_anthropic_format(&request);
-        
-        let response = self.client
-            .post(&format!("{}/messages", ANTHROPIC_API_BASE))
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&anthropic_request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API error: {}", error_text).into());
-        }
+        let response = self.post(&anthropic_request, "Anthropic API error").await?;
 
         let anthropic_response: serde_json::Value = response.json().await?;
         self.convert_from_anthropic_format(anthropic_response)
@@ -463,30 +694,20 @@ impl LlmProvider for AnthropicProvider {
         let mut anthropic_request = self.convert_to_anthropic_format(&request);
         // Add streaming parameter
         anthropic_request["stream"] = json!(true);
-        
-        let response = self.client
-            .post(&format!("{}/messages", ANTHROPIC_API_BASE))
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&anthropic_request)
-            .send()
-            .await?;
+        let response = self.post(&anthropic_request, "Anthropic API streaming error").await?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Anthropic API streaming error: {}", error_text).into());
-        }
-
-        Self::parse_anthropic_stream(response).await
-    }
-
-    fn supports_functions(&self, model: String) -> bool {
-        true // Anthropic supports function calling via tool use
+        Ok(Self::parse_anthropic_stream(response, self.thinking_blocks.clone()))
     }
 
-    fn supports_structured_output(&self, model: String) -> bool {
-        false
+    /// Tool use but no json schema constrained output, images since claude 3
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        let legacy = model.starts_with("claude-2") || model.starts_with("claude-instant");
+        ModelCapabilities {
+            tools: Some(!legacy),
+            json_schema: Some(false),
+            vision: Some(!legacy),
+            ..Default::default()
+        }
     }
 
     fn name(&self) -> &'static str {
@@ -499,6 +720,7 @@ impl LlmProvider for AnthropicProvider {
             display_name: "Anthropic (Claude 3.5 Sonnet, Claude 3 Opus)",
             env_vars: vec![
                 EnvVar::required("ANTHROPIC_API_KEY", "Anthropic API key"),
+                EnvVar::optional("ANTHROPIC_THINKING_BUDGET", "Extended thinking budget in tokens (disabled if not set)"),
             ],
         }
     }
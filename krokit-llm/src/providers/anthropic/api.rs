@@ -48,6 +48,24 @@ pub struct AnthropicContentBlock {
     #[serde(rename = "type")]
     pub block_type: String,
     pub text: Option<String>,
+    /// set on tool_use blocks
+    pub id: Option<String>,
+    /// set on tool_use blocks
+    pub name: Option<String>,
+    /// set on thinking blocks, usually empty at start and streamed as deltas
+    pub thinking: Option<String>,
+    /// set on thinking blocks
+    pub signature: Option<String>,
+    /// set on redacted_thinking blocks
+    pub data: Option<String>,
+}
+
+/// Source of an image block of a user message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnthropicImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +74,9 @@ pub enum AnthropicDelta {
     TextDelta { text: String },
     InputJsonDelta { partial_json: String },
     ThinkingDelta { thinking: String },
+    SignatureDelta { signature: String },
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,7 +89,14 @@ pub struct AnthropicMessageDelta {
 pub struct AnthropicUsage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub input_tokens: Option<u32>,
+    #[serde(default)]
     pub output_tokens: u32,
+    /// prompt tokens written to the cache, not included in input_tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_creation_input_tokens: Option<u32>,
+    /// prompt tokens read from the cache, not included in input_tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<u32>,
 }
 
 pub const ANTHROPIC_API_BASE: &str = "https://api.anthropic.com/v1";
\ No newline at end of file
@@ -3,7 +3,12 @@ mod tests {
     use super::*;
     use crate::providers::anthropic::AnthropicProvider;
     use crate::provider::LlmProvider;
-    use openai_dive::v1::resources::chat::{ChatMessage, ChatMessageContent, ChatCompletionParametersBuilder};
+    use openai_dive::v1::resources::chat::{
+        ChatMessage, ChatMessageContent, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionTool,
+        ChatCompletionToolType, ChatCompletionFunction, DeltaChatMessage, ToolCall, Function,
+    };
+    use crate::providers::fixture::FixtureServer;
+    use futures::StreamExt;
     use serde_json::json;
 
     fn setup_provider() -> AnthropicProvider {
@@ -36,13 +41,13 @@ mod tests {
         
         // Check that system message is extracted to top-level system parameter
         assert!(anthropic_format.get("system").is_some());
-        assert_eq!(anthropic_format["system"].as_str().unwrap(), "You are a helpful assistant.");
+        assert_eq!(anthropic_format["system"][0]["text"].as_str().unwrap(), "You are a helpful assistant.");
         
         // Check that messages array only contains non-system messages
         let messages = anthropic_format["messages"].as_array().unwrap();
         assert_eq!(messages.len(), 1);
         assert_eq!(messages[0]["role"].as_str().unwrap(), "user");
-        assert_eq!(messages[0]["content"].as_str().unwrap(), "Hello!");
+        assert_eq!(messages[0]["content"][0]["text"].as_str().unwrap(), "Hello!");
     }
 
     #[tokio::test]
@@ -74,7 +79,7 @@ mod tests {
         
         // Check that system messages are combined
         assert!(anthropic_format.get("system").is_some());
-        let system_content = anthropic_format["system"].as_str().unwrap();
+        let system_content = anthropic_format["system"][0]["text"].as_str().unwrap();
         assert!(system_content.contains("You are a helpful assistant."));
         assert!(system_content.contains("Always be concise."));
         
@@ -174,4 +179,174 @@ mod tests {
         assert_eq!(tool_result_content[0]["tool_use_id"].as_str().unwrap(), "toolu_018qHepKa8d4rbZ9qskd2vqw");
         assert_eq!(tool_result_content[0]["content"].as_str().unwrap(), "Successfully updated file '/Users/lloiseau/Work/test/main.py' with 22 bytes");
     }
-}
\ No newline at end of file
+
+    fn tool(name: &str) -> ChatCompletionTool {
+        ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: ChatCompletionFunction {
+                name: name.to_string(),
+                description: None,
+                parameters: json!({ "type": "object", "properties": { "path": { "type": "string" } } }),
+            },
+        }
+    }
+
+    fn read_call(id: &str) -> ChatMessage {
+        ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: Some(vec![ToolCall {
+                id: id.to_string(),
+                r#type: "function".to_string(),
+                function: Function { name: "read_file".to_string(), arguments: r#"{"path": "src/main.rs"}"#.to_string() },
+            }]),
+        }
+    }
+
+    fn request(messages: Vec<ChatMessage>) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("claude-sonnet-4-20250514")
+            .messages(messages)
+            .tools(vec![tool("read_file"), tool("write")])
+            .build()
+            .unwrap()
+    }
+
+    fn user(text: &str) -> ChatMessage {
+        ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None }
+    }
+
+    #[test]
+    fn test_cache_breakpoints() {
+        let provider = AnthropicProvider::new("test-key".to_string());
+        let body = provider.convert_to_anthropic_format(&request(vec![
+            ChatMessage::System { content: ChatMessageContent::Text("You are a coding assistant.".to_string()), name: None },
+            user("Show me main.rs"),
+            read_call("toolu_01"),
+            ChatMessage::Tool { content: "fn main() {}".to_string(), tool_call_id: "toolu_01".to_string() },
+        ]));
+
+        let ephemeral = json!({ "type": "ephemeral" });
+        assert_eq!(body["system"][0]["cache_control"], ephemeral);
+        assert!(body["tools"][0].get("cache_control").is_none());
+        assert_eq!(body["tools"][1]["cache_control"], ephemeral);
+        // only the end of the trace is marked, earlier steps are found from there
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["content"], "Show me main.rs");
+        assert_eq!(messages[2]["content"][0]["cache_control"], ephemeral);
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert!(body.get("thinking").is_none());
+    }
+
+    #[test]
+    fn test_cached_usage_and_thinking() {
+        let provider = AnthropicProvider::new("test-key".to_string());
+        let response = provider.convert_from_anthropic_format(serde_json::from_str(include_str!("fixtures/message_cached.json")).unwrap()).unwrap();
+
+        let ChatMessage::Assistant { content, reasoning_content, .. } = &response.choices[0].message else {
+            panic!("expected an assistant message");
+        };
+        assert_eq!(content, &Some(ChatMessageContent::Text("Hello! How can I help?".to_string())));
+        assert_eq!(reasoning_content.as_deref(), Some("A short greeting is enough."));
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(12 + 240 + 3712));
+        assert_eq!(usage.completion_tokens, Some(31));
+        assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, Some(3712));
+    }
+
+    #[tokio::test]
+    async fn test_stream_thinking_is_sent_back_with_tool_results() {
+        let server = FixtureServer::start(200, "", "text/event-stream", include_str!("fixtures/stream_thinking_tool_use.sse")).await;
+        let provider = AnthropicProvider::with_base_url("test-key".to_string(), format!("{}/v1", server.base_url))
+            .with_thinking_budget(Some(2048));
+
+        let mut stream = provider.chat_stream(request(vec![user("Show me main.rs")])).await.unwrap();
+        let (mut reasoning, mut arguments, mut last) = (String::new(), String::new(), None);
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            if let DeltaChatMessage::Assistant { reasoning_content, tool_calls, .. } = &chunk.choices[0].delta {
+                reasoning.push_str(reasoning_content.as_deref().unwrap_or_default());
+                for call in tool_calls.iter().flatten() {
+                    arguments.push_str(call.function.arguments.as_deref().unwrap_or_default());
+                }
+            }
+            last = Some(chunk);
+        }
+
+        let body = &server.requests()[0].body;
+        assert_eq!(server.requests()[0].path, "/v1/messages");
+        assert_eq!(body["thinking"], json!({ "type": "enabled", "budget_tokens": 2048 }));
+        assert_eq!(body["max_tokens"], 1000 + 2048);
+        assert_eq!(reasoning, "The user wants main.rs, I should read it first.");
+        assert_eq!(arguments, r#"{"path": "src/main.rs"}"#);
+        let last = last.unwrap();
+        assert_eq!(serde_json::to_value(&last.choices[0].finish_reason).unwrap(), "tool_calls");
+        let usage = last.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(24 + 3712));
+        assert_eq!(usage.completion_tokens, Some(89));
+        assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, Some(3712));
+
+        // the next step of the tool loop starts with the signed thinking block
+        let body = provider.convert_to_anthropic_format(&request(vec![
+            user("Show me main.rs"),
+            read_call("toolu_01T1x1fJ34qAmk2tNTrN7Up6"),
+            ChatMessage::Tool { content: "fn main() {}".to_string(), tool_call_id: "toolu_01T1x1fJ34qAmk2tNTrN7Up6".to_string() },
+        ]));
+        assert!(body.get("thinking").is_some());
+        assert_eq!(body["messages"][1]["content"][0], json!({
+            "type": "thinking",
+            "thinking": "The user wants main.rs, I should read it first.",
+            "signature": "EqQBCgIYAhIM1gbcDa9GJwZA2b3hGgxBdjrkzLoky3dl1pkiMOYds"
+        }));
+    }
+
+    #[test]
+    fn test_thinking_disabled_in_tool_loop_without_blocks() {
+        // e.g. a session resumed in the middle of a tool loop
+        let provider = AnthropicProvider::new("test-key".to_string()).with_thinking_budget(Some(4096));
+        let body = provider.convert_to_anthropic_format(&request(vec![
+            user("Show me main.rs"),
+            read_call("toolu_02"),
+            ChatMessage::Tool { content: "fn main() {}".to_string(), tool_call_id: "toolu_02".to_string() },
+        ]));
+        assert!(body.get("thinking").is_none());
+        assert_eq!(body["max_tokens"], 1000);
+
+        let body = provider.convert_to_anthropic_format(&request(vec![
+            user("Show me main.rs"),
+            read_call("toolu_02"),
+            ChatMessage::Tool { content: "fn main() {}".to_string(), tool_call_id: "toolu_02".to_string() },
+            user("Now explain it"),
+        ]));
+        assert_eq!(body["thinking"]["budget_tokens"], 4096);
+    }
+
+    #[test]
+    fn test_images_are_sent_as_image_blocks() {
+        let provider = AnthropicProvider::new("test-key".to_string());
+        let png = crate::image::image_part(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]).unwrap();
+        let body = provider.convert_to_anthropic_format(&request(vec![
+            ChatMessage::User {
+                content: crate::image::user_content("What is wrong here?".to_string(), vec![png.clone()]),
+                name: None,
+            },
+            read_call("toolu_03"),
+            ChatMessage::Tool { content: "Image attached below".to_string(), tool_call_id: "toolu_03".to_string() },
+            ChatMessage::User { content: crate::image::user_content("Images returned by the tools:".to_string(), vec![png]), name: None },
+        ]));
+
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["content"][0], json!({ "type": "text", "text": "What is wrong here?" }));
+        assert_eq!(messages[0]["content"][1]["type"], "image");
+        assert_eq!(messages[0]["content"][1]["source"]["type"], "base64");
+        assert_eq!(messages[0]["content"][1]["source"]["media_type"], "image/png");
+        // the image read by the tool is in the message of the tool result
+        let blocks = messages[2]["content"].as_array().unwrap();
+        assert_eq!(blocks[0]["type"], "tool_result");
+        assert_eq!(blocks[2]["type"], "image");
+    }
+}
@@ -0,0 +1,83 @@
+// llm/providers/embeddings.rs
+use serde::Deserialize;
+use serde_json::json;
+use crate::provider::{LlmError, HttpError};
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embeddings of the /embeddings endpoint of an OpenAI compatible api, in the order
+/// of the inputs
+pub async fn openai_embeddings(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    model: &str,
+    input: Vec<String>,
+) -> Result<Vec<Vec<f32>>, LlmError> {
+    if input.is_empty() {
+        return Ok(Vec::new());
+    }
+    let count = input.len();
+
+    let mut request = client
+        .post(format!("{}/embeddings", base_url.trim_end_matches('/')))
+        .json(&json!({ "model": model, "input": input }));
+    if !api_key.is_empty() {
+        request = request.bearer_auth(api_key);
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(Box::new(HttpError::from_response("Embeddings API error", response).await));
+    }
+
+    let response: EmbeddingsResponse = response.json().await?;
+    sorted_embeddings(response, count)
+}
+
+/// The data items carry the index of their input, providers may return them in any order
+fn sorted_embeddings(mut response: EmbeddingsResponse, count: usize) -> Result<Vec<Vec<f32>>, LlmError> {
+    if response.data.len() != count {
+        return Err(format!("Embeddings API returned {} embeddings for {} inputs", response.data.len(), count).into());
+    }
+    response.data.sort_by_key(|data| data.index);
+    Ok(response.data.into_iter().map(|data| data.embedding).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embeddings_are_sorted_by_index() {
+        let response: EmbeddingsResponse = serde_json::from_value(json!({
+            "object": "list",
+            "data": [
+                { "object": "embedding", "index": 1, "embedding": [0.0, 1.0] },
+                { "object": "embedding", "index": 0, "embedding": [1.0, 0.0] }
+            ],
+            "model": "text-embedding-3-small"
+        })).unwrap();
+
+        let embeddings = sorted_embeddings(response, 2).unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    }
+
+    #[test]
+    fn test_missing_embeddings_are_an_error() {
+        let response: EmbeddingsResponse = serde_json::from_value(json!({
+            "data": [{ "index": 0, "embedding": [1.0] }]
+        })).unwrap();
+
+        assert!(sorted_embeddings(response, 3).is_err());
+    }
+}
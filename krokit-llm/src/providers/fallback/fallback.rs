@@ -0,0 +1,281 @@
+// llm/providers/fallback/fallback.rs
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, HttpError, is_transient_status};
+use crate::capabilities::{builtin_capabilities, ModelCapabilities};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse},
+    model::ListModelResponse,
+};
+
+/// Messages of errors that do not keep their status (openai_dive errors for instance)
+/// but are worth retrying
+const TRANSIENT_PATTERNS: &[&str] = &[
+    "429",
+    "rate limit",
+    "ratelimit",
+    "too many requests",
+    "overloaded",
+    "timed out",
+    "timeout",
+    "internal server error",
+    "internalservererror",
+    "bad gateway",
+    "service unavailable",
+    "502",
+    "503",
+    "504",
+    "connection reset",
+    "connection refused",
+    "error sending request",
+];
+
+/// How many times a provider is retried and how long to wait in between
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// retries on a provider before moving to the next one
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    /// upper bound of the backoff, a longer Retry-After moves to the next provider
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with jitter, a random delay between half and the whole
+    /// exponential delay of the retry (starting at 0)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.initial_backoff_ms
+            .saturating_mul(1u64 << retry.min(32))
+            .min(self.max_backoff_ms);
+        Duration::from_millis(delay / 2 + fastrand::u64(0..=delay - delay / 2))
+    }
+
+    /// Delay before retrying a failed call, None if it should not be retried on this provider
+    pub fn delay(&self, error: &LlmError, retry: u32) -> Option<Duration> {
+        if retry >= self.max_retries {
+            return None;
+        }
+        match classify(error) {
+            Failure::Permanent => None,
+            Failure::Transient(None) => Some(self.backoff(retry)),
+            Failure::Transient(Some(retry_after)) => {
+                (retry_after <= Duration::from_millis(self.max_backoff_ms)).then_some(retry_after)
+            }
+        }
+    }
+}
+
+/// Kind of failure of a provider call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// worth retrying, after the delay requested by the server if any
+    Transient(Option<Duration>),
+    Permanent,
+}
+
+pub fn classify(error: &LlmError) -> Failure {
+    if let Some(error) = error.downcast_ref::<HttpError>() {
+        return if error.is_transient() { Failure::Transient(error.retry_after) } else { Failure::Permanent };
+    }
+
+    if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+        if error.is_timeout() || error.is_connect() {
+            return Failure::Transient(None);
+        }
+        if let Some(status) = error.status() {
+            return if is_transient_status(status.as_u16()) { Failure::Transient(None) } else { Failure::Permanent };
+        }
+    }
+
+    let text = format!("{} {:?}", error, error).to_lowercase();
+    if TRANSIENT_PATTERNS.iter().any(|pattern| text.contains(pattern)) {
+        Failure::Transient(None)
+    } else {
+        Failure::Permanent
+    }
+}
+
+/// Every provider of the chain failed, holds the last error of each one
+#[derive(Debug)]
+pub struct FallbackError {
+    pub failures: Vec<(&'static str, String)>,
+}
+
+impl std::fmt::Display for FallbackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.failures.is_empty() {
+            return write!(f, "no provider in the fallback chain");
+        }
+        let failures = self.failures.iter()
+            .map(|(provider, error)| format!("{}: {}", provider, error))
+            .collect::<Vec<_>>()
+            .join("; ");
+        write!(f, "all providers failed ({})", failures)
+    }
+}
+
+impl std::error::Error for FallbackError {}
+
+struct FallbackEntry {
+    provider: Box<dyn LlmProvider>,
+    model: Option<String>,
+}
+
+/// Tries a chain of providers in order, retrying each one on transient failures
+/// (rate limits, server errors, timeouts) before moving to the next
+pub struct FallbackProvider {
+    entries: Vec<FallbackEntry>,
+    policy: RetryPolicy,
+    answered: Mutex<Option<(&'static str, String)>>,
+}
+
+impl FallbackProvider {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            entries: Vec::new(),
+            policy,
+            answered: Mutex::new(None),
+        }
+    }
+
+    /// Add a provider at the end of the chain, the model replaces the one of the request when set
+    pub fn with_provider(mut self, provider: Box<dyn LlmProvider>, model: Option<String>) -> Self {
+        self.entries.push(FallbackEntry { provider, model });
+        self
+    }
+
+    pub fn providers(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|entry| entry.provider.name()).collect()
+    }
+
+    /// Run the call on each provider until one answers. A stream is only retried
+    /// until it is established, errors in the middle of it are returned as is.
+    async fn call<T, F>(&self, request: ChatCompletionParameters, call: F) -> Result<T, LlmError>
+    where
+        F: for<'a> Fn(&'a dyn LlmProvider, ChatCompletionParameters) -> BoxFuture<'a, Result<T, LlmError>> + Send + Sync,
+        T: Send,
+    {
+        let mut failures = Vec::new();
+        for entry in &self.entries {
+            let mut request = request.clone();
+            if let Some(model) = &entry.model {
+                request.model = model.clone();
+            }
+
+            let mut retry = 0;
+            loop {
+                let error = match call(entry.provider.as_ref(), request.clone()).await {
+                    Ok(response) => {
+                        *self.answered.lock().unwrap() = Some((entry.provider.name(), request.model.clone()));
+                        return Ok(response);
+                    }
+                    Err(error) => error,
+                };
+
+                match self.policy.delay(&error, retry) {
+                    Some(delay) => {
+                        tokio::time::sleep(delay).await;
+                        retry += 1;
+                    }
+                    None => {
+                        failures.push((entry.provider.name(), error.to_string()));
+                        break;
+                    }
+                }
+            }
+        }
+        Err(Box::new(FallbackError { failures }))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for FallbackProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        let mut failures = Vec::new();
+        for entry in &self.entries {
+            match entry.provider.models().await {
+                Ok(models) => return Ok(models),
+                Err(error) => failures.push((entry.provider.name(), error.to_string())),
+            }
+        }
+        Err(Box::new(FallbackError { failures }))
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        let entry = self.entries.first()
+            .ok_or_else(|| Box::new(FallbackError { failures: vec![] }) as LlmError)?;
+        match &entry.model {
+            Some(model) => Ok(model.clone()),
+            None => entry.provider.default_model().await,
+        }
+    }
+
+    fn default_model_among(&self, models: &[String]) -> Option<String> {
+        let entry = self.entries.first()?;
+        entry.model.clone().or_else(|| entry.provider.default_model_among(models))
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        self.call(request, |provider, request| provider.chat(request)).await
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        self.call(request, |provider, request| provider.chat_stream(request)).await
+    }
+
+    /// Every provider is asked for the model of the request, the vectors of different
+    /// models cannot be compared
+    async fn embeddings(&self, model: &str, input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        let mut failures = Vec::new();
+        for entry in &self.entries {
+            match entry.provider.embeddings(model, input.clone()).await {
+                Ok(embeddings) => return Ok(embeddings),
+                Err(error) => failures.push((entry.provider.name(), error.to_string())),
+            }
+        }
+        Err(Box::new(FallbackError { failures }))
+    }
+
+    /// The tool call method and the compaction are chosen once for the chain, so only
+    /// what every provider supports is reported
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        self.entries.iter()
+            .map(|entry| {
+                let model = entry.model.as_deref().unwrap_or(model);
+                entry.provider.capabilities(model).or(builtin_capabilities(model))
+            })
+            .reduce(ModelCapabilities::common)
+            .unwrap_or_default()
+    }
+
+    /// Name of the main provider, see `answered_by` for the one that answered
+    fn name(&self) -> &'static str {
+        self.entries.first().map_or("fallback", |entry| entry.provider.name())
+    }
+
+    fn answered_by(&self) -> Option<(&'static str, String)> {
+        self.answered.lock().unwrap().clone()
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "fallback",
+            display_name: "Fallback chain of providers",
+            env_vars: vec![],
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod fallback;
+pub mod tests;
+
+pub use fallback::{FallbackProvider, FallbackError, RetryPolicy, Failure, classify};
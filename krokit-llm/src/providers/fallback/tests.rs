@@ -0,0 +1,260 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use async_trait::async_trait;
+    use openai_dive::v1::resources::{
+        chat::{ChatCompletionChoice, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatMessage, ChatMessageContent},
+        model::ListModelResponse,
+        shared::FinishReason,
+    };
+    use crate::capabilities::ModelCapabilities;
+    use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, HttpError, parse_retry_after};
+    use crate::providers::fallback::{FallbackProvider, RetryPolicy, Failure, classify};
+
+    /// Fails with the given status a number of times, then answers
+    struct MockProvider {
+        name: &'static str,
+        failures: usize,
+        status: u16,
+        retry_after: Option<Duration>,
+        calls: Arc<AtomicUsize>,
+        models: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl MockProvider {
+        fn new(name: &'static str, failures: usize, status: u16) -> Self {
+            Self {
+                name,
+                failures,
+                status,
+                retry_after: None,
+                calls: Arc::new(AtomicUsize::new(0)),
+                models: Arc::new(std::sync::Mutex::new(vec![])),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for MockProvider {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Ok(ListModelResponse { object: "list".to_string(), data: vec![] })
+        }
+
+        async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            self.models.lock().unwrap().push(request.model.clone());
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                return Err(Box::new(HttpError {
+                    status: self.status,
+                    retry_after: self.retry_after,
+                    message: format!("{} error {}", self.name, self.status),
+                }));
+            }
+            Ok(ChatCompletionResponse {
+                id: Some("1".to_string()),
+                object: "chat.completion".to_string(),
+                created: 0,
+                model: request.model,
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message: ChatMessage::Assistant {
+                        content: Some(ChatMessageContent::Text(self.name.to_string())),
+                        reasoning_content: None,
+                        refusal: None,
+                        name: None,
+                        audio: None,
+                        tool_calls: None,
+                    },
+                    finish_reason: Some(FinishReason::StopSequenceReached),
+                    logprobs: None,
+                }],
+                usage: None,
+                service_tier: None,
+                system_fingerprint: None,
+            })
+        }
+
+        async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            Err("not supported".into())
+        }
+
+        fn capabilities(&self, model: &str) -> ModelCapabilities {
+            ModelCapabilities {
+                tools: Some(true),
+                json_schema: Some(self.name != "no_so"),
+                ..Default::default()
+            }
+        }
+
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn info() -> ProviderInfo {
+            ProviderInfo { name: "mock", display_name: "Mock", env_vars: vec![] }
+        }
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy { max_retries: 2, initial_backoff_ms: 1, max_backoff_ms: 50 }
+    }
+
+    fn request() -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("main-model")
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::Text("hello".to_string()),
+                name: None,
+            }])
+            .build()
+            .unwrap()
+    }
+
+    fn answer(response: &ChatCompletionResponse) -> String {
+        match &response.choices[0].message {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => text.clone(),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors() {
+        let primary = MockProvider::new("primary", 2, 429);
+        let calls = primary.calls.clone();
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(primary), None)
+            .with_provider(Box::new(MockProvider::new("backup", 0, 0)), Some("backup-model".to_string()));
+
+        let response = provider.chat(request()).await.unwrap();
+        assert_eq!(answer(&response), "primary");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(provider.answered_by(), Some(("primary", "main-model".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_after_retries() {
+        let primary = MockProvider::new("primary", usize::MAX, 503);
+        let calls = primary.calls.clone();
+        let backup = MockProvider::new("backup", 0, 0);
+        let models = backup.models.clone();
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(primary), None)
+            .with_provider(Box::new(backup), Some("backup-model".to_string()));
+
+        let response = provider.chat(request()).await.unwrap();
+        assert_eq!(answer(&response), "backup");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(*models.lock().unwrap(), vec!["backup-model".to_string()]);
+        assert_eq!(provider.answered_by(), Some(("backup", "backup-model".to_string())));
+        assert_eq!(provider.name(), "primary");
+    }
+
+    #[tokio::test]
+    async fn test_permanent_error_is_not_retried() {
+        let primary = MockProvider::new("primary", usize::MAX, 401);
+        let calls = primary.calls.clone();
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(primary), None)
+            .with_provider(Box::new(MockProvider::new("backup", 0, 0)), None);
+
+        let response = provider.chat(request()).await.unwrap();
+        assert_eq!(answer(&response), "backup");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_honors_retry_after() {
+        let mut primary = MockProvider::new("primary", 1, 429);
+        primary.retry_after = Some(Duration::from_millis(30));
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(primary), None);
+
+        let start = std::time::Instant::now();
+        let response = provider.chat(request()).await.unwrap();
+        assert_eq!(answer(&response), "primary");
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        // a delay longer than the backoff limit moves to the next provider right away
+        let mut primary = MockProvider::new("primary", 1, 429);
+        primary.retry_after = Some(Duration::from_secs(3600));
+        let calls = primary.calls.clone();
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(primary), None)
+            .with_provider(Box::new(MockProvider::new("backup", 0, 0)), None);
+
+        let response = provider.chat(request()).await.unwrap();
+        assert_eq!(answer(&response), "backup");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_all_providers_failed() {
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(MockProvider::new("primary", usize::MAX, 500)), None)
+            .with_provider(Box::new(MockProvider::new("backup", usize::MAX, 400)), None);
+
+        let error = provider.chat(request()).await.unwrap_err().to_string();
+        assert!(error.contains("primary: primary error 500"), "{}", error);
+        assert!(error.contains("backup: backup error 400"), "{}", error);
+        assert_eq!(provider.answered_by(), None);
+    }
+
+    #[test]
+    fn test_capabilities_of_the_whole_chain() {
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(MockProvider::new("primary", 0, 0)), None)
+            .with_provider(Box::new(MockProvider::new("no_so", 0, 0)), Some("gpt-4o".to_string()));
+        let capabilities = provider.capabilities("claude-sonnet-4");
+        assert!(capabilities.supports_tools());
+        assert!(!capabilities.supports_json_schema());
+        // the smallest window of the chain: claude 200k, gpt-4o 128k
+        assert_eq!(capabilities.context_window(), 128_000);
+    }
+
+    #[test]
+    fn test_classify() {
+        let error: LlmError = Box::new(HttpError { status: 429, retry_after: Some(Duration::from_secs(2)), message: "slow down".to_string() });
+        assert_eq!(classify(&error), Failure::Transient(Some(Duration::from_secs(2))));
+        let error: LlmError = Box::new(HttpError { status: 400, retry_after: None, message: "bad request".to_string() });
+        assert_eq!(classify(&error), Failure::Permanent);
+        let error: LlmError = "Rate limit exceeded, please retry".into();
+        assert_eq!(classify(&error), Failure::Transient(None));
+        let error: LlmError = "invalid api key".into();
+        assert_eq!(classify(&error), Failure::Permanent);
+    }
+
+    #[test]
+    fn test_backoff_with_jitter() {
+        let policy = RetryPolicy { max_retries: 5, initial_backoff_ms: 100, max_backoff_ms: 1000 };
+        for _ in 0..20 {
+            let first = policy.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let third = policy.backoff(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400));
+            assert!(policy.backoff(30) <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn test_default_model_among_cached_models() {
+        let models = vec!["first-model".to_string(), "second-model".to_string()];
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(MockProvider::new("primary", 0, 0)), None);
+        assert_eq!(provider.default_model_among(&models), Some("first-model".to_string()));
+        assert_eq!(provider.default_model_among(&[]), None);
+
+        let provider = FallbackProvider::new(policy())
+            .with_provider(Box::new(MockProvider::new("primary", 0, 0)), Some("main-model".to_string()));
+        assert_eq!(provider.default_model_among(&models), Some("main-model".to_string()));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after(" 0.5 "), Some(Duration::from_millis(500)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
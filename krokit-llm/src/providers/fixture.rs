@@ -0,0 +1,87 @@
+// llm/providers/fixture.rs
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Request received by the fixture server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub head: String,
+    pub body: Value,
+}
+
+/// Stand-in for a provider API, answers every request with a recorded response.
+/// The body is written in small pieces so that streamed events are split across
+/// network chunks.
+pub struct FixtureServer {
+    pub base_url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl FixtureServer {
+    pub async fn start(status: u16, headers: &'static str, content_type: &'static str, body: &'static str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let request = read_request(&mut socket).await;
+                    recorded.lock().unwrap().push(request);
+
+                    let head = format!(
+                        "HTTP/1.1 {} Fixture\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                        status, content_type, body.len(), headers);
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    for piece in body.as_bytes().chunks(37) {
+                        if socket.write_all(piece).await.is_err() {
+                            return;
+                        }
+                        let _ = socket.flush().await;
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                });
+            }
+        });
+
+        Self {
+            base_url: format!("http://{}", address),
+            requests,
+        }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(socket: &mut TcpStream) -> RecordedRequest {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = socket.read(&mut chunk).await.unwrap();
+        buffer.extend_from_slice(&chunk[..read]);
+
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&buffer[..end]).to_string();
+            let length = head.lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|value| value.trim().parse::<usize>().unwrap()))
+                .unwrap_or(0);
+            if buffer.len() >= end + 4 + length || read == 0 {
+                let body = serde_json::from_slice(&buffer[end + 4..]).unwrap_or(Value::Null);
+                let path = head.split_whitespace().nth(1).unwrap_or_default().to_string();
+                return RecordedRequest { path, head, body };
+            }
+        }
+        if read == 0 {
+            panic!("connection closed before the end of the request");
+        }
+    }
+}
+
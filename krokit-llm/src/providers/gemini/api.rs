@@ -0,0 +1,83 @@
+use serde::{Serialize, Deserialize};
+
+// Gemini generateContent response, also the payload of each streamed event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiResponse {
+    #[serde(default)]
+    pub candidates: Vec<GeminiCandidate>,
+    pub usage_metadata: Option<GeminiUsage>,
+    pub model_version: Option<String>,
+    pub response_id: Option<String>,
+    /// set when the prompt was blocked
+    pub prompt_feedback: Option<serde_json::Value>,
+    /// errors can be sent in the middle of a stream
+    pub error: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiCandidate {
+    pub content: Option<GeminiContent>,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiContent {
+    pub role: Option<String>,
+    #[serde(default)]
+    pub parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiPart {
+    pub text: Option<String>,
+    /// the text is a thought summary
+    #[serde(default)]
+    pub thought: bool,
+    pub function_call: Option<GeminiFunctionCall>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiFunctionCall {
+    /// only set by some models, generated otherwise
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GeminiUsage {
+    pub prompt_token_count: u32,
+    pub candidates_token_count: u32,
+    pub thoughts_token_count: u32,
+    pub cached_content_token_count: u32,
+    pub total_token_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiModelList {
+    #[serde(default)]
+    pub models: Vec<GeminiModel>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiModel {
+    /// "models/gemini-2.5-flash"
+    pub name: String,
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub supported_generation_methods: Vec<String>,
+    pub input_token_limit: Option<u32>,
+    pub output_token_limit: Option<u32>,
+    /// whether the model supports thinking
+    pub thinking: Option<bool>,
+}
+
+pub const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
@@ -0,0 +1,688 @@
+use std::collections::HashMap;
+use crate::capabilities::ModelCapabilities;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar, HttpError};
+use crate::providers::sse::sse_events;
+use crate::image;
+use super::api::*;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use futures::StreamExt;
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ChatCompletionChunkChoice, ChatCompletionTool, DeltaToolCall, DeltaFunction, ToolCall, Function},
+    model::{ListModelResponse, Model},
+    shared::{FinishReason, Usage},
+};
+
+/// Keywords of the json schema understood by Gemini, the others are dropped
+/// Model used when none is configured
+const DEFAULT_MODEL: &str = "gemini-2.5-flash";
+
+const SCHEMA_KEYS: &[&str] = &[
+    "type", "format", "description", "nullable", "enum", "properties", "required",
+    "items", "minItems", "maxItems", "minimum", "maximum",
+];
+
+const SCHEMA_FORMATS: &[&str] = &["enum", "date-time", "int32", "int64", "float", "double"];
+
+/// Recursive schemas are cut at this depth
+const MAX_SCHEMA_DEPTH: usize = 16;
+
+pub struct GeminiProvider {
+    api_key: String,
+    base_url: String,
+    client: Client,
+}
+
+impl GeminiProvider {
+    pub fn new(api_key: String) -> Self {
+        Self::with_base_url(api_key, GEMINI_API_BASE.to_string())
+    }
+
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
+        Self {
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Create Gemini provider from environment variables
+    /// Returns None if required environment variables are not set
+    pub fn from_env() -> Option<Self> {
+        std::env::var("GEMINI_API_KEY").ok().map(|api_key| {
+            match std::env::var("GEMINI_BASE_URL").ok().filter(|url| !url.is_empty()) {
+                Some(base_url) => Self::with_base_url(api_key, base_url),
+                None => Self::new(api_key),
+            }
+        })
+    }
+
+    /// Models that can chat, in the native format
+    pub async fn gemini_models(&self) -> Result<Vec<GeminiModel>, LlmError> {
+        let response = self.client
+            .get(format!("{}/models?pageSize=1000", self.base_url))
+            .header("x-goog-api-key", &self.api_key)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Box::new(HttpError::from_response("Gemini API error", response).await));
+        }
+
+        let gemini_models: GeminiModelList = response.json().await?;
+        Ok(gemini_models.models
+            .into_iter()
+            .filter(|model| model.supported_generation_methods.iter().any(|method| method == "generateContent"))
+            .collect())
+    }
+
+    fn model_url(&self, model: &str, method: &str) -> String {
+        let model = model.strip_prefix("models/").unwrap_or(model);
+        format!("{}/models/{}:{}", self.base_url, model, method)
+    }
+
+    async fn post(&self, url: &str, body: &Value) -> Result<reqwest::Response, LlmError> {
+        let response = self.client
+            .post(url)
+            .header("x-goog-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Box::new(HttpError::from_response("Gemini API error", response).await));
+        }
+        Ok(response)
+    }
+
+    fn parse_gemini_stream(response: reqwest::Response, model: String) -> LlmStream {
+        let stream = async_stream::stream! {
+            let mut events = Box::pin(sse_events(response));
+            let mut state = GeminiStreamState::new(model);
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+                if event.data.trim().is_empty() {
+                    continue;
+                }
+
+                let result = serde_json::from_str::<GeminiResponse>(&event.data)
+                    .map_err(|e| LlmError::from(format!("Failed to parse Gemini event {}. Error: {}", event.data, e)))
+                    .and_then(|response| state.convert(response));
+                match result {
+                    Ok(Some(chunk)) => yield Ok(chunk),
+                    Ok(None) => {}
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                }
+            }
+        };
+
+        Box::new(Box::pin(stream))
+    }
+
+    pub(crate) fn convert_to_gemini_format(&self, request: &ChatCompletionParameters) -> Value {
+        let (system_messages, contents) = self.convert_messages(&request.messages);
+
+        let mut gemini_request = json!({
+            "contents": contents
+        });
+
+        if !system_messages.is_empty() {
+            gemini_request["systemInstruction"] = json!({
+                "parts": [{ "text": system_messages.join("\n\n") }]
+            });
+        }
+
+        if let Some(tools) = request.tools.as_ref().filter(|tools| !tools.is_empty()) {
+            gemini_request["tools"] = json!([{ "functionDeclarations": self.convert_tools(tools) }]);
+        }
+
+        // optional parameters are read from their openai serialization
+        let params = serde_json::to_value(request).unwrap_or_default();
+        if let Some(tool_config) = Self::convert_tool_choice(&params["tool_choice"]) {
+            gemini_request["toolConfig"] = tool_config;
+        }
+
+        let generation_config = Self::convert_generation_config(&params);
+        if !generation_config.is_empty() {
+            gemini_request["generationConfig"] = Value::Object(generation_config);
+        }
+
+        gemini_request
+    }
+
+    fn convert_generation_config(params: &Value) -> serde_json::Map<String, Value> {
+        let mut config = serde_json::Map::new();
+
+        // max_completion_tokens replaces the deprecated max_tokens
+        for (from, to) in [
+            ("temperature", "temperature"),
+            ("top_p", "topP"),
+            ("seed", "seed"),
+            ("max_tokens", "maxOutputTokens"),
+            ("max_completion_tokens", "maxOutputTokens"),
+        ] {
+            if let Some(value) = params.get(from).filter(|value| !value.is_null()) {
+                config.insert(to.to_string(), value.clone());
+            }
+        }
+
+        match &params["stop"] {
+            Value::String(stop) => { config.insert("stopSequences".to_string(), json!([stop])); }
+            Value::Array(stop) => { config.insert("stopSequences".to_string(), json!(stop)); }
+            _ => {}
+        }
+
+        let response_format = &params["response_format"];
+        match response_format["type"].as_str() {
+            Some("json_object") => {
+                config.insert("responseMimeType".to_string(), json!("application/json"));
+            }
+            Some("json_schema") => {
+                config.insert("responseMimeType".to_string(), json!("application/json"));
+                let schema = &response_format["json_schema"]["schema"];
+                if schema.is_object() {
+                    config.insert("responseSchema".to_string(), sanitize_schema(schema));
+                }
+            }
+            _ => {}
+        }
+
+        config
+    }
+
+    fn convert_messages(&self, messages: &[ChatMessage]) -> (Vec<String>, Vec<Value>) {
+        let mut system_messages = Vec::new();
+        let mut contents: Vec<Value> = Vec::new();
+        // function responses need the name of the function, tool messages only have the call id
+        let mut call_names: HashMap<String, String> = HashMap::new();
+
+        for msg in messages {
+            let (role, parts) = match msg {
+                ChatMessage::System { content, .. } => {
+                    system_messages.push(self.extract_content_text(content));
+                    continue;
+                }
+                ChatMessage::User { content, .. } | ChatMessage::Developer { content, .. } => {
+                    ("user", self.convert_user_parts(content))
+                }
+                ChatMessage::Assistant { content, tool_calls, .. } => {
+                    let mut parts = Vec::new();
+                    let text = content.as_ref().map(|c| self.extract_content_text(c)).unwrap_or_default();
+                    if !text.is_empty() {
+                        parts.push(json!({ "text": text }));
+                    }
+                    for call in tool_calls.iter().flatten() {
+                        call_names.insert(call.id.clone(), call.function.name.clone());
+                        let args = serde_json::from_str::<Value>(&call.function.arguments)
+                            .ok()
+                            .filter(Value::is_object)
+                            .unwrap_or_else(|| json!({}));
+                        parts.push(json!({
+                            "functionCall": { "name": call.function.name, "args": args }
+                        }));
+                    }
+                    ("model", parts)
+                }
+                ChatMessage::Tool { content, tool_call_id, .. } => {
+                    let name = call_names.get(tool_call_id).cloned().unwrap_or_else(|| tool_call_id.clone());
+                    let response = serde_json::from_str::<Value>(content)
+                        .ok()
+                        .filter(Value::is_object)
+                        .unwrap_or_else(|| json!({ "content": content }));
+                    ("user", vec![json!({
+                        "functionResponse": { "name": name, "response": response }
+                    })])
+                }
+            };
+
+            if parts.is_empty() {
+                continue;
+            }
+
+            // consecutive messages of a same role are merged, the responses of parallel
+            // function calls must be in a single content
+            match contents.last_mut() {
+                Some(last) if last["role"] == role => {
+                    if let Some(last_parts) = last["parts"].as_array_mut() {
+                        last_parts.extend(parts);
+                    }
+                }
+                _ => contents.push(json!({ "role": role, "parts": parts })),
+            }
+        }
+
+        (system_messages, contents)
+    }
+
+    fn convert_tools(&self, tools: &[ChatCompletionTool]) -> Vec<Value> {
+        tools.iter().map(|tool| {
+            let mut declaration = json!({
+                "name": tool.function.name,
+                "description": tool.function.description.as_ref().unwrap_or(&tool.function.name),
+            });
+            // functions without parameters must not declare an empty object
+            let parameters = sanitize_schema(&tool.function.parameters);
+            if parameters["properties"].as_object().map_or(false, |properties| !properties.is_empty()) {
+                declaration["parameters"] = parameters;
+            }
+            declaration
+        }).collect()
+    }
+
+    fn convert_tool_choice(tool_choice: &Value) -> Option<Value> {
+        let mode = match tool_choice {
+            Value::String(choice) => match choice.as_str() {
+                "none" => "NONE",
+                "required" => "ANY",
+                _ => "AUTO",
+            },
+            Value::Object(_) => match tool_choice["function"]["name"].as_str() {
+                Some(name) => return Some(json!({
+                    "functionCallingConfig": { "mode": "ANY", "allowedFunctionNames": [name] }
+                })),
+                None => "AUTO",
+            },
+            _ => return None,
+        };
+        Some(json!({ "functionCallingConfig": { "mode": mode } }))
+    }
+
+    /// Text and inline images of a user message
+    fn convert_user_parts(&self, content: &ChatMessageContent) -> Vec<Value> {
+        let text = self.extract_content_text(content);
+        let mut parts = if text.is_empty() { vec![] } else { vec![json!({ "text": text })] };
+        for url in image::content_images(content) {
+            parts.push(match image::parse_data_url(url) {
+                Some((media_type, data)) => json!({ "inlineData": { "mimeType": media_type, "data": data } }),
+                // only files uploaded to gemini can be referenced
+                None => json!({ "text": format!("[image: {}]", url) }),
+            });
+        }
+        parts
+    }
+
+    fn extract_content_text(&self, content: &ChatMessageContent) -> String {
+        match content {
+            ChatMessageContent::Text(text) => text.clone(),
+            ChatMessageContent::ContentPart(parts) => {
+                parts.iter().filter_map(|part| {
+                    match part {
+                        ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                        _ => None, // Skip images, audio, etc.
+                    }
+                }).collect::<Vec<_>>().join(" ")
+            }
+            ChatMessageContent::None => String::new(),
+        }
+    }
+
+    pub(crate) fn convert_from_gemini_format(&self, response: GeminiResponse, model: &str) -> Result<ChatCompletionResponse, LlmError> {
+        if let Some(error) = response.error {
+            return Err(format!("Gemini API error: {}", error).into());
+        }
+
+        let usage = response.usage_metadata.as_ref().and_then(convert_usage);
+        let candidate = response.candidates.into_iter().next()
+            .ok_or_else(|| format!("Gemini returned no candidate: {}", response.prompt_feedback.unwrap_or_default()))?;
+
+        let parts = convert_parts(candidate.content);
+        let finish_reason = convert_finish_reason(candidate.finish_reason.as_deref(), !parts.tool_calls.is_empty())
+            .or(Some(FinishReason::StopSequenceReached));
+
+        Ok(ChatCompletionResponse {
+            id: response.response_id,
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: response.model_version.unwrap_or_else(|| model.to_string()),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: (!parts.text.is_empty()).then(|| ChatMessageContent::Text(parts.text)),
+                    reasoning_content: (!parts.reasoning.is_empty()).then_some(parts.reasoning),
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: (!parts.tool_calls.is_empty()).then_some(parts.tool_calls),
+                },
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            service_tier: None,
+            system_fingerprint: None,
+        })
+    }
+}
+
+/// Content of a candidate, split by kind
+#[derive(Debug, Default)]
+struct GeminiParts {
+    text: String,
+    reasoning: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+fn convert_parts(content: Option<GeminiContent>) -> GeminiParts {
+    let mut parts = GeminiParts::default();
+    for part in content.map(|content| content.parts).unwrap_or_default() {
+        match (part.text, part.function_call) {
+            (_, Some(call)) => parts.tool_calls.push(ToolCall {
+                id: call.id.unwrap_or_else(|| format!("call_{}", uuid::Uuid::new_v4().simple())),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: call.name,
+                    arguments: if call.args.is_object() { call.args.to_string() } else { "{}".to_string() },
+                },
+            }),
+            (Some(text), None) if part.thought => parts.reasoning.push_str(&text),
+            (Some(text), None) => parts.text.push_str(&text),
+            (None, None) => {}
+        }
+    }
+    parts
+}
+
+fn convert_finish_reason(reason: Option<&str>, tool_calls: bool) -> Option<FinishReason> {
+    let reason = match reason? {
+        _ if tool_calls => "tool_calls",
+        "MAX_TOKENS" => "length",
+        "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" | "IMAGE_SAFETY" => "content_filter",
+        _ => "stop",
+    };
+    serde_json::from_value(json!(reason)).ok()
+}
+
+/// Thoughts are billed as output tokens
+fn convert_usage(usage: &GeminiUsage) -> Option<Usage> {
+    let completion_tokens = usage.candidates_token_count + usage.thoughts_token_count;
+    serde_json::from_value(json!({
+        "prompt_tokens": usage.prompt_token_count,
+        "completion_tokens": completion_tokens,
+        "total_tokens": usage.prompt_token_count + completion_tokens,
+        "prompt_tokens_details": { "cached_tokens": usage.cached_content_token_count }
+    })).ok()
+}
+
+/// Converts the streamed responses into chunks. Gemini sends whole function calls,
+/// their index keeps growing along the stream.
+pub(crate) struct GeminiStreamState {
+    id: String,
+    model: String,
+    tool_calls: u32,
+}
+
+impl GeminiStreamState {
+    pub(crate) fn new(model: String) -> Self {
+        Self {
+            id: format!("gemini-{}", uuid::Uuid::new_v4()),
+            model,
+            tool_calls: 0,
+        }
+    }
+
+    pub(crate) fn convert(&mut self, response: GeminiResponse) -> Result<Option<ChatCompletionChunkResponse>, LlmError> {
+        if let Some(error) = response.error {
+            return Err(format!("Gemini API streaming error: {}", error).into());
+        }
+
+        // usage is cumulative, the last chunk holds the total
+        let usage = response.usage_metadata.as_ref().and_then(convert_usage);
+        let Some(candidate) = response.candidates.into_iter().next() else {
+            if let Some(feedback) = response.prompt_feedback {
+                return Err(format!("Gemini returned no candidate: {}", feedback).into());
+            }
+            return Ok(usage.map(|usage| self.chunk(Self::delta_message(None, None, None), None, Some(usage))));
+        };
+
+        let parts = convert_parts(candidate.content);
+        let tool_calls: Vec<DeltaToolCall> = parts.tool_calls.into_iter().map(|call| {
+            let index = self.tool_calls;
+            self.tool_calls += 1;
+            DeltaToolCall {
+                index: Some(index),
+                id: Some(call.id),
+                r#type: Some("function".to_string()),
+                function: DeltaFunction {
+                    name: Some(call.function.name),
+                    arguments: Some(call.function.arguments),
+                },
+            }
+        }).collect();
+
+        let finish_reason = convert_finish_reason(candidate.finish_reason.as_deref(), self.tool_calls > 0);
+        let message = Self::delta_message(
+            (!parts.text.is_empty()).then(|| ChatMessageContent::Text(parts.text)),
+            (!parts.reasoning.is_empty()).then_some(parts.reasoning),
+            (!tool_calls.is_empty()).then_some(tool_calls),
+        );
+        Ok(Some(self.chunk(message, finish_reason, usage)))
+    }
+
+    fn delta_message(content: Option<ChatMessageContent>, reasoning_content: Option<String>, tool_calls: Option<Vec<DeltaToolCall>>) -> DeltaChatMessage {
+        DeltaChatMessage::Assistant {
+            content,
+            reasoning_content,
+            refusal: None,
+            name: None,
+            tool_calls,
+        }
+    }
+
+    fn chunk(&self, delta: DeltaChatMessage, finish_reason: Option<FinishReason>, usage: Option<Usage>) -> ChatCompletionChunkResponse {
+        ChatCompletionChunkResponse {
+            id: Some(self.id.clone()),
+            object: "chat.completion.chunk".to_string(),
+            created: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs() as u32,
+            model: self.model.clone(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta,
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            system_fingerprint: None,
+        }
+    }
+}
+
+/// Gemini only understands a subset of the OpenAPI schema: references are inlined,
+/// `Option` (anyOf with null or a type list) becomes `nullable` and the unknown
+/// keywords (`$schema`, `additionalProperties`, `title`...) are dropped
+pub(crate) fn sanitize_schema(schema: &Value) -> Value {
+    let defs = schema.get("$defs")
+        .or_else(|| schema.get("definitions"))
+        .cloned()
+        .unwrap_or(Value::Null);
+    sanitize(schema, &defs, 0)
+}
+
+fn sanitize(schema: &Value, defs: &Value, depth: usize) -> Value {
+    let Some(object) = schema.as_object() else {
+        return json!({});
+    };
+    if depth > MAX_SCHEMA_DEPTH {
+        return json!({ "type": "object" });
+    }
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        let mut resolved = sanitize(&defs[name], defs, depth + 1);
+        if let Some(description) = object.get("description") {
+            resolved["description"] = description.clone();
+        }
+        return resolved;
+    }
+
+    let variants = object.get("anyOf").or_else(|| object.get("oneOf")).and_then(Value::as_array);
+    if let Some(variants) = variants {
+        let (nulls, others): (Vec<&Value>, Vec<&Value>) = variants.iter().partition(|variant| variant["type"] == "null");
+        let mut resolved = match others.as_slice() {
+            [single] => sanitize(single, defs, depth + 1),
+            _ => json!({ "anyOf": others.iter().map(|variant| sanitize(variant, defs, depth + 1)).collect::<Vec<_>>() }),
+        };
+        if !nulls.is_empty() {
+            resolved["nullable"] = json!(true);
+        }
+        if let Some(description) = object.get("description") {
+            resolved["description"] = description.clone();
+        }
+        return resolved;
+    }
+
+    let mut result = serde_json::Map::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "type" => match value {
+                Value::Array(types) => {
+                    if let Some(kind) = types.iter().find(|kind| kind.as_str() != Some("null")) {
+                        result.insert(key.clone(), kind.clone());
+                    }
+                    if types.iter().any(|kind| kind.as_str() == Some("null")) {
+                        result.insert("nullable".to_string(), json!(true));
+                    }
+                }
+                _ => { result.insert(key.clone(), value.clone()); }
+            },
+            "properties" => {
+                let properties = value.as_object()
+                    .map(|properties| properties.iter()
+                        .map(|(name, property)| (name.clone(), sanitize(property, defs, depth + 1)))
+                        .collect::<serde_json::Map<_, _>>())
+                    .unwrap_or_default();
+                result.insert(key.clone(), Value::Object(properties));
+            }
+            "items" => {
+                result.insert(key.clone(), sanitize(value, defs, depth + 1));
+            }
+            "format" => {
+                if value.as_str().map_or(false, |format| SCHEMA_FORMATS.contains(&format)) {
+                    result.insert(key.clone(), value.clone());
+                }
+            }
+            "const" => {
+                result.insert("enum".to_string(), json!([value]));
+            }
+            key if SCHEMA_KEYS.contains(&key) => {
+                result.insert(key.to_string(), value.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // enum values must be strings
+    if result.get("enum").and_then(Value::as_array).map_or(false, |values| !values.iter().all(Value::is_string)) {
+        result.remove("enum");
+    }
+    if result.contains_key("enum") {
+        result.entry("type").or_insert_with(|| json!("string"));
+    }
+    if result.contains_key("properties") {
+        result.entry("type").or_insert_with(|| json!("object"));
+    }
+
+    Value::Object(result)
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        let models = self.gemini_models().await?
+            .into_iter()
+            .map(|model| Model {
+                id: model.name.strip_prefix("models/").unwrap_or(&model.name).to_string(),
+                object: "model".to_string(),
+                created: None,
+                owned_by: "google".to_string(),
+            })
+            .collect();
+
+        Ok(ListModelResponse {
+            object: "list".to_string(),
+            data: models,
+        })
+    }
+
+    async fn model_capabilities(&self) -> Result<HashMap<String, ModelCapabilities>, LlmError> {
+        Ok(self.gemini_models().await?
+            .into_iter()
+            .map(|model| {
+                let capabilities = ModelCapabilities {
+                    context_window: model.input_token_limit,
+                    max_output_tokens: model.output_token_limit,
+                    reasoning: model.thinking,
+                    ..Default::default()
+                };
+                (model.name.strip_prefix("models/").unwrap_or(&model.name).to_string(), capabilities)
+            })
+            .collect())
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        Ok(DEFAULT_MODEL.to_string())
+    }
+
+    fn default_model_among(&self, _models: &[String]) -> Option<String> {
+        Some(DEFAULT_MODEL.to_string())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let gemini_request = self.convert_to_gemini_format(&request);
+        let response = self.post(&self.model_url(&request.model, "generateContent"), &gemini_request).await?;
+
+        let gemini_response: GeminiResponse = response.json().await?;
+        self.convert_from_gemini_format(gemini_response, &request.model)
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let gemini_request = self.convert_to_gemini_format(&request);
+        let url = format!("{}?alt=sse", self.model_url(&request.model, "streamGenerateContent"));
+        let response = self.post(&url, &gemini_request).await?;
+
+        Ok(Self::parse_gemini_stream(response, request.model.clone()))
+    }
+
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        ModelCapabilities {
+            tools: Some(true),
+            json_schema: Some(true),
+            vision: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "gemini",
+            display_name: "Google Gemini (Gemini 2.5 Pro, Gemini 2.5 Flash)",
+            env_vars: vec![
+                EnvVar::required("GEMINI_API_KEY", "Gemini API key (Google AI Studio)"),
+                EnvVar::optional("GEMINI_BASE_URL", "Gemini API base URL"),
+            ],
+        }
+    }
+}
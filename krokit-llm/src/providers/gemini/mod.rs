@@ -0,0 +1,5 @@
+pub mod api;
+pub mod gemini;
+pub mod tests;
+
+pub use gemini::GeminiProvider;
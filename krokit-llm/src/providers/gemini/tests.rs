@@ -0,0 +1,316 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use openai_dive::v1::resources::chat::{
+        ChatCompletionFunction, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponseFormat,
+        ChatCompletionTool, ChatCompletionToolChoice, ChatCompletionToolType, ChatMessage, ChatMessageContent,
+        DeltaChatMessage, Function, JsonSchemaBuilder, ToolCall,
+    };
+    use crate::provider::{LlmProvider, HttpError};
+    use crate::providers::gemini::GeminiProvider;
+    use crate::providers::gemini::gemini::sanitize_schema;
+    use crate::providers::fixture::FixtureServer;
+
+    fn provider(server: &FixtureServer) -> GeminiProvider {
+        GeminiProvider::with_base_url("test-key".to_string(), format!("{}/v1beta", server.base_url))
+    }
+
+    fn read_file_tool() -> ChatCompletionTool {
+        ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: ChatCompletionFunction {
+                name: "read_file".to_string(),
+                description: Some("Read a file from the filesystem".to_string()),
+                parameters: json!({
+                    "$schema": "https://json-schema.org/draft/2020-12/schema",
+                    "title": "ReadParams",
+                    "type": "object",
+                    "properties": {
+                        "path": { "type": "string", "description": "Path to the file to read" },
+                        "line_start": { "type": ["integer", "null"], "format": "uint32", "minimum": 0 },
+                        "show_line_numbers": { "type": "boolean", "default": false }
+                    },
+                    "required": ["path"],
+                    "additionalProperties": false
+                }),
+            },
+        }
+    }
+
+    fn user_request(text: &str) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("gemini-2.5-flash")
+            .messages(vec![
+                ChatMessage::System {
+                    content: ChatMessageContent::Text("You are a coding assistant.".to_string()),
+                    name: None,
+                },
+                ChatMessage::User {
+                    content: ChatMessageContent::Text(text.to_string()),
+                    name: None,
+                },
+            ])
+            .tools(vec![read_file_tool()])
+            .temperature(0.2)
+            .max_completion_tokens(256u32)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat_function_call() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/generate_content_function_call.json")).await;
+        let provider = provider(&server);
+
+        let response = provider.chat(user_request("Show me main.py with line numbers")).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1beta/models/gemini-2.5-flash:generateContent");
+        assert!(requests[0].head.to_lowercase().contains("x-goog-api-key: test-key"));
+
+        let body = &requests[0].body;
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "You are a coding assistant.");
+        assert_eq!(body["contents"], json!([{ "role": "user", "parts": [{ "text": "Show me main.py with line numbers" }] }]));
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 256);
+        let declaration = &body["tools"][0]["functionDeclarations"][0];
+        assert_eq!(declaration["name"], "read_file");
+        assert_eq!(declaration["parameters"], json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "Path to the file to read" },
+                "line_start": { "type": "integer", "nullable": true, "minimum": 0 },
+                "show_line_numbers": { "type": "boolean" }
+            },
+            "required": ["path"]
+        }));
+
+        let ChatMessage::Assistant { content, tool_calls, .. } = &response.choices[0].message else {
+            panic!("expected an assistant message");
+        };
+        assert!(content.is_none());
+        let tool_calls = tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].function.name, "read_file");
+        assert!(tool_calls[0].id.starts_with("call_"));
+        let arguments: Value = serde_json::from_str(&tool_calls[0].function.arguments).unwrap();
+        assert_eq!(arguments, json!({ "path": "main.py", "show_line_numbers": true }));
+
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(112));
+        assert_eq!(usage.completion_tokens, Some(24));
+    }
+
+    #[tokio::test]
+    async fn test_chat_text_with_thoughts() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/generate_content_text.json")).await;
+        let response = provider(&server).chat(user_request("Hello")).await.unwrap();
+
+        let ChatMessage::Assistant { content, reasoning_content, tool_calls, .. } = &response.choices[0].message else {
+            panic!("expected an assistant message");
+        };
+        assert!(matches!(content, Some(ChatMessageContent::Text(text)) if text == "Hello! How can I help you today?"));
+        assert_eq!(reasoning_content.as_deref(), Some("The user is greeting me, a short answer is enough."));
+        assert!(tool_calls.is_none());
+
+        // thoughts are output tokens, cached tokens are reported in the prompt details
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, Some(2048));
+        assert_eq!(usage.completion_tokens, Some(37));
+        assert_eq!(usage.prompt_tokens_details.and_then(|details| details.cached_tokens), Some(1024));
+    }
+
+    #[tokio::test]
+    async fn test_stream_text() {
+        let server = FixtureServer::start(200, "", "text/event-stream", include_str!("fixtures/stream_text.sse")).await;
+        let mut stream = provider(&server).chat_stream(user_request("Hello")).await.unwrap();
+
+        let mut text = String::new();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            if let DeltaChatMessage::Assistant { content: Some(ChatMessageContent::Text(delta)), .. } = &chunk.choices[0].delta {
+                text.push_str(delta);
+            }
+            chunks.push(chunk);
+        }
+
+        assert_eq!(server.requests()[0].path, "/v1beta/models/gemini-2.5-flash:streamGenerateContent?alt=sse");
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(text, "Hello! How can I help you today?");
+        assert!(chunks[..2].iter().all(|chunk| chunk.choices[0].finish_reason.is_none()));
+        assert!(chunks[2].choices[0].finish_reason.is_some());
+        assert_eq!(chunks[2].usage.as_ref().unwrap().completion_tokens, Some(10));
+    }
+
+    #[tokio::test]
+    async fn test_stream_function_calls() {
+        let server = FixtureServer::start(200, "", "text/event-stream", include_str!("fixtures/stream_function_call.sse")).await;
+        let mut stream = provider(&server).chat_stream(user_request("Read main.rs and Cargo.toml")).await.unwrap();
+
+        let mut calls = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            if let DeltaChatMessage::Assistant { tool_calls: Some(tool_calls), .. } = &chunk.unwrap().choices[0].delta {
+                calls.extend(tool_calls.clone());
+            }
+        }
+
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].index, Some(0));
+        assert_eq!(calls[1].index, Some(1));
+        assert_ne!(calls[0].id, calls[1].id);
+        assert_eq!(calls[1].function.name.as_deref(), Some("read_file"));
+        let arguments: Value = serde_json::from_str(calls[1].function.arguments.as_deref().unwrap()).unwrap();
+        assert_eq!(arguments, json!({ "path": "Cargo.toml" }));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_keeps_retry_after() {
+        let body = r#"{"error": {"code": 429, "message": "Resource has been exhausted (e.g. check quota).", "status": "RESOURCE_EXHAUSTED"}}"#;
+        let server = FixtureServer::start(429, "Retry-After: 5\r\n", "application/json", body).await;
+
+        let error = provider(&server).chat(user_request("Hello")).await.unwrap_err();
+        let http_error = error.downcast_ref::<HttpError>().expect("expected an http error");
+        assert_eq!(http_error.status, 429);
+        assert_eq!(http_error.retry_after, Some(Duration::from_secs(5)));
+        assert!(http_error.message.contains("RESOURCE_EXHAUSTED"));
+    }
+
+    #[tokio::test]
+    async fn test_models() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/models.json")).await;
+        let models = provider(&server).models().await.unwrap();
+
+        let ids: Vec<String> = models.data.into_iter().map(|model| model.id).collect();
+        assert_eq!(ids, vec!["gemini-2.5-flash".to_string(), "gemini-2.5-pro".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_model_capabilities() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/models.json")).await;
+        let capabilities = provider(&server).model_capabilities().await.unwrap();
+
+        assert!(!capabilities.contains_key("text-embedding-004"));
+        let flash = &capabilities["gemini-2.5-flash"];
+        assert_eq!(flash.context_window, Some(1_048_576));
+        assert_eq!(flash.max_output_tokens, Some(65_536));
+        assert_eq!(flash.reasoning, Some(true));
+    }
+
+    #[test]
+    fn test_conversation_with_parallel_tool_calls() {
+        let provider = GeminiProvider::new("test-key".to_string());
+        let call = |id: &str, path: &str| ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: Function {
+                name: "read_file".to_string(),
+                arguments: json!({ "path": path }).to_string(),
+            },
+        };
+        let request = ChatCompletionParametersBuilder::default()
+            .model("gemini-2.5-pro")
+            .messages(vec![
+                ChatMessage::User {
+                    content: ChatMessageContent::Text("Read a.rs and b.rs".to_string()),
+                    name: None,
+                },
+                ChatMessage::Assistant {
+                    content: None,
+                    reasoning_content: None,
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: Some(vec![call("call_a", "a.rs"), call("call_b", "b.rs")]),
+                },
+                ChatMessage::Tool { content: "fn a() {}".to_string(), tool_call_id: "call_a".to_string() },
+                ChatMessage::Tool { content: "fn b() {}".to_string(), tool_call_id: "call_b".to_string() },
+            ])
+            .tool_choice(ChatCompletionToolChoice::Required)
+            .tools(vec![read_file_tool()])
+            .build()
+            .unwrap();
+
+        let body = provider.convert_to_gemini_format(&request);
+        assert_eq!(body["contents"], json!([
+            { "role": "user", "parts": [{ "text": "Read a.rs and b.rs" }] },
+            { "role": "model", "parts": [
+                { "functionCall": { "name": "read_file", "args": { "path": "a.rs" } } },
+                { "functionCall": { "name": "read_file", "args": { "path": "b.rs" } } }
+            ] },
+            { "role": "user", "parts": [
+                { "functionResponse": { "name": "read_file", "response": { "content": "fn a() {}" } } },
+                { "functionResponse": { "name": "read_file", "response": { "content": "fn b() {}" } } }
+            ] }
+        ]));
+        assert_eq!(body["toolConfig"], json!({ "functionCallingConfig": { "mode": "ANY" } }));
+        assert!(body.get("systemInstruction").is_none());
+    }
+
+    #[test]
+    fn test_structured_output_schema() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        enum Step {
+            Read { path: String },
+            Done,
+        }
+
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Plan {
+            /// steps to run in order
+            steps: Vec<Step>,
+            retries: Option<u32>,
+        }
+
+        let schema = serde_json::to_value(schemars::schema_for!(Plan)).unwrap();
+        let sanitized = sanitize_schema(&schema);
+        let text = sanitized.to_string();
+        assert!(!text.contains("$ref") && !text.contains("$defs") && !text.contains("$schema"), "{}", text);
+        assert!(!text.contains("additionalProperties") && !text.contains("uint32"), "{}", text);
+        assert_eq!(sanitized["properties"]["retries"]["nullable"], true);
+        assert_eq!(sanitized["properties"]["steps"]["type"], "array");
+        assert_eq!(sanitized["properties"]["steps"]["description"], "steps to run in order");
+
+        let provider = GeminiProvider::new("test-key".to_string());
+        let request = ChatCompletionParametersBuilder::default()
+            .model("gemini-2.5-flash")
+            .messages(vec![ChatMessage::User {
+                content: ChatMessageContent::Text("Plan the work".to_string()),
+                name: None,
+            }])
+            .response_format(ChatCompletionResponseFormat::JsonSchema {
+                json_schema: JsonSchemaBuilder::default()
+                    .name("plan")
+                    .schema(schema)
+                    .strict(true)
+                    .build()
+                    .unwrap(),
+            })
+            .build()
+            .unwrap();
+
+        let body = provider.convert_to_gemini_format(&request);
+        assert_eq!(body["generationConfig"]["responseMimeType"], "application/json");
+        assert_eq!(body["generationConfig"]["responseSchema"], sanitized);
+    }
+
+    #[test]
+    fn test_images_are_inline_data() {
+        let provider = GeminiProvider::new("test-key".to_string());
+        let png = crate::image::image_part(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]).unwrap();
+        let mut request = user_request("");
+        request.messages[1] = ChatMessage::User {
+            content: crate::image::user_content("What is wrong here?".to_string(), vec![png]),
+            name: None,
+        };
+
+        let body = provider.convert_to_gemini_format(&request);
+        assert_eq!(body["contents"][0]["parts"][0], json!({ "text": "What is wrong here?" }));
+        assert_eq!(body["contents"][0]["parts"][1]["inlineData"]["mimeType"], "image/png");
+        assert_eq!(body["contents"][0]["parts"][1]["inlineData"]["data"], "iVBORw0KGgo=");
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Serialize, Deserialize};
+
+// Mistral chat completion response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralResponse {
+    pub id: Option<String>,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub created: u32,
+    #[serde(default)]
+    pub choices: Vec<MistralChoice>,
+    pub usage: Option<MistralUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralChoice {
+    pub message: MistralMessage,
+    pub finish_reason: Option<String>,
+}
+
+// Streamed chunk, same shape as the response with a delta instead of the message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralChunk {
+    pub id: Option<String>,
+    pub model: Option<String>,
+    #[serde(default)]
+    pub created: u32,
+    #[serde(default)]
+    pub choices: Vec<MistralChunkChoice>,
+    pub usage: Option<MistralUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralChunkChoice {
+    pub delta: MistralMessage,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MistralMessage {
+    /// a string, or a list of chunks for reasoning models (text and thinking)
+    #[serde(default)]
+    pub content: serde_json::Value,
+    pub tool_calls: Option<Vec<MistralToolCall>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralToolCall {
+    pub id: Option<String>,
+    pub index: Option<u32>,
+    pub function: MistralFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralFunction {
+    pub name: String,
+    /// usually a json string, sometimes an object
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MistralUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MistralModelCapabilities {
+    pub completion_chat: bool,
+    pub function_calling: bool,
+    pub vision: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralModel {
+    pub id: String,
+    #[serde(default)]
+    pub created: i64,
+    #[serde(default)]
+    pub owned_by: String,
+    #[serde(default)]
+    pub capabilities: MistralModelCapabilities,
+    pub max_context_length: Option<u32>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub deprecation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralListModelResponse {
+    #[serde(default)]
+    pub data: Vec<MistralModel>,
+}
+
+pub const MISTRAL_API_BASE: &str = "https://api.mistral.ai/v1";
@@ -0,0 +1,599 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use crate::capabilities::ModelCapabilities;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar, HttpError};
+use crate::providers::sse::sse_events;
+use crate::providers::embeddings::openai_embeddings;
+use crate::image;
+use super::api::*;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use futures::StreamExt;
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatMessage, DeltaChatMessage, ChatMessageContent, ChatMessageContentPart, ChatCompletionChoice, ChatCompletionChunkChoice, DeltaToolCall, DeltaFunction, ToolCall, Function},
+    model::{ListModelResponse, Model},
+    shared::{FinishReason, Usage},
+};
+
+/// Model used when none is configured
+const DEFAULT_MODEL: &str = "mistral-small-latest";
+
+const CALL_ID_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Native Mistral API adapter. The request is built from the Mistral schema (unknown
+/// fields are rejected) and the conversation is made valid for its message order rules.
+pub struct MistralProvider {
+    api_key: String,
+    base_url: String,
+    client: Client,
+}
+
+impl MistralProvider {
+    pub fn new(api_key: String) -> Self {
+        Self::with_base_url(api_key, MISTRAL_API_BASE.to_string())
+    }
+
+    pub fn with_base_url(api_key: String, base_url: String) -> Self {
+        Self {
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Create Mistral provider from environment variables
+    /// Returns None if required environment variables are not set
+    pub fn from_env() -> Option<Self> {
+        std::env::var("MISTRAL_API_KEY")
+            .ok()
+            .map(|api_key| Self::new(api_key))
+    }
+
+    /// Models of the account, in the native format
+    pub async fn mistral_models(&self) -> Result<Vec<MistralModel>, LlmError> {
+        let response = self.client
+            .get(format!("{}/models", self.base_url))
+            .bearer_auth(&self.api_key)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Box::new(HttpError::from_response("Mistral API error", response).await));
+        }
+
+        let mistral_response: MistralListModelResponse = response.json().await?;
+        Ok(mistral_response.data)
+    }
+
+    async fn post(&self, body: &Value) -> Result<reqwest::Response, LlmError> {
+        let response = self.client
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Box::new(HttpError::from_response("Mistral API error", response).await));
+        }
+        Ok(response)
+    }
+
+    fn parse_mistral_stream(response: reqwest::Response) -> LlmStream {
+        let stream = async_stream::stream! {
+            let mut events = Box::pin(sse_events(response));
+            let mut state = MistralStreamState::default();
+            while let Some(event) = events.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                };
+                let data = event.data.trim();
+                if data == "[DONE]" {
+                    break;
+                }
+                if data.is_empty() {
+                    continue;
+                }
+
+                match serde_json::from_str::<MistralChunk>(data) {
+                    Ok(chunk) => yield Ok(state.convert(chunk)),
+                    Err(e) => {
+                        yield Err(LlmError::from(format!("Failed to parse Mistral event {}. Error: {}", data, e)));
+                        break;
+                    }
+                }
+            }
+        };
+
+        Box::new(Box::pin(stream))
+    }
+
+    pub(crate) fn convert_to_mistral_format(&self, request: &ChatCompletionParameters) -> Value {
+        let mut mistral_request = json!({
+            "model": request.model,
+            "messages": self.convert_messages(&request.messages),
+        });
+
+        if let Some(tools) = request.tools.as_ref().filter(|tools| !tools.is_empty()) {
+            mistral_request["tools"] = json!(tools.iter().map(|tool| json!({
+                "type": "function",
+                "function": {
+                    "name": tool.function.name,
+                    "description": tool.function.description.as_ref().unwrap_or(&tool.function.name),
+                    "parameters": tool.function.parameters,
+                }
+            })).collect::<Vec<_>>());
+        }
+
+        // optional parameters are read from their openai serialization
+        let params = serde_json::to_value(request).unwrap_or_default();
+
+        // max_completion_tokens replaces the deprecated max_tokens
+        for (from, to) in [
+            ("temperature", "temperature"),
+            ("top_p", "top_p"),
+            ("seed", "random_seed"),
+            ("stop", "stop"),
+            ("parallel_tool_calls", "parallel_tool_calls"),
+            ("max_tokens", "max_tokens"),
+            ("max_completion_tokens", "max_tokens"),
+        ] {
+            if let Some(value) = params.get(from).filter(|value| !value.is_null()) {
+                mistral_request[to] = value.clone();
+            }
+        }
+
+        // mistral uses "any" instead of "required" to force tool usage
+        match &params["tool_choice"] {
+            Value::String(choice) if choice == "required" => mistral_request["tool_choice"] = json!("any"),
+            Value::Null => {}
+            choice => mistral_request["tool_choice"] = choice.clone(),
+        }
+
+        if matches!(params["response_format"]["type"].as_str(), Some("json_object" | "json_schema")) {
+            mistral_request["response_format"] = params["response_format"].clone();
+        }
+
+        mistral_request
+    }
+
+    /// Mistral rejects a user message right after a tool result, tool calls without
+    /// results (cancelled by the user) and tool call ids that are not 9 alphanumeric
+    /// characters. The conversation is adapted without adding messages: unanswered
+    /// calls are dropped, consecutive user messages are merged, and a user message
+    /// following tool results is appended to the last result. Images cannot be part of
+    /// a tool result, they are sent after a short assistant message.
+    fn convert_messages(&self, messages: &[ChatMessage]) -> Vec<Value> {
+        let answered: HashSet<&str> = messages.iter()
+            .filter_map(|msg| match msg {
+                ChatMessage::Tool { tool_call_id, .. } => Some(tool_call_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        // function name of each call, also used to drop the results of unknown calls
+        let mut call_names: HashMap<String, String> = HashMap::new();
+        let mut converted: Vec<Value> = Vec::new();
+
+        for msg in messages {
+            match msg {
+                ChatMessage::System { content, .. } => {
+                    let text = self.extract_content_text(content);
+                    Self::push_text(&mut converted, "system", text);
+                }
+                ChatMessage::User { content, .. } | ChatMessage::Developer { content, .. } => {
+                    let text = self.extract_content_text(content);
+                    let images = image::content_images(content);
+                    if !images.is_empty() {
+                        Self::push_images(&mut converted, text, &images);
+                        continue;
+                    }
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match converted.last_mut() {
+                        Some(last) if last["role"] == "tool" => match last["content"].as_array_mut() {
+                            Some(chunks) => chunks.push(json!({ "type": "text", "text": text })),
+                            None => {
+                                let merged = format!("{}\n\n{}", last["content"].as_str().unwrap_or_default(), text);
+                                last["content"] = json!(merged);
+                            }
+                        },
+                        _ => Self::push_text(&mut converted, "user", text),
+                    }
+                }
+                ChatMessage::Assistant { content, tool_calls, .. } => {
+                    let text = content.as_ref().map(|c| self.extract_content_text(c)).unwrap_or_default();
+                    let calls: Vec<Value> = tool_calls.iter().flatten()
+                        .filter(|call| answered.contains(call.id.as_str()))
+                        .map(|call| {
+                            let id = mistral_call_id(&call.id);
+                            call_names.insert(id.clone(), call.function.name.clone());
+                            json!({
+                                "id": id,
+                                "type": "function",
+                                "function": { "name": call.function.name, "arguments": call.function.arguments }
+                            })
+                        })
+                        .collect();
+
+                    if calls.is_empty() {
+                        if !text.is_empty() {
+                            Self::push_text(&mut converted, "assistant", text);
+                        }
+                    } else {
+                        converted.push(json!({
+                            "role": "assistant",
+                            "content": text,
+                            "tool_calls": calls,
+                        }));
+                    }
+                }
+                ChatMessage::Tool { content, tool_call_id, .. } => {
+                    let id = mistral_call_id(tool_call_id);
+                    let Some(name) = call_names.get(&id) else {
+                        continue; // the call is not in the conversation anymore
+                    };
+                    converted.push(json!({
+                        "role": "tool",
+                        "tool_call_id": id,
+                        "name": name,
+                        "content": content,
+                    }));
+                }
+            }
+        }
+
+        // the conversation must end with a user or tool message, a final assistant
+        // message is sent as a prefix that the model continues
+        if let Some(last) = converted.last_mut() {
+            if last["role"] == "assistant" && last.get("tool_calls").is_none() {
+                last["prefix"] = json!(true);
+            }
+        }
+
+        converted
+    }
+
+    /// Push a text message, merged into the previous one if it has the same role
+    fn push_text(converted: &mut Vec<Value>, role: &str, text: String) {
+        match converted.last_mut() {
+            Some(last) if last["role"] == role && last["content"].is_array() => {
+                if let Some(parts) = last["content"].as_array_mut() {
+                    parts.push(json!({ "type": "text", "text": text }));
+                }
+            }
+            Some(last) if last["role"] == role && last.get("tool_calls").is_none() => {
+                let merged = format!("{}\n\n{}", last["content"].as_str().unwrap_or_default(), text);
+                last["content"] = json!(merged);
+            }
+            _ => converted.push(json!({ "role": role, "content": text })),
+        }
+    }
+
+    /// Push a user message holding images, merged into the previous user message if any.
+    /// After a tool result the images are chunks of the tool message, as text is.
+    fn push_images(converted: &mut Vec<Value>, text: String, images: &[&str]) {
+        let mut parts: Vec<Value> = Vec::new();
+        if !text.is_empty() {
+            parts.push(json!({ "type": "text", "text": text }));
+        }
+        parts.extend(images.iter().map(|url| json!({ "type": "image_url", "image_url": url })));
+
+        match converted.last_mut() {
+            Some(last) if last["role"] == "user" => {
+                let mut merged = match &last["content"] {
+                    Value::Array(previous) => previous.clone(),
+                    previous => vec![json!({ "type": "text", "text": previous.as_str().unwrap_or_default() })],
+                };
+                merged.extend(parts);
+                last["content"] = json!(merged);
+            }
+            Some(last) if last["role"] == "tool" => {
+                let mut chunks = match &last["content"] {
+                    Value::Array(previous) => previous.clone(),
+                    previous => vec![json!({ "type": "text", "text": previous.as_str().unwrap_or_default() })],
+                };
+                chunks.extend(parts);
+                last["content"] = json!(chunks);
+            }
+            _ => converted.push(json!({ "role": "user", "content": parts })),
+        }
+    }
+
+    fn extract_content_text(&self, content: &ChatMessageContent) -> String {
+        match content {
+            ChatMessageContent::Text(text) => text.clone(),
+            ChatMessageContent::ContentPart(parts) => {
+                parts.iter().filter_map(|part| {
+                    match part {
+                        ChatMessageContentPart::Text(text_part) => Some(text_part.text.clone()),
+                        _ => None, // Skip images, audio, etc.
+                    }
+                }).collect::<Vec<_>>().join(" ")
+            }
+            ChatMessageContent::None => String::new(),
+        }
+    }
+
+    pub(crate) fn convert_from_mistral_format(&self, response: MistralResponse, model: &str) -> Result<ChatCompletionResponse, LlmError> {
+        let choice = response.choices.into_iter().next()
+            .ok_or("Mistral returned no choice")?;
+
+        let (text, reasoning) = convert_content(&choice.message.content);
+        let tool_calls: Vec<ToolCall> = choice.message.tool_calls.unwrap_or_default()
+            .into_iter()
+            .map(|call| ToolCall {
+                id: call.id.filter(|id| !id.is_empty()).unwrap_or_else(new_call_id),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: call.function.name,
+                    arguments: convert_arguments(call.function.arguments),
+                },
+            })
+            .collect();
+        let finish_reason = convert_finish_reason(choice.finish_reason.as_deref())
+            .or(Some(FinishReason::StopSequenceReached));
+
+        Ok(ChatCompletionResponse {
+            id: response.id,
+            object: "chat.completion".to_string(),
+            created: response.created,
+            model: response.model.unwrap_or_else(|| model.to_string()),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: (!text.is_empty()).then(|| ChatMessageContent::Text(text)),
+                    reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                },
+                finish_reason,
+                logprobs: None,
+            }],
+            usage: response.usage.as_ref().and_then(convert_usage),
+            service_tier: None,
+            system_fingerprint: None,
+        })
+    }
+}
+
+/// Mistral only accepts tool call ids of 9 alphanumeric characters. The ids of other
+/// providers (after a fallback or a resumed session) are replaced by a stable hash so
+/// that the calls and their results still match.
+pub(crate) fn mistral_call_id(id: &str) -> String {
+    if id.len() == 9 && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return id.to_string();
+    }
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    id.hash(&mut hasher);
+    let mut value = hasher.finish();
+    (0..9).map(|_| {
+        let c = CALL_ID_ALPHABET[(value % CALL_ID_ALPHABET.len() as u64) as usize];
+        value /= CALL_ID_ALPHABET.len() as u64;
+        c as char
+    }).collect()
+}
+
+fn new_call_id() -> String {
+    mistral_call_id(&uuid::Uuid::new_v4().to_string())
+}
+
+/// Text and thinking of a message, reasoning models send a list of chunks
+fn convert_content(content: &Value) -> (String, String) {
+    let mut text = String::new();
+    let mut reasoning = String::new();
+    match content {
+        Value::String(content) => text.push_str(content),
+        Value::Array(chunks) => {
+            for chunk in chunks {
+                match chunk["type"].as_str() {
+                    Some("text") => text.push_str(chunk["text"].as_str().unwrap_or_default()),
+                    Some("thinking") => {
+                        for thought in chunk["thinking"].as_array().into_iter().flatten() {
+                            reasoning.push_str(thought["text"].as_str().unwrap_or_default());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    (text, reasoning)
+}
+
+fn convert_arguments(arguments: Value) -> String {
+    match arguments {
+        Value::String(arguments) if !arguments.trim().is_empty() => arguments,
+        Value::Object(_) => arguments.to_string(),
+        _ => "{}".to_string(),
+    }
+}
+
+fn convert_finish_reason(reason: Option<&str>) -> Option<FinishReason> {
+    let reason = match reason? {
+        "tool_calls" => "tool_calls",
+        "length" | "model_length" => "length",
+        _ => "stop",
+    };
+    serde_json::from_value(json!(reason)).ok()
+}
+
+fn convert_usage(usage: &MistralUsage) -> Option<Usage> {
+    serde_json::from_value(json!({
+        "prompt_tokens": usage.prompt_tokens,
+        "completion_tokens": usage.completion_tokens,
+        "total_tokens": usage.total_tokens,
+    })).ok()
+}
+
+/// Converts the streamed chunks. Mistral sends each tool call whole in a single delta,
+/// sometimes without index, so the calls are numbered along the stream.
+#[derive(Debug, Default)]
+pub(crate) struct MistralStreamState {
+    tool_calls: u32,
+}
+
+impl MistralStreamState {
+    pub(crate) fn convert(&mut self, chunk: MistralChunk) -> ChatCompletionChunkResponse {
+        let usage = chunk.usage.as_ref().and_then(convert_usage);
+        let (delta, finish_reason) = match chunk.choices.into_iter().next() {
+            Some(choice) => {
+                let (text, reasoning) = convert_content(&choice.delta.content);
+                let tool_calls: Vec<DeltaToolCall> = choice.delta.tool_calls.unwrap_or_default()
+                    .into_iter()
+                    .map(|call| {
+                        // a delta without id continues the previous call
+                        let index = match call.id.as_ref().filter(|id| !id.is_empty() && *id != "null") {
+                            Some(_) => {
+                                self.tool_calls += 1;
+                                self.tool_calls - 1
+                            }
+                            None => self.tool_calls.saturating_sub(1),
+                        };
+                        DeltaToolCall {
+                            index: Some(index),
+                            id: call.id.filter(|id| !id.is_empty() && id != "null"),
+                            r#type: Some("function".to_string()),
+                            function: DeltaFunction {
+                                name: Some(call.function.name).filter(|name| !name.is_empty()),
+                                arguments: Some(match call.function.arguments {
+                                    Value::String(arguments) => arguments,
+                                    Value::Null => String::new(),
+                                    arguments => arguments.to_string(),
+                                }),
+                            },
+                        }
+                    })
+                    .collect();
+
+                let delta = DeltaChatMessage::Assistant {
+                    content: (!text.is_empty()).then(|| ChatMessageContent::Text(text)),
+                    reasoning_content: (!reasoning.is_empty()).then_some(reasoning),
+                    refusal: None,
+                    name: None,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                };
+                (delta, convert_finish_reason(choice.finish_reason.as_deref()))
+            }
+            None => (DeltaChatMessage::Assistant {
+                content: None,
+                reasoning_content: None,
+                refusal: None,
+                name: None,
+                tool_calls: None,
+            }, None),
+        };
+
+        ChatCompletionChunkResponse {
+            id: chunk.id,
+            object: "chat.completion.chunk".to_string(),
+            created: chunk.created,
+            model: chunk.model.unwrap_or_default(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta,
+                finish_reason,
+                logprobs: None,
+            }],
+            usage,
+            system_fingerprint: None,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MistralProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        // Filter models that support function calling and convert to OpenAI format
+        let models = self.mistral_models().await?
+            .into_iter()
+            .filter(|model| model.capabilities.function_calling && model.deprecation.is_none())
+            .map(|model| Model {
+                id: model.id,
+                object: "model".to_string(),
+                created: Some(model.created.try_into().unwrap_or(0)),
+                owned_by: model.owned_by,
+            })
+            .collect();
+
+        Ok(ListModelResponse {
+            object: "list".to_string(),
+            data: models,
+        })
+    }
+
+    async fn model_capabilities(&self) -> Result<HashMap<String, ModelCapabilities>, LlmError> {
+        let mut capabilities = HashMap::new();
+        for model in self.mistral_models().await?.into_iter().filter(|model| model.capabilities.completion_chat) {
+            let known = ModelCapabilities {
+                context_window: model.max_context_length,
+                tools: Some(model.capabilities.function_calling),
+                vision: Some(model.capabilities.vision),
+                ..Default::default()
+            };
+            for alias in &model.aliases {
+                capabilities.insert(alias.clone(), known.clone());
+            }
+            capabilities.insert(model.id, known);
+        }
+        Ok(capabilities)
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        Ok(DEFAULT_MODEL.to_string())
+    }
+
+    fn default_model_among(&self, _models: &[String]) -> Option<String> {
+        Some(DEFAULT_MODEL.to_string())
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let mistral_request = self.convert_to_mistral_format(&request);
+        let response = self.post(&mistral_request).await?;
+
+        let mistral_response: MistralResponse = response.json().await?;
+        self.convert_from_mistral_format(mistral_response, &request.model)
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let mut mistral_request = self.convert_to_mistral_format(&request);
+        mistral_request["stream"] = json!(true);
+        let response = self.post(&mistral_request).await?;
+
+        Ok(Self::parse_mistral_stream(response))
+    }
+
+    /// The embeddings endpoint has the OpenAI format
+    async fn embeddings(&self, model: &str, input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        openai_embeddings(&self.client, &self.base_url, &self.api_key, model, input).await
+    }
+
+    fn name(&self) -> &'static str {
+        "mistral"
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "mistral",
+            display_name: "Mistral AI (Mixtral, Pixtral)",
+            env_vars: vec![
+                EnvVar::required("MISTRAL_API_KEY", "Mistral AI API key"),
+            ],
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod api;
+pub mod mistral_native;
+pub mod tests;
+
+pub use mistral_native::MistralProvider;
@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use openai_dive::v1::resources::chat::{
+        ChatCompletionFunction, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionTool,
+        ChatCompletionToolChoice, ChatCompletionToolType, ChatMessage, ChatMessageContent, DeltaChatMessage,
+        Function, ToolCall,
+    };
+    use crate::provider::{LlmProvider, HttpError};
+    use crate::providers::mistral_native::MistralProvider;
+    use crate::providers::mistral_native::mistral_native::mistral_call_id;
+    use crate::providers::fixture::FixtureServer;
+
+    fn provider(server: &FixtureServer) -> MistralProvider {
+        MistralProvider::with_base_url("test-key".to_string(), format!("{}/v1", server.base_url))
+    }
+
+    fn read_file_tool() -> ChatCompletionTool {
+        ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function: ChatCompletionFunction {
+                name: "read_file".to_string(),
+                description: Some("Read a file from the filesystem".to_string()),
+                parameters: json!({
+                    "type": "object",
+                    "properties": { "path": { "type": "string" } },
+                    "required": ["path"]
+                }),
+            },
+        }
+    }
+
+    fn user(text: &str) -> ChatMessage {
+        ChatMessage::User {
+            content: ChatMessageContent::Text(text.to_string()),
+            name: None,
+        }
+    }
+
+    fn assistant(text: Option<&str>, tool_calls: Vec<ToolCall>) -> ChatMessage {
+        ChatMessage::Assistant {
+            content: text.map(|text| ChatMessageContent::Text(text.to_string())),
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+        }
+    }
+
+    fn call(id: &str, path: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: Function {
+                name: "read_file".to_string(),
+                arguments: json!({ "path": path }).to_string(),
+            },
+        }
+    }
+
+    fn tool(id: &str, content: &str) -> ChatMessage {
+        ChatMessage::Tool { content: content.to_string(), tool_call_id: id.to_string() }
+    }
+
+    fn request(messages: Vec<ChatMessage>) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("mistral-small-latest")
+            .messages(messages)
+            .tools(vec![read_file_tool()])
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat_tool_call() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/chat_tool_call.json")).await;
+        let mut params = request(vec![user("Show me main.rs")]);
+        params.max_completion_tokens = Some(256);
+        params.seed = Some(7);
+
+        let response = provider(&server).chat(params).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/v1/chat/completions");
+        assert!(requests[0].head.to_lowercase().contains("authorization: bearer test-key"));
+        let body = &requests[0].body;
+        assert_eq!(body["messages"], json!([{ "role": "user", "content": "Show me main.rs" }]));
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["random_seed"], 7);
+        assert!(body.get("max_completion_tokens").is_none() && body.get("seed").is_none());
+        assert_eq!(body["tools"][0]["function"]["name"], "read_file");
+
+        let ChatMessage::Assistant { content, tool_calls: Some(tool_calls), .. } = &response.choices[0].message else {
+            panic!("expected tool calls: {:?}", response.choices[0].message);
+        };
+        assert!(content.is_none());
+        assert_eq!(tool_calls[0].id, "D681PevKs");
+        assert_eq!(tool_calls[0].function.arguments, r#"{"path": "src/main.rs"}"#);
+        let finish_reason = serde_json::to_value(&response.choices[0].finish_reason).unwrap();
+        assert_eq!(finish_reason, "tool_calls");
+        assert_eq!(response.usage.unwrap().completion_tokens, Some(23));
+    }
+
+    #[tokio::test]
+    async fn test_stream_tool_calls() {
+        let server = FixtureServer::start(200, "", "text/event-stream", include_str!("fixtures/stream_tool_call.sse")).await;
+        let mut stream = provider(&server).chat_stream(request(vec![user("Read main.rs and Cargo.toml")])).await.unwrap();
+
+        let mut text = String::new();
+        let mut calls = Vec::new();
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            if let DeltaChatMessage::Assistant { content, tool_calls, .. } = &chunk.choices[0].delta {
+                if let Some(ChatMessageContent::Text(delta)) = content {
+                    text.push_str(delta);
+                }
+                calls.extend(tool_calls.clone().unwrap_or_default());
+            }
+            chunks.push(chunk);
+        }
+
+        assert_eq!(server.requests()[0].body["stream"], true);
+        assert_eq!(text, "I will read both files.");
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].index, Some(0));
+        assert_eq!(calls[1].index, Some(1));
+        assert_eq!(calls[1].id.as_deref(), Some("p8RfK2sWz"));
+        let arguments: Value = serde_json::from_str(calls[1].function.arguments.as_deref().unwrap()).unwrap();
+        assert_eq!(arguments, json!({ "path": "Cargo.toml" }));
+        let last = chunks.last().unwrap();
+        assert!(last.choices[0].finish_reason.is_some());
+        assert_eq!(last.usage.as_ref().unwrap().prompt_tokens, Some(118));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_is_http_error() {
+        let body = r#"{"object": "error", "message": "Requests rate limit exceeded", "type": "rate_limited", "code": "1300"}"#;
+        let server = FixtureServer::start(429, "", "application/json", body).await;
+
+        let error = provider(&server).chat(request(vec![user("Hello")])).await.unwrap_err();
+        let http_error = error.downcast_ref::<HttpError>().expect("expected an http error");
+        assert_eq!(http_error.status, 429);
+        assert!(http_error.message.contains("rate limit"));
+    }
+
+    #[tokio::test]
+    async fn test_models() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/models.json")).await;
+        let models = provider(&server).models().await.unwrap();
+
+        let ids: Vec<String> = models.data.into_iter().map(|model| model.id).collect();
+        assert_eq!(ids, vec!["mistral-small-latest".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_model_capabilities() {
+        let server = FixtureServer::start(200, "", "application/json", include_str!("fixtures/models.json")).await;
+        let capabilities = provider(&server).model_capabilities().await.unwrap();
+
+        assert!(!capabilities.contains_key("mistral-embed"));
+        let small = &capabilities["mistral-small-2506"];
+        assert_eq!(small.context_window, Some(131_072));
+        assert_eq!(small.tools, Some(true));
+        assert_eq!(small.vision, Some(true));
+        assert_eq!(capabilities["open-mixtral-8x7b"].vision, Some(false));
+    }
+
+    #[test]
+    fn test_call_ids() {
+        assert_eq!(mistral_call_id("D681PevKs"), "D681PevKs");
+        let id = mistral_call_id("toolu_01A09q90qw90lq917835lq9");
+        assert_eq!(id.len(), 9);
+        assert!(id.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(id, mistral_call_id("toolu_01A09q90qw90lq917835lq9"));
+        assert_ne!(id, mistral_call_id("toolu_01A09q90qw90lq917835lq8"));
+    }
+
+    #[test]
+    fn test_conversation_follows_mistral_rules() {
+        let provider = MistralProvider::new("test-key".to_string());
+        let mut params = request(vec![
+            ChatMessage::System {
+                content: ChatMessageContent::Text("You are a coding assistant.".to_string()),
+                name: None,
+            },
+            user("Read a.rs and b.rs"),
+            // b.rs was cancelled by the user, it has no result
+            assistant(None, vec![call("call_a", "a.rs"), call("call_b", "b.rs")]),
+            tool("call_a", "fn a() {}"),
+            user("Skip b.rs"),
+            user("and summarize"),
+            assistant(Some("a.rs defines"), vec![]),
+        ]);
+        params.tool_choice = Some(ChatCompletionToolChoice::Required);
+
+        let body = provider.convert_to_mistral_format(&params);
+        let id = mistral_call_id("call_a");
+        assert_eq!(body["messages"], json!([
+            { "role": "system", "content": "You are a coding assistant." },
+            { "role": "user", "content": "Read a.rs and b.rs" },
+            { "role": "assistant", "content": "", "tool_calls": [
+                { "id": id, "type": "function", "function": { "name": "read_file", "arguments": "{\"path\":\"a.rs\"}" } }
+            ] },
+            { "role": "tool", "tool_call_id": id, "name": "read_file", "content": "fn a() {}\n\nSkip b.rs\n\nand summarize" },
+            { "role": "assistant", "content": "a.rs defines", "prefix": true }
+        ]));
+        assert_eq!(body["tool_choice"], "any");
+    }
+
+    #[test]
+    fn test_cancelled_calls_are_dropped() {
+        let provider = MistralProvider::new("test-key".to_string());
+        let params = request(vec![
+            user("Read a.rs"),
+            assistant(Some("Reading a.rs"), vec![call("call_a", "a.rs")]),
+            user("Never mind, hello"),
+        ]);
+
+        let body = provider.convert_to_mistral_format(&params);
+        assert_eq!(body["messages"], json!([
+            { "role": "user", "content": "Read a.rs" },
+            { "role": "assistant", "content": "Reading a.rs" },
+            { "role": "user", "content": "Never mind, hello" }
+        ]));
+    }
+
+    #[test]
+    fn test_images_after_tool_results() {
+        let provider = MistralProvider::new("test-key".to_string());
+        let png = crate::image::image_part(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]).unwrap();
+        let params = request(vec![
+            user("What is in shot.png?"),
+            assistant(None, vec![call("call_a", "shot.png")]),
+            tool("call_a", "Image attached below"),
+            ChatMessage::User { content: crate::image::user_content("Images returned by the tools:".to_string(), vec![png]), name: None },
+            user("Describe it"),
+        ]);
+
+        // no assistant turn is made up, the images are chunks of the tool result
+        let body = provider.convert_to_mistral_format(&params);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2]["role"], "tool");
+        assert_eq!(messages[2]["content"][0], json!({ "type": "text", "text": "Image attached below" }));
+        assert_eq!(messages[2]["content"][1], json!({ "type": "text", "text": "Images returned by the tools:" }));
+        assert_eq!(messages[2]["content"][2]["type"], "image_url");
+        assert!(messages[2]["content"][2]["image_url"].as_str().unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(messages[2]["content"][3], json!({ "type": "text", "text": "Describe it" }));
+    }
+}
@@ -4,8 +4,14 @@ pub mod openrouter;
 pub mod ovhcloud;
 pub mod anthropic;
 pub mod ollama;
-pub mod mistral;
-// pub mod mistral_native; // TODO: Complete implementation
+pub mod mistral_native;
+pub mod gemini;
+pub mod fallback;
+pub mod replay;
+pub mod sse;
+pub mod embeddings;
 
 #[cfg(test)]
 mod tests;
+#[cfg(test)]
+pub(crate) mod fixture;
@@ -1,11 +1,12 @@
 // llm/providers/ovhcloud.rs
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::providers::embeddings::openai_embeddings;
 use async_trait::async_trait;
 use futures::StreamExt;
 use openai_dive::v1::{
     api::Client,
     resources::{
-        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
+        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatCompletionStreamOptions},
         model::ListModelResponse,
     },
     error::APIError
@@ -25,6 +26,12 @@ impl OllamaProvider {
         Self { client }
     }
 
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.client.http_client = http_client;
+        self
+    }
+
     /// Create OVH Cloud provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env() -> Option<Self> {
@@ -43,14 +50,11 @@ impl LlmProvider for OllamaProvider {
         Ok(response)
     }
 
-    async fn default_model(&self) -> Result<String, LlmError> {
-        let models = self.models().await?; // Get the models
-    
-        models.data.iter()
-            .find(|m| m.id.to_lowercase().contains("smol"))
-            .or_else(|| models.data.first())
-            .map(|m| m.id.clone())
-            .ok_or_else(|| "no model available".into())
+    fn default_model_among(&self, models: &[String]) -> Option<String> {
+        models.iter()
+            .find(|id| id.to_lowercase().contains("smol"))
+            .or_else(|| models.first())
+            .cloned()
     }
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
@@ -61,6 +65,8 @@ impl LlmProvider for OllamaProvider {
 
     async fn chat_stream(&self, mut request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
         request.stream = Some(true);
+        // the usage of a streamed response is only sent when asked for
+        request.stream_options = Some(ChatCompletionStreamOptions { include_usage: Some(true) });
         
         let stream = self.client.chat().create_stream(request).await
             .map_err(|e| Box::new(e) as LlmError)?;
@@ -72,12 +78,8 @@ impl LlmProvider for OllamaProvider {
         Ok(Box::new(Box::pin(converted_stream)))
     }
 
-    fn supports_functions(&self, model: String) -> bool {
-        true
-    }
-
-    fn supports_structured_output(&self, model: String) -> bool {
-        true
+    async fn embeddings(&self, model: &str, input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        openai_embeddings(&self.client.http_client, &self.client.base_url, &self.client.api_key, model, input).await
     }
 
     fn name(&self) -> &'static str {
@@ -1,11 +1,12 @@
 // llm/providers/openai_compatible.rs
 use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use crate::providers::embeddings::openai_embeddings;
 use async_trait::async_trait;
 use futures::StreamExt;
 use openai_dive::v1::{
     api::Client,
     resources::{
-        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
+        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatCompletionStreamOptions},
         model::ListModelResponse,
     },
 };
@@ -21,6 +22,12 @@ impl OpenAICompatibleProvider {
         Self { client }
     }
 
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.client.http_client = http_client;
+        self
+    }
+
     /// Create OpenAI Compatible provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env() -> Option<Self> {
@@ -50,6 +57,8 @@ impl LlmProvider for OpenAICompatibleProvider {
     async fn chat_stream(&self, mut request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
         // Ensure streaming is enabled
         request.stream = Some(true);
+        // the usage of a streamed response is only sent when asked for
+        request.stream_options = Some(ChatCompletionStreamOptions { include_usage: Some(true) });
         
         let stream = self.client.chat().create_stream(request).await
             .map_err(|e| Box::new(e) as LlmError)?;
@@ -61,12 +70,8 @@ impl LlmProvider for OpenAICompatibleProvider {
         Ok(Box::new(Box::pin(converted_stream)))
     }
 
-    fn supports_functions(&self, model: String) -> bool {
-        true
-    }
-
-    fn supports_structured_output(&self, model: String) -> bool {
-        true
+    async fn embeddings(&self, model: &str, input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        openai_embeddings(&self.client.http_client, &self.client.base_url, &self.client.api_key, model, input).await
     }
 
     fn name(&self) -> &'static str {
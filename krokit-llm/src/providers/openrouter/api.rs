@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::capabilities::ModelCapabilities;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenRouterModelsResponse {
@@ -33,6 +34,10 @@ pub struct OpenRouterArchitecture {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenRouterTopProvider {
     pub is_moderated: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<i64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -63,6 +68,20 @@ impl OpenRouterModel {
             owned_by: "openrouter".to_string(),
         }
     }
+
+    /// Capabilities from the modalities and the supported parameters of the model
+    pub fn to_capabilities(&self) -> ModelCapabilities {
+        let supports = |param: &str| self.supported_parameters.as_ref()
+            .map(|params| params.iter().any(|p| p == param));
+        ModelCapabilities {
+            context_window: u32::try_from(self.context_length).ok().filter(|&n| n > 0),
+            max_output_tokens: self.top_provider.max_completion_tokens.and_then(|n| u32::try_from(n).ok()),
+            tools: supports("tools"),
+            json_schema: supports("structured_outputs"),
+            vision: Some(self.architecture.input_modalities.iter().any(|m| m == "image")),
+            reasoning: supports("reasoning"),
+        }
+    }
 }
 
 impl OpenRouterModelsResponse {
@@ -1,4 +1,6 @@
-use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar};
+use std::collections::HashMap;
+use crate::capabilities::ModelCapabilities;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, EnvVar, HttpError};
 use super::api::OpenRouterModelsResponse;
 use async_trait::async_trait;
 use futures::StreamExt;
@@ -6,7 +8,7 @@ use reqwest;
 use openai_dive::v1::{
     api::Client,
     resources::{
-        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
+        chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatCompletionStreamOptions},
         model::ListModelResponse,
     },
 };
@@ -32,6 +34,13 @@ impl OpenRouterProvider {
         }
     }
 
+    /// Use a client configured with the http settings (proxy, CA, timeouts)
+    pub fn with_http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.client.http_client = http_client.clone();
+        self.http_client = http_client;
+        self
+    }
+
     /// Create OpenRouter provider from environment variables
     /// Returns None if required environment variables are not set
     pub fn from_env() -> Option<Self> {
@@ -53,12 +62,7 @@ impl OpenRouterProvider {
             .map_err(|e| Box::new(e) as LlmError)?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                format!("OpenRouter API error {}: {}", status, text)
-            )) as LlmError);
+            return Err(Box::new(HttpError::from_response("OpenRouter API error", response).await));
         }
 
         let openrouter_response: OpenRouterModelsResponse = response
@@ -77,16 +81,19 @@ impl LlmProvider for OpenRouterProvider {
         Ok(openrouter_response.to_openai_models_response())
     }
 
+    async fn model_capabilities(&self) -> Result<HashMap<String, ModelCapabilities>, LlmError> {
+        let openrouter_response = self.openrouter_models().await?;
+        Ok(openrouter_response.data.iter()
+            .map(|model| (model.id.clone(), model.to_capabilities()))
+            .collect())
+    }
 
-    async fn default_model(&self) -> Result<String, LlmError> {
-        let models = self.models().await?; 
-    
-        let keywords = ["free"];
-        models.data.iter()
-        .find(|m| keywords.iter().any(|kw| m.id.to_lowercase().contains(kw)))
-            .or_else(|| models.data.first())
-            .map(|m| m.id.clone())
-            .ok_or_else(|| "no model available".into())
+
+    fn default_model_among(&self, models: &[String]) -> Option<String> {
+        models.iter()
+            .find(|id| id.to_lowercase().contains("free"))
+            .or_else(|| models.first())
+            .cloned()
     }
 
     async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
@@ -98,6 +105,8 @@ impl LlmProvider for OpenRouterProvider {
     async fn chat_stream(&self, mut request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
         // Ensure streaming is enabled
         request.stream = Some(true);
+        // the usage of a streamed response is only sent when asked for
+        request.stream_options = Some(ChatCompletionStreamOptions { include_usage: Some(true) });
         
         let stream = self.client.chat().create_stream(request).await
             .map_err(|e| Box::new(e) as LlmError)?;
@@ -109,14 +118,6 @@ impl LlmProvider for OpenRouterProvider {
         Ok(Box::new(Box::pin(converted_stream)))
     }
 
-    fn supports_functions(&self, model: String) -> bool {
-        true
-    }
-
-    fn supports_structured_output(&self, model: String) -> bool {
-        true
-    }
-
     fn name(&self) -> &'static str {
         "openrouter"
     }
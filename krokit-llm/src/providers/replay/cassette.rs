@@ -0,0 +1,227 @@
+// llm/providers/replay/cassette.rs
+use std::path::Path;
+use std::time::Duration;
+use crate::provider::{LlmError, HttpError};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse},
+    model::ListModelResponse,
+};
+
+/// Request fields that do not change the answer of the model
+const IGNORED_FIELDS: &[&str] = &["stream", "stream_options", "user"];
+
+/// Recorded session with a provider, stored as a json fixture file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    /// name of the recorded provider
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub models: Option<ListModelResponse>,
+    #[serde(default)]
+    pub interactions: Vec<Interaction>,
+}
+
+/// A request and what the provider answered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// normalized hash of the request, an interaction without hash matches any request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    /// normalized request, kept to investigate mismatches
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub request: Value,
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Response(ChatCompletionResponse),
+    Stream(Vec<RecordedChunk>),
+    Error(RecordedError),
+}
+
+/// Item of a recorded stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RecordedChunk {
+    Error { error: RecordedError },
+    Chunk(ChatCompletionChunkResponse),
+}
+
+/// Error of the provider, the http status is kept so that retries behave the same
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedError {
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+}
+
+impl From<&LlmError> for RecordedError {
+    fn from(error: &LlmError) -> Self {
+        match error.downcast_ref::<HttpError>() {
+            Some(http) => Self {
+                message: http.message.clone(),
+                status: Some(http.status),
+                retry_after_ms: http.retry_after.map(|delay| delay.as_millis() as u64),
+            },
+            None => Self {
+                message: error.to_string(),
+                status: None,
+                retry_after_ms: None,
+            },
+        }
+    }
+}
+
+impl RecordedError {
+    pub fn to_error(&self) -> LlmError {
+        match self.status {
+            Some(status) => Box::new(HttpError {
+                status,
+                retry_after: self.retry_after_ms.map(Duration::from_millis),
+                message: self.message.clone(),
+            }),
+            None => LlmError::from(self.message.clone()),
+        }
+    }
+}
+
+impl Cassette {
+    pub fn new(provider: &str) -> Self {
+        Self {
+            provider: provider.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LlmError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read fixture {}: {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| LlmError::from(format!("invalid fixture {}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), LlmError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Makes requests comparable between runs. System messages are left out, they hold
+/// the date, the platform and the working directory. The working directory and the
+/// redacted strings are replaced by placeholders in the requests and the recorded
+/// answers, the placeholders of the answers are restored when replayed.
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer {
+    redactions: Vec<(String, String)>,
+}
+
+impl Normalizer {
+    /// Normalizer redacting the current working directory
+    pub fn new() -> Self {
+        let normalizer = Self::default();
+        match std::env::current_dir() {
+            Ok(dir) => normalizer.redact(dir.to_string_lossy(), "$CWD"),
+            Err(_) => normalizer,
+        }
+    }
+
+    /// Replace a string that differs between runs (a temporary path for instance)
+    pub fn redact(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        let from = from.into();
+        if !from.is_empty() {
+            self.redactions.push((from, to.into()));
+            // longest first, a path can contain another one
+            self.redactions.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        }
+        self
+    }
+
+    pub fn normalize(&self, request: &ChatCompletionParameters) -> Value {
+        let mut value = serde_json::to_value(request).unwrap_or_default();
+        if let Some(fields) = value.as_object_mut() {
+            for field in IGNORED_FIELDS {
+                fields.remove(*field);
+            }
+        }
+        if let Some(messages) = value["messages"].as_array_mut() {
+            for message in messages.iter_mut().filter(|message| message["role"] == "system") {
+                message["content"] = Value::String(String::new());
+            }
+        }
+        sorted(map_strings(value, &|text| self.apply(text, false)))
+    }
+
+    /// Hash of a normalized request, stable across runs and platforms (fnv-1a)
+    pub fn hash(&self, normalized: &Value) -> String {
+        let hash = normalized.to_string().bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// Redact an answer before it is recorded. A string split between two stream
+    /// chunks is not redacted.
+    pub fn redact_outcome(&self, outcome: Outcome) -> Outcome {
+        self.map_outcome(outcome, false)
+    }
+
+    /// Restore the redacted strings of a recorded answer
+    pub fn restore_outcome(&self, outcome: Outcome) -> Outcome {
+        self.map_outcome(outcome, true)
+    }
+
+    fn map_outcome(&self, outcome: Outcome, restore: bool) -> Outcome {
+        if self.redactions.is_empty() {
+            return outcome;
+        }
+        match serde_json::to_value(&outcome) {
+            Ok(value) => serde_json::from_value(map_strings(value, &|text| self.apply(text, restore))).unwrap_or(outcome),
+            Err(_) => outcome,
+        }
+    }
+
+    fn apply(&self, mut text: String, restore: bool) -> String {
+        for (original, placeholder) in &self.redactions {
+            let (from, to) = if restore { (placeholder, original) } else { (original, placeholder) };
+            if text.contains(from.as_str()) {
+                text = text.replace(from.as_str(), to);
+            }
+        }
+        text
+    }
+}
+
+fn map_strings(value: Value, f: &dyn Fn(String) -> String) -> Value {
+    match value {
+        Value::String(text) => Value::String(f(text)),
+        Value::Array(items) => Value::Array(items.into_iter().map(|item| map_strings(item, f)).collect()),
+        Value::Object(fields) => Value::Object(fields.into_iter().map(|(key, value)| (key, map_strings(value, f))).collect()),
+        value => value,
+    }
+}
+
+/// Same value with the keys of the objects sorted
+fn sorted(value: Value) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.into_iter().map(sorted).collect()),
+        Value::Object(fields) => {
+            let mut fields: Vec<(String, Value)> = fields.into_iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(fields.into_iter().map(|(key, value)| (key, sorted(value))).collect::<Map<_, _>>())
+        }
+        value => value,
+    }
+}
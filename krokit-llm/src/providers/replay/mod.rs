@@ -0,0 +1,8 @@
+pub mod cassette;
+pub mod recording;
+pub mod replay;
+pub mod tests;
+
+pub use cassette::{Cassette, Interaction, Outcome, RecordedChunk, RecordedError, Normalizer};
+pub use recording::RecordingProvider;
+pub use replay::ReplayProvider;
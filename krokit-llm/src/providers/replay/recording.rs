@@ -0,0 +1,160 @@
+// llm/providers/replay/recording.rs
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use crate::capabilities::ModelCapabilities;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo};
+use super::cassette::{Cassette, Interaction, Outcome, RecordedChunk, RecordedError, Normalizer};
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde_json::Value;
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse},
+    model::ListModelResponse,
+};
+
+/// Wraps a provider and records its answers to a fixture file, to be served
+/// offline by a ReplayProvider. The file is written after each interaction.
+/// A stream is recorded once consumed entirely.
+pub struct RecordingProvider {
+    inner: Box<dyn LlmProvider>,
+    path: PathBuf,
+    normalizer: Normalizer,
+    cassette: Arc<Mutex<Cassette>>,
+}
+
+impl RecordingProvider {
+    /// Start a new recording, an existing fixture is overwritten
+    pub fn new(inner: Box<dyn LlmProvider>, path: impl Into<PathBuf>) -> Self {
+        let cassette = Cassette::new(inner.name());
+        Self {
+            inner,
+            path: path.into(),
+            normalizer: Normalizer::new(),
+            cassette: Arc::new(Mutex::new(cassette)),
+        }
+    }
+
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    fn request_key(&self, request: &ChatCompletionParameters) -> (String, Value) {
+        let normalized = self.normalizer.normalize(request);
+        (self.normalizer.hash(&normalized), normalized)
+    }
+
+    fn save(cassette: &Mutex<Cassette>, path: &PathBuf) {
+        let cassette = cassette.lock().unwrap();
+        if let Err(e) = cassette.save(path) {
+            eprintln!("\x1b[2m░ cannot save llm fixture {}: {}\x1b[0m", path.display(), e);
+        }
+    }
+
+    fn record(&self, hash: String, request: Value, outcome: Outcome) {
+        self.cassette.lock().unwrap().interactions.push(Interaction {
+            hash: Some(hash),
+            request,
+            outcome: self.normalizer.redact_outcome(outcome),
+        });
+        Self::save(&self.cassette, &self.path);
+    }
+}
+
+#[async_trait]
+impl LlmProvider for RecordingProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        let models = self.inner.models().await?;
+        self.cassette.lock().unwrap().models = Some(models.clone());
+        Self::save(&self.cassette, &self.path);
+        Ok(models)
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        let model = self.inner.default_model().await?;
+        self.cassette.lock().unwrap().default_model = Some(model.clone());
+        Self::save(&self.cassette, &self.path);
+        Ok(model)
+    }
+
+    fn default_model_among(&self, models: &[String]) -> Option<String> {
+        self.inner.default_model_among(models)
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        let (hash, normalized) = self.request_key(&request);
+        match self.inner.chat(request).await {
+            Ok(response) => {
+                self.record(hash, normalized, Outcome::Response(response.clone()));
+                Ok(response)
+            }
+            Err(error) => {
+                self.record(hash, normalized, Outcome::Error(RecordedError::from(&error)));
+                Err(error)
+            }
+        }
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let (hash, normalized) = self.request_key(&request);
+        let mut inner = match self.inner.chat_stream(request).await {
+            Ok(stream) => stream,
+            Err(error) => {
+                self.record(hash, normalized, Outcome::Error(RecordedError::from(&error)));
+                return Err(error);
+            }
+        };
+
+        let cassette = self.cassette.clone();
+        let path = self.path.clone();
+        let normalizer = self.normalizer.clone();
+        let stream = async_stream::stream! {
+            let mut chunks = Vec::new();
+            while let Some(item) = inner.next().await {
+                chunks.push(match &item {
+                    Ok(chunk) => RecordedChunk::Chunk(chunk.clone()),
+                    Err(error) => RecordedChunk::Error { error: RecordedError::from(error) },
+                });
+                yield item;
+            }
+            cassette.lock().unwrap().interactions.push(Interaction {
+                hash: Some(hash),
+                request: normalized,
+                outcome: normalizer.redact_outcome(Outcome::Stream(chunks)),
+            });
+            RecordingProvider::save(&cassette, &path);
+        };
+
+        Ok(Box::new(Box::pin(stream)))
+    }
+
+    /// Embeddings are not recorded, the inner provider always answers
+    async fn embeddings(&self, model: &str, input: Vec<String>) -> Result<Vec<Vec<f32>>, LlmError> {
+        self.inner.embeddings(model, input).await
+    }
+
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        self.inner.capabilities(model)
+    }
+
+    async fn model_capabilities(&self) -> Result<HashMap<String, ModelCapabilities>, LlmError> {
+        self.inner.model_capabilities().await
+    }
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn answered_by(&self) -> Option<(&'static str, String)> {
+        self.inner.answered_by()
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "recording",
+            display_name: "Recording of another provider",
+            env_vars: vec![],
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// llm/providers/replay/replay.rs
+use std::path::Path;
+use std::sync::Mutex;
+use crate::client::LlmClient;
+use crate::capabilities::ModelCapabilities;
+use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo};
+use crate::tool::ChatStreamAssembler;
+use super::cassette::{Cassette, Outcome, RecordedChunk, Normalizer};
+use async_trait::async_trait;
+use futures::stream;
+use openai_dive::v1::resources::{
+    chat::{ChatCompletionParameters, ChatCompletionResponse, ChatCompletionChunkResponse, ChatCompletionChunkChoice, ChatMessage, DeltaChatMessage, DeltaToolCall, DeltaFunction},
+    model::ListModelResponse,
+};
+
+/// Serves the interactions of a fixture file without network. A request is answered
+/// by the first unused interaction with the same normalized hash, or without hash.
+/// Responses and streams are converted when the request is not made the same way.
+pub struct ReplayProvider {
+    cassette: Cassette,
+    name: &'static str,
+    normalizer: Normalizer,
+    used: Mutex<Vec<bool>>,
+}
+
+impl ReplayProvider {
+    pub fn new(cassette: Cassette) -> Self {
+        // usage is reported for the recorded provider when it is a known one
+        let name = LlmClient::list_providers().into_iter()
+            .map(|info| info.name)
+            .find(|name| *name == cassette.provider)
+            .unwrap_or("replay");
+        let used = Mutex::new(vec![false; cassette.interactions.len()]);
+        Self {
+            cassette,
+            name,
+            normalizer: Normalizer::new(),
+            used,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LlmError> {
+        Ok(Self::new(Cassette::load(path.as_ref())?))
+    }
+
+    /// Must redact the same strings as the normalizer of the recording
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Interactions not served yet
+    pub fn remaining(&self) -> usize {
+        self.used.lock().unwrap().iter().filter(|used| !**used).count()
+    }
+
+    fn next_outcome(&self, request: &ChatCompletionParameters) -> Result<Outcome, LlmError> {
+        let normalized = self.normalizer.normalize(request);
+        let hash = self.normalizer.hash(&normalized);
+
+        let mut used = self.used.lock().unwrap();
+        let index = self.cassette.interactions.iter().enumerate()
+            .find(|(i, interaction)| {
+                !used[*i] && interaction.hash.as_ref().map_or(true, |recorded| *recorded == hash)
+            })
+            .map(|(i, _)| i)
+            .ok_or_else(|| format!(
+                "no recorded response for request {} (model {}, {} messages), record the fixture again with KROKIT_RECORD=1",
+                hash, request.model, request.messages.len()))?;
+        used[index] = true;
+        Ok(self.normalizer.restore_outcome(self.cassette.interactions[index].outcome.clone()))
+    }
+}
+
+/// Single chunk holding a whole response
+fn response_chunk(response: ChatCompletionResponse) -> ChatCompletionChunkResponse {
+    let choice = response.choices.into_iter().next();
+    let finish_reason = choice.as_ref().and_then(|choice| choice.finish_reason.clone());
+    let delta = match choice.map(|choice| choice.message) {
+        Some(ChatMessage::Assistant { content, reasoning_content, tool_calls, .. }) => DeltaChatMessage::Assistant {
+            content,
+            reasoning_content,
+            refusal: None,
+            name: None,
+            tool_calls: tool_calls.map(|calls| calls.into_iter().enumerate().map(|(index, call)| DeltaToolCall {
+                index: Some(index as u32),
+                id: Some(call.id),
+                r#type: Some(call.r#type),
+                function: DeltaFunction {
+                    name: Some(call.function.name),
+                    arguments: Some(call.function.arguments),
+                },
+            }).collect()),
+        },
+        _ => DeltaChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            tool_calls: None,
+        },
+    };
+
+    ChatCompletionChunkResponse {
+        id: response.id,
+        object: "chat.completion.chunk".to_string(),
+        created: response.created,
+        model: response.model,
+        choices: vec![ChatCompletionChunkChoice {
+            index: Some(0),
+            delta,
+            finish_reason,
+            logprobs: None,
+        }],
+        usage: response.usage,
+        system_fingerprint: None,
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ReplayProvider {
+    async fn models(&self) -> Result<ListModelResponse, LlmError> {
+        self.cassette.models.clone().ok_or_else(|| "no models in the fixture".into())
+    }
+
+    async fn default_model(&self) -> Result<String, LlmError> {
+        self.cassette.default_model.clone().ok_or_else(|| "no default model in the fixture".into())
+    }
+
+    fn default_model_among(&self, _models: &[String]) -> Option<String> {
+        self.cassette.default_model.clone()
+    }
+
+    async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+        match self.next_outcome(&request)? {
+            Outcome::Response(response) => Ok(response),
+            Outcome::Error(error) => Err(error.to_error()),
+            Outcome::Stream(chunks) => {
+                let mut assembler = ChatStreamAssembler::new();
+                for chunk in chunks {
+                    match chunk {
+                        RecordedChunk::Chunk(chunk) => { assembler.push(chunk); }
+                        RecordedChunk::Error { error } => return Err(error.to_error()),
+                    }
+                }
+                Ok(assembler.finish())
+            }
+        }
+    }
+
+    async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+        let items: Vec<Result<ChatCompletionChunkResponse, LlmError>> = match self.next_outcome(&request)? {
+            Outcome::Response(response) => vec![Ok(response_chunk(response))],
+            Outcome::Error(error) => return Err(error.to_error()),
+            Outcome::Stream(chunks) => chunks.into_iter()
+                .map(|chunk| match chunk {
+                    RecordedChunk::Chunk(chunk) => Ok(chunk),
+                    RecordedChunk::Error { error } => Err(error.to_error()),
+                })
+                .collect(),
+        };
+        Ok(Box::new(stream::iter(items)))
+    }
+
+    /// Everything, the fixture holds whatever the recorded model answered
+    fn capabilities(&self, model: &str) -> ModelCapabilities {
+        ModelCapabilities {
+            tools: Some(true),
+            json_schema: Some(true),
+            vision: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn info() -> ProviderInfo {
+        ProviderInfo {
+            name: "replay",
+            display_name: "Replay of a recorded provider",
+            env_vars: vec![],
+        }
+    }
+}
@@ -0,0 +1,232 @@
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use async_trait::async_trait;
+    use futures::{stream, StreamExt};
+    use openai_dive::v1::resources::{
+        chat::{
+            ChatCompletionChoice, ChatCompletionChunkChoice, ChatCompletionChunkResponse, ChatCompletionParameters,
+            ChatCompletionParametersBuilder, ChatCompletionResponse, ChatMessage, ChatMessageContent, DeltaChatMessage,
+            Function, ToolCall,
+        },
+        model::ListModelResponse,
+        shared::FinishReason,
+    };
+    use crate::provider::{LlmProvider, LlmError, LlmStream, ProviderInfo, HttpError};
+    use crate::providers::replay::{Cassette, Normalizer, RecordingProvider, ReplayProvider};
+
+    /// Answers with a write of the given path, streamed in two chunks
+    struct ScriptedProvider {
+        path: String,
+    }
+
+    impl ScriptedProvider {
+        fn tool_call(&self) -> ToolCall {
+            ToolCall {
+                id: "call_1".to_string(),
+                r#type: "function".to_string(),
+                function: Function {
+                    name: "write".to_string(),
+                    arguments: serde_json::json!({ "path": format!("{}/hello.py", self.path) }).to_string(),
+                },
+            }
+        }
+
+        fn chunk(content: &str, finish_reason: Option<FinishReason>) -> ChatCompletionChunkResponse {
+            ChatCompletionChunkResponse {
+                id: Some("1".to_string()),
+                object: "chat.completion.chunk".to_string(),
+                created: 0,
+                model: "scripted".to_string(),
+                choices: vec![ChatCompletionChunkChoice {
+                    index: Some(0),
+                    delta: DeltaChatMessage::Assistant {
+                        content: Some(ChatMessageContent::Text(content.to_string())),
+                        reasoning_content: None,
+                        refusal: None,
+                        name: None,
+                        tool_calls: None,
+                    },
+                    finish_reason,
+                    logprobs: None,
+                }],
+                usage: None,
+                system_fingerprint: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Ok(ListModelResponse { object: "list".to_string(), data: vec![] })
+        }
+
+        async fn default_model(&self) -> Result<String, LlmError> {
+            Ok("scripted".to_string())
+        }
+
+        async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            if request.model == "overloaded" {
+                return Err(Box::new(HttpError { status: 529, retry_after: None, message: "overloaded".to_string() }));
+            }
+            Ok(ChatCompletionResponse {
+                id: Some("1".to_string()),
+                object: "chat.completion".to_string(),
+                created: 0,
+                model: request.model,
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message: ChatMessage::Assistant {
+                        content: Some(ChatMessageContent::Text("Writing hello.py".to_string())),
+                        reasoning_content: None,
+                        refusal: None,
+                        name: None,
+                        audio: None,
+                        tool_calls: Some(vec![self.tool_call()]),
+                    },
+                    finish_reason: Some(FinishReason::StopSequenceReached),
+                    logprobs: None,
+                }],
+                usage: None,
+                service_tier: None,
+                system_fingerprint: None,
+            })
+        }
+
+        async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            Ok(Box::new(stream::iter(vec![
+                Ok(Self::chunk("Hello, ", None)),
+                Ok(Self::chunk("World!", Some(FinishReason::StopSequenceReached))),
+            ])))
+        }
+
+        fn name(&self) -> &'static str {
+            "openai"
+        }
+
+        fn info() -> ProviderInfo {
+            ProviderInfo { name: "scripted", display_name: "Scripted", env_vars: vec![] }
+        }
+    }
+
+    fn fixture_path() -> PathBuf {
+        std::env::temp_dir().join(format!("krokit-replay-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    fn request(model: &str, system: &str, user: &str) -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model(model)
+            .messages(vec![
+                ChatMessage::System { content: ChatMessageContent::Text(system.to_string()), name: None },
+                ChatMessage::User { content: ChatMessageContent::Text(user.to_string()), name: None },
+            ])
+            .build()
+            .unwrap()
+    }
+
+    fn text(response: &ChatCompletionResponse) -> String {
+        match &response.choices[0].message {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), .. } => text.clone(),
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = fixture_path();
+        let recorder = RecordingProvider::new(Box::new(ScriptedProvider { path: "/tmp/run-1".to_string() }), &path)
+            .with_normalizer(Normalizer::default().redact("/tmp/run-1", "$TMP"));
+        assert_eq!(recorder.default_model().await.unwrap(), "scripted");
+        recorder.chat(request("scripted", "Today is monday", "Write /tmp/run-1/hello.py")).await.unwrap();
+        let mut stream = recorder.chat_stream(request("scripted", "Today is monday", "Say hello")).await.unwrap();
+        while stream.next().await.is_some() {}
+        assert!(recorder.chat(request("overloaded", "", "Say hello")).await.is_err());
+
+        let recorded = std::fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("/tmp/run-1"), "{}", recorded);
+        assert!(!recorded.contains("monday"), "{}", recorded);
+
+        // another day, in another directory
+        let replay = ReplayProvider::load(&path).unwrap()
+            .with_normalizer(Normalizer::default().redact("/tmp/run-2", "$TMP"));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.name(), "openai");
+        assert_eq!(replay.default_model().await.unwrap(), "scripted");
+
+        let response = replay.chat(request("scripted", "Today is tuesday", "Write /tmp/run-2/hello.py")).await.unwrap();
+        let ChatMessage::Assistant { tool_calls: Some(calls), .. } = &response.choices[0].message else {
+            panic!("expected a tool call");
+        };
+        assert!(calls[0].function.arguments.contains("/tmp/run-2/hello.py"));
+
+        // a recorded stream is served as a stream or as a whole response
+        let mut stream = replay.chat_stream(request("scripted", "Today is tuesday", "Say hello")).await.unwrap();
+        let mut chunks = 0;
+        while let Some(chunk) = stream.next().await {
+            chunk.unwrap();
+            chunks += 1;
+        }
+        assert_eq!(chunks, 2);
+
+        let error = replay.chat(request("overloaded", "", "Say hello")).await.unwrap_err();
+        assert_eq!(error.downcast_ref::<HttpError>().unwrap().status, 529);
+        assert_eq!(replay.remaining(), 0);
+
+        let error = replay.chat(request("scripted", "", "Something else")).await.unwrap_err();
+        assert!(error.to_string().contains("no recorded response"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_same_request_is_answered_in_order() {
+        let path = fixture_path();
+        let recorder = RecordingProvider::new(Box::new(ScriptedProvider { path: "/tmp".to_string() }), &path);
+        let mut stream = recorder.chat_stream(request("scripted", "", "Say hello")).await.unwrap();
+        while stream.next().await.is_some() {}
+        recorder.chat(request("scripted", "", "Say hello")).await.unwrap();
+
+        let replay = ReplayProvider::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(text(&replay.chat(request("scripted", "", "Say hello")).await.unwrap()), "Hello, World!");
+        assert_eq!(text(&replay.chat(request("scripted", "", "Say hello")).await.unwrap()), "Writing hello.py");
+    }
+
+    #[tokio::test]
+    async fn test_interactions_without_hash_match_any_request() {
+        let cassette: Cassette = serde_json::from_value(serde_json::json!({
+            "provider": "custom",
+            "interactions": [
+                { "response": {
+                    "id": "1", "object": "chat.completion", "created": 0, "model": "any",
+                    "choices": [{ "index": 0, "finish_reason": "stop",
+                        "message": { "role": "assistant", "content": "Done" } }]
+                } }
+            ]
+        })).unwrap();
+        let replay = ReplayProvider::new(cassette);
+        assert_eq!(replay.name(), "replay");
+
+        let mut stream = replay.chat_stream(request("any", "", "Do something")).await.unwrap();
+        let chunk = stream.next().await.unwrap().unwrap();
+        let DeltaChatMessage::Assistant { content: Some(ChatMessageContent::Text(content)), .. } = &chunk.choices[0].delta else {
+            panic!("expected content");
+        };
+        assert_eq!(content, "Done");
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn test_normalized_hash() {
+        let normalizer = Normalizer::default().redact("/home/me/project", "$CWD");
+        let hash = |request: &ChatCompletionParameters| normalizer.hash(&normalizer.normalize(request));
+
+        let base = request("model", "Today is monday", "Read /home/me/project/main.rs");
+        let mut streamed = request("model", "Today is tuesday", "Read /home/me/project/main.rs");
+        streamed.stream = Some(true);
+        assert_eq!(hash(&base), hash(&streamed));
+        let ci = Normalizer::default().redact("/ci/build", "$CWD");
+        assert_eq!(hash(&base), ci.hash(&ci.normalize(&request("model", "", "Read /ci/build/main.rs"))));
+        assert_ne!(hash(&base), hash(&request("model", "", "Read /home/me/project/lib.rs")));
+        assert_ne!(hash(&base), hash(&request("other-model", "", "Read /home/me/project/main.rs")));
+    }
+}
@@ -0,0 +1,135 @@
+// llm/providers/sse.rs
+use futures::{Stream, StreamExt};
+use crate::provider::LlmError;
+
+/// Event of a server-sent events response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Incremental parser, network chunks can end anywhere (in the middle of a line or
+/// of a utf-8 character) so incomplete lines are kept until the next chunk
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of the response, returns the events completed by it
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if let Some(event) = self.process_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// End of the response, returns the last event if it was not terminated by a blank line
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        let rest = std::mem::take(&mut self.buffer);
+        let rest = String::from_utf8_lossy(&rest);
+        let rest = rest.trim_end_matches(['\n', '\r']);
+        if !rest.is_empty() {
+            self.process_line(rest);
+        }
+        self.dispatch()
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None; // comment
+        }
+
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => self.data.push(value.to_string()),
+            _ => {} // id, retry
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some(SseEvent { event, data })
+    }
+}
+
+/// Events of a streamed http response
+pub fn sse_events(response: reqwest::Response) -> impl Stream<Item = Result<SseEvent, LlmError>> + Send {
+    async_stream::stream! {
+        let mut parser = SseParser::new();
+        let mut bytes = Box::pin(response.bytes_stream());
+        while let Some(chunk) = bytes.next().await {
+            match chunk {
+                Ok(chunk) => {
+                    for event in parser.feed(&chunk) {
+                        yield Ok(event);
+                    }
+                }
+                Err(e) => {
+                    yield Err(Box::new(e) as LlmError);
+                    return;
+                }
+            }
+        }
+        if let Some(event) = parser.finish() {
+            yield Ok(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_split_across_chunks() {
+        let body = "event: message_start\ndata: {\"a\":1}\n\n: keep-alive\n\ndata: {\"text\":\"h\u{e9}llo\"}\r\n\r\ndata: line1\ndata: line2\n\n";
+        let bytes = body.as_bytes();
+
+        // feeding byte per byte must give the same events as feeding everything at once
+        let mut parser = SseParser::new();
+        let all = parser.feed(bytes);
+        let mut parser = SseParser::new();
+        let split: Vec<SseEvent> = bytes.chunks(1).flat_map(|chunk| parser.feed(chunk)).collect();
+        assert_eq!(all, split);
+
+        assert_eq!(all, vec![
+            SseEvent { event: Some("message_start".to_string()), data: "{\"a\":1}".to_string() },
+            SseEvent { event: None, data: "{\"text\":\"h\u{e9}llo\"}".to_string() },
+            SseEvent { event: None, data: "line1\nline2".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn test_unterminated_last_event() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed(b"data: {\"done\":true}").is_empty());
+        assert_eq!(parser.finish(), Some(SseEvent { event: None, data: "{\"done\":true}".to_string() }));
+        assert_eq!(parser.finish(), None);
+    }
+}
@@ -269,7 +269,7 @@ pub fn create_provider_from_env(provider_name: &str) -> Option<Box<dyn LlmProvid
         "openrouter" => crate::providers::openrouter::OpenRouterProvider::from_env().map(|p| Box::new(p) as Box<dyn LlmProvider>),
         "openai_compatible" => crate::providers::openai_compatible::OpenAICompatibleProvider::from_env().map(|p| Box::new(p) as Box<dyn LlmProvider>),
         "ovhcloud" => crate::providers::ovhcloud::OvhCloudProvider::from_env().map(|p| Box::new(p) as Box<dyn LlmProvider>),
-        "mistral" => crate::providers::mistral::MistralProvider::from_env().map(|p| Box::new(p) as Box<dyn LlmProvider>),
+        "mistral" => crate::providers::mistral_native::MistralProvider::from_env().map(|p| Box::new(p) as Box<dyn LlmProvider>),
         _ => None,
     }
 }
@@ -583,4 +583,87 @@ mod integration_tests {
         }
     }
 
-}
\ No newline at end of file
+}
+
+/// Streamed responses of the openai compatible providers, against a local fixture server
+#[cfg(test)]
+mod stream_usage_tests {
+    use super::*;
+    use crate::providers::fixture::FixtureServer;
+    use crate::tool::ChatStreamAssembler;
+    use crate::LlmClient;
+
+    const STREAM_WITH_USAGE: &str = "data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"finish_reason\":null}]}\n\n\
+data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"m\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"finish_reason\":\"stop\"}]}\n\n\
+data: {\"id\":\"c1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"m\",\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":3,\"total_tokens\":15}}\n\n\
+data: [DONE]\n\n";
+
+    #[tokio::test]
+    async fn test_stream_asks_for_and_returns_usage() {
+        let server = FixtureServer::start(200, "", "text/event-stream", STREAM_WITH_USAGE).await;
+        let client = LlmClient::compatible("key".to_string(), server.base_url.clone());
+        let request = ChatCompletionParametersBuilder::default()
+            .model("m")
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text("hello".to_string()), name: None }])
+            .build()
+            .unwrap();
+
+        let mut stream = client.chat_stream(request).await.unwrap();
+        let mut assembler = ChatStreamAssembler::new();
+        while let Some(chunk) = stream.next().await {
+            assembler.push(chunk.unwrap());
+        }
+        let response = assembler.finish();
+
+        assert_eq!(server.requests()[0].body["stream_options"]["include_usage"], json!(true));
+        let usage = serde_json::to_value(response.usage.expect("the last chunk carries the usage")).unwrap();
+        assert_eq!(usage["prompt_tokens"], json!(12));
+        assert_eq!(usage["completion_tokens"], json!(3));
+    }
+}
+
+/// The alternation fix of mistral models, applied by the client unless the native provider is used
+#[cfg(test)]
+mod mistral_alternating_tests {
+    use super::*;
+    use openai_dive::v1::resources::chat::ChatCompletionParameters;
+    use serde_json::Value;
+    use crate::providers::fixture::FixtureServer;
+    use crate::providers::mistral_native::MistralProvider;
+    use crate::LlmClient;
+
+    const ANSWER: &str = r#"{"id":"c1","object":"chat.completion","created":1,"model":"mistral-large-latest","choices":[{"index":0,"message":{"role":"assistant","content":"ok"},"finish_reason":"stop"}]}"#;
+
+    /// Two user messages in a row, as left by a cancelled answer
+    fn request() -> ChatCompletionParameters {
+        let user = |text: &str| ChatMessage::User { content: ChatMessageContent::Text(text.to_string()), name: None };
+        ChatCompletionParametersBuilder::default()
+            .model("mistral-large-latest")
+            .messages(vec![user("first"), user("second")])
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_other_providers_alternate_mistral_turns() {
+        let server = FixtureServer::start(200, "", "application/json", ANSWER).await;
+        let client = LlmClient::compatible("key".to_string(), server.base_url.clone());
+        client.chat(request()).await.unwrap();
+
+        let roles: Vec<Value> = server.requests()[0].body["messages"].as_array().unwrap().iter()
+            .map(|message| message["role"].clone())
+            .collect();
+        assert_eq!(roles, vec![json!("user"), json!("assistant"), json!("user")]);
+    }
+
+    #[tokio::test]
+    async fn test_native_mistral_gets_the_conversation_as_is() {
+        let server = FixtureServer::start(200, "", "application/json", ANSWER).await;
+        let provider = MistralProvider::with_base_url("key".to_string(), format!("{}/v1", server.base_url));
+        let client = LlmClient::from_provider(Box::new(provider));
+        client.chat(request()).await.unwrap();
+
+        let messages = server.requests()[0].body["messages"].clone();
+        assert!(messages.as_array().unwrap().iter().all(|message| message["content"] != json!("I understand.")), "{}", messages);
+    }
+}
@@ -3,7 +3,7 @@ use async_trait::async_trait;
 
 use openai_dive::v1::resources::chat::{ChatCompletionFunction, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatCompletionTool, ChatCompletionToolChoice, ChatCompletionToolType, ChatMessage};
 
-use crate::{provider::LlmError, tool::{call_fc_auto::ToolCallFunctionCallingAuto, call_fc_required::ToolCallFunctionCallingRequired, call_structured_output::ToolCallStructuredOutput, ToolBox}, LlmClient, ToolCallMethod, ToolDescription};
+use crate::{provider::LlmError, tool::{call_fc_auto::ToolCallFunctionCallingAuto, call_fc_required::ToolCallFunctionCallingRequired, call_parsing::ToolCallParsing, call_structured_output::ToolCallStructuredOutput, ToolBox}, LlmClient, ToolCallMethod, ToolDescription};
 
 
 #[async_trait]
@@ -38,7 +38,7 @@ impl LlmToolCall for LlmClient {
                 self.chat_with_tools_so(request, tools).await
             }
             ToolCallMethod::Parsing => {
-                Err(LlmError::from("method not supported"))
+                self.chat_with_tools_parsing(request, tools).await
             }
         }
     }
@@ -60,14 +60,25 @@ impl ToolCallAuto for LlmClient {
         request: ChatCompletionParameters,
         tools: &ToolBox
     ) -> Result<ChatCompletionResponse, LlmError> {
-        if let Ok(result) = self.chat_with_tools_fc_auto(request.clone(), tools).await {
-            return Ok(result);
+        // methods the model cannot handle are skipped instead of failing one by one
+        let capabilities = self.capabilities(&request.model).await;
+
+        if capabilities.supports_tools() {
+            if let Ok(result) = self.chat_with_tools_fc_auto(request.clone(), tools).await {
+                return Ok(result);
+            }
+
+            if let Ok(result) = self.chat_with_tools_fc_required(request.clone(), tools).await {
+                return Ok(result);
+            }
         }
-        
-        if let Ok(result) = self.chat_with_tools_fc_required(request.clone(), tools).await {
-            return Ok(result);
+
+        if capabilities.supports_json_schema() {
+            if let Ok(result) = self.chat_with_tools_so(request.clone(), tools).await {
+                return Ok(result);
+            }
         }
-        
-        self.chat_with_tools_so(request, tools).await
+
+        self.chat_with_tools_parsing(request, tools).await
     }
 }
\ No newline at end of file
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use async_trait::async_trait;
+use serde_json::Value;
+use openai_dive::v1::resources::chat::{ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse};
+
+use crate::provider::LlmError;
+use crate::tool::call_structured_output::{AssistantResponse, IntoChatMessage, ToolCall as ParsedToolCall};
+use crate::tool::ToolBox;
+use crate::{ChatMessage, ChatMessageContent, LlmClient};
+
+const TOOL_CALL_OPEN: &str = "<tool_call";
+const TOOL_CALL_CLOSE: &str = "</tool_call>";
+
+static NAME_ATTRIBUTE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r#"name\s*=\s*["']?([\w\-.:]+)"#).unwrap()
+});
+
+/// Tool documentation and calling protocol appended to the system prompt
+pub fn parsing_tools_prompt(tools: &ToolBox) -> String {
+    let mut doc = String::from("\n\n# Available Tools\n\nYou have access to the following tools:\n\n");
+    for tool in tools {
+        doc.push_str(&format!("## {}\n", tool.name()));
+        doc.push_str(&format!("**Description**: {}\n\n", tool.description()));
+        doc.push_str("**Parameters Schema**:\n```json\n");
+        doc.push_str(&serde_json::to_string_pretty(&tool.parameters_schema()).unwrap_or_default());
+        doc.push_str("\n```\n\n");
+    }
+    doc.push_str(concat!(
+        "# Calling Tools\n\n",
+        "To call a tool, write a tool_call block containing a single json object with the tool name and its arguments:\n\n",
+        "<tool_call>\n{\"name\": \"tool_name\", \"arguments\": {\"param\": \"value\"}}\n</tool_call>\n\n",
+        "You may call several tools by writing several blocks. Stop after your tool calls, ",
+        "the results will be given back to you in <tool_result> blocks. ",
+        "If you don't need a tool, just answer without any tool_call block.\n"
+    ));
+    doc
+}
+
+/// Extract the <tool_call> blocks of a response.
+/// Returns the remaining text and the parsed calls, blocks that cannot be
+/// understood are left in the text.
+pub fn parse_tool_calls(text: &str) -> (String, Vec<ParsedToolCall>) {
+    let mut content = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = find_open_tag(rest) {
+        content.push_str(&rest[..start]);
+        let block = &rest[start..];
+
+        // opening tag, possibly with a name attribute: <tool_call name="bash">
+        let Some(tag_end) = block.find('>') else {
+            content.push_str(block);
+            rest = "";
+            break;
+        };
+        let attributes = &block[TOOL_CALL_OPEN.len()..tag_end];
+        let body_start = tag_end + 1;
+
+        // the body ends at the closing tag, or at the next block / end of text if the model forgot it
+        let after_open = &block[body_start..];
+        let (body, consumed) = match (after_open.find(TOOL_CALL_CLOSE), find_open_tag(after_open)) {
+            (Some(close), Some(next)) if next < close => (&after_open[..next], body_start + next),
+            (Some(close), _) => (&after_open[..close], body_start + close + TOOL_CALL_CLOSE.len()),
+            (None, Some(next)) => (&after_open[..next], body_start + next),
+            (None, None) => (after_open, block.len()),
+        };
+
+        match parse_tool_call(attribute_name(attributes).as_deref(), body) {
+            Some(call) => calls.push(call),
+            None => content.push_str(&block[..consumed]),
+        }
+        rest = &block[consumed..];
+    }
+    content.push_str(rest);
+
+    (content.trim().to_string(), calls)
+}
+
+/// Start of the next `<tool_call>` opening tag, `<tool_calls>` or `<tool_call_result>` are other tags
+fn find_open_tag(text: &str) -> Option<usize> {
+    text.match_indices(TOOL_CALL_OPEN)
+        .map(|(start, _)| start)
+        .find(|start| {
+            text[start + TOOL_CALL_OPEN.len()..].chars().next()
+                .map_or(false, |c| c == '>' || c.is_whitespace())
+        })
+}
+
+fn attribute_name(attributes: &str) -> Option<String> {
+    NAME_ATTRIBUTE.captures(attributes).map(|c| c[1].to_string())
+}
+
+/// Map the body of a block to a tool call, accepting the usual variations
+/// of key names used by models
+fn parse_tool_call(name: Option<&str>, body: &str) -> Option<ParsedToolCall> {
+    let value = repair_json(body)?;
+    let object = value.as_object()?;
+
+    let name = name.map(|n| n.to_string()).or_else(|| {
+        ["name", "tool_name", "tool", "function"].iter()
+            .find_map(|key| object.get(*key).and_then(|v| v.as_str()))
+            .map(|n| n.to_string())
+    })?;
+
+    let arguments = ["arguments", "parameters", "tool_parameter", "args", "input"].iter()
+        .find_map(|key| object.get(*key))
+        .cloned()
+        .map(|args| match args {
+            // some models send the arguments as a json encoded string
+            Value::String(s) => repair_json(&s).unwrap_or(Value::String(s)),
+            args => args,
+        });
+
+    // with a name attribute the body may be the arguments themselves
+    let arguments = arguments.unwrap_or_else(|| {
+        let mut object = object.clone();
+        for key in ["name", "tool_name", "tool", "function"] {
+            object.remove(key);
+        }
+        Value::Object(object)
+    });
+
+    Some(ParsedToolCall { tool_name: name, tool_parameter: arguments })
+}
+
+/// Best effort parsing of the json written by small models: code fences, text around
+/// the object, single quotes, python literals, unquoted keys, raw newlines in strings,
+/// trailing commas and missing closing brackets are fixed up.
+pub fn repair_json(input: &str) -> Option<Value> {
+    let trimmed = input.trim();
+    if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+        return Some(value);
+    }
+
+    let start = trimmed.find(|c| c == '{' || c == '[')?;
+    let chars: Vec<char> = trimmed[start..].chars().collect();
+
+    let mut out = String::with_capacity(chars.len());
+    let mut stack: Vec<char> = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if let Some(q) = quote {
+            match c {
+                '\\' if i + 1 < chars.len() => {
+                    // an escaped single quote is not valid json
+                    if chars[i + 1] == '\'' {
+                        out.push('\'');
+                    } else {
+                        out.push(c);
+                        out.push(chars[i + 1]);
+                    }
+                    i += 1;
+                }
+                c if c == q => {
+                    out.push('"');
+                    quote = None;
+                }
+                '"' => out.push_str("\\\""),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c => out.push(c),
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                out.push('"');
+            }
+            '{' | '[' => {
+                stack.push(if c == '{' { '}' } else { ']' });
+                out.push(c);
+            }
+            '}' | ']' => {
+                remove_trailing_comma(&mut out);
+                if let Some(close) = stack.pop() {
+                    out.push(close);
+                }
+                if stack.is_empty() {
+                    break;
+                }
+            }
+            // bare words: keys, python literals or unquoted strings (but not exponents)
+            c if (c.is_alphabetic() || c == '_') && !out.ends_with(|p: char| p.is_ascii_digit() || p == '.') => {
+                let word: String = chars[i..].iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_' || **c == '-')
+                    .collect();
+                i += word.chars().count();
+                let is_key = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+                match word.as_str() {
+                    _ if is_key => out.push_str(&format!("\"{}\"", word)),
+                    "True" | "true" => out.push_str("true"),
+                    "False" | "false" => out.push_str("false"),
+                    "None" | "null" => out.push_str("null"),
+                    _ => out.push_str(&format!("\"{}\"", word)),
+                }
+                continue;
+            }
+            c => out.push(c),
+        }
+        i += 1;
+    }
+
+    if quote.is_some() {
+        out.push('"');
+    }
+    remove_trailing_comma(&mut out);
+    while let Some(close) = stack.pop() {
+        out.push(close);
+    }
+
+    serde_json::from_str(&out).ok()
+}
+
+fn remove_trailing_comma(out: &mut String) {
+    let trimmed_len = out.trim_end().len();
+    if out[..trimmed_len].ends_with(',') {
+        out.truncate(trimmed_len - 1);
+    }
+}
+
+/// Models without function calling do not know about tool messages: previous tool calls
+/// are written back as <tool_call> blocks and tool results sent as user messages
+pub fn into_parsing_messages(messages: Vec<ChatMessage>, tools_doc: &str) -> Vec<ChatMessage> {
+    let mut tool_names: HashMap<String, String> = HashMap::new();
+    let mut result: Vec<ChatMessage> = Vec::new();
+    let mut pending_results: Vec<String> = Vec::new();
+
+    let flush = |result: &mut Vec<ChatMessage>, pending: &mut Vec<String>| {
+        if !pending.is_empty() {
+            result.push(ChatMessage::User {
+                content: ChatMessageContent::Text(pending.join("\n\n")),
+                name: None,
+            });
+            pending.clear();
+        }
+    };
+
+    for message in messages {
+        match message {
+            ChatMessage::Tool { content, tool_call_id } => {
+                let name = tool_names.get(&tool_call_id).cloned().unwrap_or_default();
+                pending_results.push(format!("<tool_result name=\"{}\">\n{}\n</tool_result>", name, content));
+            }
+            ChatMessage::Assistant { content, reasoning_content, tool_calls: Some(tool_calls), refusal, name, audio } => {
+                flush(&mut result, &mut pending_results);
+                let mut text = match content {
+                    Some(ChatMessageContent::Text(text)) => text,
+                    _ => String::new(),
+                };
+                for call in &tool_calls {
+                    tool_names.insert(call.id.clone(), call.function.name.clone());
+                    let arguments = serde_json::from_str::<Value>(&call.function.arguments)
+                        .unwrap_or(Value::String(call.function.arguments.clone()));
+                    let block = serde_json::json!({"name": call.function.name, "arguments": arguments});
+                    if !text.is_empty() {
+                        text.push_str("\n\n");
+                    }
+                    text.push_str(&format!("<tool_call>\n{}\n</tool_call>", block));
+                }
+                result.push(ChatMessage::Assistant {
+                    content: Some(ChatMessageContent::Text(text)),
+                    reasoning_content,
+                    tool_calls: None,
+                    refusal,
+                    name,
+                    audio,
+                });
+            }
+            message => {
+                flush(&mut result, &mut pending_results);
+                result.push(message);
+            }
+        }
+    }
+    flush(&mut result, &mut pending_results);
+
+    // Append the tools documentation to the system prompt
+    match result.get_mut(0) {
+        Some(ChatMessage::System { content: ChatMessageContent::Text(system_text), .. }) => {
+            system_text.push_str(tools_doc);
+        }
+        _ => {
+            result.insert(0, ChatMessage::System {
+                content: ChatMessageContent::Text(tools_doc.trim_start().to_string()),
+                name: None,
+            });
+        }
+    }
+    result
+}
+
+#[async_trait]
+pub trait ToolCallParsing {
+    async fn chat_with_tools_parsing(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl ToolCallParsing for LlmClient {
+    async fn chat_with_tools_parsing(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        let tools_doc = if tools.is_empty() { String::new() } else { parsing_tools_prompt(tools) };
+        let messages = into_parsing_messages(request.messages.clone(), &tools_doc);
+
+        let request = ChatCompletionParametersBuilder::default()
+            .model(&request.model)
+            .messages(messages)
+            .temperature(request.temperature.unwrap_or(0.3))
+            .build()
+            .map_err(|e| LlmError::from(e.to_string()))?;
+
+        let mut response = self
+            .chat(request)
+            .await
+            .map_err(|e| LlmError::from(e.to_string()))?;
+
+        let Some(choice) = response.choices.get_mut(0) else {
+            return Err("Expected at least one choice in response".into());
+        };
+        if let ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), reasoning_content, .. } = &choice.message {
+            let (content, calls) = parse_tool_calls(text);
+            if !calls.is_empty() {
+                choice.message = AssistantResponse {
+                    content,
+                    reasoning_content: reasoning_content.clone(),
+                    tools: Some(calls),
+                }.into_chatmessage();
+            }
+        }
+        Ok(response)
+    }
+}
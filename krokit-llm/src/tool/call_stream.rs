@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use async_trait::async_trait;
+use futures::StreamExt;
+
+use openai_dive::v1::resources::chat::{ChatCompletionChoice, ChatCompletionChunkResponse, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatMessage, ChatMessageContent, DeltaChatMessage, DeltaToolCall, Function, ToolCall};
+use openai_dive::v1::resources::shared::{FinishReason, Usage};
+
+use crate::{client::ExtractThinkContent, provider::LlmError, tool::{call::{LlmToolCall, ToolCallAuto}, call_fc_auto::FunctionCallingAutoBuilder, ToolBox}, LlmClient, ToolCallMethod};
+
+/// Incremental piece of an assistant message, as received from the provider
+#[derive(Debug, Clone)]
+pub enum ChatDelta {
+    /// a piece of the assistant answer
+    Content(String),
+    /// a piece of the model reasoning (thinking)
+    Reasoning(String),
+    /// the model started to write a tool call
+    ToolCall { index: u32, name: String },
+}
+
+/// Callback receiving deltas while the response is streamed
+pub type DeltaSink = Arc<dyn Fn(ChatDelta) + Send + Sync>;
+
+/// Tool call being assembled from its deltas
+#[derive(Debug, Default, Clone)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+/// Rebuilds a complete ChatCompletionResponse out of stream chunks.
+/// Tool calls are assembled by index: the first delta of a tool call carries
+/// its id and name, the following ones append to the arguments.
+#[derive(Debug, Default)]
+pub struct ChatStreamAssembler {
+    id: Option<String>,
+    model: String,
+    created: u32,
+    content: String,
+    reasoning: String,
+    tool_calls: BTreeMap<u32, PartialToolCall>,
+    finish_reason: Option<FinishReason>,
+    usage: Option<Usage>,
+}
+
+impl ChatStreamAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accumulate a chunk and returns the deltas worth displaying
+    pub fn push(&mut self, chunk: ChatCompletionChunkResponse) -> Vec<ChatDelta> {
+        let mut deltas = Vec::new();
+        if self.id.is_none() {
+            self.id = chunk.id.clone();
+        }
+        if self.model.is_empty() {
+            self.model = chunk.model.clone();
+        }
+        if self.created == 0 {
+            self.created = chunk.created;
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage.clone();
+        }
+
+        for choice in chunk.choices {
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason.clone();
+            }
+
+            let (content, reasoning_content, tool_calls) = match choice.delta {
+                DeltaChatMessage::Assistant { content, reasoning_content, tool_calls, .. } => (content, reasoning_content, tool_calls),
+                DeltaChatMessage::Untagged { content, reasoning_content, tool_calls, .. } => (content, reasoning_content, tool_calls),
+                _ => continue,
+            };
+
+            if let Some(ChatMessageContent::Text(text)) = content {
+                if !text.is_empty() {
+                    self.content.push_str(&text);
+                    deltas.push(ChatDelta::Content(text));
+                }
+            }
+
+            if let Some(reasoning) = reasoning_content {
+                if !reasoning.is_empty() {
+                    self.reasoning.push_str(&reasoning);
+                    deltas.push(ChatDelta::Reasoning(reasoning));
+                }
+            }
+
+            for tc in tool_calls.unwrap_or_default() {
+                if let Some(delta) = self.push_tool_call(tc) {
+                    deltas.push(delta);
+                }
+            }
+        }
+        deltas
+    }
+
+    fn push_tool_call(&mut self, tc: DeltaToolCall) -> Option<ChatDelta> {
+        // some providers omit the index when there is a single tool call
+        let index = tc.index.unwrap_or_else(|| {
+            match (&tc.id, self.tool_calls.keys().next_back()) {
+                (Some(_), Some(last)) => last + 1,
+                (None, Some(last)) => *last,
+                (_, None) => 0,
+            }
+        });
+
+        let partial = self.tool_calls.entry(index).or_default();
+        if let Some(id) = tc.id.filter(|id| !id.is_empty()) {
+            partial.id = id;
+        }
+
+        let mut started = None;
+        if let Some(name) = tc.function.name.filter(|name| !name.is_empty()) {
+            if partial.name.is_empty() {
+                started = Some(ChatDelta::ToolCall { index, name: name.clone() });
+            }
+            partial.name.push_str(&name);
+        }
+        if let Some(arguments) = tc.function.arguments {
+            partial.arguments.push_str(&arguments);
+        }
+        started
+    }
+
+    /// Build the final response
+    pub fn finish(self) -> ChatCompletionResponse {
+        let tool_calls: Vec<ToolCall> = self.tool_calls.into_values()
+            .filter(|tc| !tc.name.is_empty())
+            .enumerate()
+            .map(|(i, tc)| ToolCall {
+                id: if tc.id.is_empty() { format!("call_{}", i) } else { tc.id },
+                r#type: "function".to_string(),
+                function: Function {
+                    name: tc.name,
+                    arguments: if tc.arguments.trim().is_empty() { "{}".to_string() } else { tc.arguments },
+                },
+            })
+            .collect();
+
+        ChatCompletionResponse {
+            id: self.id,
+            object: "chat.completion".to_string(),
+            created: self.created,
+            model: self.model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage::Assistant {
+                    content: (!self.content.is_empty()).then(|| ChatMessageContent::Text(self.content)),
+                    reasoning_content: (!self.reasoning.is_empty()).then_some(self.reasoning),
+                    refusal: None,
+                    name: None,
+                    audio: None,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+                },
+                finish_reason: self.finish_reason.or(Some(FinishReason::StopSequenceReached)),
+                logprobs: None,
+            }],
+            usage: self.usage,
+            service_tier: None,
+            system_fingerprint: None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait LlmToolCallStream {
+    /// Same as chat_with_tools, but partial content is pushed to on_delta as it arrives.
+    /// Methods that cannot be streamed fall back to a regular call.
+    /// Dropping the returned future aborts the underlying http stream.
+    async fn chat_with_tools_stream(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        method: ToolCallMethod,
+        on_delta: DeltaSink
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl LlmToolCallStream for LlmClient {
+    async fn chat_with_tools_stream(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        method: ToolCallMethod,
+        on_delta: DeltaSink
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        match method {
+            ToolCallMethod::FunctionCall => {
+                self.chat_with_tools_fc_auto_stream(request, tools, on_delta).await
+            }
+            ToolCallMethod::Auto => {
+                if self.capabilities(&request.model).await.supports_tools() {
+                    // the deltas already shown cannot be taken back, a fallback would show the answer twice
+                    let sent = Arc::new(AtomicBool::new(false));
+                    let tracked: DeltaSink = {
+                        let sent = sent.clone();
+                        Arc::new(move |delta| {
+                            sent.store(true, Ordering::Relaxed);
+                            on_delta(delta);
+                        })
+                    };
+                    match self.chat_with_tools_fc_auto_stream(request.clone(), tools, tracked).await {
+                        Ok(result) => return Ok(result),
+                        Err(e) if sent.load(Ordering::Relaxed) => return Err(e),
+                        Err(_) => {}
+                    }
+                }
+                self.chat_with_tools_try_all(request, tools).await
+            }
+            _ => {
+                self.chat_with_tools(request, tools, method).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+pub trait ToolCallFunctionCallingAutoStream {
+    async fn chat_with_tools_fc_auto_stream(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        on_delta: DeltaSink
+    ) -> Result<ChatCompletionResponse, LlmError>;
+}
+
+#[async_trait]
+impl ToolCallFunctionCallingAutoStream for LlmClient {
+    async fn chat_with_tools_fc_auto_stream(
+        &self,
+        request: ChatCompletionParameters,
+        tools: &ToolBox,
+        on_delta: DeltaSink
+    ) -> Result<ChatCompletionResponse, LlmError> {
+        let mut builder = ChatCompletionParametersBuilder::default();
+        builder
+            .model(&request.model)
+            .messages(request.messages.clone())
+            .temperature(request.temperature.unwrap_or(0.3))
+            .stream(true);
+        if !tools.is_empty() {
+            builder.with_function_calling_auto(&tools);
+        }
+        let request = builder
+            .build()
+            .map_err(|e| LlmError::from(e.to_string()))?;
+
+        let mut stream = self
+            .chat_stream(request)
+            .await?;
+
+        let mut assembler = ChatStreamAssembler::new();
+        while let Some(chunk) = stream.next().await {
+            for delta in assembler.push(chunk?) {
+                on_delta(delta);
+            }
+        }
+
+        Ok(assembler.finish().extract_think_content())
+    }
+}
@@ -3,12 +3,20 @@ pub mod call;
 pub mod call_fc_auto;
 pub mod call_fc_required;
 pub mod call_structured_output;
+pub mod call_stream;
+pub mod call_parsing;
 
 #[cfg(test)]
 mod test_so;
+#[cfg(test)]
+mod test_stream;
+#[cfg(test)]
+mod test_parsing;
 
 pub use tool::{ToolDescription, ToolCallMethod, ToolBox, ContainsTool};
 pub use call::{LlmToolCall,ToolCallAuto};
 pub use call_structured_output::{AssistantResponse, StructuredOutputBuilder, IntoChatMessage};
 pub use call_fc_auto::FunctionCallingAutoBuilder;
-pub use call_fc_required::FunctionCallingRequiredBuilder;
\ No newline at end of file
+pub use call_fc_required::FunctionCallingRequiredBuilder;
+pub use call_stream::{LlmToolCallStream, ChatStreamAssembler, ChatDelta, DeltaSink};
+pub use call_parsing::{parse_tool_calls, repair_json};
\ No newline at end of file
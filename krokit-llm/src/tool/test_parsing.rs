@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod parsing_tests {
+    use serde_json::json;
+    use crate::{ChatMessage, ChatMessageContent, Function, ToolCall};
+    use crate::tool::call_parsing::{into_parsing_messages, parse_tool_calls, repair_json};
+
+    #[test]
+    fn test_parse_single_call() {
+        let text = "Let me look.\n<tool_call>\n{\"name\": \"read\", \"arguments\": {\"path\": \"src/main.rs\"}}\n</tool_call>";
+        let (content, calls) = parse_tool_calls(text);
+        assert_eq!(content, "Let me look.");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].tool_name, "read");
+        assert_eq!(calls[0].tool_parameter, json!({"path": "src/main.rs"}));
+    }
+
+    #[test]
+    fn test_parse_multiple_calls_and_missing_close_tag() {
+        let text = "<tool_call>{\"name\": \"ls\", \"arguments\": {}}\n<tool_call>{\"name\": \"bash\", \"arguments\": {\"command\": \"pwd\"}}";
+        let (content, calls) = parse_tool_calls(text);
+        assert!(content.is_empty());
+        assert_eq!(calls.iter().map(|c| c.tool_name.as_str()).collect::<Vec<_>>(), vec!["ls", "bash"]);
+        assert_eq!(calls[1].tool_parameter, json!({"command": "pwd"}));
+    }
+
+    #[test]
+    fn test_parse_name_attribute_and_key_variants() {
+        let (_, calls) = parse_tool_calls("<tool_call name=\"bash\">{\"command\": \"ls\"}</tool_call>");
+        assert_eq!(calls[0].tool_name, "bash");
+        assert_eq!(calls[0].tool_parameter, json!({"command": "ls"}));
+
+        let (_, calls) = parse_tool_calls("<tool_call>{\"tool\": \"read\", \"parameters\": \"{\\\"path\\\": \\\"a.rs\\\"}\"}</tool_call>");
+        assert_eq!(calls[0].tool_name, "read");
+        assert_eq!(calls[0].tool_parameter, json!({"path": "a.rs"}));
+    }
+
+    #[test]
+    fn test_unparseable_block_is_kept_in_content() {
+        let text = "before <tool_call>not json at all</tool_call> after";
+        let (content, calls) = parse_tool_calls(text);
+        assert!(calls.is_empty());
+        assert_eq!(content, text);
+
+        let (content, calls) = parse_tool_calls("no tools here");
+        assert!(calls.is_empty());
+        assert_eq!(content, "no tools here");
+    }
+
+    #[test]
+    fn test_similar_tags_are_not_tool_calls() {
+        let text = "<tool_calls>[]</tool_calls> <tool_call_result>ok</tool_call_result>";
+        let (content, calls) = parse_tool_calls(text);
+        assert!(calls.is_empty());
+        assert_eq!(content, text);
+
+        let text = "<tool_call_result>ok</tool_call_result>\n<tool_call name=\"ls\">{\"path\": \".\"}</tool_call>";
+        let (content, calls) = parse_tool_calls(text);
+        assert_eq!(content, "<tool_call_result>ok</tool_call_result>");
+        assert_eq!(calls[0].tool_name, "ls");
+    }
+
+    #[test]
+    fn test_repair_json() {
+        assert_eq!(repair_json("```json\n{\"a\": 1,}\n```"), Some(json!({"a": 1})));
+        assert_eq!(repair_json("{'path': 'it\\'s.txt', recursive: True}"), Some(json!({"path": "it's.txt", "recursive": true})));
+        assert_eq!(repair_json("{\"content\": \"line1\nline2\"}"), Some(json!({"content": "line1\nline2"})));
+        assert_eq!(repair_json("{\"name\": \"x\", \"arguments\": {\"a\": [1, 2,"), Some(json!({"name": "x", "arguments": {"a": [1, 2]}})));
+        assert_eq!(repair_json("{\"a\": 1e3, \"b\": None} trailing text }"), Some(json!({"a": 1000.0, "b": null})));
+        assert_eq!(repair_json("no json"), None);
+    }
+
+    #[test]
+    fn test_into_parsing_messages() {
+        let messages = vec![
+            ChatMessage::System { content: ChatMessageContent::Text("You are a coder.".to_string()), name: None },
+            ChatMessage::User { content: ChatMessageContent::Text("list files".to_string()), name: None },
+            ChatMessage::Assistant {
+                content: None,
+                reasoning_content: None,
+                tool_calls: Some(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    r#type: "function".to_string(),
+                    function: Function { name: "ls".to_string(), arguments: "{\"path\": \".\"}".to_string() },
+                }]),
+                refusal: None,
+                name: None,
+                audio: None,
+            },
+            ChatMessage::Tool { content: "main.rs".to_string(), tool_call_id: "call_1".to_string() },
+        ];
+
+        let converted = into_parsing_messages(messages, "\n\n# Available Tools");
+        assert_eq!(converted.len(), 4);
+        assert!(matches!(&converted[0], ChatMessage::System { content: ChatMessageContent::Text(text), .. } if text.ends_with("# Available Tools")));
+        match &converted[2] {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), tool_calls: None, .. } => {
+                let (_, calls) = parse_tool_calls(text);
+                assert_eq!(calls[0].tool_name, "ls");
+                assert_eq!(calls[0].tool_parameter, json!({"path": "."}));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        assert!(matches!(&converted[3], ChatMessage::User { content: ChatMessageContent::Text(text), .. } if text.contains("<tool_result name=\"ls\">") && text.contains("main.rs")));
+    }
+}
@@ -0,0 +1,217 @@
+#[cfg(test)]
+mod stream_assembler_tests {
+    use openai_dive::v1::resources::chat::{ChatCompletionChunkChoice, ChatCompletionChunkResponse, ChatMessage, ChatMessageContent, DeltaChatMessage, DeltaFunction, DeltaToolCall};
+    use crate::tool::{ChatDelta, ChatStreamAssembler};
+
+    fn chunk(content: Option<&str>, tool_calls: Option<Vec<DeltaToolCall>>) -> ChatCompletionChunkResponse {
+        ChatCompletionChunkResponse {
+            id: Some("chunk".to_string()),
+            object: "chat.completion.chunk".to_string(),
+            created: 1,
+            model: "test-model".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: Some(0),
+                delta: DeltaChatMessage::Assistant {
+                    content: content.map(|c| ChatMessageContent::Text(c.to_string())),
+                    reasoning_content: None,
+                    refusal: None,
+                    name: None,
+                    tool_calls,
+                },
+                finish_reason: None,
+                logprobs: None,
+            }],
+            usage: None,
+            system_fingerprint: None,
+        }
+    }
+
+    fn tool_delta(index: u32, id: Option<&str>, name: Option<&str>, arguments: &str) -> DeltaToolCall {
+        DeltaToolCall {
+            index: Some(index),
+            id: id.map(|s| s.to_string()),
+            r#type: id.map(|_| "function".to_string()),
+            function: DeltaFunction {
+                name: name.map(|s| s.to_string()),
+                arguments: Some(arguments.to_string()),
+            },
+        }
+    }
+
+    #[test]
+    fn test_assemble_content() {
+        let mut assembler = ChatStreamAssembler::new();
+        let deltas: Vec<ChatDelta> = ["Hel", "lo ", "world"].iter()
+            .flat_map(|c| assembler.push(chunk(Some(c), None)))
+            .collect();
+        assert_eq!(deltas.len(), 3);
+
+        let response = assembler.finish();
+        match &response.choices[0].message {
+            ChatMessage::Assistant { content: Some(ChatMessageContent::Text(text)), tool_calls, .. } => {
+                assert_eq!(text, "Hello world");
+                assert!(tool_calls.is_none());
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_assemble_parallel_tool_calls() {
+        let mut assembler = ChatStreamAssembler::new();
+        let mut started = 0;
+        for c in [
+            chunk(None, Some(vec![tool_delta(0, Some("call_a"), Some("read"), "")])),
+            chunk(None, Some(vec![tool_delta(0, None, None, "{\"path\":")])),
+            chunk(None, Some(vec![tool_delta(1, Some("call_b"), Some("ls"), "{}")])),
+            chunk(None, Some(vec![tool_delta(0, None, None, "\"a.txt\"}")])),
+        ] {
+            started += assembler.push(c).iter().filter(|d| matches!(d, ChatDelta::ToolCall { .. })).count();
+        }
+        assert_eq!(started, 2);
+
+        let response = assembler.finish();
+        let ChatMessage::Assistant { tool_calls: Some(calls), .. } = &response.choices[0].message else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_a");
+        assert_eq!(calls[0].function.name, "read");
+        assert_eq!(calls[0].function.arguments, "{\"path\":\"a.txt\"}");
+        assert_eq!(calls[1].id, "call_b");
+        assert_eq!(calls[1].function.name, "ls");
+    }
+}
+
+#[cfg(test)]
+mod stream_fallback_tests {
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use async_trait::async_trait;
+    use openai_dive::v1::resources::{
+        chat::{ChatCompletionChoice, ChatCompletionChunkChoice, ChatCompletionChunkResponse, ChatCompletionParameters, ChatCompletionParametersBuilder, ChatCompletionResponse, ChatMessage, ChatMessageContent, DeltaChatMessage},
+        model::ListModelResponse,
+        shared::FinishReason,
+    };
+    use crate::capabilities::ModelCapabilities;
+    use crate::provider::{LlmError, LlmProvider, LlmStream, ProviderInfo};
+    use crate::tool::{ChatDelta, DeltaSink, LlmToolCallStream, ToolBox, ToolCallMethod};
+    use crate::LlmClient;
+
+    /// Streams some content then fails, answers "fallback" without streaming
+    struct BrokenStreamProvider {
+        streamed: Vec<&'static str>,
+        chat_calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for BrokenStreamProvider {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Ok(ListModelResponse { object: "list".to_string(), data: vec![] })
+        }
+
+        async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            self.chat_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(ChatCompletionResponse {
+                id: Some("1".to_string()),
+                object: "chat.completion".to_string(),
+                created: 0,
+                model: request.model,
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message: ChatMessage::Assistant {
+                        content: Some(ChatMessageContent::Text("fallback".to_string())),
+                        reasoning_content: None,
+                        refusal: None,
+                        name: None,
+                        audio: None,
+                        tool_calls: None,
+                    },
+                    finish_reason: Some(FinishReason::StopSequenceReached),
+                    logprobs: None,
+                }],
+                usage: None,
+                service_tier: None,
+                system_fingerprint: None,
+            })
+        }
+
+        async fn chat_stream(&self, _request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            let mut items: Vec<Result<ChatCompletionChunkResponse, LlmError>> = self.streamed.iter()
+                .map(|text| Ok(ChatCompletionChunkResponse {
+                    id: Some("chunk".to_string()),
+                    object: "chat.completion.chunk".to_string(),
+                    created: 1,
+                    model: "test-model".to_string(),
+                    choices: vec![ChatCompletionChunkChoice {
+                        index: Some(0),
+                        delta: DeltaChatMessage::Assistant {
+                            content: Some(ChatMessageContent::Text(text.to_string())),
+                            reasoning_content: None,
+                            refusal: None,
+                            name: None,
+                            tool_calls: None,
+                        },
+                        finish_reason: None,
+                        logprobs: None,
+                    }],
+                    usage: None,
+                    system_fingerprint: None,
+                }))
+                .collect();
+            items.push(Err("connection reset".into()));
+            Ok(Box::new(futures::stream::iter(items)))
+        }
+
+        fn capabilities(&self, _model: &str) -> ModelCapabilities {
+            ModelCapabilities { tools: Some(true), ..Default::default() }
+        }
+
+        fn name(&self) -> &'static str {
+            "broken_stream"
+        }
+
+        fn info() -> ProviderInfo {
+            ProviderInfo { name: "broken_stream", display_name: "Broken stream", env_vars: vec![] }
+        }
+    }
+
+    async fn run(streamed: Vec<&'static str>) -> (Result<ChatCompletionResponse, LlmError>, Vec<String>, usize) {
+        let chat_calls = Arc::new(AtomicUsize::new(0));
+        let client = LlmClient::from_provider(Box::new(BrokenStreamProvider { streamed, chat_calls: chat_calls.clone() }));
+        let shown = Arc::new(Mutex::new(Vec::new()));
+        let on_delta: DeltaSink = {
+            let shown = shown.clone();
+            Arc::new(move |delta| {
+                if let ChatDelta::Content(text) = delta {
+                    shown.lock().unwrap().push(text);
+                }
+            })
+        };
+        let request = ChatCompletionParametersBuilder::default()
+            .model("test-model")
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text("hello".to_string()), name: None }])
+            .build()
+            .unwrap();
+        let tools: ToolBox = vec![];
+        let result = client.chat_with_tools_stream(request, &tools, ToolCallMethod::Auto, on_delta).await;
+        let shown = shown.lock().unwrap().clone();
+        (result, shown, chat_calls.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_no_fallback_once_deltas_were_shown() {
+        let (result, shown, chat_calls) = run(vec!["Hel", "lo"]).await;
+        assert!(result.is_err(), "the partial answer is not repeated by a fallback");
+        assert_eq!(shown, vec!["Hel", "lo"]);
+        assert_eq!(chat_calls, 0);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_when_nothing_was_shown() {
+        let (result, shown, chat_calls) = run(vec![]).await;
+        assert!(result.is_ok());
+        assert!(shown.is_empty());
+        assert!(chat_calls > 0);
+    }
+}
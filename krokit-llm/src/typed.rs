@@ -0,0 +1,343 @@
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use openai_dive::v1::resources::chat::{
+    ChatCompletionFunction, ChatCompletionParameters, ChatCompletionResponseFormat, ChatCompletionTool,
+    ChatCompletionToolChoice, ChatCompletionToolType, JsonSchemaBuilder,
+};
+use crate::provider::LlmError;
+use crate::{ChatMessage, ChatMessageContent, LlmClient};
+
+/// Requests sent again after an answer that does not parse
+pub const MAX_TYPED_RETRIES: usize = 2;
+
+/// Name of the tool the answer is passed to, for the models without json schema output
+const RESPOND_TOOL: &str = "respond";
+
+/// How the model is asked to follow the schema
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypedMethod {
+    /// `response_format` constrained by the json schema
+    JsonSchema,
+    /// a single tool taking the schema as parameters, that the model must call
+    ToolCall,
+    /// the schema in the prompt, the json is extracted from the text
+    Prompt,
+}
+
+/// Structured output helper
+impl LlmClient {
+    /// Chat and deserialize the answer into T, whose json schema is derived with schemars.
+    /// The schema is enforced with `response_format` when the model supports it, with a
+    /// forced tool call otherwise, or only described in the prompt as a last resort.
+    /// An answer that does not parse is repaired when possible (code fences, text around
+    /// the json), else the error is sent back to the model up to MAX_TYPED_RETRIES times.
+    pub async fn chat_typed<T: JsonSchema + DeserializeOwned>(&self, request: ChatCompletionParameters) -> Result<T, LlmError> {
+        let capabilities = self.capabilities(&request.model).await;
+        let method = if capabilities.supports_json_schema() {
+            TypedMethod::JsonSchema
+        } else if capabilities.supports_tools() {
+            TypedMethod::ToolCall
+        } else {
+            TypedMethod::Prompt
+        };
+        self.chat_typed_with::<T>(request, method).await
+    }
+
+    /// Same as `chat_typed` with a given method
+    pub async fn chat_typed_with<T: JsonSchema + DeserializeOwned>(&self, request: ChatCompletionParameters, method: TypedMethod) -> Result<T, LlmError> {
+        let (name, schema) = typed_schema::<T>();
+        let mut request = with_schema(request, method, &name, schema)?;
+
+        let mut retries = 0;
+        loop {
+            let response = self.chat(request.clone()).await?;
+            let message = response.choices.into_iter().next()
+                .map(|choice| choice.message)
+                .ok_or("No choice in response")?;
+            let answer = answer_text(&message, method);
+
+            let error = match parse_typed::<T>(&answer) {
+                Ok(value) => return Ok(value),
+                Err(error) if retries < MAX_TYPED_RETRIES => error,
+                Err(error) => return Err(format!("Failed to parse {} response: {}", name, error).into()),
+            };
+            retries += 1;
+
+            // the error is sent back as a user message, a dangling tool call would need a result
+            request.messages.push(ChatMessage::Assistant {
+                content: Some(ChatMessageContent::Text(answer)),
+                reasoning_content: None,
+                refusal: None,
+                name: None,
+                audio: None,
+                tool_calls: None,
+            });
+            request.messages.push(ChatMessage::User {
+                content: ChatMessageContent::Text(format!(
+                    "This answer does not match the expected schema: {}. Answer again with only the JSON object.",
+                    error
+                )),
+                name: None,
+            });
+        }
+    }
+}
+
+/// Name and strict json schema of T
+fn typed_schema<T: JsonSchema>() -> (String, Value) {
+    let mut schema = serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default();
+    strict_schema(&mut schema);
+    // the name must match ^[a-zA-Z0-9_-]+$
+    let name = schema.get("title").and_then(Value::as_str)
+        .map(|title| title.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-').collect::<String>())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "response".to_string());
+    (name, schema)
+}
+
+/// Make a schema acceptable by strict mode: no additional properties, every property
+/// required (optional fields are already nullable in the schemas of schemars)
+pub fn strict_schema(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if let Some(Value::Object(properties)) = object.get("properties") {
+                let required = properties.keys().cloned().map(Value::String).collect();
+                object.insert("required".to_string(), Value::Array(required));
+                object.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+            object.values_mut().for_each(strict_schema);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strict_schema),
+        _ => {}
+    }
+}
+
+fn with_schema(mut request: ChatCompletionParameters, method: TypedMethod, name: &str, schema: Value) -> Result<ChatCompletionParameters, LlmError> {
+    match method {
+        TypedMethod::JsonSchema => {
+            let json_schema = JsonSchemaBuilder::default()
+                .name(name)
+                .schema(schema)
+                .strict(true)
+                .build()
+                .map_err(|e| -> LlmError { e.into() })?;
+            request.response_format = Some(ChatCompletionResponseFormat::JsonSchema { json_schema });
+        }
+        TypedMethod::ToolCall => {
+            request.tools = Some(vec![ChatCompletionTool {
+                r#type: ChatCompletionToolType::Function,
+                function: ChatCompletionFunction {
+                    name: RESPOND_TOOL.to_string(),
+                    description: Some(format!("Give the answer as a {} object", name)),
+                    parameters: schema,
+                },
+            }]);
+            request.tool_choice = Some(ChatCompletionToolChoice::Required);
+        }
+        TypedMethod::Prompt => {
+            request.messages.push(ChatMessage::System {
+                content: ChatMessageContent::Text(format!(
+                    "Answer with only a JSON object matching this schema, without any other text:\n```json\n{}\n```",
+                    serde_json::to_string_pretty(&schema).unwrap_or_default()
+                )),
+                name: None,
+            });
+        }
+    }
+    Ok(request)
+}
+
+/// Text holding the json: the arguments of the tool call, or the content
+fn answer_text(message: &ChatMessage, method: TypedMethod) -> String {
+    let ChatMessage::Assistant { content, tool_calls, .. } = message else {
+        return String::new();
+    };
+    let arguments = tool_calls.iter().flatten()
+        .find(|call| method == TypedMethod::ToolCall || call.function.name == RESPOND_TOOL)
+        .map(|call| call.function.arguments.clone());
+    arguments.unwrap_or_else(|| match content {
+        Some(ChatMessageContent::Text(text)) => text.clone(),
+        _ => String::new(),
+    })
+}
+
+/// Parse the answer, repairing what models commonly add around the json
+pub fn parse_typed<T: DeserializeOwned>(answer: &str) -> Result<T, serde_json::Error> {
+    let error = match serde_json::from_str(answer) {
+        Ok(value) => return Ok(value),
+        Err(error) => error,
+    };
+    match extract_json(answer) {
+        Some(json) if json != answer => serde_json::from_str(json).map_err(|_| error),
+        _ => Err(error),
+    }
+}
+
+/// The outermost json object (or array) of a text, without code fences and prose
+fn extract_json(text: &str) -> Option<&str> {
+    let start = text.find(|c| c == '{' || c == '[')?;
+    let close = if text[start..].starts_with('{') { '}' } else { ']' };
+    let end = text.rfind(close)?;
+    (end > start).then(|| &text[start..=end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use openai_dive::v1::resources::chat::{ChatCompletionChoice, ChatCompletionParametersBuilder, ChatCompletionResponse, Function, ToolCall};
+    use openai_dive::v1::resources::model::ListModelResponse;
+    use openai_dive::v1::resources::shared::FinishReason;
+    use crate::capabilities::ModelCapabilities;
+    use crate::provider::{LlmProvider, LlmStream, ProviderInfo};
+
+    #[derive(Debug, PartialEq, Deserialize, JsonSchema)]
+    struct Fix {
+        rationale: Option<String>,
+        command: String,
+    }
+
+    /// Answers in turn, records the requests
+    struct ScriptedProvider {
+        answers: Mutex<Vec<ChatMessage>>,
+        requests: Arc<Mutex<Vec<ChatCompletionParameters>>>,
+        json_schema: bool,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        async fn models(&self) -> Result<ListModelResponse, LlmError> {
+            Err("not supported".into())
+        }
+
+        async fn chat(&self, request: ChatCompletionParameters) -> Result<ChatCompletionResponse, LlmError> {
+            self.requests.lock().unwrap().push(request);
+            let message = self.answers.lock().unwrap().remove(0);
+            Ok(ChatCompletionResponse {
+                id: None,
+                object: "chat.completion".to_string(),
+                created: 0,
+                model: "m".to_string(),
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message,
+                    finish_reason: Some(FinishReason::StopSequenceReached),
+                    logprobs: None,
+                }],
+                usage: None,
+                service_tier: None,
+                system_fingerprint: None,
+            })
+        }
+
+        async fn chat_stream(&self, request: ChatCompletionParameters) -> Result<LlmStream, LlmError> {
+            Err("not supported".into())
+        }
+
+        fn capabilities(&self, model: &str) -> ModelCapabilities {
+            ModelCapabilities { json_schema: Some(self.json_schema), tools: Some(true), ..Default::default() }
+        }
+
+        fn name(&self) -> &'static str {
+            "scripted"
+        }
+
+        fn info() -> ProviderInfo {
+            ProviderInfo { name: "scripted", display_name: "Scripted", env_vars: vec![] }
+        }
+    }
+
+    fn text(text: &str) -> ChatMessage {
+        ChatMessage::Assistant {
+            content: Some(ChatMessageContent::Text(text.to_string())),
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: None,
+        }
+    }
+
+    fn respond(arguments: &str) -> ChatMessage {
+        ChatMessage::Assistant {
+            content: None,
+            reasoning_content: None,
+            refusal: None,
+            name: None,
+            audio: None,
+            tool_calls: Some(vec![ToolCall {
+                id: "call_1".to_string(),
+                r#type: "function".to_string(),
+                function: Function { name: RESPOND_TOOL.to_string(), arguments: arguments.to_string() },
+            }]),
+        }
+    }
+
+    fn client(answers: Vec<ChatMessage>, json_schema: bool) -> (LlmClient, Arc<Mutex<Vec<ChatCompletionParameters>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let provider = ScriptedProvider { answers: Mutex::new(answers), requests: requests.clone(), json_schema };
+        (LlmClient::from_provider(Box::new(provider)), requests)
+    }
+
+    fn request() -> ChatCompletionParameters {
+        ChatCompletionParametersBuilder::default()
+            .model("m")
+            .messages(vec![ChatMessage::User { content: ChatMessageContent::Text("fix gti status".to_string()), name: None }])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_strict_schema() {
+        let (name, schema) = typed_schema::<Fix>();
+        assert_eq!(name, "Fix");
+        assert_eq!(schema["additionalProperties"], Value::Bool(false));
+        let required: Vec<&str> = schema["required"].as_array().unwrap().iter().map(|v| v.as_str().unwrap()).collect();
+        assert!(required.contains(&"rationale") && required.contains(&"command"));
+    }
+
+    #[test]
+    fn test_parse_repairs_fences_and_prose() {
+        let fix: Fix = parse_typed("Here you go:\n```json\n{\"rationale\": null, \"command\": \"git status\"}\n```").unwrap();
+        assert_eq!(fix.command, "git status");
+        assert!(parse_typed::<Fix>("git status").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_json_schema_response_format() {
+        let (client, requests) = client(vec![text(r#"{"rationale": "typo", "command": "git status"}"#)], true);
+        let fix: Fix = client.chat_typed(request()).await.unwrap();
+        assert_eq!(fix, Fix { rationale: Some("typo".to_string()), command: "git status".to_string() });
+
+        let requests = requests.lock().unwrap();
+        assert!(matches!(requests[0].response_format, Some(ChatCompletionResponseFormat::JsonSchema { .. })));
+        assert!(requests[0].tools.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_fallback_and_retry() {
+        let answers = vec![respond(r#"{"rationale": "typo"}"#), respond(r#"{"rationale": null, "command": "git status"}"#)];
+        let (client, requests) = client(answers, false);
+        let fix: Fix = client.chat_typed(request()).await.unwrap();
+        assert_eq!(fix.command, "git status");
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].response_format.is_none());
+        assert_eq!(requests[0].tools.as_ref().unwrap()[0].function.name, RESPOND_TOOL);
+        // the parse error is sent back to the model
+        assert!(matches!(requests[1].messages.last(), Some(ChatMessage::User { content: ChatMessageContent::Text(text), .. }) if text.contains("command")));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_retries() {
+        let (client, requests) = client(vec![text("no"), text("still no"), text("never")], true);
+        let error = client.chat_typed::<Fix>(request()).await.unwrap_err();
+        assert!(error.to_string().contains("Failed to parse Fix response"), "{}", error);
+        assert_eq!(requests.lock().unwrap().len(), MAX_TYPED_RETRIES + 1);
+    }
+}
@@ -83,6 +83,7 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
     let mut execute_preview_method = None;
     let mut param_type = None;
     let mut has_cancel_token = false;
+    let mut has_progress = false;
 
     for item in &input.items {
         if let syn::ImplItem::Fn(method) = item {
@@ -104,6 +105,9 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
                             } else if param_index == 1 {
                                 // Second non-self parameter is cancel_token
                                 has_cancel_token = true;
+                            } else if param_index == 2 {
+                                // Third non-self parameter is the progress sink
+                                has_progress = true;
                             }
                             param_index += 1;
                         }
@@ -162,7 +166,17 @@ fn tool_impl(args: String, input: ItemImpl) -> syn::Result<TokenStream2> {
     };
 
     // Generate the execute implementation based on whether user method has cancel_token
-    let execute_impl = if has_cancel_token {
+    let execute_impl = if has_progress {
+        quote! {
+            async fn execute(&self, parameters: Self::Params, cancel_token: Option<tokio_util::sync::CancellationToken>) -> #crate_name::tools::ToolResult {
+                <Self>::execute(self, parameters, cancel_token, None).await
+            }
+
+            async fn execute_with_progress(&self, parameters: Self::Params, cancel_token: Option<tokio_util::sync::CancellationToken>, progress: Option<#crate_name::tools::ProgressSink>) -> #crate_name::tools::ToolResult {
+                <Self>::execute(self, parameters, cancel_token, progress).await
+            }
+        }
+    } else if has_cancel_token {
         quote! {
             async fn execute(&self, parameters: Self::Params, cancel_token: Option<tokio_util::sync::CancellationToken>) -> #crate_name::tools::ToolResult {
                 <Self>::execute(self, parameters, cancel_token).await